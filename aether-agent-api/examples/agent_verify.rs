@@ -2,7 +2,7 @@
 //! Example demonstrating agent verification via gRPC API.
 
 use aether_agent_api::proto::aether_debug_client::AetherDebugClient;
-use aether_agent_api::proto::{Empty, ReadMemoryRequest, WatchVariableRequest};
+use aether_agent_api::proto::{Empty, ReadMemoryRequest, ResetRequest, WatchVariableRequest};
 use tokio_stream::StreamExt;
 
 #[tokio::main]
@@ -19,7 +19,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 2. Perform actions
     println!("[AGENT] Sending Reset...");
-    client.reset(Empty {}).await?;
+    client.reset(ResetRequest { kind: None }).await?;
 
     println!("[AGENT] Sending Watch variable 'counter'...");
     client.watch_variable(WatchVariableRequest { name: "counter".to_string() }).await?;
@@ -0,0 +1,158 @@
+//! Headless agent support.
+//!
+//! Argument parsing and attach logic for the `aether-agent` binary, kept in the
+//! library so the entry point can be exercised without hardware.
+
+use aether_core::{SessionHandle, WireProtocol};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+
+/// Command-line arguments for the headless agent.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "aether-agent", author, version, about = "Headless Aether debug agent")]
+pub struct AgentArgs {
+    /// Index of the probe to open
+    #[arg(long, default_value_t = 0, conflicts_with = "serial")]
+    pub probe_index: usize,
+
+    /// Serial number of the probe to open (instead of `--probe-index`)
+    #[arg(long)]
+    pub serial: Option<String>,
+
+    /// Chip name (e.g. `STM32L476RGTx`). Use 'auto' for auto-detection.
+    #[arg(short, long, default_value = "auto")]
+    pub chip: String,
+
+    /// Debug protocol (swd, jtag)
+    #[arg(long)]
+    pub protocol: Option<String>,
+
+    /// Probe clock speed in kHz
+    #[arg(long)]
+    pub speed: Option<u32>,
+
+    /// Connect under reset
+    #[arg(long)]
+    pub under_reset: bool,
+
+    /// Address to bind the gRPC server to
+    #[arg(long, default_value = "0.0.0.0:50051")]
+    pub bind: String,
+}
+
+/// How the agent selects a probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeSelector {
+    /// Position in the probe list.
+    Index(usize),
+    /// USB serial number, stable across re-enumeration.
+    Serial(String),
+}
+
+/// Attach parameters derived from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentAttach {
+    /// Probe to open.
+    pub probe: ProbeSelector,
+    /// Chip name or "auto".
+    pub chip: String,
+    /// Forced wire protocol, if any.
+    pub protocol: Option<WireProtocol>,
+    /// Connect under reset.
+    pub under_reset: bool,
+    /// Probe clock in kHz, if overridden.
+    pub speed_khz: Option<u32>,
+}
+
+impl AgentArgs {
+    /// Build the attach parameters.
+    ///
+    /// # Errors
+    /// Returns an error for an unknown protocol name.
+    pub fn attach(&self) -> Result<AgentAttach> {
+        let protocol = match self.protocol.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None => None,
+            Some("swd") => Some(WireProtocol::Swd),
+            Some("jtag") => Some(WireProtocol::Jtag),
+            Some(other) => bail!("Unknown protocol: {other}"),
+        };
+        let probe = self
+            .serial
+            .clone()
+            .map_or(ProbeSelector::Index(self.probe_index), ProbeSelector::Serial);
+
+        Ok(AgentAttach {
+            probe,
+            chip: self.chip.clone(),
+            protocol,
+            under_reset: self.under_reset,
+            speed_khz: self.speed,
+        })
+    }
+
+    /// Split the bind address into host and port.
+    ///
+    /// # Errors
+    /// Returns an error if the address has no valid port.
+    pub fn bind_addr(&self) -> Result<(String, u16)> {
+        let (host, port) =
+            self.bind.rsplit_once(':').ok_or_else(|| anyhow!("Missing port in {}", self.bind))?;
+        let port = port.parse().with_context(|| format!("Invalid port in {}", self.bind))?;
+        Ok((host.trim_start_matches('[').trim_end_matches(']').to_string(), port))
+    }
+}
+
+/// Open the probe described by `attach` and start a session on it.
+///
+/// # Errors
+/// Returns a user-facing message if the probe cannot be found or attached.
+pub fn connect_probe(attach: &AgentAttach) -> Result<SessionHandle> {
+    let pm = aether_core::ProbeManager::new();
+    let index = match &attach.probe {
+        ProbeSelector::Index(i) => *i,
+        ProbeSelector::Serial(s) => pm
+            .find_probe_by_serial(s)
+            .map_err(|e| anyhow!(aether_core::probe::map_probe_error(&e)))?,
+    };
+    let (info, session) = pm
+        .connect_with_speed(
+            index,
+            &attach.chip,
+            attach.protocol,
+            attach.under_reset,
+            attach.speed_khz,
+        )
+        .map_err(|e| anyhow!(aether_core::probe::map_probe_error(&e)))?;
+    log::info!("Attached to target: {}", info.name);
+    let serial = match &attach.probe {
+        ProbeSelector::Serial(s) => Some(s.clone()),
+        ProbeSelector::Index(i) => {
+            pm.list_probes().ok().and_then(|p| p.get(*i).and_then(|p| p.serial_number.clone()))
+        }
+    };
+    let reconnect = serial.map(|serial| aether_core::reconnect::ReconnectTarget {
+        serial,
+        chip: attach.chip.clone(),
+        protocol: attach.protocol,
+        under_reset: attach.under_reset,
+        speed_khz: attach.speed_khz,
+    });
+    Ok(SessionHandle::with_reconnect(session, reconnect))
+}
+
+/// Attach through `connect`, returning the session and bind address.
+///
+/// The connector is injected so tests can run without a probe. Parsing is left
+/// to the caller, so `--help` and usage errors exit the way clap intends.
+///
+/// # Errors
+/// Returns an error for invalid arguments or a failed attach.
+pub fn prepare<F>(args: &AgentArgs, connect: F) -> Result<(SessionHandle, String, u16)>
+where
+    F: FnOnce(&AgentAttach) -> Result<SessionHandle>,
+{
+    let attach = args.attach()?;
+    let (host, port) = args.bind_addr()?;
+    let session = connect(&attach)?;
+    Ok((session, host, port))
+}
@@ -0,0 +1,27 @@
+#![allow(clippy::pedantic, clippy::nursery)]
+//! Aether Agent - Headless probe attach + gRPC server for CI and remote rigs.
+
+use aether_agent_api::agent;
+use clap::Parser;
+use log::{error, info};
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+
+    let args = agent::AgentArgs::parse();
+    let (session, host, port) = match agent::prepare(&args, agent::connect_probe) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{e:#}");
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        }
+    };
+
+    info!("Starting gRPC server on {}:{}", host, port);
+    aether_agent_api::run_server(Arc::new(session), &host, port).await?;
+
+    Ok(())
+}
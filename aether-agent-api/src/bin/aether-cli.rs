@@ -3,9 +3,10 @@
 
 use aether_agent_api::proto::aether_debug_client::AetherDebugClient;
 use aether_agent_api::proto::{
-    AttachRequest, BreakpointRequest, DisasmRequest, Empty, FileRequest, ItmConfig,
-    PeripheralRequest, PeripheralWriteRequest, ReadMemoryRequest, ReadRegisterRequest,
-    RttWriteRequest, WatchVariableRequest, WriteMemoryRequest, WriteRegisterRequest,
+    AttachRequest, BreakpointRequest, DisasmRequest, DisasmSymbolRequest, Empty, FileRequest,
+    ItmConfig, PeripheralRequest, PeripheralWriteRequest, ReadMemoryRequest, ReadRegisterRequest,
+    ResetRequest, RttWriteRequest, SourceBreakpointRequest, WatchVariableRequest,
+    WriteMemoryRequest, WriteRegisterRequest,
 };
 use clap::{Parser, Subcommand};
 
@@ -70,7 +71,11 @@ enum CoreCommands {
     /// Resume execution
     Resume,
     /// Reset the target
-    Reset,
+    Reset {
+        /// "default", "system" (SYSRESETREQ) or "core" (VECTRESET)
+        #[arg(long)]
+        kind: Option<String>,
+    },
     /// Step one instruction
     Step,
     /// Step Over
@@ -116,10 +121,14 @@ enum TargetCommands {
         #[arg(default_value_t = 10)]
         count: u32,
     },
+    /// Disassemble a function by symbol name
+    DisasmSymbol { name: String },
     /// List active breakpoints
     Breakpoints,
     /// Set a hardware breakpoint
     Break { address: String },
+    /// Set a breakpoint on a source line, e.g. `src/main.c:42`
+    BreakAt { location: String },
     /// Clear a breakpoint
     Clear { address: String },
     /// Read peripheral register
@@ -153,6 +162,12 @@ enum TraceCommands {
     EnableItm {
         #[arg(default_value_t = 115200)]
         baud: u32,
+        /// SWO pin encoding: uart or manchester
+        #[arg(long, default_value = "uart")]
+        mode: String,
+        /// Where ITM packets go: swo or trace_memory
+        #[arg(long, default_value = "swo")]
+        sink: String,
     },
 }
 
@@ -175,6 +190,9 @@ pub enum ProbeCommands {
         /// Connect under reset
         #[arg(long)]
         under_reset: bool,
+        /// Pulse nRST and retry once if the attach fails (restarts the target)
+        #[arg(long)]
+        reset_recovery: bool,
     },
 }
 
@@ -229,8 +247,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 client.resume(Empty {}).await?;
                 println!("Resumed.");
             }
-            CoreCommands::Reset => {
-                client.reset(Empty {}).await?;
+            CoreCommands::Reset { kind } => {
+                client.reset(ResetRequest { kind }).await?;
                 println!("Reset.");
             }
             CoreCommands::Step => {
@@ -320,8 +338,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let addr = parse_hex(&address)?;
                 let resp =
                     client.disassemble(DisasmRequest { address: addr, count }).await?.into_inner();
-                for line in resp.instructions {
-                    println!("{line}");
+                for insn in resp.decoded {
+                    let bytes: String = insn.bytes.iter().map(|b| format!("{b:02X}")).collect();
+                    println!(
+                        "0x{:08X}:  {:<8}  {}  {}",
+                        insn.address, bytes, insn.mnemonic, insn.op_str
+                    );
+                }
+            }
+            TargetCommands::DisasmSymbol { name } => {
+                let resp =
+                    client.disassemble_symbol(DisasmSymbolRequest { name }).await?.into_inner();
+                for insn in resp.decoded {
+                    let bytes: String = insn.bytes.iter().map(|b| format!("{b:02X}")).collect();
+                    println!(
+                        "0x{:08X}:  {:<8}  {}  {}",
+                        insn.address, bytes, insn.mnemonic, insn.op_str
+                    );
                 }
             }
             TargetCommands::Breakpoints => {
@@ -335,6 +368,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 client.set_breakpoint(BreakpointRequest { address: addr }).await?;
                 println!("Breakpoint set at 0x{addr:08X}");
             }
+            TargetCommands::BreakAt { location } => {
+                let (file, line) = location
+                    .rsplit_once(':')
+                    .and_then(|(file, line)| Some((file.to_string(), line.parse().ok()?)))
+                    .ok_or("expected FILE:LINE")?;
+                let address = client
+                    .set_breakpoint_at_source(SourceBreakpointRequest { file, line })
+                    .await?
+                    .into_inner()
+                    .address;
+                println!("Breakpoint set at {location} (0x{address:08X})");
+            }
             TargetCommands::Clear { address } => {
                 let addr = parse_hex(&address)?;
                 client.clear_breakpoint(BreakpointRequest { address: addr }).await?;
@@ -395,9 +440,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 client.enable_semihosting(Empty {}).await?;
                 println!("Semihosting enabled.");
             }
-            TraceCommands::EnableItm { baud } => {
-                client.enable_itm(ItmConfig { baud_rate: baud }).await?;
-                println!("ITM enabled at {baud} baud.");
+            TraceCommands::EnableItm { baud, mode, sink } => {
+                client
+                    .enable_itm(ItmConfig {
+                        baud_rate: baud,
+                        swo_mode: mode.clone(),
+                        sink: sink.clone(),
+                    })
+                    .await?;
+                println!("ITM enabled to {sink} at {baud} baud ({mode}).");
             }
         },
         Commands::Probe { cmd } => match cmd {
@@ -408,7 +459,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("{:<5} {:<20} {:<20}", p.index, p.name, p.serial);
                 }
             }
-            ProbeCommands::Attach { index, chip, protocol, under_reset } => {
+            ProbeCommands::Attach { index, chip, protocol, under_reset, reset_recovery } => {
                 println!("Attaching to {chip} via probe {index}...");
                 client
                     .attach(AttachRequest {
@@ -416,6 +467,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         chip,
                         protocol,
                         under_reset,
+                        reset_recovery,
                     })
                     .await?;
                 println!("Successfully attached.");
@@ -432,6 +484,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             chip,
                             protocol,
                             under_reset,
+                            reset_recovery: false,
                         }),
                     })
                     .await?;
@@ -36,6 +36,10 @@ struct Args {
     /// Connect under reset
     #[arg(long)]
     under_reset: bool,
+
+    /// Print every debug event to stdout as newline-delimited JSON
+    #[arg(long)]
+    json_events: bool,
 }
 
 #[tokio::main]
@@ -115,6 +119,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     mnemonic: "mov".to_string(),
                                     op_str: format!("r{}, r{}", i, i + 1),
                                     bytes: vec![0x00, 0xbf],
+                                    is_branch: false,
+                                    is_call: false,
+                                    is_ret: false,
+                                    branch_target: None,
                                 });
                             }
                             let _ = event_tx.send(DebugEvent::Disassembly(lines));
@@ -131,6 +139,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 address_offset: 0x0,
                                 description: Some("Control Register".to_string()),
                                 size: 32,
+                                reset_value: Some(0x0),
+                                write_one_to_clear: false,
                                 value: Some(0x1),
                                 fields: vec![],
                             }];
@@ -147,11 +157,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 },
                             ));
                         }
-                        DebugCommand::Reset => {
+                        DebugCommand::RefreshWatches(names) => {
+                            let infos = names
+                                .into_iter()
+                                .map(|name| aether_core::symbols::TypeInfo {
+                                    name,
+                                    value_formatted_string: "42".to_string(),
+                                    kind: "Primitive".to_string(),
+                                    members: None,
+                                    address: Some(0x20000000),
+                                })
+                                .collect();
+                            let _ = event_tx.send(DebugEvent::WatchUpdate(infos));
+                        }
+                        DebugCommand::Reset(_) => {
                             let _ = event_tx.send(DebugEvent::Halted { pc: 0x08000000 });
                         }
                         DebugCommand::SetBreakpoint(addr) => {
-                            let _ = event_tx.send(DebugEvent::Breakpoints(vec![addr]));
+                            let _ = event_tx.send(DebugEvent::Breakpoints(vec![(addr, true)]));
                         }
                         DebugCommand::ClearBreakpoint(_) => {
                             let _ = event_tx.send(DebugEvent::Breakpoints(vec![]));
@@ -166,6 +189,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else {
         // 1. Initial Connection (Optional)
         let mut session = None;
+        let mut reconnect = None;
 
         // Only try to connect if the user provided something beyond the defaults
         // OR if they want us to try auto-discovery immediately.
@@ -194,6 +218,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     Ok((target, s)) => {
                         info!("Attached to target: {}", target.name);
                         session = Some(s);
+                        reconnect = probes[args.probe_index].serial_number.clone().map(|serial| {
+                            aether_core::reconnect::ReconnectTarget {
+                                serial,
+                                chip: args.chip.clone(),
+                                protocol,
+                                under_reset: args.under_reset,
+                                speed_khz: None,
+                            }
+                        });
                     }
                     Err(e) => {
                         error!("Initial attachment failed: {e}. Starting in disconnected mode.");
@@ -203,9 +236,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // 2. Create Session Handle
-        Arc::new(SessionHandle::new(session)?)
+        match session {
+            Some(s) => Arc::new(SessionHandle::with_reconnect(s, reconnect)),
+            None => Arc::new(SessionHandle::new(None)?),
+        }
     };
 
+    if args.json_events {
+        let rx = session_handle.subscribe();
+        std::thread::spawn(move || {
+            if let Err(e) = aether_core::session::write_events_json(rx, std::io::stdout()) {
+                error!("JSON event output stopped: {e}");
+            }
+        });
+    }
+
     // 3. Start Server
     info!("Starting gRPC server on {}:{}", args.host, args.port);
 
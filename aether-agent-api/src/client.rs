@@ -0,0 +1,184 @@
+//! Typed Agent API client.
+//!
+//! Wraps the generated [`AetherDebugClient`] so other Rust tools can drive a
+//! remote agent with core types instead of protobuf messages.
+
+use crate::proto::aether_debug_client::AetherDebugClient;
+use crate::proto::{
+    BreakpointRequest, DisasmRequest, Empty, EventFilter, ReadMemoryRequest, ReadRegisterRequest,
+    RttReadUntilRequest, SourceBreakpointRequest,
+};
+use aether_core::DebugEvent;
+use futures_core::Stream;
+use tokio_stream::StreamExt;
+use tonic::transport::Channel;
+use tonic::Status;
+
+/// A connection to an Aether agent.
+#[derive(Debug, Clone)]
+pub struct AetherClient {
+    inner: AetherDebugClient<Channel>,
+}
+
+impl AetherClient {
+    /// Connect to the agent at `endpoint`, e.g. `http://127.0.0.1:50051`.
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self, tonic::transport::Error> {
+        Ok(Self::new(AetherDebugClient::connect(endpoint.into()).await?))
+    }
+
+    /// Wrap an already connected generated client.
+    #[must_use]
+    pub const fn new(inner: AetherDebugClient<Channel>) -> Self {
+        Self { inner }
+    }
+
+    /// The generated client, for RPCs without a wrapper here.
+    #[must_use]
+    pub fn into_inner(self) -> AetherDebugClient<Channel> {
+        self.inner
+    }
+
+    /// Features the agent implements; agents without `GetInfo` support only run control.
+    pub async fn capabilities(&mut self) -> Result<aether_core::Capabilities, Status> {
+        let info = self.inner.get_info(Empty {}).await?.into_inner();
+        Ok(info
+            .capabilities
+            .as_ref()
+            .map_or_else(aether_core::Capabilities::default, crate::map_proto_capabilities_to_core))
+    }
+
+    /// Halt the core.
+    pub async fn halt(&mut self) -> Result<(), Status> {
+        self.inner.halt(Empty {}).await?;
+        Ok(())
+    }
+
+    /// Resume the core.
+    pub async fn resume(&mut self) -> Result<(), Status> {
+        self.inner.resume(Empty {}).await?;
+        Ok(())
+    }
+
+    /// Execute one instruction.
+    pub async fn step(&mut self) -> Result<(), Status> {
+        self.inner.step(Empty {}).await?;
+        Ok(())
+    }
+
+    /// Step over the instruction at the PC, running through calls.
+    pub async fn step_over(&mut self) -> Result<(), Status> {
+        self.inner.step_over(Empty {}).await?;
+        Ok(())
+    }
+
+    /// Read core register `register` (e.g. 15 for the PC on Arm).
+    pub async fn read_register(&mut self, register: u16) -> Result<u64, Status> {
+        let request = ReadRegisterRequest { register_number: u32::from(register) };
+        Ok(self.inner.read_register(request).await?.into_inner().value)
+    }
+
+    /// Read `length` bytes of target memory at `address`.
+    pub async fn read_memory(&mut self, address: u64, length: u32) -> Result<Vec<u8>, Status> {
+        let request = ReadMemoryRequest { address, length };
+        Ok(self.inner.read_memory(request).await?.into_inner().data)
+    }
+
+    /// Read RTT up channel `channel` until `delimiter` (e.g. a console prompt)
+    /// arrives. Returns the data and whether the delimiter was seen before
+    /// `timeout_ms` expired.
+    pub async fn rtt_read_until(
+        &mut self,
+        channel: u32,
+        delimiter: &[u8],
+        timeout_ms: u32,
+    ) -> Result<(Vec<u8>, bool), Status> {
+        let request = RttReadUntilRequest { channel, delimiter: delimiter.to_vec(), timeout_ms };
+        let response = self.inner.rtt_read_until(request).await?.into_inner();
+        Ok((response.data, response.found))
+    }
+
+    /// Set a breakpoint at `address`.
+    pub async fn set_breakpoint(&mut self, address: u64) -> Result<(), Status> {
+        self.inner.set_breakpoint(BreakpointRequest { address }).await?;
+        Ok(())
+    }
+
+    /// Set a breakpoint at `file:line`, returning the address it resolved to.
+    pub async fn set_breakpoint_at_source(&mut self, file: &str, line: u32) -> Result<u64, Status> {
+        let request = SourceBreakpointRequest { file: file.to_string(), line };
+        Ok(self.inner.set_breakpoint_at_source(request).await?.into_inner().address)
+    }
+
+    /// Clear the breakpoint at `address`.
+    pub async fn clear_breakpoint(&mut self, address: u64) -> Result<(), Status> {
+        self.inner.clear_breakpoint(BreakpointRequest { address }).await?;
+        Ok(())
+    }
+
+    /// Disassemble `count` instructions starting at `address`.
+    pub async fn disassemble(
+        &mut self,
+        address: u64,
+        count: u32,
+    ) -> Result<Vec<aether_core::disasm::InstructionInfo>, Status> {
+        let response = self.inner.disassemble(DisasmRequest { address, count }).await?;
+        Ok(response
+            .into_inner()
+            .decoded
+            .into_iter()
+            .map(crate::map_proto_instruction_to_core)
+            .collect())
+    }
+
+    /// The agent's events from now on, as core events.
+    pub async fn events(
+        &mut self,
+    ) -> Result<impl Stream<Item = Result<DebugEvent, Status>>, Status> {
+        let stream = self.inner.subscribe_events(Empty {}).await?.into_inner();
+        Ok(core_events(stream))
+    }
+
+    /// Like [`events`](Self::events), but only the given kinds, e.g.
+    /// `["halted", "status"]`; see [`EVENT_KINDS`](crate::EVENT_KINDS).
+    pub async fn events_of(
+        &mut self,
+        kinds: &[&str],
+    ) -> Result<impl Stream<Item = Result<DebugEvent, Status>>, Status> {
+        let filter = EventFilter { kinds: kinds.iter().map(ToString::to_string).collect() };
+        let stream = self.inner.subscribe_events_filtered(filter).await?.into_inner();
+        Ok(core_events(stream))
+    }
+}
+
+/// Convert a stream of protobuf events into core events. Events without a core
+/// mapping are skipped; stream errors are passed through.
+pub fn core_events<S>(stream: S) -> impl Stream<Item = Result<DebugEvent, Status>>
+where
+    S: Stream<Item = Result<crate::proto::DebugEvent, Status>>,
+{
+    stream.filter_map(|item| item.map(crate::map_proto_event_to_core).transpose())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{self, debug_event::Event};
+
+    #[tokio::test]
+    async fn test_core_events_skips_unmapped_and_keeps_errors() {
+        let event = |event| proto::DebugEvent { event: Some(event), seq: 0 };
+        let proto_events = vec![
+            Ok(event(Event::Halted(proto::HaltedEvent { pc: 0x0800_0100 }))),
+            // Status has no core mapping.
+            Ok(event(Event::Status(proto::StatusResponse::default()))),
+            Ok(event(Event::Register(proto::RegisterEvent { register: 15, value: 0x0800_0104 }))),
+            Err(Status::unavailable("agent went away")),
+        ];
+
+        let events: Vec<_> = core_events(tokio_stream::iter(proto_events)).collect().await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Ok(DebugEvent::Halted { pc: 0x0800_0100 })));
+        assert!(matches!(events[1], Ok(DebugEvent::RegisterValue(15, 0x0800_0104))));
+        assert!(matches!(&events[2], Err(status) if status.code() == tonic::Code::Unavailable));
+    }
+}
@@ -0,0 +1,146 @@
+//! Server-side replay buffer for the event stream.
+//!
+//! Keeps the most recent events with sequence numbers so a client that
+//! reconnects can resume from the last event it saw instead of losing
+//! everything emitted while it was disconnected.
+
+use crate::map_sequenced_event_to_proto;
+use crate::proto::DebugEvent;
+use aether_core::SessionHandle;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Number of events kept for replay by default.
+pub const DEFAULT_EVENT_BUFFER_CAPACITY: usize = 1024;
+
+/// Bounded ring buffer of sequenced events.
+///
+/// Events keep the sequence number the session assigned; unnumbered events
+/// get the next one. New events are also re-broadcast so a subscriber can
+/// switch from replay to live events without a gap.
+#[derive(Debug)]
+pub struct EventBuffer {
+    capacity: usize,
+    next_seq: u64,
+    events: VecDeque<DebugEvent>,
+    tx: broadcast::Sender<DebugEvent>,
+}
+
+impl EventBuffer {
+    /// Create a buffer holding at most `capacity` events.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { capacity, next_seq: 1, events: VecDeque::with_capacity(capacity), tx }
+    }
+
+    /// Store and broadcast `event`, numbering it if it has no sequence number yet.
+    pub fn push(&mut self, mut event: DebugEvent) -> u64 {
+        if event.seq == 0 {
+            event.seq = self.next_seq;
+        }
+        let seq = event.seq;
+        self.next_seq = seq + 1;
+        if self.capacity > 0 {
+            if self.events.len() == self.capacity {
+                self.events.pop_front();
+            }
+            self.events.push_back(event.clone());
+        }
+        let _ = self.tx.send(event);
+        seq
+    }
+
+    /// Sequence number of the oldest event still buffered.
+    #[must_use]
+    pub fn oldest_seq(&self) -> Option<u64> {
+        self.events.front().map(|e| e.seq)
+    }
+
+    /// Buffered events with a sequence number of at least `seq`.
+    ///
+    /// If `seq` has already been evicted, replay starts at the oldest event;
+    /// the client sees the jump in sequence numbers.
+    #[must_use]
+    pub fn replay_from(&self, seq: u64) -> Vec<DebugEvent> {
+        self.events.iter().filter(|e| e.seq >= seq).cloned().collect()
+    }
+
+    /// Replay from `seq` and subscribe to the events that follow.
+    ///
+    /// Both happen under the caller's lock, so no event is missed or repeated
+    /// between the replay and the live stream.
+    #[must_use]
+    pub fn subscribe_from(&self, seq: u64) -> (Vec<DebugEvent>, broadcast::Receiver<DebugEvent>) {
+        (self.replay_from(seq), self.tx.subscribe())
+    }
+}
+
+/// Create a buffer of `capacity` events fed from the session's event stream.
+///
+/// Must be called from within a Tokio runtime.
+#[must_use]
+pub fn spawn_event_buffer(session: &SessionHandle, capacity: usize) -> Arc<Mutex<EventBuffer>> {
+    let buffer = Arc::new(Mutex::new(EventBuffer::new(capacity)));
+    let mut rx = session.subscribe_sequenced();
+    let feed = buffer.clone();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if let Some(proto_event) = map_sequenced_event_to_proto(event) {
+                        if let Ok(mut buffer) = feed.lock() {
+                            buffer.push(proto_event);
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Event buffer lagged, {n} events not buffered");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{debug_event::Event, HaltedEvent};
+
+    fn halted(pc: u64) -> DebugEvent {
+        DebugEvent { event: Some(Event::Halted(HaltedEvent { pc })), seq: 0 }
+    }
+
+    fn pc(event: &DebugEvent) -> u64 {
+        match event.event {
+            Some(Event::Halted(HaltedEvent { pc })) => pc,
+            _ => panic!("unexpected event {event:?}"),
+        }
+    }
+
+    #[test]
+    fn test_events_during_gap_are_replayed() {
+        let mut buffer = EventBuffer::new(4);
+        assert_eq!(buffer.push(halted(0x100)), 1);
+        let last_seen = buffer.push(halted(0x104));
+
+        // Client disconnects; three more events are emitted.
+        for pc in [0x108, 0x10C, 0x110] {
+            buffer.push(halted(pc));
+        }
+
+        let (missed, mut live) = buffer.subscribe_from(last_seen + 1);
+        assert_eq!(missed.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(missed.iter().map(pc).collect::<Vec<_>>(), vec![0x108, 0x10C, 0x110]);
+
+        buffer.push(halted(0x114));
+        assert_eq!(live.try_recv().unwrap().seq, 6);
+
+        // Bounded: only the last four events are kept.
+        assert_eq!(buffer.oldest_seq(), Some(3));
+        assert_eq!(buffer.replay_from(1).len(), 4);
+    }
+}
@@ -2,10 +2,19 @@
 //!
 //! Provides the gRPC service and client for interacting with the Aether debugger core.
 
-use aether_core::{DebugCommand, DebugEvent as CoreDebugEvent, SessionHandle};
-use std::sync::Arc;
+pub mod agent;
+pub mod client;
+pub mod event_buffer;
+
+use aether_core::debug::reset::ResetKind;
+use aether_core::session::DEFAULT_REQUEST_TIMEOUT;
+use aether_core::{
+    DebugCommand, DebugError, DebugEvent as CoreDebugEvent, RequestError, SequencedEvent,
+    SessionHandle,
+};
+use event_buffer::EventBuffer;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::broadcast;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 use tonic::{transport::Server, Request, Response, Status};
@@ -21,50 +30,89 @@ pub mod proto {
 
 use proto::aether_debug_server::{AetherDebug, AetherDebugServer};
 use proto::{
-    AttachRequest, BreakpointList, BreakpointRequest, DebugEvent, DisasmRequest, DisasmResponse,
-    Empty, FileRequest, FlashProgress, ItmConfig, ItmEvent, PeripheralRequest, PeripheralResponse,
+    AgentInfo, AttachRequest, BreakpointList, BreakpointRequest, DebugEvent, DisasmRequest,
+    DisasmResponse, DisasmSymbolRequest, Empty, EventFilter, FileRequest, FlagsResponse,
+    FlashProgress, ItmConfig, ItmEvent, Location, PeripheralRequest, PeripheralResponse,
     PeripheralWriteRequest, ProbeInfo as ProtoProbeInfo, ProbeList, ReadMemoryRequest,
-    ReadMemoryResponse, ReadRegisterRequest, ReadRegisterResponse, RttWriteRequest,
-    SemihostingEvent, StackResponse, StatusResponse, TasksEvent, WatchVariableRequest,
+    ReadMemoryResponse, ReadRegisterRequest, ReadRegisterResponse, ResetRequest, RttAttachRequest,
+    RttChannels, RttEvent, RttReadUntilRequest, RttReadUntilResponse, RttStreamRequest,
+    RttWriteRequest, SemihostingEvent, SourceBreakpointRequest, SourceBreakpointResponse,
+    StackResponse, StatusResponse, SubscribeFromRequest, TasksEvent, WatchVariableRequest,
     WriteMemoryRequest, WriteRegisterRequest,
 };
 
+/// Features this agent implements, reported through `GetInfo`.
+///
+/// Keep in sync with the RPCs that still return `unimplemented`.
+pub const AGENT_CAPABILITIES: aether_core::Capabilities = aether_core::Capabilities {
+    write_memory: false,
+    write_register: false,
+    breakpoints: false,
+    peripherals: false,
+    rtt_write: false,
+    tasks: false,
+    flash: true,
+    disassemble: true,
+};
+
 /// Service implementation for the Aether Debug gRPC API.
 pub struct AetherDebugService {
     session: Arc<SessionHandle>,
+    event_buffer: Option<Arc<Mutex<EventBuffer>>>,
 }
 
 impl AetherDebugService {
     /// Create a new `AetherDebugService` with a session handle.
     #[must_use]
     pub const fn new(session: Arc<SessionHandle>) -> Self {
-        Self { session }
+        Self { session, event_buffer: None }
     }
 
-    async fn wait_for_match<F>(
+    /// Keep recent events in `buffer` so clients can resume with `SubscribeEventsFrom`.
+    #[must_use]
+    pub fn with_event_buffer(mut self, buffer: Arc<Mutex<EventBuffer>>) -> Self {
+        self.event_buffer = Some(buffer);
+        self
+    }
+
+    /// Replay buffered events from `seq`, then stream live events.
+    fn buffered_stream(
+        buffer: &Mutex<EventBuffer>,
+        seq: u64,
+    ) -> Result<<Self as AetherDebug>::SubscribeEventsStream, Status> {
+        let (missed, rx) = buffer
+            .lock()
+            .map_err(|_| Status::internal("Event buffer poisoned"))?
+            .subscribe_from(seq);
+        let live = BroadcastStream::new(rx).filter_map(Result::ok);
+        Ok(Box::pin(tokio_stream::iter(missed).chain(live).map(Ok)))
+    }
+
+    /// Sends `cmd` and waits for the event `matcher` accepts, as a gRPC status on failure.
+    async fn request<F>(
         &self,
-        rx: &mut broadcast::Receiver<CoreDebugEvent>,
+        cmd: DebugCommand,
         matcher: F,
+        timeout: Duration,
     ) -> Result<CoreDebugEvent, Status>
     where
-        F: Fn(&CoreDebugEvent) -> bool + Send + 'static,
+        F: Fn(&CoreDebugEvent) -> bool + Send,
     {
-        let timeout = Duration::from_secs(15); // Increased further to allow for multi-stage SWD/JTAG/Reset scan
+        self.session.request(cmd, matcher, timeout).await.map_err(|e| request_status(&e))
+    }
+}
 
-        loop {
-            match tokio::time::timeout(timeout, rx.recv()).await {
-                Ok(Ok(event)) => {
-                    if matcher(&event) {
-                        return Ok(event);
-                    }
-                    if let CoreDebugEvent::Error(e) = event {
-                        return Err(Status::internal(format!("Core error: {e}")));
-                    }
-                }
-                Ok(Err(_)) => return Err(Status::internal("Event stream lagged or closed")),
-                Err(_) => return Err(Status::deadline_exceeded("Timeout waiting for debug event")),
-            }
-        }
+/// Maps a failed session request to the gRPC status reported to the client.
+fn request_status(err: &RequestError) -> Status {
+    match err {
+        RequestError::Closed | RequestError::Lagged => Status::internal(err.to_string()),
+        RequestError::Timeout(_) => Status::deadline_exceeded("Timeout waiting for debug event"),
+        RequestError::Core(message) => Status::internal(format!("Core error: {message}")),
+        RequestError::Failed {
+            error: DebugError::UnknownSymbol(_) | DebugError::NoCodeAtLine { .. },
+            ..
+        } => Status::not_found(err.to_string()),
+        RequestError::Failed { .. } => Status::failed_precondition(err.to_string()),
     }
 }
 
@@ -73,6 +121,8 @@ impl AetherDebug for AetherDebugService {
     type SubscribeEventsStream = std::pin::Pin<
         Box<dyn tokio_stream::Stream<Item = Result<DebugEvent, Status>> + Send + Sync>,
     >;
+    type SubscribeEventsFromStream = Self::SubscribeEventsStream;
+    type SubscribeEventsFilteredStream = Self::SubscribeEventsStream;
 
     async fn halt(&self, _request: Request<Empty>) -> Result<Response<Empty>, Status> {
         self.session.send(DebugCommand::Halt).map_err(|e| Status::internal(e.to_string()))?;
@@ -104,8 +154,14 @@ impl AetherDebug for AetherDebugService {
         Ok(Response::new(Empty {}))
     }
 
-    async fn reset(&self, _request: Request<Empty>) -> Result<Response<Empty>, Status> {
-        self.session.send(DebugCommand::Reset).map_err(|e| Status::internal(e.to_string()))?;
+    async fn reset(&self, request: Request<ResetRequest>) -> Result<Response<Empty>, Status> {
+        let kind = request.into_inner().kind;
+        let kind = parse_reset_kind(kind.as_deref()).ok_or_else(|| {
+            Status::invalid_argument(format!("Unknown reset kind '{}'", kind.unwrap_or_default()))
+        })?;
+        self.session
+            .send(DebugCommand::Reset(kind))
+            .map_err(|e| Status::internal(e.to_string()))?;
         Ok(Response::new(Empty {}))
     }
 
@@ -158,13 +214,13 @@ impl AetherDebug for AetherDebugService {
         request: Request<ReadMemoryRequest>,
     ) -> Result<Response<ReadMemoryResponse>, Status> {
         let req = request.into_inner();
-        let mut rx = self.session.subscribe();
-        self.session
-            .send(DebugCommand::ReadMemory(req.address, req.length as usize))
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        let event =
-            self.wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::MemoryData(..))).await?;
+        let event = self
+            .request(
+                DebugCommand::ReadMemory(req.address, req.length as usize),
+                |e| matches!(e, CoreDebugEvent::MemoryData(..)),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
         if let CoreDebugEvent::MemoryData(_, data) = event {
             Ok(Response::new(ReadMemoryResponse { data }))
         } else {
@@ -177,13 +233,12 @@ impl AetherDebug for AetherDebugService {
         request: Request<ReadRegisterRequest>,
     ) -> Result<Response<ReadRegisterResponse>, Status> {
         let req = request.into_inner();
-        let mut rx = self.session.subscribe();
-        self.session
-            .send(DebugCommand::ReadRegister(u16::try_from(req.register_number).unwrap_or(0)))
-            .map_err(|e| Status::internal(e.to_string()))?;
-
         let event = self
-            .wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::RegisterValue(..)))
+            .request(
+                DebugCommand::ReadRegister(u16::try_from(req.register_number).unwrap_or(0)),
+                |e| matches!(e, CoreDebugEvent::RegisterValue(..)),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
             .await?;
         if let CoreDebugEvent::RegisterValue(_, value) = event {
             Ok(Response::new(ReadRegisterResponse { value }))
@@ -192,6 +247,32 @@ impl AetherDebug for AetherDebugService {
         }
     }
 
+    async fn read_flags(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<FlagsResponse>, Status> {
+        let event = self
+            .request(
+                DebugCommand::ReadFlags,
+                |e| matches!(e, CoreDebugEvent::Flags(_)),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
+        if let CoreDebugEvent::Flags(flags) = event {
+            Ok(Response::new(FlagsResponse {
+                n: flags.n,
+                z: flags.z,
+                c: flags.c,
+                v: flags.v,
+                q: flags.q,
+                thumb: flags.thumb,
+                exception_number: u32::from(flags.exception_number),
+            }))
+        } else {
+            Err(Status::internal("Unexpected event"))
+        }
+    }
+
     async fn write_memory(
         &self,
         _request: Request<WriteMemoryRequest>,
@@ -208,12 +289,12 @@ impl AetherDebug for AetherDebugService {
 
     async fn load_svd(&self, request: Request<FileRequest>) -> Result<Response<Empty>, Status> {
         let req = request.into_inner();
-        let mut rx = self.session.subscribe();
-        self.session
-            .send(DebugCommand::LoadSvd(std::path::PathBuf::from(req.path)))
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        let _ = self.wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::SvdLoaded)).await?;
+        self.request(
+            DebugCommand::LoadSvd(std::path::PathBuf::from(req.path)),
+            |e| matches!(e, CoreDebugEvent::SvdLoaded),
+            DEFAULT_REQUEST_TIMEOUT,
+        )
+        .await?;
         Ok(Response::new(Empty {}))
     }
 
@@ -245,6 +326,25 @@ impl AetherDebug for AetherDebugService {
         Err(Status::unimplemented("SetBreakpoint not implemented"))
     }
 
+    async fn set_breakpoint_at_source(
+        &self,
+        request: Request<SourceBreakpointRequest>,
+    ) -> Result<Response<SourceBreakpointResponse>, Status> {
+        let req = request.into_inner();
+        let event = self
+            .request(
+                DebugCommand::SetBreakpointAtSource { file: req.file.into(), line: req.line },
+                |e| matches!(e, CoreDebugEvent::SourceBreakpointResolved { .. }),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
+        if let CoreDebugEvent::SourceBreakpointResolved { address, .. } = event {
+            Ok(Response::new(SourceBreakpointResponse { address }))
+        } else {
+            Err(Status::internal("Unexpected event"))
+        }
+    }
+
     async fn clear_breakpoint(
         &self,
         _request: Request<BreakpointRequest>,
@@ -277,15 +377,77 @@ impl AetherDebug for AetherDebugService {
         Err(Status::unimplemented("RttWrite not implemented"))
     }
 
+    async fn attach_rtt(
+        &self,
+        request: Request<RttAttachRequest>,
+    ) -> Result<Response<RttChannels>, Status> {
+        let cmd = match request.into_inner().address {
+            Some(address) => DebugCommand::RttAttachAt(address),
+            None => DebugCommand::RttAttach,
+        };
+        let event = self
+            .request(
+                cmd,
+                |e| matches!(e, CoreDebugEvent::RttChannels { .. }),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
+
+        if let CoreDebugEvent::RttChannels { up_channels, down_channels } = event {
+            Ok(Response::new(RttChannels {
+                up_channels: up_channels.iter().map(map_rtt_channel_to_proto).collect(),
+                down_channels: down_channels.iter().map(map_rtt_channel_to_proto).collect(),
+            }))
+        } else {
+            Err(Status::internal("Unexpected event"))
+        }
+    }
+
+    type StreamRttStream =
+        std::pin::Pin<Box<dyn tokio_stream::Stream<Item = Result<RttEvent, Status>> + Send + Sync>>;
+
+    async fn stream_rtt(
+        &self,
+        request: Request<RttStreamRequest>,
+    ) -> Result<Response<Self::StreamRttStream>, Status> {
+        let channel = request.into_inner().channel;
+        let stream = BroadcastStream::new(self.session.subscribe());
+        let output = stream
+            .filter_map(move |res| res.ok().and_then(|event| rtt_for_channel(event, channel)))
+            .map(Ok);
+        Ok(Response::new(Box::pin(output)))
+    }
+
+    async fn rtt_read_until(
+        &self,
+        request: Request<RttReadUntilRequest>,
+    ) -> Result<Response<RttReadUntilResponse>, Status> {
+        let req = request.into_inner();
+        let timeout = if req.timeout_ms == 0 {
+            DEFAULT_REQUEST_TIMEOUT
+        } else {
+            Duration::from_millis(u64::from(req.timeout_ms))
+        };
+        let read = self
+            .session
+            .rtt_read_until(req.channel as usize, &req.delimiter, timeout)
+            .await
+            .map_err(|e| request_status(&e))?;
+        Ok(Response::new(RttReadUntilResponse { data: read.data, found: read.found }))
+    }
+
     async fn get_tasks(&self, _request: Request<Empty>) -> Result<Response<TasksEvent>, Status> {
         Err(Status::unimplemented("GetTasks not implemented"))
     }
 
     async fn get_stack(&self, _request: Request<Empty>) -> Result<Response<StackResponse>, Status> {
-        let mut rx = self.session.subscribe();
-        self.session.send(DebugCommand::GetStack).map_err(|e| Status::internal(e.to_string()))?;
-
-        let event = self.wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::Stack(_))).await?;
+        let event = self
+            .request(
+                DebugCommand::GetStack,
+                |e| matches!(e, CoreDebugEvent::Stack(_)),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
 
         if let CoreDebugEvent::Stack(frames) = event {
             let proto_frames = frames
@@ -306,6 +468,27 @@ impl AetherDebug for AetherDebugService {
         }
     }
 
+    async fn where_am_i(&self, _request: Request<Empty>) -> Result<Response<Location>, Status> {
+        let event = self
+            .request(
+                DebugCommand::WhereAmI,
+                |e| matches!(e, CoreDebugEvent::Location { .. }),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
+
+        if let CoreDebugEvent::Location { pc, function, file, line } = event {
+            Ok(Response::new(Location {
+                pc,
+                function,
+                file: file.map(|f| f.to_string_lossy().into_owned()),
+                line,
+            }))
+        } else {
+            Err(Status::internal("Unexpected event"))
+        }
+    }
+
     async fn load_symbols(&self, request: Request<FileRequest>) -> Result<Response<Empty>, Status> {
         let req = request.into_inner();
         self.session
@@ -359,6 +542,17 @@ impl AetherDebug for AetherDebugService {
                             .await;
                         break;
                     }
+                    aether_core::DebugEvent::OperationFailed { operation, error } => {
+                        let _ = tx
+                            .send(Ok(FlashProgress {
+                                status: "Error".to_string(),
+                                progress: 0.0,
+                                done: true,
+                                error: format!("{operation} failed: {error}"),
+                            }))
+                            .await;
+                        break;
+                    }
                     aether_core::DebugEvent::Error(e) => {
                         let _ = tx
                             .send(Ok(FlashProgress {
@@ -386,34 +580,35 @@ impl AetherDebug for AetherDebugService {
         request: Request<DisasmRequest>,
     ) -> Result<Response<DisasmResponse>, Status> {
         let req = request.into_inner();
-        // Disassembly is tricky because it returns via event usually.
-        // We'll implemented a request-response pattern by waiting for the specific event.
-        // This acts as a bridge.
-
-        let mut session_rx = self.session.subscribe();
-        self.session
-            .send(DebugCommand::Disassemble(req.address, req.count as usize))
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        // Wait for response with timeout
-        let result = tokio::time::timeout(std::time::Duration::from_secs(2), async {
-            while let Ok(event) = session_rx.recv().await {
-                if let aether_core::DebugEvent::Disassembly(lines) = event {
-                    let instructions = lines
-                        .iter()
-                        .map(|l| format!("0x{:08X}:  {}  {}", l.address, l.mnemonic, l.op_str))
-                        .collect();
-                    return Ok(DisasmResponse { instructions });
-                }
-            }
-            Err(Status::internal("Stream closed"))
-        })
-        .await;
+        let event = self
+            .request(
+                DebugCommand::Disassemble(req.address, req.count as usize),
+                |e| matches!(e, CoreDebugEvent::Disassembly(_)),
+                Duration::from_secs(2),
+            )
+            .await?;
+        if let CoreDebugEvent::Disassembly(lines) = event {
+            Ok(Response::new(map_disassembly_to_proto(&lines)))
+        } else {
+            Err(Status::internal("Unexpected event"))
+        }
+    }
 
-        match result {
-            Ok(Ok(resp)) => Ok(Response::new(resp)),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(Status::deadline_exceeded("Disassembly timed out")),
+    async fn disassemble_symbol(
+        &self,
+        request: Request<DisasmSymbolRequest>,
+    ) -> Result<Response<DisasmResponse>, Status> {
+        let event = self
+            .request(
+                DebugCommand::DisassembleSymbol(request.into_inner().name),
+                |e| matches!(e, CoreDebugEvent::Disassembly(_)),
+                Duration::from_secs(2),
+            )
+            .await?;
+        if let CoreDebugEvent::Disassembly(lines) = event {
+            Ok(Response::new(map_disassembly_to_proto(&lines)))
+        } else {
+            Err(Status::internal("Unexpected event"))
         }
     }
 
@@ -429,18 +624,45 @@ impl AetherDebug for AetherDebugService {
 
     async fn enable_itm(&self, request: Request<ItmConfig>) -> Result<Response<Empty>, Status> {
         let req = request.into_inner();
+        let mode = parse_swo_mode(&req.swo_mode).ok_or_else(|| {
+            Status::invalid_argument(format!("unknown SWO mode '{}'", req.swo_mode))
+        })?;
+        let sink = parse_itm_sink(&req.sink)
+            .ok_or_else(|| Status::invalid_argument(format!("unknown ITM sink '{}'", req.sink)))?;
         self.session
-            .send(DebugCommand::EnableItm { baud_rate: req.baud_rate })
+            .send(DebugCommand::EnableItm { baud_rate: req.baud_rate, mode, sink })
             .map_err(|e| Status::internal(e.to_string()))?;
         Ok(Response::new(Empty {}))
     }
 
-    async fn list_probes(&self, _request: Request<Empty>) -> Result<Response<ProbeList>, Status> {
-        let mut rx = self.session.subscribe();
-        self.session.send(DebugCommand::ListProbes).map_err(|e| Status::internal(e.to_string()))?;
+    async fn get_info(&self, _request: Request<Empty>) -> Result<Response<AgentInfo>, Status> {
+        // Best effort: a busy or stopped session should not fail the handshake.
+        let build_id = match self
+            .request(
+                DebugCommand::GetBuildId,
+                |e| matches!(e, CoreDebugEvent::BuildId(_)),
+                Duration::from_millis(500),
+            )
+            .await
+        {
+            Ok(CoreDebugEvent::BuildId(id)) => id,
+            _ => None,
+        };
+        Ok(Response::new(AgentInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            capabilities: Some(map_capabilities_to_proto(AGENT_CAPABILITIES)),
+            build_id,
+        }))
+    }
 
-        let event =
-            self.wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::Probes(_))).await?;
+    async fn list_probes(&self, _request: Request<Empty>) -> Result<Response<ProbeList>, Status> {
+        let event = self
+            .request(
+                DebugCommand::ListProbes,
+                |e| matches!(e, CoreDebugEvent::Probes(_)),
+                DEFAULT_REQUEST_TIMEOUT,
+            )
+            .await?;
 
         if let CoreDebugEvent::Probes(probes) = event {
             let proto_probes = probes
@@ -460,7 +682,6 @@ impl AetherDebug for AetherDebugService {
 
     async fn attach(&self, request: Request<AttachRequest>) -> Result<Response<Empty>, Status> {
         let req = request.into_inner();
-        let mut rx = self.session.subscribe();
 
         let protocol = match req.protocol.as_deref() {
             Some("swd") => Some(aether_core::WireProtocol::Swd),
@@ -469,15 +690,20 @@ impl AetherDebug for AetherDebugService {
         };
 
         self.session
-            .send(DebugCommand::Attach {
+            .send(DebugCommand::SetAttachResetRecovery(req.reset_recovery))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        self.request(
+            DebugCommand::Attach {
                 probe_index: req.probe_index as usize,
                 chip: req.chip,
                 protocol,
                 under_reset: req.under_reset,
-            })
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        let _ = self.wait_for_match(&mut rx, |e| matches!(e, CoreDebugEvent::Attached(_))).await?;
+                speed_khz: None,
+            },
+            |e| matches!(e, CoreDebugEvent::Attached(_)),
+            DEFAULT_REQUEST_TIMEOUT,
+        )
+        .await?;
         Ok(Response::new(Empty {}))
     }
 
@@ -487,7 +713,6 @@ impl AetherDebug for AetherDebugService {
     ) -> Result<Response<Empty>, Status> {
         let req = request.into_inner();
         let attr = req.request.ok_or_else(|| Status::invalid_argument("Missing attach request"))?;
-        let mut rx = self.session.subscribe();
 
         let protocol = match attr.protocol.as_deref() {
             Some("swd") => Some(aether_core::WireProtocol::Swd),
@@ -495,23 +720,19 @@ impl AetherDebug for AetherDebugService {
             _ => None,
         };
 
-        self.session
-            .send(DebugCommand::AttachSubSession {
-                name: req.name.clone(),
+        let name = req.name;
+        self.request(
+            DebugCommand::AttachSubSession {
+                name: name.clone(),
                 probe_index: attr.probe_index as usize,
                 chip: attr.chip,
                 protocol,
                 under_reset: attr.under_reset,
-            })
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        let name_clone = req.name;
-        let _ = self
-            .wait_for_match(
-                &mut rx,
-                move |e| matches!(e, CoreDebugEvent::SubSessionAttached(n, _) if n == &name_clone),
-            )
-            .await?;
+            },
+            move |e| matches!(e, CoreDebugEvent::SubSessionAttached(n, _) if n == &name),
+            DEFAULT_REQUEST_TIMEOUT,
+        )
+        .await?;
         Ok(Response::new(Empty {}))
     }
 
@@ -548,15 +769,183 @@ impl AetherDebug for AetherDebugService {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
-        let rx = self.session.subscribe();
+        if let Some(buffer) = &self.event_buffer {
+            return Ok(Response::new(Self::buffered_stream(buffer, u64::MAX)?));
+        }
+
+        let rx = self.session.subscribe_sequenced();
         let stream = BroadcastStream::new(rx);
 
         let output = stream.filter_map(|res| {
-            res.ok().and_then(|core_event| map_core_event_to_proto(core_event).map(Ok))
+            res.ok().and_then(|event| map_sequenced_event_to_proto(event).map(Ok))
         });
 
         Ok(Response::new(Box::pin(output)))
     }
+
+    async fn subscribe_events_from(
+        &self,
+        request: Request<SubscribeFromRequest>,
+    ) -> Result<Response<Self::SubscribeEventsFromStream>, Status> {
+        let buffer = self
+            .event_buffer
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Event buffering is disabled"))?;
+        Ok(Response::new(Self::buffered_stream(buffer, request.into_inner().seq)?))
+    }
+
+    async fn subscribe_events_filtered(
+        &self,
+        request: Request<EventFilter>,
+    ) -> Result<Response<Self::SubscribeEventsFilteredStream>, Status> {
+        let kinds = request.into_inner().kinds;
+        if let Some(unknown) = kinds.iter().find(|k| !EVENT_KINDS.contains(&k.as_str())) {
+            return Err(Status::invalid_argument(format!("Unknown event kind: {unknown}")));
+        }
+        let events = self.subscribe_events(Request::new(Empty {})).await?.into_inner();
+        let filtered = events.filter(move |item| match item {
+            Ok(DebugEvent { event: Some(event), .. }) => {
+                kinds.is_empty() || kinds.iter().any(|k| k == event_kind(event))
+            }
+            Ok(_) => false,
+            // Stream errors always reach the client.
+            Err(_) => true,
+        });
+        Ok(Response::new(Box::pin(filtered)))
+    }
+}
+
+/// Every kind accepted in an `EventFilter`.
+pub const EVENT_KINDS: [&str; 19] = [
+    "halted",
+    "resumed",
+    "memory",
+    "register",
+    "tasks",
+    "task_switch",
+    "plot",
+    "rtt",
+    "breakpoint",
+    "variable",
+    "semihosting",
+    "itm",
+    "probes",
+    "attached",
+    "status",
+    "sub_session_attached",
+    "parity_diverged",
+    "error",
+    "reset",
+];
+
+/// The `EventFilter` kind of `event`: the name of its `DebugEvent.event` field.
+#[must_use]
+pub const fn event_kind(event: &proto::debug_event::Event) -> &'static str {
+    use proto::debug_event::Event;
+    match event {
+        Event::Halted(_) => "halted",
+        Event::Resumed(_) => "resumed",
+        Event::Memory(_) => "memory",
+        Event::Register(_) => "register",
+        Event::Tasks(_) => "tasks",
+        Event::TaskSwitch(_) => "task_switch",
+        Event::Plot(_) => "plot",
+        Event::Rtt(_) => "rtt",
+        Event::Breakpoint(_) => "breakpoint",
+        Event::Variable(_) => "variable",
+        Event::Semihosting(_) => "semihosting",
+        Event::Itm(_) => "itm",
+        Event::Probes(_) => "probes",
+        Event::Attached(_) => "attached",
+        Event::Status(_) => "status",
+        Event::SubSessionAttached(_) => "sub_session_attached",
+        Event::ParityDiverged(_) => "parity_diverged",
+        Event::Error(_) => "error",
+        Event::Reset(_) => "reset",
+    }
+}
+
+/// Parses the reset kind of a `ResetRequest`; unset means the target's default reset.
+/// Returns `None` for an unknown kind.
+#[must_use]
+pub fn parse_reset_kind(kind: Option<&str>) -> Option<ResetKind> {
+    match kind {
+        None | Some("default") => Some(ResetKind::Default),
+        Some("system") => Some(ResetKind::System),
+        Some("core") => Some(ResetKind::Core),
+        Some(_) => None,
+    }
+}
+
+/// The `ResetRequest.kind` name of `kind`.
+#[must_use]
+pub const fn reset_kind_name(kind: ResetKind) -> &'static str {
+    match kind {
+        ResetKind::Default => "default",
+        ResetKind::System => "system",
+        ResetKind::Core => "core",
+    }
+}
+
+/// The `TargetInfo.detection_method` name of `method`.
+#[must_use]
+pub const fn detection_method_name(method: aether_core::DetectionMethod) -> &'static str {
+    match method {
+        aether_core::DetectionMethod::Requested => "requested",
+        aether_core::DetectionMethod::Auto => "auto",
+        aether_core::DetectionMethod::Heuristic => "heuristic",
+    }
+}
+
+/// Parses a `TargetInfo.detection_method`; unknown or empty means the chip was requested.
+#[must_use]
+pub fn parse_detection_method(name: &str) -> aether_core::DetectionMethod {
+    match name {
+        "auto" => aether_core::DetectionMethod::Auto,
+        "heuristic" => aether_core::DetectionMethod::Heuristic,
+        _ => aether_core::DetectionMethod::Requested,
+    }
+}
+
+/// Parses an `ItmConfig.swo_mode`; empty means UART.
+#[must_use]
+pub fn parse_swo_mode(name: &str) -> Option<aether_core::itm::SwoMode> {
+    match name {
+        "" | "uart" => Some(aether_core::itm::SwoMode::Uart),
+        "manchester" => Some(aether_core::itm::SwoMode::Manchester),
+        _ => None,
+    }
+}
+
+/// Parses an `ItmConfig.sink`; empty means SWO.
+#[must_use]
+pub fn parse_itm_sink(name: &str) -> Option<aether_core::itm::ItmSink> {
+    match name {
+        "" | "swo" => Some(aether_core::itm::ItmSink::Swo),
+        "trace_memory" => Some(aether_core::itm::ItmSink::TraceMemory),
+        _ => None,
+    }
+}
+
+/// Maps a core RTT channel description to its protocol buffer form.
+#[must_use]
+pub fn map_rtt_channel_to_proto(info: &aether_core::rtt::RttChannelInfo) -> proto::RttChannelInfo {
+    proto::RttChannelInfo {
+        number: u32::try_from(info.number).unwrap_or(u32::MAX),
+        name: info.name.clone(),
+        buffer_size: u32::try_from(info.buffer_size).unwrap_or(u32::MAX),
+    }
+}
+
+/// The RTT data in `event` if it came from up channel `channel`.
+#[must_use]
+pub fn rtt_for_channel(event: CoreDebugEvent, channel: u32) -> Option<RttEvent> {
+    match event {
+        CoreDebugEvent::RttData(ch, data) if u32::try_from(ch) == Ok(channel) => {
+            Some(RttEvent { channel, data })
+        }
+        _ => None,
+    }
 }
 
 /// Maps a core debug event to a protocol buffer debug event.
@@ -566,18 +955,22 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
     match event {
         CoreDebugEvent::Halted { pc } => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Halted(proto::HaltedEvent { pc })),
+            seq: 0,
         }),
         CoreDebugEvent::Resumed => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Resumed(proto::ResumedEvent {})),
+            seq: 0,
         }),
         CoreDebugEvent::MemoryData(address, data) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Memory(proto::MemoryEvent { address, data })),
+            seq: 0,
         }),
         CoreDebugEvent::RegisterValue(address, value) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Register(proto::RegisterEvent {
                 register: u32::from(address),
                 value,
             })),
+            seq: 0,
         }),
         CoreDebugEvent::Tasks(tasks) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Tasks(proto::TasksEvent {
@@ -594,6 +987,7 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                     })
                     .collect(),
             })),
+            seq: 0,
         }),
         CoreDebugEvent::TaskSwitch { from, to, timestamp } => Some(DebugEvent {
             event: Some(proto::debug_event::Event::TaskSwitch(proto::TaskSwitchEvent {
@@ -601,6 +995,7 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                 to,
                 timestamp,
             })),
+            seq: 0,
         }),
         CoreDebugEvent::PlotData { name, timestamp, value } => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Plot(proto::PlotEvent {
@@ -608,19 +1003,23 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                 timestamp,
                 value,
             })),
+            seq: 0,
         }),
         CoreDebugEvent::RttData(channel, data) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Rtt(proto::RttEvent {
                 channel: u32::try_from(channel).unwrap_or(0),
                 data,
             })),
+            seq: 0,
         }),
         CoreDebugEvent::SemihostingOutput(output) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Semihosting(SemihostingEvent { output })),
+            seq: 0,
+        }),
+        CoreDebugEvent::ItmPacket(data) => Some(DebugEvent {
+            event: Some(proto::debug_event::Event::Itm(ItmEvent { data })),
+            seq: 0,
         }),
-        CoreDebugEvent::ItmPacket(data) => {
-            Some(DebugEvent { event: Some(proto::debug_event::Event::Itm(ItmEvent { data })) })
-        }
         CoreDebugEvent::Probes(probes) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Probes(proto::ProbeList {
                 probes: probes
@@ -633,6 +1032,7 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                     })
                     .collect(),
             })),
+            seq: 0,
         }),
         CoreDebugEvent::Attached(info) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Attached(proto::TargetInfo {
@@ -640,10 +1040,15 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                 flash_size: info.flash_size,
                 ram_size: info.ram_size,
                 architecture: info.architecture,
+                big_endian: info.endian == aether_core::Endian::Big,
+                requested: info.requested,
+                detection_method: detection_method_name(info.detection_method).to_string(),
             })),
+            seq: 0,
         }),
         CoreDebugEvent::VariableResolved(info) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Variable(map_type_info_to_proto(&info))),
+            seq: 0,
         }),
         CoreDebugEvent::Status(s) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::Status(proto::StatusResponse {
@@ -651,6 +1056,7 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                 pc: 0,
                 core_status: format!("{s:?}"),
             })),
+            seq: 0,
         }),
         CoreDebugEvent::SubSessionAttached(name, info) => Some(DebugEvent {
             event: Some(proto::debug_event::Event::SubSessionAttached(
@@ -661,17 +1067,44 @@ pub fn map_core_event_to_proto(event: CoreDebugEvent) -> Option<DebugEvent> {
                         flash_size: info.flash_size,
                         ram_size: info.ram_size,
                         architecture: info.architecture,
+                        big_endian: info.endian == aether_core::Endian::Big,
+                        requested: info.requested,
+                        detection_method: detection_method_name(info.detection_method).to_string(),
                     }),
                 },
             )),
+            seq: 0,
         }),
         CoreDebugEvent::ParityDiverged { location, master_val, slave_val, info } => {
             Some(DebugEvent {
                 event: Some(proto::debug_event::Event::ParityDiverged(
                     proto::ParityDivergedEvent { location, master_val, slave_val, info },
                 )),
+                seq: 0,
             })
         }
+        CoreDebugEvent::ResetPerformed(kind) => Some(DebugEvent {
+            event: Some(proto::debug_event::Event::Reset(proto::ResetEvent {
+                kind: reset_kind_name(kind).to_string(),
+            })),
+            seq: 0,
+        }),
+        CoreDebugEvent::Error(message) => Some(DebugEvent {
+            event: Some(proto::debug_event::Event::Error(proto::ErrorEvent {
+                operation: String::new(),
+                message,
+                guidance: String::new(),
+            })),
+            seq: 0,
+        }),
+        CoreDebugEvent::OperationFailed { operation, error } => Some(DebugEvent {
+            event: Some(proto::debug_event::Event::Error(proto::ErrorEvent {
+                operation,
+                message: error.to_string(),
+                guidance: error.guidance().unwrap_or_default().to_string(),
+            })),
+            seq: 0,
+        }),
         _ => None,
     }
 }
@@ -691,6 +1124,20 @@ fn map_type_info_to_proto(info: &aether_core::symbols::TypeInfo) -> proto::Varia
     }
 }
 
+/// Maps a sequenced core event to a protocol buffer debug event carrying its sequence number.
+#[must_use]
+pub fn map_sequenced_event_to_proto(event: SequencedEvent) -> Option<DebugEvent> {
+    map_core_event_to_proto(event.event)
+        .map(|proto_event| DebugEvent { seq: event.seq, ..proto_event })
+}
+
+/// Maps a protocol buffer debug event back to a core event with its sequence number.
+#[must_use]
+pub fn map_proto_event_to_sequenced(event: DebugEvent) -> Option<SequencedEvent> {
+    let seq = event.seq;
+    map_proto_event_to_core(event).map(|event| SequencedEvent { seq, event })
+}
+
 /// Maps a protocol buffer debug event back to a core debug event.
 #[must_use]
 pub fn map_proto_event_to_core(event: DebugEvent) -> Option<CoreDebugEvent> {
@@ -741,6 +1188,16 @@ pub fn map_proto_event_to_core(event: DebugEvent) -> Option<CoreDebugEvent> {
             Some(CoreDebugEvent::SemihostingOutput(s.output))
         }
         proto::debug_event::Event::Itm(i) => Some(CoreDebugEvent::ItmPacket(i.data)),
+        proto::debug_event::Event::Reset(r) => {
+            parse_reset_kind(Some(r.kind.as_str())).map(CoreDebugEvent::ResetPerformed)
+        }
+        proto::debug_event::Event::Error(e) if e.operation.is_empty() => {
+            Some(CoreDebugEvent::Error(e.message))
+        }
+        proto::debug_event::Event::Error(e) => Some(CoreDebugEvent::OperationFailed {
+            operation: e.operation,
+            error: DebugError::Other(e.message),
+        }),
         proto::debug_event::Event::Probes(p) => Some(CoreDebugEvent::Probes(
             p.probes
                 .into_iter()
@@ -759,23 +1216,118 @@ pub fn map_proto_event_to_core(event: DebugEvent) -> Option<CoreDebugEvent> {
                 flash_size: i.flash_size,
                 ram_size: i.ram_size,
                 architecture: i.architecture,
+                endian: if i.big_endian {
+                    aether_core::Endian::Big
+                } else {
+                    aether_core::Endian::Little
+                },
+                requested: i.requested,
+                detection_method: parse_detection_method(&i.detection_method),
             }))
         }
     }
 }
 
+/// Maps disassembled instructions to a response with both structured and formatted lines.
+#[must_use]
+pub fn map_disassembly_to_proto(lines: &[aether_core::disasm::InstructionInfo]) -> DisasmResponse {
+    let instructions = lines
+        .iter()
+        .map(|l| format!("0x{:08X}:  {}  {}", l.address, l.mnemonic, l.op_str))
+        .collect();
+    let decoded = lines
+        .iter()
+        .map(|l| proto::Instruction {
+            address: l.address,
+            bytes: l.bytes.clone(),
+            mnemonic: l.mnemonic.clone(),
+            op_str: l.op_str.clone(),
+            size: u32::try_from(l.bytes.len()).unwrap_or(u32::MAX),
+            is_branch: l.is_branch,
+            is_call: l.is_call,
+            is_ret: l.is_ret,
+            branch_target: l.branch_target,
+        })
+        .collect();
+    DisasmResponse { instructions, decoded }
+}
+
+/// Maps a structured instruction from a disassembly response back to the core type.
+#[must_use]
+pub fn map_proto_instruction_to_core(
+    insn: proto::Instruction,
+) -> aether_core::disasm::InstructionInfo {
+    aether_core::disasm::InstructionInfo {
+        address: insn.address,
+        mnemonic: insn.mnemonic,
+        op_str: insn.op_str,
+        bytes: insn.bytes,
+        is_branch: insn.is_branch,
+        is_call: insn.is_call,
+        is_ret: insn.is_ret,
+        branch_target: insn.branch_target,
+    }
+}
+
+/// Maps core capabilities to their protocol buffer representation.
+#[must_use]
+pub const fn map_capabilities_to_proto(caps: aether_core::Capabilities) -> proto::Capabilities {
+    proto::Capabilities {
+        write_memory: caps.write_memory,
+        write_register: caps.write_register,
+        breakpoints: caps.breakpoints,
+        peripherals: caps.peripherals,
+        rtt_write: caps.rtt_write,
+        tasks: caps.tasks,
+        flash: caps.flash,
+        disassemble: caps.disassemble,
+    }
+}
+
+/// Maps the capabilities reported by a remote agent back to core capabilities.
+#[must_use]
+pub const fn map_proto_capabilities_to_core(
+    caps: &proto::Capabilities,
+) -> aether_core::Capabilities {
+    aether_core::Capabilities {
+        write_memory: caps.write_memory,
+        write_register: caps.write_register,
+        breakpoints: caps.breakpoints,
+        peripherals: caps.peripherals,
+        rtt_write: caps.rtt_write,
+        tasks: caps.tasks,
+        flash: caps.flash,
+        disassemble: caps.disassemble,
+    }
+}
+
 /// Runs the gRPC server on the specified host and port.
 pub async fn run_server(
     session: Arc<SessionHandle>,
     host: &str,
     port: u16,
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_server_with_shutdown(session, host, port, std::future::pending()).await
+}
+
+/// Like [`run_server`], but stops serving and releases the port once `shutdown` resolves.
+pub async fn run_server_with_shutdown(
+    session: Arc<SessionHandle>,
+    host: &str,
+    port: u16,
+    shutdown: impl std::future::Future<Output = ()> + Send,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let addr = format!("{host}:{port}").parse()?;
-    let service = AetherDebugService::new(session);
+    let buffer =
+        event_buffer::spawn_event_buffer(&session, event_buffer::DEFAULT_EVENT_BUFFER_CAPACITY);
+    let service = AetherDebugService::new(session).with_event_buffer(buffer);
 
     println!("Agent API Server listening on {addr}");
 
-    Server::builder().add_service(AetherDebugServer::new(service)).serve(addr).await?;
+    Server::builder()
+        .add_service(AetherDebugServer::new(service))
+        .serve_with_shutdown(addr, shutdown)
+        .await?;
 
     Ok(())
 }
@@ -823,6 +1375,39 @@ mod tests {
         assert_eq!(proto_frame.line.unwrap(), 42);
     }
 
+    #[test]
+    fn test_disassembly_mapping_is_structured() {
+        let lines = vec![aether_core::disasm::InstructionInfo {
+            address: 0x0800_0100,
+            mnemonic: "bl".to_string(),
+            op_str: "#0x8000200".to_string(),
+            bytes: vec![0x00, 0xf0, 0x7e, 0xf8],
+            is_branch: true,
+            is_call: true,
+            is_ret: false,
+            branch_target: Some(0x0800_0200),
+        }];
+        let resp = map_disassembly_to_proto(&lines);
+
+        assert_eq!(resp.instructions, vec!["0x08000100:  bl  #0x8000200"]);
+        let insn = &resp.decoded[0];
+        assert_eq!(insn.address, 0x0800_0100);
+        assert_eq!(insn.bytes, vec![0x00, 0xf0, 0x7e, 0xf8]);
+        assert_eq!(insn.mnemonic, "bl");
+        assert_eq!(insn.op_str, "#0x8000200");
+        assert_eq!(insn.size, 4);
+        assert!(insn.is_call);
+        assert_eq!(insn.branch_target, Some(0x0800_0200));
+
+        let back = map_proto_instruction_to_core(resp.decoded[0].clone());
+        assert_eq!(back.address, lines[0].address);
+        assert_eq!(back.bytes, lines[0].bytes);
+        assert_eq!(back.mnemonic, lines[0].mnemonic);
+        assert_eq!(back.op_str, lines[0].op_str);
+        assert!(back.is_branch && back.is_call && !back.is_ret);
+        assert_eq!(back.branch_target, lines[0].branch_target);
+    }
+
     #[test]
     fn test_event_mapping_resumed() {
         let core_event = CoreDebugEvent::Resumed;
@@ -865,4 +1450,273 @@ mod tests {
             panic!("Wrong event type");
         }
     }
+
+    #[test]
+    fn test_capabilities_round_trip() {
+        let proto_caps = map_capabilities_to_proto(AGENT_CAPABILITIES);
+        // Stubbed RPCs must not be advertised.
+        assert!(!proto_caps.write_memory);
+        assert!(!proto_caps.breakpoints);
+        assert!(proto_caps.flash);
+        assert_eq!(map_proto_capabilities_to_core(&proto_caps), AGENT_CAPABILITIES);
+    }
+
+    #[tokio::test]
+    async fn test_attach_rtt_returns_channels() {
+        let (handle, cmd_rx, event_tx) = SessionHandle::new_test();
+        let service = AetherDebugService::new(Arc::new(handle));
+
+        // Stand-in for the session thread: answer the attach with a discovery result.
+        let session = std::thread::spawn(move || {
+            let cmd = cmd_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(matches!(cmd, DebugCommand::RttAttach), "unexpected {cmd:?}");
+            let channel = |number, name: &str, buffer_size| aether_core::rtt::RttChannelInfo {
+                number,
+                name: Some(name.to_string()),
+                buffer_size,
+            };
+            event_tx
+                .send(CoreDebugEvent::RttChannels {
+                    up_channels: vec![channel(0, "Terminal", 1024), channel(1, "Log", 512)],
+                    down_channels: vec![channel(0, "Terminal", 16)],
+                })
+                .unwrap();
+        });
+
+        let channels = service
+            .attach_rtt(Request::new(RttAttachRequest { address: None }))
+            .await
+            .unwrap()
+            .into_inner();
+        session.join().unwrap();
+
+        let names: Vec<_> =
+            channels.up_channels.iter().map(|c| (c.number, c.name.as_deref())).collect();
+        assert_eq!(names, vec![(0, Some("Terminal")), (1, Some("Log"))]);
+        assert_eq!(channels.up_channels[1].buffer_size, 512);
+        assert_eq!(channels.down_channels.len(), 1);
+        assert_eq!(channels.down_channels[0].buffer_size, 16);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_symbol_resolves_address() {
+        let (handle, cmd_rx, event_tx) = SessionHandle::new_test();
+        let service = AetherDebugService::new(Arc::new(handle));
+
+        let mut symbols = aether_core::SymbolManager::new();
+        symbols
+            .load_elf(std::path::Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../aether-core/tests/fixtures/c_types.elf"
+            )))
+            .unwrap();
+        let main = symbols.lookup_symbol("main").unwrap();
+
+        // Stand-in for the session thread: resolve the name like the session
+        // loop does and answer with one instruction at the resolved address.
+        let session = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let cmd = cmd_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+                let DebugCommand::DisassembleSymbol(name) = cmd else {
+                    panic!("unexpected {cmd:?}");
+                };
+                match aether_core::session::resolve_disassemble_symbol(&symbols, &name) {
+                    Ok(DebugCommand::DisassembleFunction(address)) => {
+                        let insn = aether_core::disasm::InstructionInfo {
+                            address,
+                            mnemonic: "push".to_string(),
+                            op_str: "rbp".to_string(),
+                            bytes: vec![0x55],
+                            is_branch: false,
+                            is_call: false,
+                            is_ret: false,
+                            branch_target: None,
+                        };
+                        event_tx.send(CoreDebugEvent::Disassembly(vec![insn])).unwrap();
+                    }
+                    Ok(other) => panic!("unexpected {other:?}"),
+                    Err(e) => {
+                        event_tx
+                            .send(CoreDebugEvent::operation_failed("Disassemble", &e.into()))
+                            .unwrap();
+                    }
+                }
+            }
+        });
+
+        let resp = service
+            .disassemble_symbol(Request::new(DisasmSymbolRequest { name: "main".to_string() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.decoded[0].address, main);
+
+        let err = service
+            .disassemble_symbol(Request::new(DisasmSymbolRequest { name: "nope".to_string() }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        session.join().unwrap();
+    }
+
+    #[cfg(feature = "hardware")]
+    #[tokio::test]
+    async fn test_set_breakpoint_at_source_resolves_line() {
+        let fake = aether_core::fake::FakeSession::new();
+        let handle = Arc::new(SessionHandle::with_target(fake.clone()));
+        let service = AetherDebugService::new(handle.clone());
+
+        let elf = std::path::Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../aether-core/tests/fixtures/c_types.elf"
+        ));
+        let mut symbols = aether_core::SymbolManager::new();
+        symbols.load_elf(elf).unwrap();
+        // The body of `main` in c_types.c.
+        let expected = symbols.get_address(std::path::Path::new("c_types.c"), 28).unwrap();
+        handle.send(DebugCommand::LoadSymbols(elf.to_path_buf())).unwrap();
+
+        let resp = service
+            .set_breakpoint_at_source(Request::new(SourceBreakpointRequest {
+                file: "fixtures/c_types.c".to_string(),
+                line: 28,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.address, expected);
+        assert_eq!(fake.breakpoints(), vec![expected], "the breakpoint is armed");
+
+        // Line 1 is a comment.
+        let err = service
+            .set_breakpoint_at_source(Request::new(SourceBreakpointRequest {
+                file: "c_types.c".to_string(),
+                line: 1,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        assert_eq!(fake.breakpoints(), vec![expected]);
+
+        handle.send(DebugCommand::Exit).unwrap();
+    }
+
+    #[test]
+    fn test_parse_reset_kind() {
+        assert_eq!(parse_reset_kind(None), Some(ResetKind::Default));
+        assert_eq!(parse_reset_kind(Some("system")), Some(ResetKind::System));
+        assert_eq!(parse_reset_kind(Some("core")), Some(ResetKind::Core));
+        assert_eq!(parse_reset_kind(Some("nrst")), None);
+    }
+
+    #[test]
+    fn test_reset_event_round_trip() {
+        for kind in ResetKind::ALL {
+            let event = map_core_event_to_proto(CoreDebugEvent::ResetPerformed(kind)).unwrap();
+            assert_eq!(event_kind(event.event.as_ref().unwrap()), "reset");
+            let back = map_proto_event_to_core(event).unwrap();
+            assert!(matches!(back, CoreDebugEvent::ResetPerformed(k) if k == kind), "{back:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_rtt_filters_channel() {
+        let (handle, _cmd_rx, event_tx) = SessionHandle::new_test();
+        let service = AetherDebugService::new(Arc::new(handle));
+        let mut stream = service
+            .stream_rtt(Request::new(RttStreamRequest { channel: 1 }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        event_tx.send(CoreDebugEvent::RttData(0, b"boot".to_vec())).unwrap();
+        event_tx.send(CoreDebugEvent::Halted { pc: 0x100 }).unwrap();
+        event_tx.send(CoreDebugEvent::RttData(1, b"log".to_vec())).unwrap();
+        event_tx.send(CoreDebugEvent::RttData(2, b"trace".to_vec())).unwrap();
+        event_tx.send(CoreDebugEvent::RttData(1, b"more".to_vec())).unwrap();
+
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!((first.channel, first.data), (1, b"log".to_vec()));
+        let second = stream.next().await.unwrap().unwrap();
+        assert_eq!((second.channel, second.data), (1, b"more".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscription_yields_requested_kinds() {
+        let (handle, _cmd_rx, event_tx) = SessionHandle::new_test();
+        let service = AetherDebugService::new(Arc::new(handle));
+        let filter = EventFilter {
+            kinds: vec!["halted".to_string(), "status".to_string(), "error".to_string()],
+        };
+        let mut stream =
+            service.subscribe_events_filtered(Request::new(filter)).await.unwrap().into_inner();
+
+        event_tx.send(CoreDebugEvent::RttData(0, b"boot".to_vec())).unwrap();
+        event_tx.send(CoreDebugEvent::Halted { pc: 0x100 }).unwrap();
+        event_tx
+            .send(CoreDebugEvent::PlotData { name: "adc".to_string(), timestamp: 0.0, value: 1.0 })
+            .unwrap();
+        event_tx.send(CoreDebugEvent::Status(aether_core::CoreStatus::Running)).unwrap();
+        event_tx.send(CoreDebugEvent::Resumed).unwrap();
+        event_tx
+            .send(CoreDebugEvent::operation_failed(
+                "Checksum at 0x08000000",
+                &anyhow::anyhow!("Core is not halted"),
+            ))
+            .unwrap();
+        event_tx.send(CoreDebugEvent::Halted { pc: 0x200 }).unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..4 {
+            events.push(stream.next().await.unwrap().unwrap().event.unwrap());
+        }
+        let kinds: Vec<&str> = events.iter().map(event_kind).collect();
+        assert_eq!(kinds, vec!["halted", "status", "error", "halted"]);
+        let proto::debug_event::Event::Error(error) = &events[2] else { unreachable!() };
+        assert_eq!(error.operation, "Checksum at 0x08000000");
+        assert!(error.guidance.starts_with("Halt the core"));
+
+        let unknown = EventFilter { kinds: vec!["rtt_data".to_string()] };
+        let err = service.subscribe_events_filtered(Request::new(unknown)).await.err().unwrap();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_event_kinds_cover_every_event() {
+        use proto::debug_event::Event;
+        let events = [
+            Event::Halted(Default::default()),
+            Event::Resumed(Default::default()),
+            Event::Memory(Default::default()),
+            Event::Register(Default::default()),
+            Event::Tasks(Default::default()),
+            Event::TaskSwitch(Default::default()),
+            Event::Plot(Default::default()),
+            Event::Rtt(Default::default()),
+            Event::Breakpoint(Default::default()),
+            Event::Variable(Default::default()),
+            Event::Semihosting(Default::default()),
+            Event::Itm(Default::default()),
+            Event::Probes(Default::default()),
+            Event::Attached(Default::default()),
+            Event::Status(Default::default()),
+            Event::SubSessionAttached(Default::default()),
+            Event::ParityDiverged(Default::default()),
+            Event::Error(Default::default()),
+            Event::Reset(Default::default()),
+        ];
+        let kinds: Vec<&str> = events.iter().map(event_kind).collect();
+        assert_eq!(kinds, EVENT_KINDS);
+    }
+
+    #[test]
+    fn test_sequence_number_round_trip() {
+        let event = SequencedEvent { seq: 42, event: CoreDebugEvent::Halted { pc: 0x100 } };
+        let proto_event = map_sequenced_event_to_proto(event).unwrap();
+        assert_eq!(proto_event.seq, 42);
+
+        let back = map_proto_event_to_sequenced(proto_event).unwrap();
+        assert_eq!(back.seq, 42);
+        assert!(matches!(back.event, CoreDebugEvent::Halted { pc: 0x100 }));
+    }
 }
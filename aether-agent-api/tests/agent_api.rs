@@ -4,12 +4,14 @@
 #![allow(clippy::unreadable_literal)]
 #![allow(clippy::uninlined_format_args)]
 #![allow(clippy::significant_drop_tightening)]
+use aether_agent_api::client::AetherClient;
 use aether_agent_api::proto::aether_debug_client::AetherDebugClient;
 use aether_agent_api::proto::Empty;
 use aether_agent_api::run_server;
 use aether_core::{DebugCommand, DebugEvent, SessionHandle};
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
+use tokio_stream::StreamExt;
 
 #[tokio::test]
 async fn test_agent_api_basic_ops() {
@@ -75,3 +77,61 @@ async fn test_agent_api_basic_ops() {
         _ => panic!("Expected Halted event, got {:?}", event),
     }
 }
+
+#[tokio::test]
+async fn test_typed_client_against_in_process_server() {
+    let (handle, cmd_rx, event_tx) = SessionHandle::new_test();
+    let handle = Arc::new(handle);
+
+    let port = 50061;
+    let server_handle = handle.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_server(server_handle, "127.0.0.1", port).await {
+            eprintln!("Test server error during run: {:?}", e);
+        }
+    });
+
+    // Stand in for the core: answer register reads and report halts.
+    std::thread::spawn(move || {
+        for cmd in cmd_rx.iter() {
+            let event = match cmd {
+                DebugCommand::ReadRegister(n) => {
+                    DebugEvent::RegisterValue(n, 0x0800_0000 + u64::from(n))
+                }
+                DebugCommand::Halt => DebugEvent::Halted { pc: 0x0800_0100 },
+                _ => continue,
+            };
+            let _ = event_tx.send(event);
+        }
+    });
+
+    let mut started = false;
+    for _ in 0..300 {
+        if std::net::TcpStream::connect(format!("127.0.0.1:{port}")).is_ok() {
+            started = true;
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(started, "Server did not start on port {port}");
+
+    let mut client = tokio::time::timeout(
+        Duration::from_secs(5),
+        AetherClient::connect(format!("http://127.0.0.1:{port}")),
+    )
+    .await
+    .expect("Connection timed out")
+    .expect("Failed to connect");
+
+    assert_eq!(client.read_register(15).await.expect("ReadRegister failed"), 0x0800_000F);
+
+    let mut events = std::pin::pin!(client.events().await.expect("Subscribe failed"));
+    client.halt().await.expect("Halt failed");
+
+    let event = tokio::time::timeout(Duration::from_secs(2), events.next())
+        .await
+        .expect("Event receipt timed out")
+        .expect("Stream ended")
+        .expect("Stream error");
+    assert!(matches!(event, DebugEvent::Halted { pc: 0x0800_0100 }), "got {:?}", event);
+}
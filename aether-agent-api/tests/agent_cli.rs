@@ -0,0 +1,73 @@
+//! Tests for the headless agent entry point.
+
+use aether_agent_api::agent::{prepare, AgentArgs, AgentAttach, ProbeSelector};
+use aether_core::{SessionHandle, WireProtocol};
+use clap::Parser;
+use std::cell::RefCell;
+
+#[test]
+fn test_agent_args_build_attach_command() {
+    let seen: RefCell<Option<AgentAttach>> = RefCell::new(None);
+    let args = AgentArgs::try_parse_from([
+        "aether-agent",
+        "--serial",
+        "066DFF555",
+        "--chip",
+        "STM32F407VGTx",
+        "--protocol",
+        "SWD",
+        "--speed",
+        "4000",
+        "--under-reset",
+        "--bind",
+        "127.0.0.1:50070",
+    ])
+    .unwrap();
+    let (_session, host, port) = prepare(&args, |attach| {
+        *seen.borrow_mut() = Some(attach.clone());
+        Ok(SessionHandle::new_test().0)
+    })
+    .unwrap();
+
+    assert_eq!(host, "127.0.0.1");
+    assert_eq!(port, 50070);
+    assert_eq!(
+        seen.into_inner().unwrap(),
+        AgentAttach {
+            probe: ProbeSelector::Serial("066DFF555".to_string()),
+            chip: "STM32F407VGTx".to_string(),
+            protocol: Some(WireProtocol::Swd),
+            under_reset: true,
+            speed_khz: Some(4000),
+        }
+    );
+}
+
+#[test]
+fn test_agent_args_defaults_and_errors() {
+    let defaults = AgentArgs::try_parse_from(["aether-agent"]).unwrap();
+    let (_session, host, port) = prepare(&defaults, |attach| {
+        assert_eq!(attach.probe, ProbeSelector::Index(0));
+        assert_eq!(attach.chip, "auto");
+        assert_eq!(attach.protocol, None);
+        Ok(SessionHandle::new_test().0)
+    })
+    .unwrap();
+    assert_eq!((host.as_str(), port), ("0.0.0.0", 50051));
+
+    let spi = AgentArgs::try_parse_from(["aether-agent", "--protocol", "spi"]).unwrap();
+    let err = prepare(&spi, |_| Ok(SessionHandle::new_test().0)).err().unwrap();
+    assert!(err.to_string().contains("Unknown protocol"));
+
+    let err =
+        prepare(&defaults, |_| Err(anyhow::anyhow!("Probe index out of range"))).err().unwrap();
+    assert_eq!(err.to_string(), "Probe index out of range");
+
+    // Help is not an error, and a serial cannot be combined with an index
+    let help = AgentArgs::try_parse_from(["aether-agent", "--help"]).unwrap_err();
+    assert_eq!(help.kind(), clap::error::ErrorKind::DisplayHelp);
+    assert_eq!(help.exit_code(), 0);
+    assert!(
+        AgentArgs::try_parse_from(["aether-agent", "--serial", "A", "--probe-index", "1"]).is_err()
+    );
+}
@@ -0,0 +1,175 @@
+//! Command log for session replay.
+//!
+//! Records the commands sent to a session so a bug report can carry the exact
+//! sequence that triggered it. Host file paths are reduced to their file names
+//! when recorded, as the directories only exist on the reporter's machine;
+//! [`CommandLog::rebase`] points them at local copies before replaying.
+
+use crate::session::{DebugCommand, SessionHandle};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLogEntry {
+    /// Seconds since the log was enabled.
+    pub timestamp: f64,
+    pub command: DebugCommand,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandLog {
+    pub entries: Vec<CommandLogEntry>,
+}
+
+impl CommandLog {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Resolve the recorded file names against `dir`.
+    pub fn rebase(&mut self, dir: &Path) {
+        for entry in &mut self.entries {
+            if let Some(path) = host_path(&mut entry.command) {
+                *path = dir.join(&*path);
+            }
+        }
+    }
+}
+
+/// The host file path a command carries, if any. Source breakpoint paths and a
+/// source mapping's `from` are left alone: they name files as recorded in the
+/// ELF's debug info, not on the host.
+fn host_path(cmd: &mut DebugCommand) -> Option<&mut PathBuf> {
+    match cmd {
+        DebugCommand::LoadSvd(path)
+        | DebugCommand::LoadSymbols(path)
+        | DebugCommand::StartFlashing(path)
+        | DebugCommand::AddSourceMapping { to: path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+/// Appends commands to a [`CommandLog`] as they are sent.
+#[derive(Debug)]
+pub(crate) struct CommandRecorder {
+    start: Instant,
+    log: CommandLog,
+}
+
+impl CommandRecorder {
+    pub(crate) fn new() -> Self {
+        Self { start: Instant::now(), log: CommandLog::default() }
+    }
+
+    /// Record `cmd`. `Exit` is skipped so a replay leaves the session running,
+    /// and so are the polls a UI sends every frame or on every halt (status and
+    /// watch refreshes, re-reads of the block read last), which would otherwise
+    /// grow the log without bound.
+    pub(crate) fn record(&mut self, cmd: &DebugCommand) {
+        if matches!(
+            cmd,
+            DebugCommand::Exit | DebugCommand::PollStatus | DebugCommand::RefreshWatches(_)
+        ) {
+            return;
+        }
+        if let (
+            DebugCommand::ReadMemory(address, len),
+            Some(DebugCommand::ReadMemory(last_address, last_len)),
+        ) = (cmd, self.log.entries.last().map(|e| &e.command))
+        {
+            if address == last_address && len == last_len {
+                return;
+            }
+        }
+        let mut command = cmd.clone();
+        if let Some(path) = host_path(&mut command) {
+            if let Some(name) = path.file_name() {
+                *path = PathBuf::from(name);
+            }
+        }
+        let timestamp = self.start.elapsed().as_secs_f64();
+        self.log.entries.push(CommandLogEntry { timestamp, command });
+    }
+
+    pub(crate) fn log(&self) -> &CommandLog {
+        &self.log
+    }
+}
+
+/// Send every command in `log` to `handle`, in order. Returns the number sent.
+pub fn replay_log(handle: &SessionHandle, log: &CommandLog) -> Result<usize> {
+    for (i, entry) in log.entries.iter().enumerate() {
+        handle
+            .send(entry.command.clone())
+            .with_context(|| format!("Failed to replay command {}", i))?;
+    }
+    Ok(log.entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_log_record_and_replay() {
+        let (handle, cmd_rx, _evt_tx) = SessionHandle::new_test();
+        handle.enable_command_log();
+        handle.send(DebugCommand::LoadSymbols(PathBuf::from("/home/alice/fw/app.elf"))).unwrap();
+        handle
+            .send(DebugCommand::AddSourceMapping {
+                from: PathBuf::from("/build/fw"),
+                to: PathBuf::from("/home/alice/fw"),
+            })
+            .unwrap();
+        handle.send(DebugCommand::SetBreakpoint(0x0800_0100)).unwrap();
+        handle.send(DebugCommand::Resume).unwrap();
+        handle.send(DebugCommand::Exit).unwrap();
+        assert_eq!(cmd_rx.try_iter().count(), 5);
+
+        let log = handle.command_log().unwrap();
+        assert_eq!(log.entries.len(), 4);
+        assert!(log.entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        let json = log.to_json().unwrap();
+        assert!(!json.contains("alice"));
+
+        let mut log = CommandLog::from_json(&json).unwrap();
+        log.rebase(Path::new("/tmp/report"));
+
+        let (replay, replay_rx, _replay_evt) = SessionHandle::new_test();
+        assert_eq!(replay_log(&replay, &log).unwrap(), 4);
+        let replayed: Vec<_> = replay_rx.try_iter().collect();
+        assert!(matches!(
+            &replayed[0],
+            DebugCommand::LoadSymbols(p) if p == Path::new("/tmp/report/app.elf")
+        ));
+        assert!(matches!(
+            &replayed[1],
+            DebugCommand::AddSourceMapping { from, to }
+                if from == Path::new("/build/fw") && to == Path::new("/tmp/report/fw")
+        ));
+        assert!(matches!(replayed[2], DebugCommand::SetBreakpoint(0x0800_0100)));
+        assert!(matches!(replayed[3], DebugCommand::Resume));
+    }
+
+    #[test]
+    fn test_command_log_skips_polls() {
+        let mut recorder = CommandRecorder::new();
+        for _ in 0..100 {
+            recorder.record(&DebugCommand::PollStatus);
+            recorder.record(&DebugCommand::RefreshWatches(vec!["counter".to_string()]));
+            recorder.record(&DebugCommand::ReadMemory(0x2000_0000, 256));
+        }
+        recorder.record(&DebugCommand::ReadMemory(0x2000_0100, 256));
+
+        let commands: Vec<_> = recorder.log().entries.iter().map(|e| &e.command).collect();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(commands[0], DebugCommand::ReadMemory(0x2000_0000, 256)));
+        assert!(matches!(commands[1], DebugCommand::ReadMemory(0x2000_0100, 256)));
+    }
+}
@@ -0,0 +1,142 @@
+//! Persisted session settings.
+//!
+//! Kept as JSON in the user's config directory so they carry over between runs
+//! and firmware builds.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where a memory bookmark points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookmarkTarget {
+    Address(u64),
+    /// A symbol plus an offset, so the bookmark follows the symbol between builds.
+    Symbol {
+        name: String,
+        offset: u64,
+    },
+}
+
+impl BookmarkTarget {
+    /// Parse a memory view address: hex (`0x2000_0000`), or a symbol with an
+    /// optional offset (`rx_ring`, `status+0x10`).
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            return u64::from_str_radix(&hex.replace('_', ""), 16).ok().map(Self::Address);
+        }
+        let (name, offset) = match text.split_once('+') {
+            Some((name, offset)) => {
+                let offset = offset.trim();
+                let offset = match offset.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+                    None => offset.parse().ok()?,
+                };
+                (name.trim(), offset)
+            }
+            None => (text, 0),
+        };
+        let valid = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':');
+        valid.then(|| Self::Symbol { name: name.to_string(), offset })
+    }
+
+    /// The address this points to, looking symbols up with `lookup`.
+    pub fn resolve(&self, lookup: impl Fn(&str) -> Option<u64>) -> Option<u64> {
+        match self {
+            Self::Address(address) => Some(*address),
+            Self::Symbol { name, offset } => lookup(name).map(|address| address + offset),
+        }
+    }
+}
+
+impl std::fmt::Display for BookmarkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "0x{:08X}", address),
+            Self::Symbol { name, offset: 0 } => write!(f, "{}", name),
+            Self::Symbol { name, offset } => write!(f, "{}+0x{:X}", name, offset),
+        }
+    }
+}
+
+/// A named memory view.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryBookmark {
+    pub name: String,
+    pub target: BookmarkTarget,
+    /// Bytes shown.
+    pub window: usize,
+    /// Show 32-bit words rather than bytes.
+    pub words: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub bookmarks: Vec<MemoryBookmark>,
+    /// Periodically save RTT, plot and timeline data so it survives a crash.
+    #[serde(default)]
+    pub autosave: bool,
+}
+
+impl SessionConfig {
+    /// `$XDG_CONFIG_HOME/aether/session.json`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("aether").join("session.json"))
+    }
+
+    /// Load the config at `path`; a missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid session config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmark_target_resolution() {
+        let lookup = |name: &str| match name {
+            "rx_ring" => Some(0x2000_0100),
+            "app::STATUS" => Some(0x2000_0400),
+            _ => None,
+        };
+
+        let ring = BookmarkTarget::parse("rx_ring").unwrap();
+        assert_eq!(ring.resolve(lookup), Some(0x2000_0100));
+
+        let field = BookmarkTarget::parse("app::STATUS + 0x10").unwrap();
+        assert_eq!(field, BookmarkTarget::Symbol { name: "app::STATUS".to_string(), offset: 16 });
+        assert_eq!(field.resolve(lookup), Some(0x2000_0410));
+        assert_eq!(field.to_string(), "app::STATUS+0x10");
+
+        let fixed = BookmarkTarget::parse("0x2000_8000").unwrap();
+        assert_eq!(fixed, BookmarkTarget::Address(0x2000_8000));
+        assert_eq!(fixed.resolve(|_| None), Some(0x2000_8000));
+
+        // A symbol missing from the new build does not resolve.
+        assert_eq!(BookmarkTarget::parse("old_buffer").unwrap().resolve(lookup), None);
+        assert_eq!(BookmarkTarget::parse("12 apples"), None);
+        assert_eq!(BookmarkTarget::parse("0xZZ"), None);
+    }
+}
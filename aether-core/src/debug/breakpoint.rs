@@ -5,54 +5,151 @@ use crate::probe_rs::Core;
 use anyhow::{Context, Result};
 #[cfg(feature = "hardware")]
 use probe_rs::Core;
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+/// Hardware breakpoint operations needed by the manager.
+///
+/// Only the comparator writes go through this trait; which addresses are set
+/// and whether each is enabled is kept by [`BreakpointManager`].
+pub trait HwBreakpoints {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()>;
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()>;
+}
+
+#[cfg(feature = "hardware")]
+impl HwBreakpoints for Core<'_> {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        Ok(Core::set_hw_breakpoint(self, address)?)
+    }
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        Ok(Core::clear_hw_breakpoint(self, address)?)
+    }
+}
+
+#[cfg(not(feature = "hardware"))]
+impl HwBreakpoints for Core {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        Core::set_hw_breakpoint(self, address)
+    }
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        Core::clear_hw_breakpoint(self, address)
+    }
+}
 
 /// Manager for core breakpoints.
+///
+/// Breakpoints can be disabled without being removed; only enabled ones are
+/// armed in hardware.
 pub struct BreakpointManager {
-    breakpoints: HashSet<u64>,
+    /// Address -> enabled.
+    breakpoints: HashMap<u64, bool>,
 }
 
 impl BreakpointManager {
     pub fn new() -> Self {
-        Self { breakpoints: HashSet::new() }
+        Self { breakpoints: HashMap::new() }
     }
 
     /// Set a hardware breakpoint at the given address.
-    pub fn set_breakpoint(&mut self, core: &mut Core, address: u64) -> Result<()> {
+    pub fn set_breakpoint<C: HwBreakpoints + ?Sized>(
+        &mut self,
+        core: &mut C,
+        address: u64,
+    ) -> Result<()> {
         core.set_hw_breakpoint(address).context("Failed to set hardware breakpoint")?;
-        self.breakpoints.insert(address);
+        self.breakpoints.insert(address, true);
         Ok(())
     }
 
     /// Clear a hardware breakpoint at the given address.
-    pub fn clear_breakpoint(&mut self, core: &mut Core, address: u64) -> Result<()> {
-        core.clear_hw_breakpoint(address).context("Failed to clear hardware breakpoint")?;
+    pub fn clear_breakpoint<C: HwBreakpoints + ?Sized>(
+        &mut self,
+        core: &mut C,
+        address: u64,
+    ) -> Result<()> {
+        if self.is_enabled(address) {
+            core.clear_hw_breakpoint(address).context("Failed to clear hardware breakpoint")?;
+        }
         self.breakpoints.remove(&address);
         Ok(())
     }
 
+    /// Arm or disarm a breakpoint while keeping it in the list.
+    pub fn set_enabled<C: HwBreakpoints + ?Sized>(
+        &mut self,
+        core: &mut C,
+        address: u64,
+        enabled: bool,
+    ) -> Result<()> {
+        let current = self
+            .breakpoints
+            .get_mut(&address)
+            .with_context(|| format!("No breakpoint @ 0x{:08X}", address))?;
+        if *current != enabled {
+            if enabled {
+                core.set_hw_breakpoint(address).context("Failed to set hardware breakpoint")?;
+            } else {
+                core.clear_hw_breakpoint(address).context("Failed to clear hardware breakpoint")?;
+            }
+            *current = enabled;
+        }
+        Ok(())
+    }
+
+    /// Whether a breakpoint exists at `address` and is armed.
+    pub fn is_enabled(&self, address: u64) -> bool {
+        self.breakpoints.get(&address).copied().unwrap_or(false)
+    }
+
     /// Clear all breakpoints.
-    pub fn clear_all(&mut self, core: &mut Core) -> Result<()> {
-        for &addr in &self.breakpoints {
-            core.clear_hw_breakpoint(addr)
-                .context(format!("Failed to clear breakpoint @ 0x{:08X}", addr))?;
+    pub fn clear_all<C: HwBreakpoints + ?Sized>(&mut self, core: &mut C) -> Result<()> {
+        for (&addr, &enabled) in &self.breakpoints {
+            if enabled {
+                core.clear_hw_breakpoint(addr)
+                    .context(format!("Failed to clear breakpoint @ 0x{:08X}", addr))?;
+            }
         }
         self.breakpoints.clear();
         Ok(())
     }
 
     /// Toggle a hardware breakpoint at the given address.
-    pub fn toggle_breakpoint(&mut self, core: &mut Core, address: u64) -> Result<()> {
-        if self.breakpoints.contains(&address) {
+    pub fn toggle_breakpoint<C: HwBreakpoints + ?Sized>(
+        &mut self,
+        core: &mut C,
+        address: u64,
+    ) -> Result<()> {
+        if self.breakpoints.contains_key(&address) {
             self.clear_breakpoint(core, address)
         } else {
             self.set_breakpoint(core, address)
         }
     }
 
-    /// List active breakpoint addresses.
+    /// Re-apply all enabled breakpoints, e.g. after a reconnect.
+    /// Returns the addresses that were restored.
+    pub fn replay<C: HwBreakpoints + ?Sized>(&self, core: &mut C) -> Vec<u64> {
+        let mut restored = Vec::new();
+        for (&addr, _) in self.breakpoints.iter().filter(|(_, &enabled)| enabled) {
+            match core.set_hw_breakpoint(addr) {
+                Ok(()) => restored.push(addr),
+                Err(e) => log::warn!("Failed to restore breakpoint @ 0x{:08X}: {}", addr, e),
+            }
+        }
+        restored.sort_unstable();
+        restored
+    }
+
+    /// List breakpoint addresses, enabled or not.
     pub fn list(&self) -> Vec<u64> {
-        self.breakpoints.iter().cloned().collect()
+        self.breakpoints.keys().cloned().collect()
+    }
+
+    /// List breakpoints with their enabled state, sorted by address.
+    pub fn entries(&self) -> Vec<(u64, bool)> {
+        let mut entries: Vec<_> = self.breakpoints.iter().map(|(&a, &e)| (a, e)).collect();
+        entries.sort_unstable();
+        entries
     }
 }
 
@@ -66,12 +163,54 @@ impl Default for BreakpointManager {
 mod tests {
     use super::*;
 
+    #[derive(Default)]
+    struct MockCore {
+        hw: std::collections::HashSet<u64>,
+    }
+
+    impl HwBreakpoints for MockCore {
+        fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.insert(address);
+            Ok(())
+        }
+        fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.remove(&address);
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_breakpoint_manager_tracking() {
-        let mgr = BreakpointManager::new();
+        let mut mgr = BreakpointManager::new();
         assert!(mgr.list().is_empty());
 
-        // We can't easily test set_breakpoint without a Core mock,
-        // but we can at least verify the manager creation.
+        let mut core = MockCore::default();
+        mgr.set_breakpoint(&mut core, 0x0800_0100).unwrap();
+        mgr.toggle_breakpoint(&mut core, 0x0800_0200).unwrap();
+        mgr.toggle_breakpoint(&mut core, 0x0800_0200).unwrap();
+        assert_eq!(mgr.list(), vec![0x0800_0100]);
+        assert!(core.hw.contains(&0x0800_0100));
+        assert!(!core.hw.contains(&0x0800_0200));
+    }
+
+    #[test]
+    fn test_disabled_breakpoint_is_not_armed() {
+        let mut mgr = BreakpointManager::new();
+        let mut core = MockCore::default();
+        mgr.set_breakpoint(&mut core, 0x0800_0100).unwrap();
+
+        mgr.set_enabled(&mut core, 0x0800_0100, false).unwrap();
+        assert_eq!(mgr.entries(), vec![(0x0800_0100, false)]);
+        assert!(!core.hw.contains(&0x0800_0100));
+
+        // Disabled breakpoints are not restored after a reconnect.
+        let mut fresh = MockCore::default();
+        assert!(mgr.replay(&mut fresh).is_empty());
+
+        mgr.set_enabled(&mut core, 0x0800_0100, true).unwrap();
+        assert_eq!(mgr.entries(), vec![(0x0800_0100, true)]);
+        assert!(core.hw.contains(&0x0800_0100));
+
+        assert!(mgr.set_enabled(&mut core, 0x0800_0200, true).is_err());
     }
 }
@@ -0,0 +1,344 @@
+//! Cortex-M debug registers.
+//!
+//! Halting debug, DWT, FPB and fault status live in the System Control Space
+//! rather than in the core register file, so they are read and written through
+//! memory. This module names them and guards writes: only registers in
+//! [`DEBUG_REGISTERS`] can be written, never a read-only one, and registers
+//! that need a key in their upper halfword reject writes without it.
+
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::MemoryInterface;
+use anyhow::{anyhow, bail, Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::MemoryInterface;
+use serde::{Deserialize, Serialize};
+
+/// Private Peripheral Bus, where every Cortex-M debug register lives.
+const PPB: std::ops::RangeInclusive<u64> = 0xE000_0000..=0xE00F_FFFF;
+
+/// How a debug register may be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite,
+    /// Writes are ignored unless the upper halfword holds this key.
+    Keyed(u16),
+}
+
+/// A named debug register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugRegister {
+    pub name: &'static str,
+    pub address: u64,
+    pub access: Access,
+    pub description: &'static str,
+}
+
+const fn reg(
+    name: &'static str,
+    address: u64,
+    access: Access,
+    description: &'static str,
+) -> DebugRegister {
+    DebugRegister { name, address, access, description }
+}
+
+/// The known Cortex-M (ARMv6-M/ARMv7-M/ARMv8-M) debug registers. DWT has up to
+/// four comparators and FPB up to eight; which exist depends on the core.
+pub const DEBUG_REGISTERS: &[DebugRegister] = &[
+    reg("CPUID", 0xE000_ED00, Access::ReadOnly, "CPUID Base"),
+    reg("ICSR", 0xE000_ED04, Access::ReadWrite, "Interrupt Control and State"),
+    reg("VTOR", crate::debug::reset::VTOR, Access::ReadWrite, "Vector Table Offset"),
+    reg(
+        "AIRCR",
+        crate::debug::reset::AIRCR,
+        Access::Keyed(0x05FA),
+        "Application Interrupt and Reset Control",
+    ),
+    reg("CFSR", 0xE000_ED28, Access::ReadWrite, "Configurable Fault Status (write 1 to clear)"),
+    reg("HFSR", 0xE000_ED2C, Access::ReadWrite, "HardFault Status (write 1 to clear)"),
+    reg("DFSR", 0xE000_ED30, Access::ReadWrite, "Debug Fault Status (write 1 to clear)"),
+    reg("MMFAR", 0xE000_ED34, Access::ReadWrite, "MemManage Fault Address"),
+    reg("BFAR", 0xE000_ED38, Access::ReadWrite, "BusFault Address"),
+    reg("DHCSR", 0xE000_EDF0, Access::Keyed(0xA05F), "Debug Halting Control and Status"),
+    reg(
+        "DEMCR",
+        crate::debug::vector_catch::DEMCR,
+        Access::ReadWrite,
+        "Debug Exception and Monitor Control",
+    ),
+    reg("DWT_CTRL", 0xE000_1000, Access::ReadWrite, "DWT Control"),
+    reg("DWT_CYCCNT", 0xE000_1004, Access::ReadWrite, "DWT Cycle Count"),
+    reg("DWT_PCSR", 0xE000_101C, Access::ReadOnly, "DWT Program Counter Sample"),
+    reg("DWT_COMP0", 0xE000_1020, Access::ReadWrite, "DWT Comparator 0"),
+    reg("DWT_MASK0", 0xE000_1024, Access::ReadWrite, "DWT Mask 0"),
+    reg("DWT_FUNCTION0", 0xE000_1028, Access::ReadWrite, "DWT Function 0"),
+    reg("DWT_COMP1", 0xE000_1030, Access::ReadWrite, "DWT Comparator 1"),
+    reg("DWT_MASK1", 0xE000_1034, Access::ReadWrite, "DWT Mask 1"),
+    reg("DWT_FUNCTION1", 0xE000_1038, Access::ReadWrite, "DWT Function 1"),
+    reg("DWT_COMP2", 0xE000_1040, Access::ReadWrite, "DWT Comparator 2"),
+    reg("DWT_MASK2", 0xE000_1044, Access::ReadWrite, "DWT Mask 2"),
+    reg("DWT_FUNCTION2", 0xE000_1048, Access::ReadWrite, "DWT Function 2"),
+    reg("DWT_COMP3", 0xE000_1050, Access::ReadWrite, "DWT Comparator 3"),
+    reg("DWT_MASK3", 0xE000_1054, Access::ReadWrite, "DWT Mask 3"),
+    reg("DWT_FUNCTION3", 0xE000_1058, Access::ReadWrite, "DWT Function 3"),
+    reg("FP_CTRL", 0xE000_2000, Access::ReadWrite, "Flash Patch Control (set KEY to write)"),
+    reg("FP_REMAP", 0xE000_2004, Access::ReadWrite, "Flash Patch Remap"),
+    reg("FP_COMP0", 0xE000_2008, Access::ReadWrite, "Flash Patch Comparator 0"),
+    reg("FP_COMP1", 0xE000_200C, Access::ReadWrite, "Flash Patch Comparator 1"),
+    reg("FP_COMP2", 0xE000_2010, Access::ReadWrite, "Flash Patch Comparator 2"),
+    reg("FP_COMP3", 0xE000_2014, Access::ReadWrite, "Flash Patch Comparator 3"),
+    reg("FP_COMP4", 0xE000_2018, Access::ReadWrite, "Flash Patch Comparator 4"),
+    reg("FP_COMP5", 0xE000_201C, Access::ReadWrite, "Flash Patch Comparator 5"),
+    reg("FP_COMP6", 0xE000_2020, Access::ReadWrite, "Flash Patch Comparator 6"),
+    reg("FP_COMP7", 0xE000_2024, Access::ReadWrite, "Flash Patch Comparator 7"),
+];
+
+/// A debug register and the value read from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugRegisterValue {
+    /// `None` for an address outside [`DEBUG_REGISTERS`].
+    pub name: Option<String>,
+    pub address: u64,
+    pub value: u32,
+}
+
+/// Look up a register by name, ignoring case.
+pub fn lookup(name: &str) -> Option<&'static DebugRegister> {
+    DEBUG_REGISTERS.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+}
+
+fn lookup_address(address: u64) -> Option<&'static DebugRegister> {
+    DEBUG_REGISTERS.iter().find(|r| r.address == address)
+}
+
+/// Resolve a register name or a `0x`-prefixed hexadecimal address. Unnamed
+/// addresses must be word-aligned and on the Private Peripheral Bus.
+pub fn resolve(name_or_address: &str) -> Result<(Option<&'static DebugRegister>, u64)> {
+    let spec = name_or_address.trim();
+    if let Some(reg) = lookup(spec) {
+        return Ok((Some(reg), reg.address));
+    }
+    let hex = spec
+        .strip_prefix("0x")
+        .or_else(|| spec.strip_prefix("0X"))
+        .ok_or_else(|| anyhow!("Unknown debug register '{}'", spec))?;
+    let address = u64::from_str_radix(&hex.replace('_', ""), 16)
+        .with_context(|| format!("Invalid address '{}'", spec))?;
+    if !PPB.contains(&address) || address % 4 != 0 {
+        bail!("0x{:08X} is not a word-aligned debug register address", address);
+    }
+    Ok((lookup_address(address), address))
+}
+
+/// Check that writing `value` to `register` is permitted.
+fn check_write(register: Option<&DebugRegister>, address: u64, value: u32) -> Result<()> {
+    let Some(register) = register else {
+        bail!("Writes are limited to known debug registers; 0x{:08X} is not one", address);
+    };
+    match register.access {
+        Access::ReadOnly => bail!("{} is read-only", register.name),
+        Access::Keyed(key) if (value >> 16) as u16 != key => {
+            bail!("Writes to {} need 0x{:04X} in bits 31:16", register.name, key)
+        }
+        Access::Keyed(_) | Access::ReadWrite => Ok(()),
+    }
+}
+
+/// Read the register named by `name_or_address`; see [`resolve`].
+pub fn read_debug_register(
+    core: &mut dyn MemoryInterface,
+    name_or_address: &str,
+) -> Result<DebugRegisterValue> {
+    let (register, address) = resolve(name_or_address)?;
+    let value = core
+        .read_word_32(address)
+        .with_context(|| format!("Failed to read debug register at 0x{:08X}", address))?;
+    Ok(DebugRegisterValue { name: register.map(|r| r.name.to_string()), address, value })
+}
+
+/// Write a known, writable register and return the value read back.
+pub fn write_debug_register(
+    core: &mut dyn MemoryInterface,
+    name_or_address: &str,
+    value: u32,
+) -> Result<DebugRegisterValue> {
+    let (register, address) = resolve(name_or_address)?;
+    check_write(register, address, value)?;
+    core.write_word_32(address, value)
+        .with_context(|| format!("Failed to write debug register at 0x{:08X}", address))?;
+    read_debug_register(core, name_or_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockMemory {
+        data: std::collections::HashMap<u64, u8>,
+    }
+
+    #[cfg(feature = "hardware")]
+    impl MemoryInterface for MockMemory {
+        fn read_word_8(&mut self, address: u64) -> Result<u8, probe_rs::Error> {
+            let mut b = [0u8; 1];
+            self.read_8(address, &mut b)?;
+            Ok(b[0])
+        }
+        fn read_word_16(&mut self, address: u64) -> Result<u16, probe_rs::Error> {
+            let mut b = [0u8; 2];
+            self.read_8(address, &mut b)?;
+            Ok(u16::from_le_bytes(b))
+        }
+        fn read_word_32(&mut self, address: u64) -> Result<u32, probe_rs::Error> {
+            let mut b = [0u8; 4];
+            self.read_8(address, &mut b)?;
+            Ok(u32::from_le_bytes(b))
+        }
+        fn read_word_64(&mut self, address: u64) -> Result<u64, probe_rs::Error> {
+            let mut b = [0u8; 8];
+            self.read_8(address, &mut b)?;
+            Ok(u64::from_le_bytes(b))
+        }
+        fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), probe_rs::Error> {
+            self.write_8(address, &[data])
+        }
+        fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), probe_rs::Error> {
+            self.write_8(address, &data.to_le_bytes())
+        }
+        fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), probe_rs::Error> {
+            self.write_8(address, &data.to_le_bytes())
+        }
+        fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), probe_rs::Error> {
+            self.write_8(address, &data.to_le_bytes())
+        }
+        fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), probe_rs::Error> {
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte = *self.data.get(&(address + i as u64)).unwrap_or(&0);
+            }
+            Ok(())
+        }
+        fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), probe_rs::Error> {
+            for (i, &byte) in data.iter().enumerate() {
+                self.data.insert(address + i as u64, byte);
+            }
+            Ok(())
+        }
+        fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), probe_rs::Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_16(address + (i * 2) as u64)?;
+            }
+            Ok(())
+        }
+        fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), probe_rs::Error> {
+            for (i, &word) in data.iter().enumerate() {
+                self.write_word_16(address + (i * 2) as u64, word)?;
+            }
+            Ok(())
+        }
+        fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), probe_rs::Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_32(address + (i * 4) as u64)?;
+            }
+            Ok(())
+        }
+        fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), probe_rs::Error> {
+            for (i, &word) in data.iter().enumerate() {
+                self.write_word_32(address + (i * 4) as u64, word)?;
+            }
+            Ok(())
+        }
+        fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), probe_rs::Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_64(address + (i * 8) as u64)?;
+            }
+            Ok(())
+        }
+        fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), probe_rs::Error> {
+            for (i, &word) in data.iter().enumerate() {
+                self.write_word_64(address + (i * 8) as u64, word)?;
+            }
+            Ok(())
+        }
+        fn flush(&mut self) -> Result<(), probe_rs::Error> {
+            Ok(())
+        }
+        fn supports_native_64bit_access(&mut self) -> bool {
+            false
+        }
+        fn supports_8bit_transfers(&self) -> Result<bool, probe_rs::Error> {
+            Ok(true)
+        }
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    impl MemoryInterface for MockMemory {
+        fn read_word_32(&mut self, address: u64) -> anyhow::Result<u32> {
+            let mut b = [0u8; 4];
+            self.read(address, &mut b)?;
+            Ok(u32::from_le_bytes(b))
+        }
+        fn read(&mut self, address: u64, data: &mut [u8]) -> anyhow::Result<()> {
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte = *self.data.get(&(address + i as u64)).unwrap_or(&0);
+            }
+            Ok(())
+        }
+        fn write_8(&mut self, address: u64, data: &[u8]) -> anyhow::Result<()> {
+            for (i, &byte) in data.iter().enumerate() {
+                self.data.insert(address + i as u64, byte);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_debug_register_names_resolve_to_addresses() {
+        assert_eq!(resolve("DHCSR").unwrap().1, 0xE000_EDF0);
+        assert_eq!(resolve("demcr").unwrap().1, 0xE000_EDFC);
+        assert_eq!(resolve("DWT_COMP2").unwrap().1, 0xE000_1040);
+        assert_eq!(resolve("FP_COMP7").unwrap().1, 0xE000_2024);
+
+        // Addresses find their name when there is one.
+        let (register, address) = resolve("0xE000_1004").unwrap();
+        assert_eq!((register.unwrap().name, address), ("DWT_CYCCNT", 0xE000_1004));
+        let (register, address) = resolve("0xE000E100").unwrap();
+        assert_eq!((register, address), (None, 0xE000_E100));
+
+        assert!(resolve("DHCRS").is_err());
+        assert!(resolve("0x20000000").is_err());
+        assert!(resolve("0xE000EDF2").is_err());
+
+        // Names and addresses are unique.
+        for (i, a) in DEBUG_REGISTERS.iter().enumerate() {
+            for b in &DEBUG_REGISTERS[i + 1..] {
+                assert!(a.name != b.name && a.address != b.address, "{} / {}", a.name, b.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_register_read_write_round_trip() {
+        let mut mock = MockMemory { data: std::collections::HashMap::new() };
+
+        let written = write_debug_register(&mut mock, "DWT_CYCCNT", 1234).unwrap();
+        assert_eq!(
+            written,
+            DebugRegisterValue {
+                name: Some("DWT_CYCCNT".to_string()),
+                address: 0xE000_1004,
+                value: 1234
+            }
+        );
+        assert_eq!(read_debug_register(&mut mock, "0xE0001004").unwrap().value, 1234);
+
+        // Keyed writes need the key; read-only and unknown registers are refused.
+        let halt = 0xA05F_0003;
+        assert_eq!(write_debug_register(&mut mock, "DHCSR", halt).unwrap().value, halt);
+        assert!(write_debug_register(&mut mock, "DHCSR", 0x0000_0003).is_err());
+        assert!(write_debug_register(&mut mock, "CPUID", 0).is_err());
+        assert!(write_debug_register(&mut mock, "0xE000E100", 1).is_err());
+        assert_eq!(read_debug_register(&mut mock, "DHCSR").unwrap().value, halt);
+        assert_eq!(read_debug_register(&mut mock, "0xE000E100").unwrap().value, 0);
+    }
+}
@@ -0,0 +1,154 @@
+//! Low-power aware halt module.
+//!
+//! A core sleeping in WFI/WFE or a stop mode may have its debug clock gated and
+//! ignore halt requests. When enabled for a target, the chip's debug-in-low-power
+//! bits (e.g. STM32 `DBGMCU_CR.DBG_SLEEP/STOP/STANDBY`) are set and the halt is
+//! retried with a backoff.
+
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::{Core, MemoryInterface};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// STM32 `DBGMCU_CR` address (Cortex-M3/M4/M7 parts).
+pub const STM32_DBGMCU_CR: u64 = 0xE004_2004;
+/// STM32 `DBG_SLEEP | DBG_STOP | DBG_STANDBY`.
+pub const STM32_DBGMCU_LOW_POWER_BITS: u32 = 0x7;
+
+/// Halt attempts made before giving up.
+pub const HALT_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubled after each failed attempt.
+pub const HALT_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Chip-specific register bits that keep the debug domain clocked in low-power modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LowPowerDebug {
+    pub register: u64,
+    pub mask: u32,
+}
+
+impl LowPowerDebug {
+    pub fn stm32() -> Self {
+        Self { register: STM32_DBGMCU_CR, mask: STM32_DBGMCU_LOW_POWER_BITS }
+    }
+}
+
+/// Core operations needed by [`halt_with_retry`].
+///
+/// A halt request plus 32-bit register access, enough to set the
+/// debug-in-low-power bits between attempts.
+pub trait HaltControl {
+    /// Request a halt, returning the PC.
+    fn halt(&mut self, timeout: Duration) -> Result<u64>;
+    fn read_u32(&mut self, address: u64) -> Result<u32>;
+    fn write_u32(&mut self, address: u64, value: u32) -> Result<()>;
+}
+
+#[cfg(feature = "hardware")]
+impl HaltControl for Core<'_> {
+    fn halt(&mut self, timeout: Duration) -> Result<u64> {
+        Ok(Core::halt(self, timeout)?.pc)
+    }
+    fn read_u32(&mut self, address: u64) -> Result<u32> {
+        Ok(self.read_word_32(address)?)
+    }
+    fn write_u32(&mut self, address: u64, value: u32) -> Result<()> {
+        Ok(self.write_word_32(address, value)?)
+    }
+}
+
+/// Halt the core, keeping the debug domain powered and retrying on failure.
+///
+/// Before every retry the `low_power` bits are set (read-modify-write) and the
+/// delay doubles, starting at `backoff`. Returns the PC once halted.
+pub fn halt_with_retry<C: HaltControl>(
+    core: &mut C,
+    low_power: &LowPowerDebug,
+    attempts: u32,
+    backoff: Duration,
+) -> Result<u64> {
+    let mut delay = backoff;
+    let mut last_err = None;
+    for attempt in 0..attempts {
+        if attempt > 0 {
+            let cr = core.read_u32(low_power.register).context("Failed to read DBGMCU")?;
+            if cr & low_power.mask != low_power.mask {
+                core.write_u32(low_power.register, cr | low_power.mask)
+                    .context("Failed to enable debug in low-power modes")?;
+            }
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+        match core.halt(super::HALT_TIMEOUT) {
+            Ok(pc) => return Ok(pc),
+            Err(e) => {
+                log::warn!("Halt attempt {} of {} failed: {}", attempt + 1, attempts, e);
+                last_err = Some(e);
+            }
+        }
+    }
+    let err = last_err.unwrap_or_else(|| anyhow::anyhow!("no halt attempts made"));
+    Err(err.context(format!(
+        "Target did not halt after {} attempts; it may be in a low-power mode with the debug clock gated",
+        attempts
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Sleeping core that only responds once the debug domain is kept powered.
+    #[derive(Default)]
+    struct SleepyCore {
+        memory: HashMap<u64, u32>,
+        halt_calls: u32,
+        halts_on_attempt: u32,
+    }
+
+    impl HaltControl for SleepyCore {
+        fn halt(&mut self, _timeout: Duration) -> Result<u64> {
+            self.halt_calls += 1;
+            let powered = self.memory.get(&STM32_DBGMCU_CR).copied().unwrap_or(0)
+                & STM32_DBGMCU_LOW_POWER_BITS
+                == STM32_DBGMCU_LOW_POWER_BITS;
+            if powered && self.halt_calls >= self.halts_on_attempt {
+                Ok(0x0800_0200)
+            } else {
+                anyhow::bail!("Operation timed out")
+            }
+        }
+        fn read_u32(&mut self, address: u64) -> Result<u32> {
+            Ok(self.memory.get(&address).copied().unwrap_or(0))
+        }
+        fn write_u32(&mut self, address: u64, value: u32) -> Result<()> {
+            self.memory.insert(address, value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_halt_retries_after_enabling_low_power_debug() {
+        let mut core = SleepyCore { halts_on_attempt: 2, ..Default::default() };
+        core.memory.insert(STM32_DBGMCU_CR, 0x100);
+
+        let pc = halt_with_retry(&mut core, &LowPowerDebug::stm32(), HALT_ATTEMPTS, Duration::ZERO)
+            .unwrap();
+        assert_eq!(pc, 0x0800_0200);
+        assert_eq!(core.halt_calls, 2);
+        // Existing DBGMCU bits are preserved.
+        assert_eq!(core.memory[&STM32_DBGMCU_CR], 0x107);
+    }
+
+    #[test]
+    fn test_halt_gives_up_with_diagnostic() {
+        let mut core = SleepyCore { halts_on_attempt: u32::MAX, ..Default::default() };
+        let err =
+            halt_with_retry(&mut core, &LowPowerDebug::stm32(), HALT_ATTEMPTS, Duration::ZERO)
+                .unwrap_err();
+        assert_eq!(core.halt_calls, HALT_ATTEMPTS);
+        assert!(format!("{:#}", err).contains("low-power mode"));
+    }
+}
@@ -3,6 +3,20 @@
 //! Handles core debug operations: halt, resume, step, and register access.
 
 pub mod breakpoint;
+pub mod debug_regs;
+pub mod low_power;
+pub mod psr;
+pub mod reset;
+pub mod run_to_main;
+pub mod special_regs;
+pub mod stack_pointer;
+pub mod status;
+pub mod step_over;
+pub mod systick;
+pub mod task_switch;
+pub mod tracepoint;
+pub mod vector_catch;
+pub mod vector_table;
 
 pub use breakpoint::BreakpointManager;
 
@@ -13,6 +27,9 @@ use anyhow::{Context, Result};
 use probe_rs::{Core, CoreInformation, CoreStatus};
 use std::time::Duration;
 
+/// How long a halt request waits for the core to stop.
+pub const HALT_TIMEOUT: Duration = Duration::from_millis(100);
+
 /// Manager for debug operations.
 pub struct DebugManager;
 
@@ -23,7 +40,7 @@ impl DebugManager {
 
     /// Halt the core.
     pub fn halt(&self, core: &mut Core) -> Result<CoreInformation> {
-        core.halt(Duration::from_millis(100)).context("Failed to halt core")
+        core.halt(HALT_TIMEOUT).context("Failed to halt core")
     }
 
     /// Resume the core.
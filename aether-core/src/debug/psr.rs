@@ -0,0 +1,66 @@
+//! Program status register decoding.
+//!
+//! xPSR combines the APSR condition flags, the EPSR Thumb bit and the IPSR
+//! exception number, which tells which handler is running.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PsrFlags {
+    /// Negative.
+    pub n: bool,
+    /// Zero.
+    pub z: bool,
+    /// Carry.
+    pub c: bool,
+    /// Overflow.
+    pub v: bool,
+    /// Sticky saturation.
+    pub q: bool,
+    /// Thumb state; always set on Cortex-M while running normally.
+    pub thumb: bool,
+    /// Active exception number; 0 in Thread mode.
+    pub exception_number: u16,
+}
+
+impl PsrFlags {
+    pub fn from_xpsr(xpsr: u32) -> Self {
+        let bit = |n: u32| xpsr & (1 << n) != 0;
+        Self {
+            n: bit(31),
+            z: bit(30),
+            c: bit(29),
+            v: bit(28),
+            q: bit(27),
+            thumb: bit(24),
+            exception_number: (xpsr & 0x1FF) as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xpsr_flags_and_exception_number() {
+        // Z and V set, Thumb, in IRQ5 (exception 21).
+        assert_eq!(
+            PsrFlags::from_xpsr(0x5100_0015),
+            PsrFlags {
+                n: false,
+                z: true,
+                c: false,
+                v: true,
+                q: false,
+                thumb: true,
+                exception_number: 21,
+            }
+        );
+        // IPSR is 9 bits wide: exception 256 (IRQ240).
+        let flags = PsrFlags::from_xpsr(0x0800_0100);
+        assert!(flags.q && !flags.thumb);
+        assert_eq!(flags.exception_number, 256);
+        assert_eq!(PsrFlags::from_xpsr(0x0100_0000).exception_number, 0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Target reset: the kind of reset to request, and detection of resets the
+//! debugger did not request.
+//!
+//! A watchdog or brown-out reset that we did not ask for means breakpoints, RTT
+//! and other state derived from the running firmware may be stale. The sticky
+//! DHCSR.S_RESET_ST flag reports it even while the core keeps running; a halt at
+//! the reset handler with SP back at its initial value is the fallback when the
+//! flag was already consumed.
+
+use super::run_to_main::RunControl;
+use super::vector_catch::{DEMCR, VC_CORERESET};
+use crate::memory::history::WriteTarget;
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::MemoryInterface;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::MemoryInterface;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Cortex-M Vector Table Offset Register.
+pub const VTOR: u64 = 0xE000_ED08;
+/// Cortex-M Application Interrupt and Reset Control Register.
+pub const AIRCR: u64 = 0xE000_ED0C;
+/// Cortex-M Debug Halting Control and Status Register.
+pub const DHCSR: u64 = 0xE000_EDF0;
+/// DHCSR.S_RESET_ST: the core reset since DHCSR was last read. Reading clears it.
+pub const DHCSR_S_RESET_ST: u32 = 1 << 25;
+
+const AIRCR_VECTKEY: u32 = 0x05FA << 16;
+const AIRCR_VECTRESET: u32 = 1 << 0;
+const AIRCR_SYSRESETREQ: u32 = 1 << 2;
+
+/// How long to wait for the core to halt after a reset request.
+pub const RESET_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Which reset to perform.
+///
+/// A hardware nRST pulse is not listed: probe-rs only drives nRST while
+/// attaching, through the target's reset sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResetKind {
+    /// The target's reset sequence, as probe-rs defines it for the chip. Usually a
+    /// system reset; some targets need extra steps.
+    #[default]
+    Default,
+    /// System reset through AIRCR.SYSRESETREQ: core and peripherals.
+    System,
+    /// Core-only reset through AIRCR.VECTRESET; peripherals keep their state.
+    /// Only ARMv7-M implements it.
+    Core,
+}
+
+impl ResetKind {
+    pub const ALL: [ResetKind; 3] = [Self::Default, Self::System, Self::Core];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::System => "System (SYSRESETREQ)",
+            Self::Core => "Core only (VECTRESET)",
+        }
+    }
+
+    /// The AIRCR request bit, for the kinds that write AIRCR directly.
+    fn aircr_request(self) -> Option<u32> {
+        match self {
+            Self::Default => None,
+            Self::System => Some(AIRCR_SYSRESETREQ),
+            Self::Core => Some(AIRCR_VECTRESET),
+        }
+    }
+}
+
+fn read_u32<T: WriteTarget + ?Sized>(target: &mut T, address: u64) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    target.read_memory(address, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_u32<T: WriteTarget + ?Sized>(target: &mut T, address: u64, value: u32) -> Result<()> {
+    target.write_memory(address, &value.to_le_bytes())
+}
+
+/// Reset the core with `kind` and halt at the reset handler, returning the PC.
+///
+/// AIRCR resets halt through DEMCR's reset vector catch, which is restored
+/// afterwards.
+pub fn reset_and_halt<C>(core: &mut C, kind: ResetKind, timeout: Duration) -> Result<u64>
+where
+    C: RunControl + WriteTarget + ?Sized,
+{
+    let Some(request) = kind.aircr_request() else {
+        return core.reset_and_halt();
+    };
+
+    let demcr = read_u32(core, DEMCR).context("Failed to read DEMCR")?;
+    write_u32(core, DEMCR, demcr | VC_CORERESET).context("Failed to enable reset catch")?;
+    let result = write_u32(core, AIRCR, AIRCR_VECTKEY | request)
+        .context("Failed to write AIRCR")
+        .and_then(|()| core.wait_for_halt(timeout).context("Core did not halt after reset"));
+    write_u32(core, DEMCR, demcr).context("Failed to restore DEMCR")?;
+    result
+}
+
+/// The first two entries of the vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VectorTable {
+    pub initial_sp: u64,
+    /// Reset handler address with the Thumb bit cleared.
+    pub reset_handler: u64,
+}
+
+/// Read the active vector table through VTOR.
+///
+/// VTOR is reset along with the core, so reading it right after a reset yields
+/// the vectors the core actually started from.
+pub fn read_vector_table(core: &mut dyn MemoryInterface) -> Result<VectorTable> {
+    let base = core.read_word_32(VTOR)? as u64;
+    let initial_sp = core.read_word_32(base)? as u64;
+    let reset_handler = core.read_word_32(base + 4)? as u64 & !1;
+    Ok(VectorTable { initial_sp, reset_handler })
+}
+
+/// Flags halts that look like a reset the debugger did not request.
+#[derive(Debug, Default)]
+pub struct ResetDetector {
+    expected: bool,
+}
+
+impl ResetDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The next halt at the reset handler is ours (reset or run-to-main command).
+    pub fn expect_reset(&mut self) {
+        self.expected = true;
+    }
+
+    /// The core was seen running, so a requested reset has completed.
+    pub fn observe_running(&mut self) {
+        self.expected = false;
+    }
+
+    /// Read the sticky reset flag. `None` if the core has not reset since the
+    /// last read, otherwise whether the reset was one the debugger did not request.
+    pub fn poll_reset_flag<T: WriteTarget + ?Sized>(
+        &mut self,
+        core: &mut T,
+    ) -> Result<Option<bool>> {
+        let dhcsr = read_u32(core, DHCSR).context("Failed to read DHCSR")?;
+        if dhcsr & DHCSR_S_RESET_ST == 0 {
+            return Ok(None);
+        }
+        Ok(Some(!std::mem::take(&mut self.expected)))
+    }
+
+    /// Check a halt that followed a running period. Returns `true` if the core sits at
+    /// the reset handler with a freshly initialized SP and no reset was requested.
+    pub fn is_unexpected_reset(&mut self, vectors: &VectorTable, pc: u64, sp: u64) -> bool {
+        let expected = std::mem::take(&mut self.expected);
+        !expected && pc & !1 == vectors.reset_handler && sp == vectors.initial_sp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VECTORS: VectorTable =
+        VectorTable { initial_sp: 0x2000_8000, reset_handler: 0x0800_0188 };
+
+    #[test]
+    fn test_halt_at_reset_handler_is_reset() {
+        let mut detector = ResetDetector::new();
+        assert!(detector.is_unexpected_reset(&VECTORS, 0x0800_0188, 0x2000_8000));
+        // A breakpoint elsewhere, or deeper in the reset handler, is not a reset.
+        assert!(!detector.is_unexpected_reset(&VECTORS, 0x0800_0400, 0x2000_7FF0));
+        assert!(!detector.is_unexpected_reset(&VECTORS, 0x0800_0188, 0x2000_7FF0));
+    }
+
+    #[derive(Default)]
+    struct MockCore {
+        memory: std::collections::HashMap<u64, u32>,
+        /// Reset requests in order, as the target saw them.
+        actions: Vec<String>,
+    }
+
+    impl crate::debug::breakpoint::HwBreakpoints for MockCore {
+        fn set_hw_breakpoint(&mut self, _address: u64) -> Result<()> {
+            unreachable!()
+        }
+        fn clear_hw_breakpoint(&mut self, _address: u64) -> Result<()> {
+            unreachable!()
+        }
+    }
+
+    impl RunControl for MockCore {
+        fn reset_and_halt(&mut self) -> Result<u64> {
+            self.actions.push("probe-rs reset_and_halt".to_string());
+            Ok(VECTORS.reset_handler)
+        }
+        fn run(&mut self) -> Result<()> {
+            unreachable!()
+        }
+        fn wait_for_halt(&mut self, _timeout: Duration) -> Result<u64> {
+            // The reset only halts if the reset vector catch was armed.
+            anyhow::ensure!(self.memory[&DEMCR] & VC_CORERESET != 0, "core keeps running");
+            Ok(VECTORS.reset_handler)
+        }
+    }
+
+    impl WriteTarget for MockCore {
+        fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()> {
+            data.copy_from_slice(&self.memory.get(&address).copied().unwrap_or(0).to_le_bytes());
+            Ok(())
+        }
+        fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+            let value = u32::from_le_bytes(data.try_into()?);
+            if address == AIRCR {
+                self.actions.push(format!("AIRCR = 0x{:08X}", value));
+            } else {
+                self.memory.insert(address, value);
+            }
+            Ok(())
+        }
+        fn read_register(&mut self, _id: u16) -> Result<u64> {
+            unreachable!()
+        }
+        fn write_register(&mut self, _id: u16, _value: u64) -> Result<()> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_reset_kind_actions() {
+        let expected = [
+            (ResetKind::Default, "probe-rs reset_and_halt"),
+            (ResetKind::System, "AIRCR = 0x05FA0004"),
+            (ResetKind::Core, "AIRCR = 0x05FA0001"),
+        ];
+        for (kind, action) in expected {
+            // TRCENA is set and must survive the temporary reset catch.
+            let mut core = MockCore::default();
+            core.memory.insert(DEMCR, 1 << 24);
+
+            let pc = reset_and_halt(&mut core, kind, RESET_TIMEOUT).unwrap();
+            assert_eq!(pc, VECTORS.reset_handler);
+            assert_eq!(core.actions, vec![action], "{:?}", kind);
+            assert_eq!(core.memory[&DEMCR], 1 << 24, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_reset_flag_catches_reset_while_running() {
+        let mut detector = ResetDetector::new();
+        let mut core = MockCore::default();
+        assert_eq!(detector.poll_reset_flag(&mut core).unwrap(), None);
+
+        // The watchdog fires; the core restarts and keeps running.
+        core.memory.insert(DHCSR, DHCSR_S_RESET_ST | 1 << 17);
+        assert_eq!(detector.poll_reset_flag(&mut core).unwrap(), Some(true));
+
+        // A requested reset sets the flag too, but is not reported.
+        detector.expect_reset();
+        assert_eq!(detector.poll_reset_flag(&mut core).unwrap(), Some(false));
+    }
+
+    #[test]
+    fn test_requested_reset_is_not_reported() {
+        let mut detector = ResetDetector::new();
+        detector.expect_reset();
+        assert!(!detector.is_unexpected_reset(&VECTORS, 0x0800_0188, 0x2000_8000));
+        // The expectation only covers one halt.
+        assert!(detector.is_unexpected_reset(&VECTORS, 0x0800_0188, 0x2000_8000));
+
+        // A reset requested while halted never produces a halt transition; once the
+        // core runs, the next reset is unexpected again.
+        detector.expect_reset();
+        detector.observe_running();
+        assert!(detector.is_unexpected_reset(&VECTORS, 0x0800_0188, 0x2000_8000));
+    }
+}
@@ -0,0 +1,144 @@
+//! Reset-and-run-to-main module.
+//!
+//! Resets the core, runs it to a temporary breakpoint on `main` and stops there,
+//! the usual first step of a startup debugging session.
+
+use super::breakpoint::HwBreakpoints;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::Core;
+use std::time::Duration;
+
+/// How long to wait for the core to reach `main` after reset.
+pub const RUN_TO_MAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run control operations needed by [`run_to_main`].
+///
+/// Reset, resume and wait for the next halt, on top of the breakpoint
+/// comparators. Step-over, tracepoints and the reset kinds drive the core
+/// through the same three calls.
+pub trait RunControl: HwBreakpoints {
+    /// Reset the core and halt at the reset handler, returning the PC.
+    fn reset_and_halt(&mut self) -> Result<u64>;
+    fn run(&mut self) -> Result<()>;
+    /// Wait until the core halts, returning the PC.
+    fn wait_for_halt(&mut self, timeout: Duration) -> Result<u64>;
+}
+
+#[cfg(feature = "hardware")]
+impl RunControl for Core<'_> {
+    fn reset_and_halt(&mut self) -> Result<u64> {
+        Ok(Core::reset_and_halt(self, super::HALT_TIMEOUT)?.pc)
+    }
+    fn run(&mut self) -> Result<()> {
+        Ok(Core::run(self)?)
+    }
+    fn wait_for_halt(&mut self, timeout: Duration) -> Result<u64> {
+        self.wait_for_core_halted(timeout)?;
+        Ok(self.read_core_reg(self.program_counter())?)
+    }
+}
+
+/// Reset the core and run to `main`, returning the PC it halted at.
+///
+/// Without a `main` address the core stays halted at the reset handler. The
+/// temporary breakpoint is removed afterwards unless `keep_breakpoint` is set,
+/// e.g. because the user already has a breakpoint on `main`. Not reaching
+/// `main` within `timeout` is an error and leaves the core running, for the
+/// caller to halt.
+pub fn run_to_main<C: RunControl>(
+    core: &mut C,
+    main: Option<u64>,
+    keep_breakpoint: bool,
+    timeout: Duration,
+) -> Result<u64> {
+    let reset_pc = core.reset_and_halt().context("Failed to reset and halt")?;
+    let Some(main) = main else {
+        log::info!("main not found, halted at reset handler 0x{:08X}", reset_pc);
+        return Ok(reset_pc);
+    };
+    // Thumb function symbols carry the mode in bit 0.
+    let main = main & !1;
+
+    core.set_hw_breakpoint(main).context("Failed to set breakpoint at main")?;
+    let result = core.run().and_then(|()| core.wait_for_halt(timeout));
+    if !keep_breakpoint {
+        core.clear_hw_breakpoint(main).context("Failed to clear breakpoint at main")?;
+    }
+    result.context("Core did not halt at main")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::SymbolManager;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    const RESET_HANDLER: u64 = 0x0800_0130;
+
+    #[derive(Default)]
+    struct MockCore {
+        pc: u64,
+        hw: HashSet<u64>,
+        running: bool,
+    }
+
+    impl HwBreakpoints for MockCore {
+        fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.insert(address);
+            Ok(())
+        }
+        fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.remove(&address);
+            Ok(())
+        }
+    }
+
+    impl RunControl for MockCore {
+        fn reset_and_halt(&mut self) -> Result<u64> {
+            self.pc = RESET_HANDLER;
+            self.running = false;
+            Ok(self.pc)
+        }
+        fn run(&mut self) -> Result<()> {
+            self.running = true;
+            Ok(())
+        }
+        fn wait_for_halt(&mut self, _timeout: Duration) -> Result<u64> {
+            // Execution reaches the first breakpoint.
+            self.pc = *self.hw.iter().next().context("core never halts")?;
+            self.running = false;
+            Ok(self.pc)
+        }
+    }
+
+    #[test]
+    fn test_run_to_main_halts_at_main() {
+        let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        elf_path.push("tests/fixtures/c_types.elf");
+        let mut symbols = SymbolManager::new();
+        symbols.load_elf(&elf_path).unwrap();
+        let main = symbols.lookup_symbol("main").expect("main not found");
+
+        let mut core = MockCore::default();
+        let pc = run_to_main(&mut core, Some(main), false, RUN_TO_MAIN_TIMEOUT).unwrap();
+        assert_eq!(pc, main);
+        assert!(!core.running);
+        assert!(core.hw.is_empty(), "temporary breakpoint is removed");
+
+        // A user breakpoint on main survives.
+        run_to_main(&mut core, Some(main), true, RUN_TO_MAIN_TIMEOUT).unwrap();
+        assert!(core.hw.contains(&main));
+    }
+
+    #[test]
+    fn test_run_to_main_falls_back_to_reset_handler() {
+        let symbols = SymbolManager::new();
+        let mut core = MockCore::default();
+        let pc = run_to_main(&mut core, symbols.lookup_symbol("main"), false, RUN_TO_MAIN_TIMEOUT)
+            .unwrap();
+        assert_eq!(pc, RESET_HANDLER);
+        assert!(!core.running);
+    }
+}
@@ -0,0 +1,150 @@
+//! Cortex-M special registers.
+//!
+//! CONTROL, FAULTMASK, BASEPRI and PRIMASK are not individual core registers
+//! for the debugger: the DCRSR exposes them packed into one word (selector
+//! 0b10100, which probe-rs calls EXTRA), one byte each. Writing one of them is
+//! a read-modify-write of that word.
+
+use crate::memory::history::WriteTarget;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Register ID of the packed CONTROL/FAULTMASK/BASEPRI/PRIMASK word.
+pub const EXTRA_REGISTER_ID: u16 = 0b1_0100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpecialRegister {
+    Control,
+    Faultmask,
+    Basepri,
+    Primask,
+}
+
+impl SpecialRegister {
+    pub const ALL: [SpecialRegister; 4] =
+        [Self::Control, Self::Faultmask, Self::Basepri, Self::Primask];
+
+    /// Look up a register by name, ignoring case.
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|reg| reg.name().eq_ignore_ascii_case(name))
+            .with_context(|| format!("Unknown special register '{}'", name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Control => "CONTROL",
+            Self::Faultmask => "FAULTMASK",
+            Self::Basepri => "BASEPRI",
+            Self::Primask => "PRIMASK",
+        }
+    }
+
+    /// The probe-rs register ID holding this register and its bit offset in it.
+    pub fn location(self) -> (u16, u32) {
+        let shift = match self {
+            Self::Control => 24,
+            Self::Faultmask => 16,
+            Self::Basepri => 8,
+            Self::Primask => 0,
+        };
+        (EXTRA_REGISTER_ID, shift)
+    }
+
+    /// Largest value the register can hold.
+    pub fn max_value(self) -> u32 {
+        match self {
+            Self::Faultmask | Self::Primask => 1,
+            Self::Control | Self::Basepri => 0xFF,
+        }
+    }
+}
+
+pub fn read_special_reg<T: WriteTarget + ?Sized>(
+    target: &mut T,
+    reg: SpecialRegister,
+) -> Result<u32> {
+    let (id, shift) = reg.location();
+    let word =
+        target.read_register(id).with_context(|| format!("Failed to read {}", reg.name()))?;
+    Ok((word >> shift) as u32 & 0xFF)
+}
+
+/// Write a special register. Only allowed while the core is halted, as a
+/// running core could change the other registers sharing the word between the
+/// read and the write.
+pub fn write_special_reg<T: WriteTarget + ?Sized>(
+    target: &mut T,
+    halted: bool,
+    reg: SpecialRegister,
+    value: u32,
+) -> Result<()> {
+    if !halted {
+        anyhow::bail!("Core is not halted; halt it before writing {}", reg.name());
+    }
+    if value > reg.max_value() {
+        anyhow::bail!("{} cannot hold 0x{:X} (max 0x{:X})", reg.name(), value, reg.max_value());
+    }
+    let (id, shift) = reg.location();
+    let word =
+        target.read_register(id).with_context(|| format!("Failed to read {}", reg.name()))?;
+    let word = (word & !(0xFF << shift)) | (u64::from(value) << shift);
+    target.write_register(id, word).with_context(|| format!("Failed to write {}", reg.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockCore {
+        extra: u64,
+    }
+
+    impl WriteTarget for MockCore {
+        fn read_memory(&mut self, _address: u64, _data: &mut [u8]) -> Result<()> {
+            unreachable!()
+        }
+        fn write_memory(&mut self, _address: u64, _data: &[u8]) -> Result<()> {
+            unreachable!()
+        }
+        fn read_register(&mut self, id: u16) -> Result<u64> {
+            assert_eq!(id, EXTRA_REGISTER_ID);
+            Ok(self.extra)
+        }
+        fn write_register(&mut self, id: u16, value: u64) -> Result<()> {
+            assert_eq!(id, EXTRA_REGISTER_ID);
+            self.extra = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_special_register_names() {
+        assert_eq!(SpecialRegister::from_name("control").unwrap(), SpecialRegister::Control);
+        assert_eq!(SpecialRegister::from_name("BASEPRI").unwrap(), SpecialRegister::Basepri);
+        assert!(SpecialRegister::from_name("xpsr").is_err());
+
+        assert_eq!(SpecialRegister::Control.location(), (EXTRA_REGISTER_ID, 24));
+        assert_eq!(SpecialRegister::Faultmask.location(), (EXTRA_REGISTER_ID, 16));
+        assert_eq!(SpecialRegister::Basepri.location(), (EXTRA_REGISTER_ID, 8));
+        assert_eq!(SpecialRegister::Primask.location(), (EXTRA_REGISTER_ID, 0));
+    }
+
+    #[test]
+    fn test_write_special_reg_requires_halt() {
+        // CONTROL = 0x02 (SPSEL), BASEPRI = 0x40, PRIMASK = 1.
+        let mut core = MockCore { extra: 0x0200_4001 };
+        assert_eq!(read_special_reg(&mut core, SpecialRegister::Control).unwrap(), 0x02);
+        assert_eq!(read_special_reg(&mut core, SpecialRegister::Basepri).unwrap(), 0x40);
+
+        assert!(write_special_reg(&mut core, false, SpecialRegister::Control, 0x03).is_err());
+        assert_eq!(core.extra, 0x0200_4001);
+
+        assert!(write_special_reg(&mut core, true, SpecialRegister::Primask, 2).is_err());
+
+        write_special_reg(&mut core, true, SpecialRegister::Control, 0x03).unwrap();
+        write_special_reg(&mut core, true, SpecialRegister::Primask, 0).unwrap();
+        assert_eq!(core.extra, 0x0300_4000);
+    }
+}
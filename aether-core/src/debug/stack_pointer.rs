@@ -0,0 +1,119 @@
+//! Active stack pointer.
+//!
+//! Cortex-M banks two stack pointers. Handler mode always runs on the MSP;
+//! Thread mode uses the PSP when CONTROL.SPSEL is set. Inside a handler, bit 2 of
+//! the EXC_RETURN value in LR says which stack the interrupted code was on, and
+//! so where its exception frame was pushed.
+
+use super::special_regs::{read_special_reg, write_special_reg, SpecialRegister};
+use crate::memory::history::WriteTarget;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const XPSR_REGISTER_ID: u16 = 16;
+pub const MSP_REGISTER_ID: u16 = 17;
+pub const PSP_REGISTER_ID: u16 = 18;
+
+/// CONTROL.SPSEL: Thread mode uses the PSP.
+const CONTROL_SPSEL: u32 = 1 << 1;
+/// xPSR exception number field; zero in Thread mode.
+const XPSR_EXCEPTION: u32 = 0x1FF;
+/// EXC_RETURN bit 2: return to the PSP.
+const EXC_RETURN_PSP: u32 = 1 << 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StackPointer {
+    Msp,
+    Psp,
+}
+
+impl StackPointer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Msp => "MSP",
+            Self::Psp => "PSP",
+        }
+    }
+
+    pub fn register_id(self) -> u16 {
+        match self {
+            Self::Msp => MSP_REGISTER_ID,
+            Self::Psp => PSP_REGISTER_ID,
+        }
+    }
+}
+
+/// The stack pointer in use for the given CONTROL and xPSR values.
+pub fn active_stack_pointer(control: u32, xpsr: u32) -> StackPointer {
+    let handler_mode = xpsr & XPSR_EXCEPTION != 0;
+    if !handler_mode && control & CONTROL_SPSEL != 0 {
+        StackPointer::Psp
+    } else {
+        StackPointer::Msp
+    }
+}
+
+/// The stack an exception frame was pushed to, if `lr` holds an EXC_RETURN value.
+pub fn exc_return_stack(lr: u32) -> Option<StackPointer> {
+    if lr & 0xFF00_0000 != 0xFF00_0000 {
+        return None;
+    }
+    Some(if lr & EXC_RETURN_PSP != 0 { StackPointer::Psp } else { StackPointer::Msp })
+}
+
+/// Which stack pointer the core is using and its value.
+pub fn read_active_stack_pointer<T: WriteTarget + ?Sized>(
+    target: &mut T,
+) -> Result<(StackPointer, u64)> {
+    let control = read_special_reg(target, SpecialRegister::Control)?;
+    let xpsr = target.read_register(XPSR_REGISTER_ID).context("Failed to read xPSR")? as u32;
+    let which = active_stack_pointer(control, xpsr);
+    let value = target
+        .read_register(which.register_id())
+        .with_context(|| format!("Failed to read {}", which.name()))?;
+    Ok((which, value))
+}
+
+/// Make Thread mode use `which` by writing CONTROL.SPSEL. Refused unless the
+/// core is halted in Thread mode, as Handler mode always uses the MSP.
+pub fn select_stack_pointer<T: WriteTarget + ?Sized>(
+    target: &mut T,
+    halted: bool,
+    which: StackPointer,
+) -> Result<()> {
+    let xpsr = target.read_register(XPSR_REGISTER_ID).context("Failed to read xPSR")? as u32;
+    if xpsr & XPSR_EXCEPTION != 0 {
+        anyhow::bail!("The core is in an exception handler, which always uses the MSP");
+    }
+    let control = read_special_reg(target, SpecialRegister::Control)?;
+    let control = match which {
+        StackPointer::Msp => control & !CONTROL_SPSEL,
+        StackPointer::Psp => control | CONTROL_SPSEL,
+    };
+    write_special_reg(target, halted, SpecialRegister::Control, control)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_pointer_decoding() {
+        // Thread mode follows SPSEL.
+        assert_eq!(active_stack_pointer(0x0, 0x0100_0000), StackPointer::Msp);
+        assert_eq!(active_stack_pointer(0x2, 0x0100_0000), StackPointer::Psp);
+        assert_eq!(active_stack_pointer(0x3, 0x0100_0000), StackPointer::Psp);
+        // Handler mode (HardFault, SysTick) is always on the MSP.
+        assert_eq!(active_stack_pointer(0x2, 0x0100_0003), StackPointer::Msp);
+        assert_eq!(active_stack_pointer(0x2, 0x0100_000F), StackPointer::Msp);
+
+        // Return to Thread mode on PSP / MSP, and to Handler mode.
+        assert_eq!(exc_return_stack(0xFFFF_FFFD), Some(StackPointer::Psp));
+        assert_eq!(exc_return_stack(0xFFFF_FFF9), Some(StackPointer::Msp));
+        assert_eq!(exc_return_stack(0xFFFF_FFF1), Some(StackPointer::Msp));
+        // With the FPU frame bit clear.
+        assert_eq!(exc_return_stack(0xFFFF_FFED), Some(StackPointer::Psp));
+        // An ordinary return address.
+        assert_eq!(exc_return_stack(0x0800_0125), None);
+    }
+}
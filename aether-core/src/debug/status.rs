@@ -0,0 +1,127 @@
+//! Core status polling module.
+//!
+//! `PollStatus` requests only mark the status as wanted; the session's idle
+//! cycle then does one status read, so a burst of requests costs a single
+//! probe access.
+
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::CoreStatus;
+use anyhow::Result;
+#[cfg(feature = "hardware")]
+use probe_rs::{Core, CoreStatus};
+
+/// Source of the core status.
+///
+/// One read per poll; a failed read is what the session counts towards
+/// declaring the probe lost.
+pub trait StatusSource {
+    fn read_status(&mut self) -> Result<CoreStatus>;
+}
+
+#[cfg(feature = "hardware")]
+impl StatusSource for Core<'_> {
+    fn read_status(&mut self) -> Result<CoreStatus> {
+        Ok(self.status()?)
+    }
+}
+
+/// Tracks the last reported status and pending `PollStatus` requests.
+#[derive(Debug, Default)]
+pub struct StatusPoller {
+    last: Option<CoreStatus>,
+    requested: bool,
+}
+
+impl StatusPoller {
+    /// A poller with no status seen yet, so the first poll always reports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report the status on the next poll even if it did not change.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Forget the last status, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// The status seen by the last poll (or set by `mark_running`); `None`
+    /// before the first poll and after `reset`.
+    pub fn last(&self) -> Option<CoreStatus> {
+        self.last
+    }
+
+    /// Record that the session resumed the core itself, e.g. to run on from a
+    /// task switch or tracepoint. A halt before the next poll is then still a
+    /// change and gets reported.
+    pub fn mark_running(&mut self) {
+        self.last = Some(CoreStatus::Running);
+    }
+
+    /// Read the status once and return it when it changed or was requested.
+    pub fn poll(&mut self, core: &mut (impl StatusSource + ?Sized)) -> Result<Option<CoreStatus>> {
+        let status = core.read_status()?;
+        let report = self.requested || self.last != Some(status);
+        self.requested = false;
+        self.last = Some(status);
+        Ok(report.then_some(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "hardware"))]
+    use crate::probe_rs::HaltReason;
+    #[cfg(feature = "hardware")]
+    use probe_rs::HaltReason;
+
+    struct MockCore {
+        reads: usize,
+    }
+
+    impl StatusSource for MockCore {
+        fn read_status(&mut self) -> Result<CoreStatus> {
+            self.reads += 1;
+            Ok(CoreStatus::Running)
+        }
+    }
+
+    #[test]
+    fn test_poll_status_requests_coalesce() {
+        let mut core = MockCore { reads: 0 };
+        let mut poller = StatusPoller::new();
+        assert_eq!(poller.poll(&mut core).unwrap(), Some(CoreStatus::Running));
+
+        for _ in 0..5 {
+            poller.request();
+        }
+        assert_eq!(poller.poll(&mut core).unwrap(), Some(CoreStatus::Running));
+        assert_eq!(core.reads, 2);
+
+        // Unchanged and not requested: read, but nothing to report.
+        assert_eq!(poller.poll(&mut core).unwrap(), None);
+        assert_eq!(core.reads, 3);
+    }
+
+    struct HaltedCore;
+
+    impl StatusSource for HaltedCore {
+        fn read_status(&mut self) -> Result<CoreStatus> {
+            Ok(CoreStatus::Halted(HaltReason::Request))
+        }
+    }
+
+    #[test]
+    fn test_halt_after_session_resume_is_reported() {
+        let mut poller = StatusPoller::new();
+        assert!(poller.poll(&mut HaltedCore).unwrap().is_some());
+
+        // Resumed by the session and halted again before the next poll.
+        poller.mark_running();
+        assert!(poller.poll(&mut HaltedCore).unwrap().is_some_and(|s| s.is_halted()));
+    }
+}
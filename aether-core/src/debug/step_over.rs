@@ -0,0 +1,151 @@
+//! Instruction-level step over.
+//!
+//! Steps a single instruction, except that a call is run to completion: a
+//! temporary breakpoint on the instruction after the call catches the return.
+//! Unlike statement stepping this needs no debug info, only the disassembly of
+//! the current instruction.
+
+use super::run_to_main::RunControl;
+use crate::disasm::InstructionInfo;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::Core;
+use std::time::Duration;
+
+/// How long to wait for a called function to return.
+pub const STEP_OVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run control plus single-stepping.
+pub trait StepControl: RunControl {
+    /// Execute one instruction, returning the new PC.
+    fn step(&mut self) -> Result<u64>;
+}
+
+#[cfg(feature = "hardware")]
+impl StepControl for Core<'_> {
+    fn step(&mut self) -> Result<u64> {
+        Ok(Core::step(self)?.pc)
+    }
+}
+
+/// Step over `insn`, the instruction at the current PC, returning the PC the
+/// core halted at.
+///
+/// If the call hits another breakpoint first, the core stays halted there. The
+/// temporary breakpoint is removed afterwards unless `keep_breakpoint` is set
+/// because the user already has one at the return address. A call that does
+/// not return within `timeout` is an error and leaves the core running, for
+/// the caller to halt.
+pub fn step_over_instruction<C: StepControl + ?Sized>(
+    core: &mut C,
+    insn: &InstructionInfo,
+    keep_breakpoint: bool,
+    timeout: Duration,
+) -> Result<u64> {
+    if !insn.is_call {
+        return core.step();
+    }
+
+    let return_address = insn.next_address();
+    core.set_hw_breakpoint(return_address).context("Failed to set breakpoint after call")?;
+    let result = core.run().and_then(|()| core.wait_for_halt(timeout));
+    if !keep_breakpoint {
+        core.clear_hw_breakpoint(return_address)
+            .context("Failed to clear breakpoint after call")?;
+    }
+    result.context("Call did not return")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::breakpoint::HwBreakpoints;
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct MockCore {
+        pc: u64,
+        hw: HashSet<u64>,
+        /// Breakpoints that were armed while the core ran.
+        armed_while_running: Vec<u64>,
+        steps: usize,
+    }
+
+    impl HwBreakpoints for MockCore {
+        fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.insert(address);
+            Ok(())
+        }
+        fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.remove(&address);
+            Ok(())
+        }
+    }
+
+    impl RunControl for MockCore {
+        fn reset_and_halt(&mut self) -> Result<u64> {
+            unreachable!("step over never resets")
+        }
+        fn run(&mut self) -> Result<()> {
+            self.armed_while_running.extend(self.hw.iter().copied());
+            Ok(())
+        }
+        fn wait_for_halt(&mut self, _timeout: Duration) -> Result<u64> {
+            self.pc = *self.hw.iter().next().context("core never halts")?;
+            Ok(self.pc)
+        }
+    }
+
+    impl StepControl for MockCore {
+        fn step(&mut self) -> Result<u64> {
+            self.steps += 1;
+            self.pc += 2;
+            Ok(self.pc)
+        }
+    }
+
+    fn insn(mnemonic: &str, bytes: Vec<u8>, is_call: bool) -> InstructionInfo {
+        InstructionInfo {
+            address: 0x0800_0100,
+            mnemonic: mnemonic.to_string(),
+            op_str: String::new(),
+            bytes,
+            is_branch: is_call,
+            is_call,
+            is_ret: false,
+            branch_target: None,
+        }
+    }
+
+    #[test]
+    fn test_step_over_call_breaks_after_it() {
+        let mut core = MockCore { pc: 0x0800_0100, ..Default::default() };
+        let bl = insn("bl", vec![0x00, 0xf0, 0x06, 0xf8], true);
+
+        let pc = step_over_instruction(&mut core, &bl, false, STEP_OVER_TIMEOUT).unwrap();
+        assert_eq!(pc, 0x0800_0104);
+        assert_eq!(core.armed_while_running, vec![0x0800_0104]);
+        assert_eq!(core.steps, 0);
+        assert!(core.hw.is_empty(), "temporary breakpoint is removed");
+
+        // A user breakpoint on the return address survives.
+        core.pc = 0x0800_0100;
+        step_over_instruction(&mut core, &bl, true, STEP_OVER_TIMEOUT).unwrap();
+        assert!(core.hw.contains(&0x0800_0104));
+    }
+
+    #[test]
+    fn test_step_over_plain_instruction_steps() {
+        let mut core = MockCore { pc: 0x0800_0100, ..Default::default() };
+        let pc = step_over_instruction(
+            &mut core,
+            &insn("movs", vec![0x01, 0x20], false),
+            false,
+            STEP_OVER_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(pc, 0x0800_0102);
+        assert_eq!(core.steps, 1);
+        assert!(core.armed_while_running.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! SysTick timebase module.
+//!
+//! Reads the Cortex-M SysTick registers to derive the tick rate, so timeline
+//! timestamps can be related to the target's own timebase.
+
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::MemoryInterface;
+use crate::symbols::SymbolManager;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::MemoryInterface;
+use serde::{Deserialize, Serialize};
+
+/// SysTick Control and Status Register.
+pub const SYST_CSR: u64 = 0xE000_E010;
+/// SysTick Reload Value Register.
+pub const SYST_RVR: u64 = 0xE000_E014;
+/// SysTick Current Value Register.
+pub const SYST_CVR: u64 = 0xE000_E018;
+/// SysTick Calibration Value Register.
+pub const SYST_CALIB: u64 = 0xE000_E01C;
+
+const CSR_ENABLE: u32 = 1 << 0;
+const CSR_CLKSOURCE: u32 = 1 << 2;
+const CALIB_NOREF: u32 = 1 << 31;
+const CALIB_TENMS: u32 = 0x00FF_FFFF;
+/// RVR and CVR hold 24-bit values; bits 31:24 are reserved.
+const COUNTER_MASK: u32 = 0x00FF_FFFF;
+
+/// Decoded SysTick configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Timebase {
+    /// Core clock in Hz.
+    pub core_hz: u32,
+    /// SysTick interrupt rate in Hz (0 if the counter is disabled).
+    pub tick_hz: f64,
+    /// Reload value (RVR).
+    pub reload: u32,
+    /// Current counter value (CVR).
+    pub current: u32,
+    /// Counter clocked from the processor clock rather than the reference clock.
+    pub processor_clock: bool,
+}
+
+/// Derive the tick rate from raw SysTick register values.
+///
+/// When SysTick runs from the external reference clock, its frequency is taken
+/// from SYST_CALIB (TENMS is the reload for 10ms) or assumed to be `core_hz / 8`.
+pub fn decode_systick(csr: u32, rvr: u32, cvr: u32, calib: u32, core_hz: u32) -> Timebase {
+    let processor_clock = csr & CSR_CLKSOURCE != 0;
+    let source_hz = if processor_clock {
+        f64::from(core_hz)
+    } else if calib & CALIB_NOREF == 0 && calib & CALIB_TENMS != 0 {
+        f64::from((calib & CALIB_TENMS) + 1) * 100.0
+    } else {
+        f64::from(core_hz) / 8.0
+    };
+
+    let reload = rvr & COUNTER_MASK;
+    let tick_hz =
+        if csr & CSR_ENABLE != 0 && reload != 0 { source_hz / f64::from(reload + 1) } else { 0.0 };
+
+    Timebase { core_hz, tick_hz, reload, current: cvr & COUNTER_MASK, processor_clock }
+}
+
+/// Read the SysTick registers and the core clock: CMSIS `SystemCoreClock` when
+/// the symbol is known, otherwise `fallback_hz` (e.g. detected from RCC).
+pub fn read_timebase(
+    core: &mut dyn MemoryInterface,
+    symbols: &SymbolManager,
+    fallback_hz: Option<u32>,
+) -> Result<Timebase> {
+    let core_hz = match symbols.lookup_symbol("SystemCoreClock") {
+        Some(clock_addr) => core.read_word_32(clock_addr)?,
+        None => fallback_hz.context(
+            "SystemCoreClock not found and no core clock detected; load symbols or an SVD",
+        )?,
+    };
+
+    let csr = core.read_word_32(SYST_CSR)?;
+    let rvr = core.read_word_32(SYST_RVR)?;
+    let cvr = core.read_word_32(SYST_CVR)?;
+    let calib = core.read_word_32(SYST_CALIB)?;
+    Ok(decode_systick(csr, rvr, cvr, calib, core_hz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_systick_tick_rate() {
+        // 168 MHz core, processor clock, 1 kHz tick (RVR = 167999).
+        let tb = decode_systick(0b111, 167_999, 1234, 0, 168_000_000);
+        assert!(tb.processor_clock);
+        assert_eq!(tb.reload, 167_999);
+        assert_eq!(tb.current, 1234);
+        assert!((tb.tick_hz - 1000.0).abs() < 1e-9);
+
+        // Reference clock from SYST_CALIB: TENMS = 20999 -> 2.1 MHz, RVR = 2099 -> 1 kHz.
+        let tb = decode_systick(0b011, 2099, 0, 20_999, 168_000_000);
+        assert!(!tb.processor_clock);
+        assert!((tb.tick_hz - 1000.0).abs() < 1e-9);
+
+        // Reserved upper bits of RVR/CVR are ignored.
+        let tb = decode_systick(0b111, 0xFF00_0000 | 167_999, 0xFF00_0000 | 1234, 0, 168_000_000);
+        assert_eq!((tb.reload, tb.current), (167_999, 1234));
+
+        // Disabled counter has no tick rate.
+        assert_eq!(decode_systick(0b100, 167_999, 0, 0, 168_000_000).tick_hz, 0.0);
+    }
+}
@@ -0,0 +1,328 @@
+//! Break on RTOS task switch.
+//!
+//! A breakpoint on the scheduler's switch function shows the outgoing task, but
+//! the incoming one is only chosen inside it. So on entry a temporary breakpoint
+//! is set at the return address, and the switch is reported once it is hit.
+
+use super::breakpoint::HwBreakpoints;
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::Core;
+use crate::rtos::RtosAware;
+use crate::symbols::SymbolManager;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::Core;
+use serde::{Deserialize, Serialize};
+
+/// Link register, holding the switch function's return address on entry.
+const LR_REGISTER_ID: u16 = 14;
+
+/// What to do after reporting a task switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskSwitchMode {
+    /// Keep running, e.g. to log the schedule.
+    Resume,
+    /// Stop at the first instruction after the switch.
+    Halt,
+}
+
+/// Target operations a task switch break needs.
+pub trait TaskSwitchTarget: HwBreakpoints {
+    fn read_register(&mut self, id: u16) -> Result<u64>;
+    fn resume(&mut self) -> Result<()>;
+    /// Handle of the task the scheduler has selected.
+    fn current_task(&mut self) -> Result<Option<u32>>;
+}
+
+/// A core together with the RTOS used to find the current task.
+pub struct CoreWithRtos<'a, C> {
+    pub core: &'a mut C,
+    pub symbols: &'a SymbolManager,
+    pub rtos: &'a dyn RtosAware,
+}
+
+#[cfg(feature = "hardware")]
+impl HwBreakpoints for CoreWithRtos<'_, Core<'_>> {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        HwBreakpoints::set_hw_breakpoint(self.core, address)
+    }
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        HwBreakpoints::clear_hw_breakpoint(self.core, address)
+    }
+}
+
+#[cfg(feature = "hardware")]
+impl TaskSwitchTarget for CoreWithRtos<'_, Core<'_>> {
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        crate::DebugManager::new().read_core_reg(self.core, id)
+    }
+    fn resume(&mut self) -> Result<()> {
+        crate::DebugManager::new().resume(self.core)
+    }
+    fn current_task(&mut self) -> Result<Option<u32>> {
+        self.rtos.current_task(&mut *self.core, self.symbols)
+    }
+}
+
+#[cfg(not(feature = "hardware"))]
+impl HwBreakpoints for CoreWithRtos<'_, Core> {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        HwBreakpoints::set_hw_breakpoint(self.core, address)
+    }
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        HwBreakpoints::clear_hw_breakpoint(self.core, address)
+    }
+}
+
+#[cfg(not(feature = "hardware"))]
+impl TaskSwitchTarget for CoreWithRtos<'_, Core> {
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        crate::DebugManager::new().read_core_reg(self.core, id)
+    }
+    fn resume(&mut self) -> Result<()> {
+        crate::DebugManager::new().resume(self.core)
+    }
+    fn current_task(&mut self) -> Result<Option<u32>> {
+        self.rtos.current_task(&mut *self.core, self.symbols)
+    }
+}
+
+/// A completed task switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskSwitch {
+    pub from: Option<u32>,
+    pub to: u32,
+}
+
+/// An armed task switch breakpoint.
+#[derive(Debug)]
+pub struct TaskSwitchBreak {
+    entry: u64,
+    mode: TaskSwitchMode,
+    /// Return address and outgoing task while inside the switch function.
+    returning: Option<(u64, Option<u32>)>,
+}
+
+impl TaskSwitchBreak {
+    /// Set a breakpoint on the switch function at `entry`.
+    pub fn arm<C: HwBreakpoints + ?Sized>(
+        core: &mut C,
+        entry: u64,
+        mode: TaskSwitchMode,
+    ) -> Result<Self> {
+        core.set_hw_breakpoint(entry)
+            .with_context(|| format!("Failed to set breakpoint on 0x{:08X}", entry))?;
+        Ok(Self { entry, mode, returning: None })
+    }
+
+    /// Remove the breakpoints this set.
+    pub fn disarm<C: HwBreakpoints + ?Sized>(self, core: &mut C) -> Result<()> {
+        if let Some((ret, _)) = self.returning {
+            core.clear_hw_breakpoint(ret)?;
+        }
+        core.clear_hw_breakpoint(self.entry)
+    }
+
+    pub fn mode(&self) -> TaskSwitchMode {
+        self.mode
+    }
+
+    /// Handle a halt at `pc`. Returns `None` for halts that are not ours.
+    ///
+    /// On entry the core is resumed to the return address and `Ok(None)` is
+    /// returned. At the return address the switch is returned; the core is
+    /// resumed in [`TaskSwitchMode::Resume`] and left halted otherwise.
+    pub fn on_halt(
+        &mut self,
+        target: &mut dyn TaskSwitchTarget,
+        pc: u64,
+    ) -> Option<Result<Option<TaskSwitch>>> {
+        if pc == self.entry && self.returning.is_none() {
+            return Some(self.enter(target).map(|()| None));
+        }
+        let (ret, from) = self.returning.filter(|&(ret, _)| ret == pc)?;
+        self.returning = None;
+        Some(self.leave(target, ret, from).map(Some))
+    }
+
+    fn enter(&mut self, target: &mut dyn TaskSwitchTarget) -> Result<()> {
+        let from = target.current_task().context("Failed to read the current task")?;
+        // Thumb return addresses carry the mode in bit 0.
+        let ret = target.read_register(LR_REGISTER_ID).context("Failed to read LR")? & !1;
+        target
+            .set_hw_breakpoint(ret)
+            .with_context(|| format!("Failed to set breakpoint on return to 0x{:08X}", ret))?;
+        self.returning = Some((ret, from));
+        target.resume()
+    }
+
+    fn leave(
+        &mut self,
+        target: &mut dyn TaskSwitchTarget,
+        ret: u64,
+        from: Option<u32>,
+    ) -> Result<TaskSwitch> {
+        target.clear_hw_breakpoint(ret)?;
+        let to = target
+            .current_task()
+            .context("Failed to read the current task")?
+            .context("The scheduler selected no task")?;
+        if self.mode == TaskSwitchMode::Resume {
+            target.resume()?;
+        }
+        Ok(TaskSwitch { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::run_to_main::RunControl;
+    use crate::debug::status::StatusPoller;
+    use crate::fake::FakeSession;
+    use crate::memory::history::WriteTarget;
+    #[cfg(not(feature = "hardware"))]
+    use crate::probe_rs::MemoryInterface;
+    use crate::TaskInfo;
+    #[cfg(feature = "hardware")]
+    use probe_rs::MemoryInterface;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    /// An RTOS whose scheduler switches tasks in `main`.
+    struct MockRtos;
+
+    impl RtosAware for MockRtos {
+        fn name(&self) -> &str {
+            "Mock"
+        }
+        fn get_tasks(
+            &self,
+            _core: &mut dyn MemoryInterface,
+            _symbols: &SymbolManager,
+        ) -> Result<Vec<TaskInfo>> {
+            Ok(Vec::new())
+        }
+        fn switch_function(&self, symbols: &SymbolManager) -> Option<u64> {
+            symbols.function_address("main")
+        }
+    }
+
+    #[derive(Default)]
+    struct MockTarget {
+        hw: HashSet<u64>,
+        lr: u64,
+        current: Option<u32>,
+        running: bool,
+    }
+
+    impl HwBreakpoints for MockTarget {
+        fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.insert(address);
+            Ok(())
+        }
+        fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.hw.remove(&address);
+            Ok(())
+        }
+    }
+
+    impl TaskSwitchTarget for MockTarget {
+        fn read_register(&mut self, id: u16) -> Result<u64> {
+            assert_eq!(id, LR_REGISTER_ID);
+            Ok(self.lr)
+        }
+        fn resume(&mut self) -> Result<()> {
+            self.running = true;
+            Ok(())
+        }
+        fn current_task(&mut self) -> Result<Option<u32>> {
+            Ok(self.current)
+        }
+    }
+
+    #[test]
+    fn test_task_switch_break_reports_switch() {
+        let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        elf_path.push("tests/fixtures/c_types.elf");
+        let mut symbols = SymbolManager::new();
+        symbols.load_elf(&elf_path).unwrap();
+        let entry = MockRtos.switch_function(&symbols).expect("switch function not found");
+        assert_eq!(crate::rtos::freertos::FreeRtos::new().switch_function(&symbols), None);
+
+        let mut target =
+            MockTarget { lr: 0x0800_0201, current: Some(0x2000_1000), ..Default::default() };
+        let mut brk = TaskSwitchBreak::arm(&mut target, entry, TaskSwitchMode::Halt).unwrap();
+        assert!(target.hw.contains(&entry));
+        assert!(brk.on_halt(&mut target, 0x0800_0300).is_none());
+
+        // Entry: run on to the return address.
+        assert_eq!(brk.on_halt(&mut target, entry).unwrap().unwrap(), None);
+        assert!(target.running);
+        assert!(target.hw.contains(&0x0800_0200));
+
+        // Return: the scheduler picked another task.
+        target.running = false;
+        target.current = Some(0x2000_2000);
+        let switch = brk.on_halt(&mut target, 0x0800_0200).unwrap().unwrap();
+        assert_eq!(switch, Some(TaskSwitch { from: Some(0x2000_1000), to: 0x2000_2000 }));
+        assert!(!target.running, "halt mode stays halted");
+        assert!(!target.hw.contains(&0x0800_0200));
+
+        brk.disarm(&mut target).unwrap();
+        assert!(target.hw.is_empty());
+    }
+
+    /// A [`FakeSession`] whose scheduler state is set by the test.
+    struct FakeTarget {
+        fake: FakeSession,
+        current: Option<u32>,
+    }
+
+    impl HwBreakpoints for FakeTarget {
+        fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.fake.set_hw_breakpoint(address)
+        }
+        fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+            self.fake.clear_hw_breakpoint(address)
+        }
+    }
+
+    impl TaskSwitchTarget for FakeTarget {
+        fn read_register(&mut self, id: u16) -> Result<u64> {
+            WriteTarget::read_register(&mut self.fake, id)
+        }
+        fn resume(&mut self) -> Result<()> {
+            self.fake.run()
+        }
+        fn current_task(&mut self) -> Result<Option<u32>> {
+            Ok(self.current)
+        }
+    }
+
+    #[test]
+    fn test_return_hit_before_next_poll_reports_switch() {
+        let (entry, ret) = (0x0800_0100, 0x0800_0200);
+        let fake = FakeSession::new();
+        fake.set_register(LR_REGISTER_ID, ret | 1);
+        let mut target = FakeTarget { fake: fake.clone(), current: Some(0x2000_1000) };
+        let mut poller = StatusPoller::new();
+        let mut brk = TaskSwitchBreak::arm(&mut target, entry, TaskSwitchMode::Halt).unwrap();
+        target.fake.run().unwrap();
+        assert!(poller.poll(&mut target.fake).unwrap().is_some_and(|s| !s.is_halted()));
+
+        // Entry: the session runs on to the return address.
+        assert!(fake.hit(entry));
+        assert!(poller.poll(&mut target.fake).unwrap().is_some_and(|s| s.is_halted()));
+        assert_eq!(brk.on_halt(&mut target, entry).unwrap().unwrap(), None);
+        poller.mark_running();
+
+        // The return is reached well inside the poll interval.
+        target.current = Some(0x2000_2000);
+        assert!(fake.hit(ret));
+        assert!(poller.poll(&mut target.fake).unwrap().is_some_and(|s| s.is_halted()));
+        let switch = brk.on_halt(&mut target, ret).unwrap().unwrap();
+        assert_eq!(switch, Some(TaskSwitch { from: Some(0x2000_1000), to: 0x2000_2000 }));
+        assert_eq!(fake.breakpoints(), vec![entry]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Tracepoints: breakpoints that log values and resume instead of stopping.
+
+#[cfg(feature = "hardware")]
+use super::run_to_main::RunControl;
+#[cfg(feature = "hardware")]
+use crate::memory::history::WriteTarget;
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::Core;
+use crate::symbols::SymbolManager;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Values logged by one tracepoint hit: each expression with its value, or `None`
+/// when it could not be read.
+pub type TracepointValues = Vec<(String, Option<String>)>;
+
+/// Target operations a tracepoint hit needs.
+pub trait TracepointTarget {
+    fn read_register(&mut self, id: u16) -> Result<u64>;
+    /// Formatted value of a global variable.
+    fn read_variable(&mut self, name: &str) -> Option<String>;
+    fn resume(&mut self) -> Result<()>;
+}
+
+/// A core together with the symbols used to resolve variable names.
+pub struct CoreWithSymbols<'a, C> {
+    pub core: &'a mut C,
+    pub symbols: &'a SymbolManager,
+}
+
+/// Any session core; variables can only be read through a probe-rs core.
+#[cfg(feature = "hardware")]
+impl<'p, C: crate::session::TargetCore<'p>> TracepointTarget for CoreWithSymbols<'_, C> {
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        WriteTarget::read_register(self.core, id)
+    }
+    fn read_variable(&mut self, name: &str) -> Option<String> {
+        let address = self.symbols.lookup_symbol(name)?;
+        let core = self.core.probe_core()?;
+        let info = self.symbols.resolve_variable(core, name, address)?;
+        Some(info.value_formatted_string)
+    }
+    fn resume(&mut self) -> Result<()> {
+        RunControl::run(self.core)
+    }
+}
+
+#[cfg(not(feature = "hardware"))]
+impl TracepointTarget for CoreWithSymbols<'_, Core> {
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        crate::DebugManager::new().read_core_reg(self.core, id)
+    }
+    fn read_variable(&mut self, name: &str) -> Option<String> {
+        let address = self.symbols.lookup_symbol(name)?;
+        let info = self.symbols.resolve_variable(&mut *self.core, name, address)?;
+        Some(info.value_formatted_string)
+    }
+    fn resume(&mut self) -> Result<()> {
+        crate::DebugManager::new().resume(self.core)
+    }
+}
+
+/// Core register number for an ARM register name (`r0`-`r12`, `sp`, `lr`, `pc`).
+pub fn register_id(name: &str) -> Option<u16> {
+    match name.to_ascii_lowercase().as_str() {
+        "sp" => Some(13),
+        "lr" => Some(14),
+        "pc" => Some(15),
+        other => other.strip_prefix('r')?.parse().ok().filter(|&n| n <= 15),
+    }
+}
+
+/// Tracepoint addresses with the expressions read on each hit.
+///
+/// The session arms tracepoints as regular hardware breakpoints; this only keeps
+/// track of which halts should be logged and resumed.
+#[derive(Debug, Default)]
+pub struct TracepointManager {
+    points: BTreeMap<u64, Vec<String>>,
+}
+
+impl TracepointManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, address: u64, reads: Vec<String>) {
+        self.points.insert(address, reads);
+    }
+
+    /// Returns `true` if `address` was a tracepoint.
+    pub fn remove(&mut self, address: u64) -> bool {
+        self.points.remove(&address).is_some()
+    }
+
+    pub fn is_tracepoint(&self, address: u64) -> bool {
+        self.points.contains_key(&address)
+    }
+
+    /// Handle a halt at `pc`. If it is a tracepoint, read its expressions and resume
+    /// the core. Returns `None` for ordinary halts, which must be reported as usual.
+    pub fn on_halt(
+        &self,
+        target: &mut dyn TracepointTarget,
+        pc: u64,
+    ) -> Option<Result<TracepointValues>> {
+        let reads = self.points.get(&pc)?;
+        let values = reads
+            .iter()
+            .map(|expr| {
+                let value = match register_id(expr) {
+                    Some(id) => target.read_register(id).ok().map(|v| format!("0x{:08X}", v)),
+                    None => target.read_variable(expr),
+                };
+                (expr.clone(), value)
+            })
+            .collect();
+        Some(target.resume().map(|_| values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MockTarget {
+        registers: HashMap<u16, u64>,
+        variables: HashMap<String, String>,
+        resumed: bool,
+    }
+
+    impl TracepointTarget for MockTarget {
+        fn read_register(&mut self, id: u16) -> Result<u64> {
+            self.registers.get(&id).copied().ok_or_else(|| anyhow::anyhow!("no register {}", id))
+        }
+        fn read_variable(&mut self, name: &str) -> Option<String> {
+            self.variables.get(name).cloned()
+        }
+        fn resume(&mut self) -> Result<()> {
+            self.resumed = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_register_names() {
+        assert_eq!(register_id("r0"), Some(0));
+        assert_eq!(register_id("R12"), Some(12));
+        assert_eq!(register_id("sp"), Some(13));
+        assert_eq!(register_id("pc"), Some(15));
+        assert_eq!(register_id("r16"), None);
+        assert_eq!(register_id("counter"), None);
+    }
+
+    #[test]
+    fn test_tracepoint_hit_logs_values_and_resumes() {
+        let mut tracepoints = TracepointManager::new();
+        tracepoints.set(0x0800_0100, vec!["r0".into(), "counter".into(), "missing".into()]);
+
+        let mut target = MockTarget::default();
+        target.registers.insert(0, 0x2A);
+        target.variables.insert("counter".into(), "7".into());
+
+        // An ordinary halt is left alone.
+        assert!(tracepoints.on_halt(&mut target, 0x0800_0200).is_none());
+        assert!(!target.resumed);
+
+        let values = tracepoints.on_halt(&mut target, 0x0800_0100).unwrap().unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("r0".to_string(), Some("0x0000002A".to_string())),
+                ("counter".to_string(), Some("7".to_string())),
+                ("missing".to_string(), None),
+            ]
+        );
+        assert!(target.resumed);
+
+        assert!(tracepoints.remove(0x0800_0100));
+        assert!(!tracepoints.is_tracepoint(0x0800_0100));
+    }
+}
@@ -0,0 +1,111 @@
+//! Vector catch.
+//!
+//! The Cortex-M Debug Exception and Monitor Control Register can halt the core as
+//! soon as it takes selected exceptions, before any handler code runs. That is
+//! the only reliable way to stop at a transient fault.
+
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::MemoryInterface;
+use anyhow::{Context, Result};
+#[cfg(feature = "hardware")]
+use probe_rs::MemoryInterface;
+use serde::{Deserialize, Serialize};
+
+/// Debug Exception and Monitor Control Register.
+pub const DEMCR: u64 = 0xE000_EDFC;
+
+pub(crate) const VC_CORERESET: u32 = 1 << 0;
+const VC_MMERR: u32 = 1 << 4;
+const VC_NOCPERR: u32 = 1 << 5;
+const VC_CHKERR: u32 = 1 << 6;
+const VC_STATERR: u32 = 1 << 7;
+const VC_BUSERR: u32 = 1 << 8;
+const VC_INTERR: u32 = 1 << 9;
+const VC_HARDERR: u32 = 1 << 10;
+/// UsageFault causes: no coprocessor, checking error, state error.
+const VC_USAGE: u32 = VC_NOCPERR | VC_CHKERR | VC_STATERR;
+const VC_ALL: u32 = VC_CORERESET | VC_MMERR | VC_USAGE | VC_BUSERR | VC_INTERR | VC_HARDERR;
+
+/// Exceptions that halt the core when taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorCatch {
+    pub hardfault: bool,
+    pub reset: bool,
+    pub bus_fault: bool,
+    pub mem_manage: bool,
+    pub usage_fault: bool,
+}
+
+impl VectorCatch {
+    /// The DEMCR vector-catch bits for this configuration. HardFault also catches
+    /// faults during exception entry and return (VC_INTERR).
+    pub fn demcr_bits(&self) -> u32 {
+        let mut bits = 0;
+        if self.hardfault {
+            bits |= VC_HARDERR | VC_INTERR;
+        }
+        if self.reset {
+            bits |= VC_CORERESET;
+        }
+        if self.bus_fault {
+            bits |= VC_BUSERR;
+        }
+        if self.mem_manage {
+            bits |= VC_MMERR;
+        }
+        if self.usage_fault {
+            bits |= VC_USAGE;
+        }
+        bits
+    }
+
+    /// Decode the vector-catch bits of a DEMCR value.
+    pub fn from_demcr(demcr: u32) -> Self {
+        Self {
+            hardfault: demcr & VC_HARDERR != 0,
+            reset: demcr & VC_CORERESET != 0,
+            bus_fault: demcr & VC_BUSERR != 0,
+            mem_manage: demcr & VC_MMERR != 0,
+            usage_fault: demcr & VC_USAGE == VC_USAGE,
+        }
+    }
+}
+
+/// Program the vector-catch bits, leaving the rest of DEMCR (e.g. TRCENA) alone.
+/// Returns the configuration read back from the target.
+pub fn set_vector_catch(core: &mut dyn MemoryInterface, catch: VectorCatch) -> Result<VectorCatch> {
+    let demcr = core.read_word_32(DEMCR).context("Failed to read DEMCR")?;
+    core.write_word_32(DEMCR, (demcr & !VC_ALL) | catch.demcr_bits())
+        .context("Failed to write DEMCR")?;
+    let demcr = core.read_word_32(DEMCR).context("Failed to read back DEMCR")?;
+    Ok(VectorCatch::from_demcr(demcr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demcr_bits() {
+        assert_eq!(VectorCatch::default().demcr_bits(), 0);
+
+        let hardfault = VectorCatch { hardfault: true, ..Default::default() };
+        assert_eq!(hardfault.demcr_bits(), 0x0000_0600);
+
+        let reset = VectorCatch { reset: true, ..Default::default() };
+        assert_eq!(reset.demcr_bits(), 0x0000_0001);
+
+        let all = VectorCatch {
+            hardfault: true,
+            reset: true,
+            bus_fault: true,
+            mem_manage: true,
+            usage_fault: true,
+        };
+        assert_eq!(all.demcr_bits(), 0x0000_07F1);
+
+        // Round trip, ignoring unrelated DEMCR bits such as TRCENA.
+        assert_eq!(VectorCatch::from_demcr(all.demcr_bits() | 1 << 24), all);
+        assert_eq!(VectorCatch::from_demcr(1 << 24), VectorCatch::default());
+    }
+}
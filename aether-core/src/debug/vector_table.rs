@@ -0,0 +1,106 @@
+//! Vector table dump.
+//!
+//! Reads the exception vectors and names the handler each one points to, so a
+//! fault can be traced to the ISR that runs for it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Entries read when no count is given: the 16 system exceptions and the first
+/// 32 interrupts.
+pub const DEFAULT_VECTOR_COUNT: usize = 48;
+
+/// Architectural maximum: 16 system exceptions and 496 interrupts.
+pub const MAX_VECTOR_COUNT: usize = 16 + 496;
+
+/// One vector table entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorEntry {
+    pub index: usize,
+    /// Handler address with the Thumb bit cleared; the initial SP for entry 0.
+    pub address: u64,
+    /// Function the handler address falls in, if symbols are loaded.
+    pub symbol: Option<String>,
+}
+
+/// Read `count` 32-bit vectors at `base` and resolve each handler with `resolve`.
+/// At most [`MAX_VECTOR_COUNT`] entries are read.
+///
+/// Entry 0 holds the initial stack pointer and zero entries are reserved; neither
+/// is resolved.
+pub fn read_vector_entries<F, R>(
+    base: u64,
+    count: usize,
+    read: F,
+    resolve: R,
+) -> Result<Vec<VectorEntry>>
+where
+    F: FnOnce(u64, &mut [u8]) -> Result<()>,
+    R: Fn(u64) -> Option<String>,
+{
+    let mut data = vec![0u8; count.min(MAX_VECTOR_COUNT) * 4];
+    read(base, &mut data)
+        .with_context(|| format!("Failed to read vector table at 0x{:08X}", base))?;
+
+    Ok(data
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(index, word)| {
+            let value = u64::from(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+            if index == 0 {
+                return VectorEntry { index, address: value, symbol: None };
+            }
+            let address = value & !1;
+            let symbol = if address == 0 { None } else { resolve(address) };
+            VectorEntry { index, address, symbol }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_entries_clear_thumb_bit() {
+        let words = [0x2000_8001u32, 0x0800_0101, 0, 0x0800_0200];
+        let memory: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let entries = read_vector_entries(
+            0,
+            words.len(),
+            |_, buf| {
+                buf.copy_from_slice(&memory);
+                Ok(())
+            },
+            |address| (address == 0x0800_0100).then(|| "Reset_Handler".to_string()),
+        )
+        .unwrap();
+
+        // The initial SP is kept as is; handlers lose the Thumb bit.
+        let decoded: Vec<_> =
+            entries.iter().map(|e| (e.index, e.address, e.symbol.as_deref())).collect();
+        assert_eq!(
+            decoded,
+            vec![
+                (0, 0x2000_8001, None),
+                (1, 0x0800_0100, Some("Reset_Handler")),
+                (2, 0, None),
+                (3, 0x0800_0200, None),
+            ]
+        );
+
+        let mut requested = 0;
+        let entries = read_vector_entries(
+            0,
+            usize::MAX / 8,
+            |_, buf| {
+                requested = buf.len();
+                Ok(())
+            },
+            |_| None,
+        )
+        .unwrap();
+        assert_eq!(requested, MAX_VECTOR_COUNT * 4);
+        assert_eq!(entries.len(), MAX_VECTOR_COUNT);
+    }
+}
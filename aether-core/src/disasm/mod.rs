@@ -2,18 +2,65 @@
 //!
 //! Handles instruction disassembly using Capstone.
 
-use anyhow::{anyhow, Result};
+use crate::symbols::{SourceInfo, SymbolManager};
+use anyhow::{anyhow, Context, Result};
 use capstone::prelude::*;
+use std::ops::Range;
 
 /// Manager for disassembly operations.
 pub struct DisassemblyManager;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct InstructionInfo {
     pub address: u64,
     pub mnemonic: String,
     pub op_str: String,
     pub bytes: Vec<u8>,
+    /// Transfers control (jump, call or return).
+    pub is_branch: bool,
+    pub is_call: bool,
+    pub is_ret: bool,
+    /// Destination of a direct branch or call.
+    pub branch_target: Option<u64>,
+}
+
+impl InstructionInfo {
+    /// Address of the instruction that follows, where a call returns to.
+    pub fn next_address(&self) -> u64 {
+        self.address + self.bytes.len() as u64
+    }
+}
+
+/// Consecutive instructions generated for one source line, as in `objdump -S`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SourceLineBlock {
+    /// `None` for instructions the line table does not cover.
+    pub source_line: Option<SourceInfo>,
+    pub instructions: Vec<InstructionInfo>,
+}
+
+/// Group `instructions` under their source lines, found with `lookup`. A line
+/// gets a new block each time the code returns to it.
+pub fn interleave_source(
+    instructions: Vec<InstructionInfo>,
+    mut lookup: impl FnMut(u64) -> Option<SourceInfo>,
+) -> Vec<SourceLineBlock> {
+    let same_line = |a: &Option<SourceInfo>, b: &Option<SourceInfo>| match (a, b) {
+        (Some(a), Some(b)) => a.file == b.file && a.line == b.line,
+        (None, None) => true,
+        _ => false,
+    };
+    let mut blocks: Vec<SourceLineBlock> = Vec::new();
+    for insn in instructions {
+        let source_line = lookup(insn.address);
+        match blocks.last_mut() {
+            Some(block) if same_line(&block.source_line, &source_line) => {
+                block.instructions.push(insn)
+            }
+            _ => blocks.push(SourceLineBlock { source_line, instructions: vec![insn] }),
+        }
+    }
+    blocks
 }
 
 impl DisassemblyManager {
@@ -33,12 +80,14 @@ impl DisassemblyManager {
                 Capstone::new()
                     .arm()
                     .mode(arch::arm::ArchMode::Thumb) // Most Microcontrollers are Thumb
+                    .detail(true)
                     .build()
                     .map_err(|e| anyhow!("Failed to create Capstone: {}", e))?
             }
             "Riscv32" => Capstone::new()
                 .riscv()
                 .mode(arch::riscv::ArchMode::RiscV32)
+                .detail(true)
                 .build()
                 .map_err(|e| anyhow!("Failed to create Capstone: {}", e))?,
             _ => {
@@ -46,6 +95,7 @@ impl DisassemblyManager {
                 Capstone::new()
                     .arm()
                     .mode(arch::arm::ArchMode::Thumb)
+                    .detail(true)
                     .build()
                     .map_err(|e| anyhow!("Failed to create Capstone for {}: {}", arch, e))?
             }
@@ -56,14 +106,96 @@ impl DisassemblyManager {
 
         Ok(instructions
             .iter()
-            .map(|insn| InstructionInfo {
-                address: insn.address(),
-                mnemonic: insn.mnemonic().unwrap_or("???").to_string(),
-                op_str: insn.op_str().unwrap_or("").to_string(),
-                bytes: insn.bytes().to_vec(),
+            .map(|insn| {
+                let groups: Vec<String> = cs
+                    .insn_detail(insn)
+                    .map(|detail| {
+                        detail.groups().iter().filter_map(|&g| cs.group_name(g)).collect()
+                    })
+                    .unwrap_or_default();
+                let mut info = InstructionInfo {
+                    address: insn.address(),
+                    mnemonic: insn.mnemonic().unwrap_or("???").to_string(),
+                    op_str: insn.op_str().unwrap_or("").to_string(),
+                    bytes: insn.bytes().to_vec(),
+                    is_branch: false,
+                    is_call: false,
+                    is_ret: false,
+                    branch_target: None,
+                };
+                classify_flow(&mut info, &groups);
+                info
             })
             .collect())
     }
+
+    /// Decode the single instruction at `address`, reading its bytes with `read`.
+    pub fn instruction_at<F>(&self, arch: &str, address: u64, read: F) -> Result<InstructionInfo>
+    where
+        F: FnOnce(&mut [u8]) -> Result<()>,
+    {
+        // Long enough for any Thumb or RV32 instruction.
+        let mut code = [0u8; 4];
+        read(&mut code)?;
+        self.disassemble(arch, &code, address)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No valid instruction at 0x{:08X}", address))
+    }
+
+    /// Disassemble `range`, reading the code with `read`, and group the
+    /// instructions under their source lines.
+    pub fn disassemble_with_source<F>(
+        &self,
+        arch: &str,
+        range: Range<u64>,
+        symbols: &SymbolManager,
+        read: F,
+    ) -> Result<Vec<SourceLineBlock>>
+    where
+        F: FnOnce(u64, &mut [u8]) -> Result<()>,
+    {
+        let mut code = vec![0u8; range.end.saturating_sub(range.start) as usize];
+        read(range.start, &mut code)
+            .with_context(|| format!("Failed to read code at 0x{:08X}", range.start))?;
+        let instructions = self.disassemble(arch, &code, range.start)?;
+        Ok(interleave_source(instructions, |address| symbols.lookup(address)))
+    }
+}
+
+/// Fill in the control-flow fields from Capstone's instruction groups.
+///
+/// Capstone does not reliably put ARM `bl`/`blx` in the call group or `bx lr`
+/// and `pop {.., pc}` in the return group, so those are also recognized by
+/// mnemonic.
+fn classify_flow(info: &mut InstructionInfo, groups: &[String]) {
+    let has = |name: &str| groups.iter().any(|g| g == name);
+    let mnemonic = info.mnemonic.split('.').next().unwrap_or_default();
+    let op_str = info.op_str.as_str();
+
+    info.is_call = has("call")
+        || match mnemonic {
+            "bl" | "blx" => true,
+            // RISC-V links through `ra` unless another register is named.
+            "jal" | "jalr" => !op_str.contains(',') || op_str.starts_with("ra,"),
+            _ => false,
+        };
+    info.is_ret = has("ret")
+        || mnemonic == "ret"
+        || (mnemonic == "bx" && op_str == "lr")
+        || (mnemonic == "pop" && op_str.contains("pc"));
+    info.is_branch = info.is_call
+        || info.is_ret
+        || has("jump")
+        || has("branch_relative")
+        || matches!(mnemonic, "b" | "bx" | "cbz" | "cbnz");
+
+    if info.is_branch && !info.is_ret {
+        // The destination is the last operand when it is an immediate address.
+        let last = op_str.rsplit(',').next().unwrap_or_default().trim().trim_start_matches('#');
+        info.branch_target =
+            last.strip_prefix("0x").and_then(|hex| u64::from_str_radix(hex, 16).ok());
+    }
 }
 
 impl Default for DisassemblyManager {
@@ -90,6 +222,111 @@ mod tests {
         assert_eq!(insns[0].mnemonic, "nop");
     }
 
+    #[test]
+    fn test_instruction_at_reads_memory() {
+        let manager = DisassemblyManager::new();
+        // Target memory at 0x0800_0100: movs r0, #1; nop
+        let memory = [0x01u8, 0x20, 0x00, 0xbf];
+        let insn = manager
+            .instruction_at("Armv7em", 0x0800_0100, |buf| {
+                buf.copy_from_slice(&memory[..buf.len()]);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(insn.address, 0x0800_0100);
+        assert_eq!(insn.mnemonic, "movs");
+        assert_eq!(insn.bytes, vec![0x01, 0x20]);
+
+        assert!(manager.instruction_at("Armv7em", 0, |_| Err(anyhow!("bus fault"))).is_err());
+    }
+
+    #[test]
+    fn test_branch_groups_and_targets() {
+        let manager = DisassemblyManager::new();
+        let code = [
+            0x00, 0xf0, 0x06, 0xf8, // 0x1000: bl #0x1010
+            0xfc, 0xe7, // 0x1004: b #0x1000
+            0x70, 0x47, // 0x1006: bx lr
+            0x00, 0xbf, // 0x1008: nop
+        ];
+        let insns = manager.disassemble("Armv7m", &code, 0x1000).unwrap();
+        assert_eq!(insns.len(), 4);
+
+        let bl = &insns[0];
+        assert_eq!(bl.mnemonic, "bl");
+        assert!(bl.is_branch && bl.is_call && !bl.is_ret);
+        assert_eq!(bl.branch_target, Some(0x1010));
+        assert_eq!(bl.next_address(), 0x1004);
+
+        let b = &insns[1];
+        assert!(b.is_branch && !b.is_call && !b.is_ret);
+        assert_eq!(b.branch_target, Some(0x1000));
+
+        let ret = &insns[2];
+        assert!(ret.is_branch && ret.is_ret && !ret.is_call);
+        assert_eq!(ret.branch_target, None);
+
+        let nop = &insns[3];
+        assert!(!nop.is_branch && !nop.is_call && !nop.is_ret);
+    }
+
+    #[test]
+    fn test_interleave_source_groups_by_line() {
+        let mut elf_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        elf_path.push("tests/fixtures/c_types.elf");
+        let mut symbols = SymbolManager::new();
+        symbols.load_elf(&elf_path).unwrap();
+
+        // main in c_types.elf: prologue on line 27, the sum on line 28, the
+        // epilogue on line 29. Only addresses and lengths matter here.
+        let insn = |address: u64, len: usize| InstructionInfo {
+            address,
+            mnemonic: "insn".to_string(),
+            op_str: String::new(),
+            bytes: vec![0; len],
+            is_branch: false,
+            is_call: false,
+            is_ret: false,
+            branch_target: None,
+        };
+        let instructions = vec![
+            insn(0x40_1106, 1),
+            insn(0x40_1107, 3),
+            insn(0x40_110a, 7),
+            insn(0x40_1111, 2),
+            insn(0x40_1113, 7),
+            insn(0x40_115a, 2),
+            insn(0x40_115c, 1),
+            insn(0x40_115d, 1),
+        ];
+        let blocks = interleave_source(instructions, |address| symbols.lookup(address));
+
+        let summary: Vec<(Option<u32>, Vec<u64>)> = blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.source_line.as_ref().map(|s| s.line),
+                    block.instructions.iter().map(|i| i.address).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some(27), vec![0x40_1106, 0x40_1107]),
+                (Some(28), vec![0x40_110a, 0x40_1111, 0x40_1113, 0x40_115a]),
+                (Some(29), vec![0x40_115c, 0x40_115d]),
+            ]
+        );
+        assert!(blocks[1].source_line.as_ref().unwrap().file.ends_with("c_types.c"));
+
+        // Code with no line information is grouped on its own.
+        let blocks = interleave_source(vec![insn(0x100, 2), insn(0x102, 2)], |_| None);
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].source_line.is_none());
+    }
+
     #[test]
     fn test_disassemble_riscv() {
         let manager = DisassemblyManager::new();
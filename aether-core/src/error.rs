@@ -0,0 +1,193 @@
+//! Classification of probe errors.
+//!
+//! Probe operations fail with `anyhow::Error`s wrapping probe-rs errors. Many of
+//! them have a known cause the user can fix, so the session maps them to a
+//! [`DebugError`] and the UI can say what to do about it.
+
+use serde::{Deserialize, Serialize};
+
+/// Why a probe operation failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum DebugError {
+    #[error("the core is not halted")]
+    NotHalted,
+    #[error("the memory region is locked or read-protected")]
+    Locked,
+    #[error("the probe is busy")]
+    ProbeBusy,
+    #[error("the target is not powered")]
+    TargetPowerOff,
+    #[error("the target did not respond in time")]
+    Timeout,
+    #[error("no attach strategy succeeded ({})", summarize_attempts(attempts))]
+    AttachFailed { attempts: Vec<AttachAttempt> },
+    #[error("no function named '{0}'")]
+    UnknownSymbol(String),
+    #[error("no code at {file}:{line}")]
+    NoCodeAtLine { file: String, line: u32 },
+    #[error("{0}")]
+    Other(String),
+}
+
+/// One way of attaching that was tried and how it failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachAttempt {
+    /// Protocol, reset mode and chip, e.g. "SWD under reset (auto)".
+    pub stage: String,
+    pub error: String,
+}
+
+fn summarize_attempts(attempts: &[AttachAttempt]) -> String {
+    attempts.iter().map(|a| format!("{}: {}", a.stage, a.error)).collect::<Vec<_>>().join("; ")
+}
+
+/// Message fragments for each cause, checked in order against every error in
+/// the chain of errors that do not come from probe-rs (e.g. Aether's own checks).
+const PATTERNS: &[(&[&str], DebugError)] = &[
+    (&["not halted", "requires it to be halted", "core is running"], DebugError::NotHalted),
+    (&["locked", "read protection", "readout protection", "approtect"], DebugError::Locked),
+    (&["busy", "in use", "access denied"], DebugError::ProbeBusy),
+    (
+        &["target power", "powered off", "power off", "vtref", "target voltage"],
+        DebugError::TargetPowerOff,
+    ),
+    (&["timeout", "timed out"], DebugError::Timeout),
+];
+
+impl DebugError {
+    /// Map an operation error to its cause. Unrecognized errors keep their message.
+    pub fn classify(err: &anyhow::Error) -> Self {
+        if let Some(classified) = err.downcast_ref::<Self>() {
+            return classified.clone();
+        }
+        #[cfg(feature = "hardware")]
+        if let Some(typed) = err.chain().find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<probe_rs::Error>() {
+                return Some(from_probe_rs(e));
+            }
+            cause.downcast_ref::<probe_rs::probe::DebugProbeError>().map(from_debug_probe)
+        }) {
+            return typed.unwrap_or_else(|| Self::Other(format!("{:#}", err)));
+        }
+
+        for cause in err.chain() {
+            let message = cause.to_string().to_ascii_lowercase();
+            for (fragments, kind) in PATTERNS {
+                if fragments.iter().any(|f| message.contains(f)) {
+                    return kind.clone();
+                }
+            }
+        }
+        Self::Other(format!("{:#}", err))
+    }
+
+    /// What the user can do about it.
+    pub fn guidance(&self) -> Option<&'static str> {
+        match self {
+            Self::NotHalted => Some("Halt the core before reading registers or memory."),
+            Self::Locked => Some("Unlock the device or mass-erase it to remove read protection."),
+            Self::ProbeBusy => {
+                Some("Close other debuggers or tools using the probe and reconnect.")
+            }
+            Self::TargetPowerOff => {
+                Some("Check the target's power supply and the probe's VTref connection.")
+            }
+            Self::Timeout => Some("Check the wiring, lower the SWD speed or reset the target."),
+            Self::AttachFailed { .. } => Some(
+                "Check the SWD/JTAG wiring and that the board is powered, or select the exact \
+                 chip instead of auto-detection.",
+            ),
+            Self::UnknownSymbol(_) => {
+                Some("Load the ELF the target runs and check the spelling of the name.")
+            }
+            Self::NoCodeAtLine { .. } => {
+                Some("Pick a line with a statement on it; blank lines and comments have no code.")
+            }
+            Self::Other(_) => None,
+        }
+    }
+}
+
+/// The cause behind a probe-rs error, if it is one the user can act on.
+#[cfg(feature = "hardware")]
+fn from_probe_rs(err: &probe_rs::Error) -> Option<DebugError> {
+    use probe_rs::architecture::arm::ArmError;
+    match err {
+        probe_rs::Error::Timeout | probe_rs::Error::Arm(ArmError::Timeout) => {
+            Some(DebugError::Timeout)
+        }
+        probe_rs::Error::Probe(e) | probe_rs::Error::Arm(ArmError::Probe(e)) => from_debug_probe(e),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "hardware")]
+fn from_debug_probe(err: &probe_rs::probe::DebugProbeError) -> Option<DebugError> {
+    use probe_rs::probe::{DebugProbeError, ProbeCreationError};
+    match err {
+        DebugProbeError::Timeout => Some(DebugError::Timeout),
+        DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::CouldNotOpen) => {
+            Some(DebugError::ProbeBusy)
+        }
+        DebugProbeError::Usb(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            Some(DebugError::ProbeBusy)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_classify_probe_errors() {
+        let cases = [
+            ("Core is running but operation requires it to be halted", DebugError::NotHalted),
+            ("Flash memory is locked and cannot be written", DebugError::Locked),
+            ("USB error: Resource busy", DebugError::ProbeBusy),
+            ("Access denied (insufficient permissions)", DebugError::ProbeBusy),
+            ("Target power appears to be off (VTref = 0.00V)", DebugError::TargetPowerOff),
+            ("An operation timed out", DebugError::Timeout),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(DebugError::classify(&anyhow!(message)), expected, "{}", message);
+        }
+
+        // The cause is found below added context.
+        let err = Err::<(), _>(anyhow!("Core is not halted"))
+            .context("Failed to read register 15")
+            .unwrap_err();
+        assert_eq!(DebugError::classify(&err), DebugError::NotHalted);
+
+        let other = DebugError::classify(&anyhow!("Unknown chip"));
+        assert_eq!(other, DebugError::Other("Unknown chip".to_string()));
+        assert!(other.guidance().is_none());
+        assert!(DebugError::NotHalted.guidance().unwrap().starts_with("Halt the core"));
+    }
+
+    #[cfg(feature = "hardware")]
+    #[test]
+    fn test_classify_probe_rs_variants() {
+        use probe_rs::probe::{DebugProbeError, ProbeCreationError};
+
+        let err = anyhow::Error::from(probe_rs::Error::Timeout).context("Failed to halt");
+        assert_eq!(DebugError::classify(&err), DebugError::Timeout);
+
+        let err = anyhow::Error::from(probe_rs::Error::Probe(DebugProbeError::Timeout));
+        assert_eq!(DebugError::classify(&err), DebugError::Timeout);
+
+        let err = anyhow::Error::from(DebugProbeError::ProbeCouldNotBeCreated(
+            ProbeCreationError::CouldNotOpen,
+        ))
+        .context("Failed to open probe");
+        assert_eq!(DebugError::classify(&err), DebugError::ProbeBusy);
+
+        // A probe-rs error is classified by its variant, whatever its message says.
+        let err = anyhow::Error::from(probe_rs::Error::Probe(DebugProbeError::Usb(
+            std::io::Error::new(std::io::ErrorKind::Other, "device busy"),
+        )));
+        assert!(matches!(DebugError::classify(&err), DebugError::Other(_)));
+    }
+}
@@ -0,0 +1,228 @@
+//! In-memory debug target.
+//!
+//! [`FakeSession`] implements [`crate::session::DebugTarget`] on top of sparse
+//! memory and a register file, so the session's command/event loop can be
+//! driven end to end without a probe. Clones share state: a test keeps one clone to inject halts
+//! while the session thread owns the other.
+
+use crate::debug::breakpoint::HwBreakpoints;
+use crate::debug::low_power::HaltControl;
+use crate::debug::run_to_main::RunControl;
+use crate::debug::status::StatusSource;
+use crate::debug::step_over::StepControl;
+use crate::memory::history::WriteTarget;
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::HaltReason;
+#[cfg(feature = "hardware")]
+use crate::session::{DebugTarget, TargetCore};
+use crate::CoreStatus;
+use anyhow::Result;
+#[cfg(feature = "hardware")]
+use probe_rs::HaltReason;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Program counter register number (ARM `R15`).
+const PC: u16 = 15;
+
+#[cfg(feature = "hardware")]
+const BREAKPOINT_HALT: HaltReason = HaltReason::Breakpoint(probe_rs::BreakpointCause::Hardware);
+#[cfg(not(feature = "hardware"))]
+const BREAKPOINT_HALT: HaltReason = HaltReason::Breakpoint;
+
+#[derive(Debug)]
+struct FakeState {
+    status: CoreStatus,
+    registers: HashMap<u16, u64>,
+    memory: HashMap<u64, u8>,
+    breakpoints: BTreeSet<u64>,
+    /// Cleared to simulate the probe dropping off the bus.
+    connected: bool,
+}
+
+/// A target that lives entirely in memory. Starts halted at PC 0.
+#[derive(Debug, Clone)]
+pub struct FakeSession {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl Default for FakeSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeSession {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(FakeState {
+                status: CoreStatus::Halted(HaltReason::Request),
+                registers: HashMap::new(),
+                memory: HashMap::new(),
+                breakpoints: BTreeSet::new(),
+                connected: true,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Simulate the running core reaching `pc`. It halts if a hardware breakpoint
+    /// is armed there; returns whether it did.
+    pub fn hit(&self, pc: u64) -> bool {
+        let mut state = self.state();
+        if state.status.is_halted() || !state.breakpoints.contains(&pc) {
+            return false;
+        }
+        state.status = CoreStatus::Halted(BREAKPOINT_HALT);
+        state.registers.insert(PC, pc);
+        true
+    }
+
+    pub fn set_register(&self, id: u16, value: u64) {
+        self.state().registers.insert(id, value);
+    }
+
+    /// Place `data` in target memory at `address`, e.g. code to step over.
+    pub fn set_memory(&self, address: u64, data: &[u8]) {
+        let mut state = self.state();
+        for (i, &byte) in data.iter().enumerate() {
+            state.memory.insert(address + i as u64, byte);
+        }
+    }
+
+    /// Simulate the probe being unplugged (`false`) or coming back: status reads
+    /// fail while it is gone.
+    pub fn set_connected(&self, connected: bool) {
+        self.state().connected = connected;
+    }
+
+    /// Armed hardware breakpoints.
+    pub fn breakpoints(&self) -> Vec<u64> {
+        self.state().breakpoints.iter().copied().collect()
+    }
+}
+
+impl StatusSource for FakeSession {
+    fn read_status(&mut self) -> Result<CoreStatus> {
+        let state = self.state();
+        anyhow::ensure!(state.connected, "Probe disconnected");
+        Ok(state.status)
+    }
+}
+
+impl HwBreakpoints for FakeSession {
+    fn set_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        self.state().breakpoints.insert(address);
+        Ok(())
+    }
+    fn clear_hw_breakpoint(&mut self, address: u64) -> Result<()> {
+        self.state().breakpoints.remove(&address);
+        Ok(())
+    }
+}
+
+impl WriteTarget for FakeSession {
+    fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()> {
+        let state = self.state();
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = *state.memory.get(&(address + i as u64)).unwrap_or(&0);
+        }
+        Ok(())
+    }
+    fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+        let mut state = self.state();
+        for (i, &byte) in data.iter().enumerate() {
+            state.memory.insert(address + i as u64, byte);
+        }
+        Ok(())
+    }
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        Ok(*self.state().registers.get(&id).unwrap_or(&0))
+    }
+    fn write_register(&mut self, id: u16, value: u64) -> Result<()> {
+        self.state().registers.insert(id, value);
+        Ok(())
+    }
+}
+
+impl RunControl for FakeSession {
+    /// Halt at the reset handler from the vector table at address 0.
+    fn reset_and_halt(&mut self) -> Result<u64> {
+        let mut vector = [0u8; 4];
+        self.read_memory(4, &mut vector)?;
+        let pc = u64::from(u32::from_le_bytes(vector) & !1);
+        let mut state = self.state();
+        state.status = CoreStatus::Halted(HaltReason::Request);
+        state.registers.insert(PC, pc);
+        Ok(pc)
+    }
+    fn run(&mut self) -> Result<()> {
+        self.state().status = CoreStatus::Running;
+        Ok(())
+    }
+    /// Nothing runs on its own, so only a [`FakeSession::hit`] ends the wait.
+    fn wait_for_halt(&mut self, _timeout: Duration) -> Result<u64> {
+        let state = self.state();
+        anyhow::ensure!(state.status.is_halted(), "Timed out waiting for the core to halt");
+        Ok(*state.registers.get(&PC).unwrap_or(&0))
+    }
+}
+
+impl StepControl for FakeSession {
+    fn step(&mut self) -> Result<u64> {
+        let mut state = self.state();
+        let pc = state.registers.get(&PC).unwrap_or(&0) + 2;
+        state.registers.insert(PC, pc);
+        state.status = CoreStatus::Halted(HaltReason::Step);
+        Ok(pc)
+    }
+}
+
+impl HaltControl for FakeSession {
+    fn halt(&mut self, _timeout: Duration) -> Result<u64> {
+        let mut state = self.state();
+        if !state.status.is_halted() {
+            state.status = CoreStatus::Halted(HaltReason::Request);
+        }
+        Ok(*state.registers.get(&PC).unwrap_or(&0))
+    }
+    fn read_u32(&mut self, address: u64) -> Result<u32> {
+        let mut bytes = [0u8; 4];
+        self.read_memory(address, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+    fn write_u32(&mut self, address: u64, value: u32) -> Result<()> {
+        self.write_memory(address, &value.to_le_bytes())
+    }
+}
+
+#[cfg(feature = "hardware")]
+impl<'p> TargetCore<'p> for FakeSession {
+    fn pc(&mut self) -> Result<u64> {
+        self.read_register(PC)
+    }
+}
+
+/// A single Arm core without flash; clones share the state, so each
+/// [`DebugTarget::core`] is a clone.
+#[cfg(feature = "hardware")]
+impl DebugTarget for FakeSession {
+    type Core<'a> = FakeSession;
+
+    fn from_probe(_session: probe_rs::Session) -> Option<Self> {
+        None
+    }
+    fn core(&mut self, _index: usize) -> Result<FakeSession> {
+        Ok(self.clone())
+    }
+    fn architecture(&self) -> String {
+        "Arm".to_string()
+    }
+    fn flash_base(&self) -> Option<u64> {
+        None
+    }
+}
@@ -3,9 +3,10 @@
 //! Handles flash erase, program, and verify operations.
 
 use anyhow::{Context, Result};
+use probe_rs::config::MemoryRegion;
 pub use probe_rs::flashing::ProgressEvent;
 use probe_rs::flashing::{BinOptions, DownloadOptions, FlashProgress};
-use probe_rs::Session;
+use probe_rs::{Session, Target};
 use std::path::Path;
 use std::sync::mpsc;
 
@@ -22,6 +23,140 @@ pub enum FlashingProgress {
     Message(String),
 }
 
+/// Default ceiling for the probe clock during a flash download, in kHz.
+pub const DEFAULT_FLASH_SPEED_CAP_KHZ: u32 = 24_000;
+
+/// Probe clock for a flash download: as fast as the probe and target allow,
+/// within `cap_khz`. Unknown limits are left to the probe, which clamps requests
+/// to the closest clock it supports.
+pub fn select_flash_speed(
+    cap_khz: u32,
+    probe_max_khz: Option<u32>,
+    target_max_khz: Option<u32>,
+) -> u32 {
+    [probe_max_khz, target_max_khz].into_iter().flatten().fold(cap_khz, u32::min).max(1)
+}
+
+/// Start of the target's first flash region, where a raw image and the reset
+/// vector table go.
+pub fn flash_base(target: &Target) -> Option<u64> {
+    target.memory_map.iter().find_map(|region| match region {
+        MemoryRegion::Nvm(flash) => Some(flash.range.start),
+        _ => None,
+    })
+}
+
+/// Open probe `probe_index` and attach for flashing at up to `cap_khz`.
+///
+/// The target is first brought up at the robust
+/// [`DEFAULT_SPEED_KHZ`](crate::probe::DEFAULT_SPEED_KHZ). The download is
+/// bandwidth-bound, so the probe is then re-attached at the fastest clock the
+/// probe, `target_max_khz` (e.g. the clock the user attaches this target at) and
+/// the cap allow, falling back to the default if the target does not come up at
+/// that speed. Returns the session and the effective clock in kHz.
+pub fn attach_for_flashing(
+    probe_manager: &crate::ProbeManager,
+    probe_index: usize,
+    cap_khz: u32,
+    target_max_khz: Option<u32>,
+) -> Result<(Session, u32)> {
+    let attach_at = |speed_khz: u32| -> Result<(Session, u32)> {
+        let mut probe = probe_manager.open_probe(probe_index)?;
+        let effective = probe.set_speed(speed_khz).unwrap_or(speed_khz);
+        let session =
+            probe.attach("any", probe_rs::Permissions::default()).context("Failed to attach")?;
+        Ok((session, effective))
+    };
+
+    let (session, safe_khz) = attach_at(crate::probe::DEFAULT_SPEED_KHZ)?;
+    if select_flash_speed(cap_khz, None, target_max_khz) <= safe_khz {
+        return Ok((session, safe_khz));
+    }
+    // probe-rs cannot change the clock of an attached session, so the probe is
+    // reopened. It clamps a request to the closest clock it supports, which
+    // reveals its limit.
+    drop(session);
+    let mut probe = probe_manager.open_probe(probe_index)?;
+    let probe_max_khz = probe.set_speed(cap_khz).ok();
+    let fast = select_flash_speed(cap_khz, probe_max_khz, target_max_khz);
+    let effective = probe.set_speed(fast).unwrap_or(fast);
+    match probe.attach("any", probe_rs::Permissions::default()) {
+        Ok(session) => Ok((session, effective)),
+        Err(e) => {
+            log::warn!("Attach at {} kHz failed ({}), retrying at default speed", effective, e);
+            attach_at(crate::probe::DEFAULT_SPEED_KHZ)
+        }
+    }
+}
+
+/// Firmware image formats understood by [`FlashManager::flash_file`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashFormat {
+    Elf,
+    IntelHex,
+    Bin,
+}
+
+impl FlashFormat {
+    /// Format implied by the file extension alone.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "elf" | "axf" | "out" => Some(Self::Elf),
+            "hex" | "ihex" => Some(Self::IntelHex),
+            "bin" => Some(Self::Bin),
+            _ => None,
+        }
+    }
+
+    /// Detect the format of an image from its first bytes.
+    ///
+    /// The ELF magic is conclusive. A first line that parses as an Intel HEX record
+    /// means HEX, unless the extension says `.bin`: a raw image can start with ASCII
+    /// by chance. Everything else is flashed as a raw binary.
+    pub fn detect(data: &[u8], path: &Path) -> Self {
+        if data.starts_with(b"\x7FELF") {
+            return Self::Elf;
+        }
+        if is_hex_record(data) && Self::from_extension(path) != Some(Self::Bin) {
+            return Self::IntelHex;
+        }
+        Self::Bin
+    }
+
+    /// Whether flash bytes outside the image are preserved by default.
+    ///
+    /// Keeping them costs a read-back of every partially written sector, so a
+    /// sparse image leaves e.g. calibration data in the same sectors alone but
+    /// flashes more slowly. ELF and HEX images are often sparse and keep them; a
+    /// raw binary is one contiguous block and is written with a plain erase.
+    pub fn default_keep_unwritten(self) -> bool {
+        match self {
+            Self::Elf | Self::IntelHex => true,
+            Self::Bin => false,
+        }
+    }
+}
+
+/// Download options shared by all formats.
+fn download_options(keep_unwritten: bool, progress: FlashProgress) -> DownloadOptions {
+    let mut options = DownloadOptions::default();
+    options.progress = progress;
+    options.keep_unwritten_bytes = keep_unwritten;
+    options
+}
+
+/// Whether `data` starts with a well-formed Intel HEX record line.
+fn is_hex_record(data: &[u8]) -> bool {
+    let Some(record) = data.strip_prefix(b":") else {
+        return false;
+    };
+    let line = record.split(|&b| b == b'\n').next().unwrap_or_default();
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    // Byte count, address, record type and checksum take at least 10 hex digits.
+    line.len() >= 10 && line.len() % 2 == 0 && line.iter().all(u8::is_ascii_hexdigit)
+}
+
 /// A progress reporter that sends updates over a channel.
 pub struct MpscFlashProgress {
     sender: mpsc::Sender<FlashingProgress>,
@@ -58,16 +193,51 @@ impl FlashManager {
         Self
     }
 
+    /// Flash an image, picking the format from its content.
+    ///
+    /// Raw binaries are written to the start of the first flash region.
+    /// `keep_unwritten` overrides the format's
+    /// [default](FlashFormat::default_keep_unwritten).
+    pub fn flash_file(
+        &self,
+        session: &mut Session,
+        path: &Path,
+        keep_unwritten: Option<bool>,
+        progress: FlashProgress,
+    ) -> Result<()> {
+        let mut header = [0u8; 64];
+        let len = {
+            use std::io::Read;
+            let mut file = std::fs::File::open(path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            file.read(&mut header)?
+        };
+
+        let format = FlashFormat::detect(&header[..len], path);
+        let keep = keep_unwritten.unwrap_or_else(|| format.default_keep_unwritten());
+        match format {
+            FlashFormat::Elf => self.flash_elf(session, path, keep, progress),
+            FlashFormat::IntelHex => self.flash_hex(session, path, keep, progress),
+            FlashFormat::Bin => {
+                let address = flash_base(session.target())
+                    .context("Target has no flash region for a raw binary")?;
+                self.flash_bin(session, path, address, keep, progress)
+            }
+        }
+    }
+
     /// Flash an ELF file to the target.
+    ///
+    /// With `keep_unwritten`, flash bytes the image does not cover keep their
+    /// contents; otherwise the touched sectors are erased.
     pub fn flash_elf(
         &self,
         session: &mut Session,
         path: &Path,
+        keep_unwritten: bool,
         progress: FlashProgress,
     ) -> Result<()> {
-        let mut options = DownloadOptions::default();
-        options.progress = progress;
-        options.keep_unwritten_bytes = true;
+        let options = download_options(keep_unwritten, progress);
 
         probe_rs::flashing::download_file_with_options(
             session,
@@ -78,16 +248,35 @@ impl FlashManager {
         .context("Failed to flash ELF file")
     }
 
+    /// Flash an Intel HEX file to the target.
+    pub fn flash_hex(
+        &self,
+        session: &mut Session,
+        path: &Path,
+        keep_unwritten: bool,
+        progress: FlashProgress,
+    ) -> Result<()> {
+        let options = download_options(keep_unwritten, progress);
+
+        probe_rs::flashing::download_file_with_options(
+            session,
+            path,
+            probe_rs::flashing::Format::Hex,
+            options,
+        )
+        .context("Failed to flash HEX file")
+    }
+
     /// Flash a raw binary at a specific address.
     pub fn flash_bin(
         &self,
         session: &mut Session,
         path: &Path,
         address: u64,
+        keep_unwritten: bool,
         progress: FlashProgress,
     ) -> Result<()> {
-        let mut options = DownloadOptions::default();
-        options.progress = progress;
+        let options = download_options(keep_unwritten, progress);
 
         let bin_options = BinOptions { base_address: Some(address), skip: 0 };
 
@@ -120,6 +309,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_detect_format_by_content() {
+        let elf = b"\x7FELF\x01\x01\x01\x00";
+        let hex = b":020000040800F2\r\n:10000000";
+        let bin = b"\x00\x80\x00\x20\x89\x01\x00\x08";
+
+        // Content wins over a misleading or unknown extension.
+        assert_eq!(FlashFormat::detect(elf, Path::new("firmware.out")), FlashFormat::Elf);
+        assert_eq!(FlashFormat::detect(elf, Path::new("firmware.bin")), FlashFormat::Elf);
+        assert_eq!(FlashFormat::detect(hex, Path::new("firmware")), FlashFormat::IntelHex);
+        assert_eq!(FlashFormat::detect(bin, Path::new("firmware.hex")), FlashFormat::Bin);
+        assert_eq!(FlashFormat::detect(b"", Path::new("firmware.elf")), FlashFormat::Bin);
+
+        // A binary that happens to start like a HEX record keeps its `.bin` extension.
+        assert_eq!(FlashFormat::detect(hex, Path::new("firmware.bin")), FlashFormat::Bin);
+        assert_eq!(FlashFormat::detect(b":hello", Path::new("data")), FlashFormat::Bin);
+    }
+
+    #[test]
+    fn test_keep_unwritten_option() {
+        // The option reaches probe-rs unchanged, whatever the format.
+        for keep in [true, false] {
+            assert_eq!(
+                download_options(keep, FlashProgress::new(|_| {})).keep_unwritten_bytes,
+                keep
+            );
+        }
+        assert!(FlashFormat::Elf.default_keep_unwritten());
+        assert!(FlashFormat::IntelHex.default_keep_unwritten());
+        assert!(!FlashFormat::Bin.default_keep_unwritten());
+    }
+
+    #[test]
+    fn test_flash_speed_selection() {
+        // Nothing known: the cap is requested and the probe clamps it.
+        assert_eq!(select_flash_speed(24_000, None, None), 24_000);
+        // The slowest known limit wins.
+        assert_eq!(select_flash_speed(24_000, Some(10_000), None), 10_000);
+        assert_eq!(select_flash_speed(24_000, Some(10_000), Some(4_000)), 4_000);
+        assert_eq!(select_flash_speed(24_000, Some(50_000), Some(30_000)), 24_000);
+        // A cap below the limits is honored, but never zero.
+        assert_eq!(select_flash_speed(2_000, Some(10_000), Some(4_000)), 2_000);
+        assert_eq!(select_flash_speed(0, None, None), 1);
+    }
+
     #[test]
     fn test_mpsc_progress_reporting() {
         let (tx, rx) = mpsc::channel();
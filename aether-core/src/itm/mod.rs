@@ -1,24 +1,457 @@
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::Session;
 #[cfg(not(feature = "hardware"))]
-use crate::probe_rs::TraceSink;
+use crate::probe_rs::{SwoConfig, TraceSink};
+use crate::DebugEvent;
 use anyhow::Result;
 #[cfg(feature = "hardware")]
 use probe_rs::architecture::arm::component::TraceSink;
 #[cfg(feature = "hardware")]
+use probe_rs::architecture::arm::SwoConfig;
+#[cfg(feature = "hardware")]
 use probe_rs::Session;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What happened to an exception, as reported by DWT exception trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExceptionAction {
+    Entered,
+    Exited,
+    Returned,
+}
+
+/// A decoded DWT exception trace packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionEvent {
+    pub number: u16,
+    pub action: ExceptionAction,
+}
+
+/// Extract exception trace packets from a raw ITM byte stream.
+///
+/// Other packets (instrumentation, timestamps, sync, overflow) are skipped.
+pub fn decode_exception_trace(data: &[u8]) -> Vec<ExceptionEvent> {
+    let mut events = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let header = data[i];
+        i += 1;
+        if header == 0x00 || header == 0x80 {
+            // Synchronization packet bytes.
+            continue;
+        }
+
+        let size = match header & 0x03 {
+            0 => {
+                // Protocol packet: sync, overflow, timestamp or extension.
+                // Continuation bytes carry bit 7.
+                if header & 0x80 != 0 {
+                    while i < data.len() && data[i] & 0x80 != 0 {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+            1 => 1,
+            2 => 2,
+            _ => 4,
+        };
+        let Some(payload) = data.get(i..i + size) else {
+            break;
+        };
+        i += size;
+
+        // Hardware source packet with discriminator 1 is exception trace.
+        let hardware = header & 0x04 != 0;
+        if hardware && header >> 3 == 1 && size == 2 {
+            let number = u16::from(payload[0]) | (u16::from(payload[1] & 0x01) << 8);
+            let action = match (payload[1] >> 4) & 0x03 {
+                1 => ExceptionAction::Entered,
+                2 => ExceptionAction::Exited,
+                3 => ExceptionAction::Returned,
+                _ => continue,
+            };
+            events.push(ExceptionEvent { number, action });
+        }
+    }
+    events
+}
+
+/// A decoded ITM source packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItmData {
+    /// Software write to a stimulus port.
+    Stimulus {
+        port: u8,
+        data: Vec<u8>,
+    },
+    Exception(ExceptionEvent),
+}
+
+/// An ITM packet with the local timestamp that followed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedItmData {
+    pub data: ItmData,
+    /// Timestamp clock ticks since the decoder started.
+    pub timestamp: u64,
+    /// False if the timestamp packet said the data or the timestamp was
+    /// delayed, or an overflow dropped packets before it.
+    pub exact: bool,
+}
+
+/// ITM overflow packet: the ITM FIFO was full and packets were lost.
+const ITM_OVERFLOW: u8 = 0x70;
+
+/// Health of the SWO byte stream since ITM was enabled.
+///
+/// Decode errors are bytes that are not valid ITM packets, usually because the
+/// SWO baud rate does not match the trace clock. Overflows are packets the ITM
+/// dropped itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStats {
+    pub bytes: u64,
+    /// Stimulus and exception trace packets decoded.
+    pub packets: u64,
+    pub decode_errors: u64,
+    pub overflows: u64,
+}
+
+/// Stateful ITM decoder that keeps the running local timestamp across reads.
+///
+/// The ITM emits a local timestamp packet after the packets it applies to, so
+/// decoded packets are held until the next timestamp arrives. Until the stream
+/// has carried a timestamp (e.g. TSENA is off) packets are returned straight
+/// away, stamped with the running time and marked inexact.
+#[derive(Debug, Default)]
+pub struct ItmDecoder {
+    time: u64,
+    timestamps_seen: bool,
+    overflowed: bool,
+    pending: Vec<ItmData>,
+    /// Bytes of a packet cut off at the end of the previous read.
+    partial: Vec<u8>,
+    stats: TraceStats,
+}
+
+impl ItmDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Totals over every chunk decoded so far.
+    pub fn stats(&self) -> TraceStats {
+        self.stats
+    }
+
+    /// Decode the next chunk of the byte stream.
+    pub fn decode(&mut self, data: &[u8]) -> Vec<TimedItmData> {
+        self.stats.bytes += data.len() as u64;
+        let mut buf = std::mem::take(&mut self.partial);
+        buf.extend_from_slice(data);
+
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < buf.len() {
+            let start = i;
+            let header = buf[i];
+            i += 1;
+            if header == 0x00 || header == 0x80 {
+                // Synchronization packet bytes.
+                continue;
+            }
+
+            if header & 0x03 == 0 {
+                // Protocol packet. Continuation bytes carry bit 7.
+                let mut payload = Vec::new();
+                if header & 0x80 != 0 {
+                    loop {
+                        let Some(&byte) = buf.get(i) else {
+                            self.partial = buf[start..].to_vec();
+                            return out;
+                        };
+                        i += 1;
+                        payload.push(byte);
+                        if byte & 0x80 == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                if header == ITM_OVERFLOW {
+                    self.overflowed = true;
+                    self.stats.overflows += 1;
+                } else if header & 0x8F == 0x00 {
+                    // Local timestamp format 2: a 3-bit delta in the header.
+                    self.stamp(u64::from(header >> 4), true, &mut out);
+                } else if header & 0xCF == 0xC0 {
+                    // Local timestamp format 1: TC in bits 5:4, then up to 28 bits of delta.
+                    let delta = payload
+                        .iter()
+                        .take(4)
+                        .enumerate()
+                        .fold(0u64, |acc, (n, b)| acc | (u64::from(b & 0x7F) << (7 * n)));
+                    self.stamp(delta, (header >> 4) & 0x03 == 0, &mut out);
+                } else if header & 0xDF != 0x94 && header & 0x0B != 0x08 {
+                    // Not a global timestamp or extension packet, which are
+                    // skipped, but a reserved header.
+                    self.stats.decode_errors += 1;
+                }
+                continue;
+            }
+
+            let size = match header & 0x03 {
+                1 => 1,
+                2 => 2,
+                _ => 4,
+            };
+            let Some(payload) = buf.get(i..i + size) else {
+                self.partial = buf[start..].to_vec();
+                return out;
+            };
+            i += size;
+
+            let port = header >> 3;
+            let packet = if header & 0x04 == 0 {
+                ItmData::Stimulus { port, data: payload.to_vec() }
+            } else if port == 1 && size == 2 {
+                let number = u16::from(payload[0]) | (u16::from(payload[1] & 0x01) << 8);
+                let action = match (payload[1] >> 4) & 0x03 {
+                    1 => ExceptionAction::Entered,
+                    2 => ExceptionAction::Exited,
+                    3 => ExceptionAction::Returned,
+                    _ => {
+                        self.stats.decode_errors += 1;
+                        continue;
+                    }
+                };
+                ItmData::Exception(ExceptionEvent { number, action })
+            } else {
+                continue;
+            };
+            self.stats.packets += 1;
+
+            if self.timestamps_seen {
+                self.pending.push(packet);
+            } else {
+                out.push(TimedItmData { data: packet, timestamp: self.time, exact: false });
+            }
+        }
+        out
+    }
+
+    /// Apply a local timestamp delta and release the packets it applies to.
+    fn stamp(&mut self, delta: u64, in_sync: bool, out: &mut Vec<TimedItmData>) {
+        self.time += delta;
+        self.timestamps_seen = true;
+        let exact = in_sync && !self.overflowed;
+        self.overflowed = false;
+        out.extend(self.pending.drain(..).map(|data| TimedItmData {
+            data,
+            timestamp: self.time,
+            exact,
+        }));
+    }
+}
+
+/// Where the data written to an ITM stimulus port goes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItmPortRoute {
+    /// Text output, sent as `ItmConsole`.
+    Console,
+    /// Samples of the named plot: each write is an unsigned little-endian value.
+    Plot(String),
+    Discard,
+}
+
+/// Stimulus port routing. Ports without a route go to the console.
+///
+/// Firmware writes text a byte or word at a time, so a multi-byte UTF-8
+/// character can span writes; console ports hold back an incomplete trailing
+/// sequence until the rest arrives.
+#[derive(Debug, Clone, Default)]
+pub struct ItmRoutes {
+    routes: HashMap<u8, ItmPortRoute>,
+    pending: HashMap<u8, Vec<u8>>,
+}
+
+impl ItmRoutes {
+    pub fn set(&mut self, port: u8, route: ItmPortRoute) {
+        self.pending.remove(&port);
+        self.routes.insert(port, route);
+    }
+
+    pub fn route(&self, port: u8) -> &ItmPortRoute {
+        self.routes.get(&port).unwrap_or(&ItmPortRoute::Console)
+    }
+
+    /// The event for `data` written to `port` at `timestamp` seconds, or `None`
+    /// if the port is discarded or `data` only starts a UTF-8 character.
+    pub fn dispatch(&mut self, port: u8, data: &[u8], timestamp: f64) -> Option<DebugEvent> {
+        match self.route(port) {
+            ItmPortRoute::Console => {
+                let buf = self.pending.entry(port).or_default();
+                buf.extend_from_slice(data);
+                let text = take_complete_utf8(buf);
+                (!text.is_empty()).then_some(DebugEvent::ItmConsole { port, text })
+            }
+            ItmPortRoute::Plot(name) => {
+                let value = data.iter().rev().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+                Some(DebugEvent::PlotData { name: name.clone(), timestamp, value: value as f64 })
+            }
+            ItmPortRoute::Discard => None,
+        }
+    }
+}
+
+/// Decode the complete UTF-8 sequences at the front of `buf` and remove them,
+/// leaving an incomplete trailing sequence. Invalid bytes become U+FFFD.
+fn take_complete_utf8(buf: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    let mut start = 0;
+    loop {
+        match std::str::from_utf8(&buf[start..]) {
+            Ok(s) => {
+                text.push_str(s);
+                start = buf.len();
+                break;
+            }
+            Err(e) => {
+                let valid_end = start + e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&buf[start..valid_end]));
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        start = valid_end + len;
+                    }
+                    // A sequence cut off by the end of the buffer.
+                    None => {
+                        start = valid_end;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    buf.drain(..start);
+    text
+}
+
+/// Display name of a Cortex-M exception number.
+pub fn exception_name(number: u16) -> String {
+    match number {
+        0 => "Thread".to_string(),
+        1 => "Reset".to_string(),
+        2 => "NMI".to_string(),
+        3 => "HardFault".to_string(),
+        4 => "MemManage".to_string(),
+        5 => "BusFault".to_string(),
+        6 => "UsageFault".to_string(),
+        11 => "SVCall".to_string(),
+        12 => "DebugMonitor".to_string(),
+        14 => "PendSV".to_string(),
+        15 => "SysTick".to_string(),
+        n if n >= 16 => format!("IRQ{}", n - 16),
+        n => format!("Exception {}", n),
+    }
+}
+
+/// Encoding of the SWO pin. The probe must decode the same encoding the TPIU
+/// drives, otherwise the stream arrives as garbage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwoMode {
+    /// NRZ, as most probes expect.
+    #[default]
+    Uart,
+    Manchester,
+}
+
+impl SwoMode {
+    #[cfg(feature = "hardware")]
+    fn to_probe_rs(self) -> probe_rs::architecture::arm::SwoMode {
+        match self {
+            SwoMode::Uart => probe_rs::architecture::arm::SwoMode::Uart,
+            SwoMode::Manchester => probe_rs::architecture::arm::SwoMode::Manchester,
+        }
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    fn to_probe_rs(self) -> crate::probe_rs::SwoMode {
+        match self {
+            SwoMode::Uart => crate::probe_rs::SwoMode::Uart,
+            SwoMode::Manchester => crate::probe_rs::SwoMode::Manchester,
+        }
+    }
+}
+
+/// Where the trace unit sends ITM packets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItmSink {
+    /// The SWO pin, read by the probe. Needs the core clock to set the baud rate.
+    #[default]
+    Swo,
+    /// The on-chip trace buffer (ETB/ETF), read over the debug port.
+    TraceMemory,
+}
+
+/// SWO settings for [`ItmManager::configure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItmConfig {
+    pub baud_rate: u32,
+    #[serde(default)]
+    pub mode: SwoMode,
+    #[serde(default)]
+    pub sink: ItmSink,
+}
+
+impl ItmConfig {
+    /// The probe-rs SWO setup for a TPIU clocked at `tpiu_clock` Hz.
+    pub fn swo_config(&self, tpiu_clock: u32) -> SwoConfig {
+        SwoConfig::new(tpiu_clock).set_baud(self.baud_rate).set_mode(self.mode.to_probe_rs())
+    }
+}
 
 pub struct ItmManager {
     enabled: bool,
+    decoder: ItmDecoder,
+    timestamp_hz: Option<u32>,
 }
 
 impl ItmManager {
     pub fn new() -> Self {
-        Self { enabled: false }
+        Self { enabled: false, decoder: ItmDecoder::new(), timestamp_hz: None }
+    }
+
+    /// Set the local timestamp clock, normally the core clock, so ticks can be
+    /// converted to seconds.
+    pub fn set_timestamp_clock(&mut self, hz: Option<u32>) {
+        self.timestamp_hz = hz;
+    }
+
+    /// Seconds since ITM was enabled for a decoded timestamp, if the timestamp
+    /// clock is known.
+    pub fn seconds(&self, timestamp: u64) -> Option<f64> {
+        self.timestamp_hz.filter(|&hz| hz > 0).map(|hz| timestamp as f64 / f64::from(hz))
+    }
+
+    /// Decode a chunk returned by [`Self::read_swo`].
+    pub fn decode(&mut self, data: &[u8]) -> Vec<TimedItmData> {
+        self.decoder.decode(data)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
     }
 
-    /// Configure ITM/SWO
-    pub fn configure(&mut self, session: &mut Session, _baud_rate: u32) -> Result<()> {
+    pub fn stats(&self) -> TraceStats {
+        self.decoder.stats()
+    }
+
+    /// Configure ITM/SWO. The TPIU is assumed to run from the timestamp clock
+    /// (see [`Self::set_timestamp_clock`]), so the SWO sink fails while that is
+    /// unknown.
+    pub fn configure(&mut self, session: &mut Session, config: &ItmConfig) -> Result<()> {
         #[cfg(feature = "hardware")]
         let is_arm = session.target().architecture() == probe_rs::Architecture::Arm;
         #[cfg(not(feature = "hardware"))]
@@ -28,17 +461,25 @@ impl ItmManager {
             return Err(anyhow::anyhow!("ITM is only supported on ARM targets"));
         }
 
-        // probe-rs 0.31 uses setup_tracing to enable SWO.
-        // It likely configures default baud rate or uses values from chip definition if available?
-        // Limitations: We can't easily set baud rate without finding where SwoConfig goes.
-        // But setup_tracing is the entry point.
-
+        let sink = match config.sink {
+            ItmSink::Swo => {
+                let hz = self.timestamp_hz.filter(|&hz| hz > 0).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Core clock unknown, cannot set the SWO baud rate; load an SVD or use \
+                         the trace memory sink"
+                    )
+                })?;
+                TraceSink::Swo(config.swo_config(hz))
+            }
+            ItmSink::TraceMemory => TraceSink::TraceMemory,
+        };
         #[cfg(feature = "hardware")]
-        session.setup_tracing(0, TraceSink::TraceMemory)?;
+        session.setup_tracing(0, sink)?;
         #[cfg(not(feature = "hardware"))]
-        let _ = session;
+        let _ = (session, sink);
 
         self.enabled = true;
+        self.decoder = ItmDecoder::new();
         Ok(())
     }
 
@@ -71,3 +512,164 @@ impl Default for ItmManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swo_mode_is_carried_into_trace_setup() {
+        let manchester =
+            ItmConfig { baud_rate: 2_000_000, mode: SwoMode::Manchester, sink: ItmSink::Swo };
+        let swo = manchester.swo_config(72_000_000);
+        assert_eq!(swo.mode(), SwoMode::Manchester.to_probe_rs());
+        assert_eq!(swo.baud(), 2_000_000);
+        assert_eq!(swo.tpiu_clk(), 72_000_000);
+
+        // Configs that predate the mode setting decode as UART.
+        let legacy: ItmConfig = serde_json::from_str(r#"{"baud_rate":115200}"#).unwrap();
+        assert_eq!(legacy.mode, SwoMode::Uart);
+        assert_eq!(legacy.sink, ItmSink::Swo);
+        assert_eq!(legacy.swo_config(16_000_000).mode(), SwoMode::Uart.to_probe_rs());
+    }
+
+    #[test]
+    fn test_decode_exception_trace() {
+        let data = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // sync
+            0x01, b'A', // stimulus port 0, one byte
+            0x0E, 0x1F, 0x10, // enter IRQ15 (exception 31)
+            0xC0, 0x85, 0x01, // local timestamp with continuation
+            0x0E, 0x1F, 0x20, // exit IRQ15
+            0x0E, 0x0F, 0x30, // return to SysTick
+        ];
+        let events = decode_exception_trace(&data);
+        assert_eq!(
+            events,
+            vec![
+                ExceptionEvent { number: 31, action: ExceptionAction::Entered },
+                ExceptionEvent { number: 31, action: ExceptionAction::Exited },
+                ExceptionEvent { number: 15, action: ExceptionAction::Returned },
+            ]
+        );
+        assert_eq!(exception_name(31), "IRQ15");
+        assert_eq!(exception_name(15), "SysTick");
+    }
+
+    #[test]
+    fn test_itm_decoder_local_timestamps() {
+        let stream = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // sync
+            0x01, b'A', // stimulus port 0
+            0x30, // local timestamp format 2: +3
+            0x0E, 0x1F, 0x10, // enter IRQ15
+            0x0A, 0x34, 0x12, // stimulus port 1, two bytes
+            0xC0, 0x85, 0x01, // local timestamp, TC=0: +133
+            0x70, // overflow
+            0x01, b'B', // stimulus port 0
+            0xD0, 0x0A, // local timestamp, TC=1 (delayed): +10
+            0x01, b'C', // not yet followed by a timestamp
+        ];
+        let mut decoder = ItmDecoder::new();
+        // Split inside the first format 1 timestamp packet.
+        let mut packets = decoder.decode(&stream[..17]);
+        packets.extend(decoder.decode(&stream[17..]));
+
+        let stimulus = |port, data: &[u8]| ItmData::Stimulus { port, data: data.to_vec() };
+        let enter =
+            ItmData::Exception(ExceptionEvent { number: 31, action: ExceptionAction::Entered });
+        assert_eq!(
+            packets,
+            vec![
+                TimedItmData { data: stimulus(0, b"A"), timestamp: 0, exact: false },
+                TimedItmData { data: enter, timestamp: 136, exact: true },
+                TimedItmData { data: stimulus(1, &[0x34, 0x12]), timestamp: 136, exact: true },
+                TimedItmData { data: stimulus(0, b"B"), timestamp: 146, exact: false },
+            ]
+        );
+
+        let packets = decoder.decode(&[0x10]);
+        assert_eq!(
+            packets,
+            vec![TimedItmData { data: stimulus(0, b"C"), timestamp: 147, exact: true }]
+        );
+    }
+
+    #[test]
+    fn test_itm_port_routes_dispatch_to_events() {
+        let mut routes = ItmRoutes::default();
+        routes.set(1, ItmPortRoute::Plot("counter".to_string()));
+        routes.set(2, ItmPortRoute::Discard);
+
+        let stream = [
+            0x01, b'o', 0x01, b'k', // port 0, one byte each
+            0x0B, 0x2A, 0x01, 0x00, 0x00, // port 1, 32-bit write of 298
+            0x11, b'x', // port 2
+        ];
+        let events: Vec<DebugEvent> = ItmDecoder::new()
+            .decode(&stream)
+            .into_iter()
+            .filter_map(|packet| match packet.data {
+                ItmData::Stimulus { port, data } => routes.dispatch(port, &data, 2.5),
+                ItmData::Exception(_) => None,
+            })
+            .collect();
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], DebugEvent::ItmConsole { port: 0, text } if text == "o"));
+        assert!(matches!(&events[1], DebugEvent::ItmConsole { port: 0, text } if text == "k"));
+        assert!(matches!(
+            &events[2],
+            DebugEvent::PlotData { name, timestamp, value }
+                if name == "counter" && *timestamp == 2.5 && *value == 298.0
+        ));
+    }
+
+    #[test]
+    fn test_itm_console_joins_utf8_split_across_writes() {
+        let mut routes = ItmRoutes::default();
+        let text = |event: Option<DebugEvent>| match event {
+            Some(DebugEvent::ItmConsole { text, .. }) => Some(text),
+            _ => None,
+        };
+
+        // "°C" written a byte at a time: the degree sign is two bytes.
+        assert_eq!(text(routes.dispatch(0, &[0xC2], 0.0)), None);
+        assert_eq!(text(routes.dispatch(0, &[0xB0], 0.0)).as_deref(), Some("°"));
+        assert_eq!(text(routes.dispatch(0, b"C", 0.0)).as_deref(), Some("C"));
+
+        // Ports are buffered separately, and invalid bytes are replaced.
+        assert_eq!(text(routes.dispatch(0, &[b'a', 0xE2, 0x82], 0.0)).as_deref(), Some("a"));
+        assert_eq!(text(routes.dispatch(1, &[0xFF, b'b'], 0.0)).as_deref(), Some("\u{FFFD}b"));
+        assert_eq!(text(routes.dispatch(0, &[0xAC], 0.0)).as_deref(), Some("€"));
+    }
+
+    #[test]
+    fn test_trace_stats_accumulate_across_batches() {
+        let mut decoder = ItmDecoder::new();
+        assert_eq!(decoder.stats(), TraceStats::default());
+
+        decoder.decode(&[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // sync
+            0x01, b'A', // stimulus port 0
+            0x0E, 0x1F, // enter IRQ15, cut off after one payload byte
+        ]);
+        assert_eq!(
+            decoder.stats(),
+            TraceStats { bytes: 10, packets: 1, decode_errors: 0, overflows: 0 }
+        );
+
+        decoder.decode(&[
+            0x10, // rest of the exception packet
+            0x70, // overflow
+            0x94, 0x05, // global timestamp 1: skipped, not an error
+            0x0E, 0x1F, 0x00, // exception packet with a reserved action
+            0x04, 0x24, // reserved protocol headers, as a wrong baud rate produces
+            0x01, b'B',
+        ]);
+        assert_eq!(
+            decoder.stats(),
+            TraceStats { bytes: 21, packets: 3, decode_errors: 3, overflows: 1 }
+        );
+    }
+}
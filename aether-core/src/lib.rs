@@ -3,15 +3,25 @@
 //! This crate handles the interaction with debug probes, target memory/registers,
 //! and provides the high-performance backend for the Aether debugger.
 
+pub mod command_log;
+pub mod config;
 pub mod debug;
 #[cfg(feature = "hardware")]
 pub mod disasm;
+pub mod error;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
 #[cfg(feature = "hardware")]
 pub mod flash;
 pub mod itm;
 pub mod memory;
+pub mod metrics;
+pub mod peripheral_dump;
+pub mod plot;
 #[cfg(feature = "hardware")]
 pub mod probe;
+pub mod probe_lock;
+pub mod reconnect;
 pub mod rtos;
 pub mod rtt;
 pub mod semihosting;
@@ -22,11 +32,13 @@ pub mod symbols;
 
 pub mod stack;
 pub mod trace;
+pub mod watch;
 
 // Re-export commonly used types
 pub use debug::DebugManager;
 #[cfg(feature = "hardware")]
 pub use disasm::DisassemblyManager;
+pub use error::{AttachAttempt, DebugError};
 pub use flash::{FlashManager, FlashingProgress, MpscFlashProgress};
 pub use memory::MemoryManager;
 pub use probe_rs::{CoreStatus, RegisterValue};
@@ -78,10 +90,45 @@ pub mod probe_rs {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum TraceSink {
         TraceMemory,
-        Swo,
+        Swo(SwoConfig),
         Tpiu,
     }
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SwoMode {
+        Uart,
+        Manchester,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SwoConfig {
+        mode: SwoMode,
+        baud: u32,
+        tpiu_clk: u32,
+    }
+    impl SwoConfig {
+        pub fn new(tpiu_clk: u32) -> Self {
+            Self { mode: SwoMode::Uart, baud: 1_000_000, tpiu_clk }
+        }
+        pub fn set_baud(mut self, baud: u32) -> Self {
+            self.baud = baud;
+            self
+        }
+        pub fn set_mode(mut self, mode: SwoMode) -> Self {
+            self.mode = mode;
+            self
+        }
+        pub fn mode(&self) -> SwoMode {
+            self.mode
+        }
+        pub fn baud(&self) -> u32 {
+            self.baud
+        }
+        pub fn tpiu_clk(&self) -> u32 {
+            self.tpiu_clk
+        }
+    }
+
     #[derive(Clone)]
     pub struct Session;
     impl Session {
@@ -256,6 +303,98 @@ pub enum TaskState {
     Pending,
     Unknown,
 }
+
+/// A named RTOS queue, semaphore or mutex.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncObjectInfo {
+    pub name: String,
+    pub handle: u32, // address of the queue object
+    pub kind: SyncObjectKind,
+    /// Items in a queue, or the available count of a semaphore/mutex.
+    pub count: u32,
+    /// Queue capacity or maximum semaphore count.
+    pub length: u32,
+    /// Handles of tasks blocked sending (or giving).
+    pub waiting_to_send: Vec<u32>,
+    /// Handles of tasks blocked receiving (or taking).
+    pub waiting_to_receive: Vec<u32>,
+    /// Task holding a mutex.
+    pub owner: Option<u32>,
+    /// A lower-priority owner is blocking a higher-priority waiter.
+    pub priority_inversion: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SyncObjectKind {
+    Queue,
+    Mutex,
+    BinarySemaphore,
+    CountingSemaphore,
+}
+
+/// How the attached chip was chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum DetectionMethod {
+    /// The chip was named in the attach request.
+    #[default]
+    Requested,
+    /// probe-rs identified the chip.
+    Auto,
+    /// Auto-detection failed and a fallback chip attached; it may be the wrong one.
+    Heuristic,
+}
+
+/// Byte order of the target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Decode an unsigned integer from up to 8 bytes of target memory.
+    pub fn read_uint(self, bytes: &[u8]) -> u64 {
+        let bytes = &bytes[..bytes.len().min(8)];
+        let push = |acc: u64, b: &u8| (acc << 8) | u64::from(*b);
+        match self {
+            Self::Little => bytes.iter().rev().fold(0, push),
+            Self::Big => bytes.iter().fold(0, push),
+        }
+    }
+}
+
+/// Optional features a debug agent implements, as reported by its `GetInfo` RPC.
+///
+/// The default value supports nothing, which is what agents predating
+/// `GetInfo` are assumed to provide beyond the basic run control.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Capabilities {
+    pub write_memory: bool,
+    pub write_register: bool,
+    pub breakpoints: bool,
+    pub peripherals: bool,
+    pub rtt_write: bool,
+    pub tasks: bool,
+    pub flash: bool,
+    pub disassemble: bool,
+}
+
+impl Capabilities {
+    /// Everything supported, as in a local session.
+    pub const fn all() -> Self {
+        Self {
+            write_memory: true,
+            write_register: true,
+            breakpoints: true,
+            peripherals: true,
+            rtt_write: true,
+            tasks: true,
+            flash: true,
+            disassemble: true,
+        }
+    }
+}
 #[cfg(not(feature = "hardware"))]
 pub mod probe {
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -286,6 +425,12 @@ pub mod probe {
         pub flash_size: u64,
         pub ram_size: u64,
         pub architecture: String,
+        #[serde(default)]
+        pub endian: crate::Endian,
+        #[serde(default)]
+        pub requested: String,
+        #[serde(default)]
+        pub detection_method: crate::DetectionMethod,
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -311,6 +456,33 @@ pub mod probe {
         ) -> anyhow::Result<(TargetInfo, crate::probe_rs::Session)> {
             anyhow::bail!("Hardware support disabled")
         }
+        pub fn connect_with_speed(
+            &self,
+            _idx: usize,
+            _chip: &str,
+            _proto: Option<WireProtocol>,
+            _reset: bool,
+            _speed_khz: Option<u32>,
+        ) -> anyhow::Result<(TargetInfo, crate::probe_rs::Session)> {
+            anyhow::bail!("Hardware support disabled")
+        }
+        pub fn connect_with_reset_recovery(
+            &self,
+            _idx: usize,
+            _chip: &str,
+            _proto: Option<WireProtocol>,
+            _reset: bool,
+            _speed_khz: Option<u32>,
+            _progress: impl FnMut(String),
+        ) -> anyhow::Result<(TargetInfo, crate::probe_rs::Session)> {
+            anyhow::bail!("Hardware support disabled")
+        }
+        pub fn find_probe_by_serial(&self, _serial: &str) -> anyhow::Result<usize> {
+            anyhow::bail!("Hardware support disabled")
+        }
+        pub fn target_voltage(&self, _idx: usize) -> anyhow::Result<Option<f32>> {
+            anyhow::bail!("Hardware support disabled")
+        }
     }
     pub fn map_probe_error(e: &anyhow::Error) -> String {
         e.to_string()
@@ -321,10 +493,15 @@ pub mod probe {
 pub use probe::{ProbeInfo, ProbeManager, ProbeType, TargetInfo, WireProtocol};
 #[cfg(feature = "hardware")]
 pub use probe::{ProbeInfo, ProbeManager, ProbeType, TargetInfo, WireProtocol};
-pub use session::{DebugCommand, DebugEvent, SessionHandle};
+pub use session::{
+    event_to_json, DebugCommand, DebugEvent, EventSender, RequestError, RttReadUntil,
+    SequencedEvent, SessionHandle,
+};
+#[cfg(feature = "hardware")]
+pub use session::{DebugTarget, TargetCore};
 pub use stack::StackFrame;
 pub use svd::SvdManager;
-pub use symbols::{SourceInfo, SymbolManager};
+pub use symbols::{SourceInfo, SymbolManager, VariableFormat};
 
 #[cfg(not(feature = "hardware"))]
 pub mod svd {
@@ -333,7 +510,7 @@ pub mod svd {
         pub name: String,
         pub base_address: u64,
         pub description: Option<String>,
-        pub registers: Vec<RegisterInfo>,
+        pub group_name: Option<String>,
     }
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
     pub struct RegisterInfo {
@@ -341,6 +518,8 @@ pub mod svd {
         pub address_offset: u32,
         pub description: Option<String>,
         pub size: u32,
+        pub reset_value: Option<u64>,
+        pub write_one_to_clear: bool,
         pub fields: Vec<FieldInfo>,
         pub value: Option<u64>,
     }
@@ -349,14 +528,28 @@ pub mod svd {
         pub name: String,
         pub bit_offset: u32,
         pub bit_width: u32,
-        pub value: u64,
         pub description: Option<String>,
+        #[serde(default)]
+        pub write_one_to_clear: bool,
     }
     impl FieldInfo {
-        pub fn decode(&self, _val: u64) -> u64 {
-            0
+        pub fn mask(&self) -> u64 {
+            field_mask(self.bit_offset, self.bit_width)
+        }
+        pub fn max_value(&self) -> u64 {
+            field_mask(0, self.bit_width)
+        }
+        pub fn decode(&self, reg_value: u64) -> u64 {
+            (reg_value & self.mask()) >> self.bit_offset
         }
     }
+    pub fn field_mask(offset: u32, width: u32) -> u64 {
+        u64::MAX
+            .checked_shr(64u32.saturating_sub(width))
+            .unwrap_or(0)
+            .checked_shl(offset)
+            .unwrap_or(0)
+    }
     pub struct SvdManager;
     impl SvdManager {
         pub fn new() -> Self {
@@ -376,6 +569,20 @@ pub mod disasm {
         pub mnemonic: String,
         pub op_str: String,
         pub bytes: Vec<u8>,
+        pub is_branch: bool,
+        pub is_call: bool,
+        pub is_ret: bool,
+        pub branch_target: Option<u64>,
+    }
+    impl InstructionInfo {
+        pub fn next_address(&self) -> u64 {
+            self.address + self.bytes.len() as u64
+        }
+    }
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct SourceLineBlock {
+        pub source_line: Option<crate::symbols::SourceInfo>,
+        pub instructions: Vec<InstructionInfo>,
     }
     pub struct DisassemblyManager;
     impl DisassemblyManager {
@@ -387,6 +594,10 @@ pub mod disasm {
 
 #[cfg(not(feature = "hardware"))]
 pub mod flash {
+    pub const DEFAULT_FLASH_SPEED_CAP_KHZ: u32 = 24_000;
+    pub fn flash_base(_target: &crate::probe_rs::Target) -> Option<u64> {
+        None
+    }
     pub struct FlashManager;
     impl FlashManager {
         pub fn new() -> Self {
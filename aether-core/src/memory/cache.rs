@@ -0,0 +1,158 @@
+//! Read cache for memory and core registers.
+//!
+//! While the core is halted, memory and registers only change through our own
+//! writes, so repeated reads (e.g. the UI refreshing every view on each halt) can
+//! be answered without touching the probe. Anything that lets the core run or
+//! writes to the target must invalidate the cache.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Regions that can change while the core is halted (Cortex-M peripherals,
+/// external devices and the private peripheral bus). Reads there are never cached.
+const VOLATILE_REGIONS: [Range<u64>; 2] = [0x4000_0000..0x6000_0000, 0xA000_0000..0x1_0000_0000];
+
+/// Caches memory blocks and register values while the core is halted.
+#[derive(Debug, Default)]
+pub struct ReadCache {
+    halted: bool,
+    memory: HashMap<(u64, usize), Vec<u8>>,
+    registers: HashMap<u16, u64>,
+}
+
+impl ReadCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record whether the core is halted. Reads are only cached while halted;
+    /// leaving the halted state drops everything.
+    pub fn set_halted(&mut self, halted: bool) {
+        if !halted {
+            self.invalidate();
+        }
+        self.halted = halted;
+    }
+
+    /// Drop all cached values.
+    pub fn invalidate(&mut self) {
+        self.memory.clear();
+        self.registers.clear();
+    }
+
+    /// Drop cached blocks overlapping `[address, address + len)`.
+    pub fn invalidate_memory(&mut self, address: u64, len: usize) {
+        let end = address + len as u64;
+        self.memory.retain(|&(start, size), _| start + size as u64 <= address || start >= end);
+    }
+
+    pub fn invalidate_register(&mut self, id: u16) {
+        self.registers.remove(&id);
+    }
+
+    /// Read `size` bytes at `address`, using `read` only on a cache miss.
+    pub fn read_memory<F>(&mut self, address: u64, size: usize, read: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce(&mut [u8]) -> Result<()>,
+    {
+        let cacheable = self.halted && !is_volatile(address, size);
+        if cacheable {
+            if let Some(data) = self.memory.get(&(address, size)) {
+                return Ok(data.clone());
+            }
+        }
+        let mut data = vec![0u8; size];
+        read(&mut data)?;
+        if cacheable {
+            self.memory.insert((address, size), data.clone());
+        }
+        Ok(data)
+    }
+
+    /// Read register `id`, using `read` only on a cache miss.
+    pub fn read_register<F>(&mut self, id: u16, read: F) -> Result<u64>
+    where
+        F: FnOnce() -> Result<u64>,
+    {
+        if self.halted {
+            if let Some(&value) = self.registers.get(&id) {
+                return Ok(value);
+            }
+        }
+        let value = read()?;
+        if self.halted {
+            self.registers.insert(id, value);
+        }
+        Ok(value)
+    }
+}
+
+/// Whether `[address, address + size)` touches a region that can change on its
+/// own or whose reads have side effects.
+pub fn is_volatile(address: u64, size: usize) -> bool {
+    let end = address + size as u64;
+    VOLATILE_REGIONS.iter().any(|r| address < r.end && end > r.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_reads_while_halted_hit_probe_once() {
+        let mut cache = ReadCache::new();
+        cache.set_halted(true);
+        let mut probe_reads = 0;
+        let mut read = |data: &mut [u8]| {
+            probe_reads += 1;
+            data.fill(0xAB);
+            Ok(())
+        };
+
+        let first = cache.read_memory(0x2000_0000, 256, &mut read).unwrap();
+        let second = cache.read_memory(0x2000_0000, 256, &mut read).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(probe_reads, 1);
+
+        // A write inside the block forces a re-read.
+        cache.invalidate_memory(0x2000_0010, 4);
+        cache.read_memory(0x2000_0000, 256, &mut read).unwrap();
+        assert_eq!(probe_reads, 2);
+
+        // Running: nothing is cached.
+        cache.set_halted(false);
+        cache.read_memory(0x2000_0000, 256, &mut read).unwrap();
+        cache.read_memory(0x2000_0000, 256, &mut read).unwrap();
+        assert_eq!(probe_reads, 4);
+    }
+
+    #[test]
+    fn test_peripheral_reads_are_not_cached() {
+        let mut cache = ReadCache::new();
+        cache.set_halted(true);
+        let mut probe_reads = 0;
+        for _ in 0..2 {
+            cache
+                .read_memory(0x4001_3800, 4, |_| {
+                    probe_reads += 1;
+                    Ok(())
+                })
+                .unwrap();
+        }
+        assert_eq!(probe_reads, 2);
+    }
+
+    #[test]
+    fn test_register_cache_invalidation() {
+        let mut cache = ReadCache::new();
+        cache.set_halted(true);
+        assert_eq!(cache.read_register(15, || Ok(0x0800_0100)).unwrap(), 0x0800_0100);
+        assert_eq!(cache.read_register(15, || panic!("cache miss")).unwrap(), 0x0800_0100);
+
+        cache.invalidate_register(15);
+        assert_eq!(cache.read_register(15, || Ok(0x0800_0104)).unwrap(), 0x0800_0104);
+        cache.invalidate();
+        assert_eq!(cache.read_register(15, || Ok(0x0800_0108)).unwrap(), 0x0800_0108);
+    }
+}
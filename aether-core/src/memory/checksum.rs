@@ -0,0 +1,169 @@
+//! Checksums of target memory.
+//!
+//! Lets a flashed image be verified against a known value without a reference
+//! file, e.g. to check that CI installed the right build. Large ranges are read
+//! a few chunks per session-loop iteration so the loop keeps serving commands.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Bytes read from the target per request.
+pub const CHECKSUM_CHUNK: usize = 4096;
+
+/// Number of [`CHECKSUM_CHUNK`] reads per session-loop iteration.
+pub const CHECKSUM_CHUNKS_PER_STEP: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChecksumAlgo {
+    /// CRC-32 (IEEE 802.3), as computed by zlib and `crc32` tools.
+    Crc32,
+    /// Wrapping 32-bit sum of all bytes.
+    Sum32,
+}
+
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// A running checksum fed in chunks.
+#[derive(Debug, Clone)]
+pub struct Checksum {
+    algo: ChecksumAlgo,
+    state: u32,
+}
+
+impl Checksum {
+    pub fn new(algo: ChecksumAlgo) -> Self {
+        let state = match algo {
+            ChecksumAlgo::Crc32 => u32::MAX,
+            ChecksumAlgo::Sum32 => 0,
+        };
+        Self { algo, state }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self.algo {
+            ChecksumAlgo::Crc32 => {
+                for &byte in data {
+                    self.state = CRC32_TABLE[((self.state ^ u32::from(byte)) & 0xFF) as usize]
+                        ^ (self.state >> 8);
+                }
+            }
+            ChecksumAlgo::Sum32 => {
+                for &byte in data {
+                    self.state = self.state.wrapping_add(u32::from(byte));
+                }
+            }
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        match self.algo {
+            ChecksumAlgo::Crc32 => !self.state,
+            ChecksumAlgo::Sum32 => self.state,
+        }
+    }
+}
+
+/// An in-progress checksum of `len` bytes at `address`.
+#[derive(Debug, Clone)]
+pub struct ChecksumJob {
+    address: u64,
+    len: u64,
+    offset: u64,
+    checksum: Checksum,
+}
+
+impl ChecksumJob {
+    pub fn new(address: u64, len: u64, algo: ChecksumAlgo) -> Self {
+        Self { address, len, offset: 0, checksum: Checksum::new(algo) }
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Length of the range in bytes.
+    pub fn size(&self) -> u64 {
+        self.len
+    }
+
+    pub fn algo(&self) -> ChecksumAlgo {
+        self.checksum.algo
+    }
+
+    /// Read the next `chunks` [`CHECKSUM_CHUNK`] pieces with `read(address, buf)`,
+    /// returning the checksum once the whole range has been read, or the first
+    /// read error.
+    pub fn step<F>(&mut self, chunks: usize, mut read: F) -> Option<Result<u32>>
+    where
+        F: FnMut(u64, &mut [u8]) -> Result<()>,
+    {
+        let mut buf = [0u8; CHECKSUM_CHUNK];
+        for _ in 0..chunks {
+            if self.offset >= self.len {
+                break;
+            }
+            let n = (self.len - self.offset).min(CHECKSUM_CHUNK as u64) as usize;
+            let Some(chunk_address) = self.address.checked_add(self.offset) else {
+                return Some(Err(anyhow::anyhow!("Range wraps past the end of memory")));
+            };
+            if let Err(e) = read(chunk_address, &mut buf[..n])
+                .with_context(|| format!("Failed to read 0x{:08X}", chunk_address))
+            {
+                return Some(Err(e));
+            }
+            self.checksum.update(&buf[..n]);
+            self.offset += n as u64;
+        }
+        (self.offset >= self.len).then(|| Ok(self.checksum.finish()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_job_from_mock_memory() {
+        let mut crc = Checksum::new(ChecksumAlgo::Crc32);
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+
+        // A region spanning several chunks, with a partial last one.
+        let base = 0x0800_0000u64;
+        let memory: Vec<u8> = (0..10_000u32).map(|i| (i * 7 + 3) as u8).collect();
+        let mut reads = 0;
+        let mut read = |address: u64, buf: &mut [u8]| -> Result<()> {
+            reads += 1;
+            let start = (address - base) as usize;
+            buf.copy_from_slice(&memory[start..start + buf.len()]);
+            Ok(())
+        };
+
+        let len = memory.len() as u64;
+        let mut crc = ChecksumJob::new(base, len, ChecksumAlgo::Crc32);
+        assert!(crc.step(2, &mut read).is_none());
+        assert_eq!(crc.step(2, &mut read).unwrap().unwrap(), 0xD5E5_0A16);
+        let mut sum = ChecksumJob::new(base, len, ChecksumAlgo::Sum32);
+        assert_eq!(sum.step(CHECKSUM_CHUNKS_PER_STEP, &mut read).unwrap().unwrap(), 0x0013_6FF8);
+        assert_eq!(reads, 6);
+
+        let mut locked = ChecksumJob::new(base, 16, ChecksumAlgo::Crc32);
+        assert!(locked.step(1, |_, _| anyhow::bail!("locked")).unwrap().is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! Audit log of memory and register writes, with undo.
+//!
+//! Every write made through the session records the value it replaced, so a bad
+//! poke can be reverted with `UndoWrite`. Writes whose previous value could not
+//! be read, or must not be (peripheral registers with read side effects), are
+//! still logged but cannot be undone.
+
+use crate::debug::special_regs::SpecialRegister;
+#[cfg(not(feature = "hardware"))]
+use crate::probe_rs::MemoryInterface;
+use anyhow::Result;
+#[cfg(feature = "hardware")]
+use probe_rs::MemoryInterface;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of writes kept in the log; the oldest are dropped first.
+pub const MAX_WRITE_HISTORY: usize = 256;
+
+/// What a write changed. A memory write's `old` is `None` when the replaced
+/// bytes were not read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WriteKind {
+    Memory { address: u64, old: Option<Vec<u8>>, new: Vec<u8> },
+    Register { id: u16, old: u64, new: u64 },
+}
+
+/// One entry of the write log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WriteRecord {
+    pub kind: WriteKind,
+    /// Seconds since the session started.
+    pub timestamp: f64,
+}
+
+/// Target access needed to apply and revert writes.
+pub trait WriteTarget {
+    fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()>;
+    fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()>;
+    fn read_register(&mut self, id: u16) -> Result<u64>;
+    fn write_register(&mut self, id: u16, value: u64) -> Result<()>;
+}
+
+#[cfg(feature = "hardware")]
+impl WriteTarget for probe_rs::Core<'_> {
+    fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()> {
+        Ok(self.read(address, data)?)
+    }
+
+    fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+        Ok(self.write_8(address, data)?)
+    }
+
+    fn read_register(&mut self, id: u16) -> Result<u64> {
+        Ok(match self.read_core_reg(id)? {
+            probe_rs::RegisterValue::U32(v) => v as u64,
+            probe_rs::RegisterValue::U64(v) => v,
+            probe_rs::RegisterValue::U128(v) => v as u64,
+        })
+    }
+
+    fn write_register(&mut self, id: u16, value: u64) -> Result<()> {
+        Ok(self.write_core_reg(id, value)?)
+    }
+}
+
+#[cfg(not(feature = "hardware"))]
+impl WriteTarget for crate::probe_rs::Core {
+    fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()> {
+        self.read(address, data)
+    }
+
+    fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+        self.write_8(address, data)
+    }
+
+    fn read_register(&mut self, _id: u16) -> Result<u64> {
+        anyhow::bail!("Hardware support disabled")
+    }
+
+    fn write_register(&mut self, _id: u16, _value: u64) -> Result<()> {
+        anyhow::bail!("Hardware support disabled")
+    }
+}
+
+/// Bounded log of the writes made during a session.
+#[derive(Debug, Default)]
+pub struct WriteHistory {
+    records: VecDeque<WriteRecord>,
+}
+
+impl WriteHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write `data` at `address`, recording the bytes it replaces if
+    /// `read_old` is set and they can be read. Pass `false` for ranges where a
+    /// read has side effects, e.g. clear-on-read peripheral registers.
+    pub fn write_memory(
+        &mut self,
+        target: &mut dyn WriteTarget,
+        address: u64,
+        data: &[u8],
+        read_old: bool,
+        timestamp: f64,
+    ) -> Result<()> {
+        let mut old = read_old.then(|| vec![0u8; data.len()]);
+        if let Some(buf) = &mut old {
+            if let Err(e) = target.read_memory(address, buf) {
+                log::debug!("No undo data for write at 0x{:08X}: {}", address, e);
+                old = None;
+            }
+        }
+        target.write_memory(address, data)?;
+        self.push(WriteRecord {
+            kind: WriteKind::Memory { address, old, new: data.to_vec() },
+            timestamp,
+        });
+        Ok(())
+    }
+
+    /// Write core register `id`, recording the value it replaces.
+    pub fn write_register(
+        &mut self,
+        target: &mut dyn WriteTarget,
+        id: u16,
+        value: u64,
+        timestamp: f64,
+    ) -> Result<()> {
+        let old = target.read_register(id)?;
+        target.write_register(id, value)?;
+        self.push(WriteRecord { kind: WriteKind::Register { id, old, new: value }, timestamp });
+        Ok(())
+    }
+
+    /// Write special register `reg`, recording the packed word holding it.
+    pub fn write_special_reg(
+        &mut self,
+        target: &mut dyn WriteTarget,
+        halted: bool,
+        reg: SpecialRegister,
+        value: u32,
+        timestamp: f64,
+    ) -> Result<()> {
+        let (id, _) = reg.location();
+        let old = target.read_register(id)?;
+        crate::debug::special_regs::write_special_reg(target, halted, reg, value)?;
+        let new = target.read_register(id)?;
+        self.push(WriteRecord { kind: WriteKind::Register { id, old, new }, timestamp });
+        Ok(())
+    }
+
+    /// Restore the value replaced by the most recent write. Returns the reverted
+    /// record, or `None` when the log is empty. A failed restore stays in the log;
+    /// a write without undo data is dropped with an error so it does not block
+    /// undoing the writes before it.
+    pub fn undo(&mut self, target: &mut dyn WriteTarget) -> Result<Option<WriteRecord>> {
+        let Some(record) = self.records.pop_back() else {
+            return Ok(None);
+        };
+        let restored = match &record.kind {
+            WriteKind::Memory { address, old: Some(old), .. } => target.write_memory(*address, old),
+            WriteKind::Memory { address, old: None, .. } => {
+                anyhow::bail!(
+                    "The write at 0x{:08X} recorded no previous value to restore",
+                    address
+                )
+            }
+            WriteKind::Register { id, old, .. } => target.write_register(*id, *old),
+        };
+        match restored {
+            Ok(()) => Ok(Some(record)),
+            Err(e) => {
+                self.records.push_back(record);
+                Err(e)
+            }
+        }
+    }
+
+    /// Oldest first.
+    pub fn records(&self) -> Vec<WriteRecord> {
+        self.records.iter().cloned().collect()
+    }
+
+    /// Forget all writes, e.g. after the firmware was replaced.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    fn push(&mut self, record: WriteRecord) {
+        if self.records.len() == MAX_WRITE_HISTORY {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MockCore {
+        memory: HashMap<u64, u8>,
+        registers: HashMap<u16, u64>,
+        /// Memory reads fail, like a write-only or unmapped region.
+        unreadable: bool,
+    }
+
+    impl WriteTarget for MockCore {
+        fn read_memory(&mut self, address: u64, data: &mut [u8]) -> Result<()> {
+            anyhow::ensure!(!self.unreadable, "Read fault");
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte = *self.memory.get(&(address + i as u64)).unwrap_or(&0);
+            }
+            Ok(())
+        }
+
+        fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<()> {
+            for (i, &byte) in data.iter().enumerate() {
+                self.memory.insert(address + i as u64, byte);
+            }
+            Ok(())
+        }
+
+        fn read_register(&mut self, id: u16) -> Result<u64> {
+            Ok(*self.registers.get(&id).unwrap_or(&0))
+        }
+
+        fn write_register(&mut self, id: u16, value: u64) -> Result<()> {
+            self.registers.insert(id, value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_then_undo_restores_memory() {
+        let mut core = MockCore::default();
+        core.write_memory(0x2000_0000, &[1, 2, 3, 4]).unwrap();
+        core.write_register(0, 0x1234).unwrap();
+
+        let mut history = WriteHistory::new();
+        history.write_memory(&mut core, 0x2000_0000, &[0xFF, 0xEE], true, 1.0).unwrap();
+        history.write_register(&mut core, 0, 0xDEAD, 2.0).unwrap();
+        assert_eq!(history.records().len(), 2);
+        assert_eq!(
+            history.records()[0].kind,
+            WriteKind::Memory {
+                address: 0x2000_0000,
+                old: Some(vec![1, 2]),
+                new: vec![0xFF, 0xEE]
+            }
+        );
+
+        let undone = history.undo(&mut core).unwrap().unwrap();
+        assert_eq!(undone.kind, WriteKind::Register { id: 0, old: 0x1234, new: 0xDEAD });
+        assert_eq!(core.read_register(0).unwrap(), 0x1234);
+
+        history.undo(&mut core).unwrap();
+        let mut data = [0u8; 4];
+        core.read_memory(0x2000_0000, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4]);
+
+        assert!(history.undo(&mut core).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_write_without_undo_data() {
+        let mut core = MockCore { unreadable: true, ..Default::default() };
+        let mut history = WriteHistory::new();
+        history.write_memory(&mut core, 0x2000_0000, &[1], true, 1.0).unwrap();
+        history.write_memory(&mut core, 0x4000_0000, &[2], false, 2.0).unwrap();
+        assert_eq!(core.memory.get(&0x4000_0000), Some(&2));
+        assert!(history
+            .records()
+            .iter()
+            .all(|r| matches!(r.kind, WriteKind::Memory { old: None, .. })));
+
+        // Each is dropped with an error, so older writes stay reachable.
+        assert!(history.undo(&mut core).is_err());
+        assert_eq!(history.records().len(), 1);
+        assert!(history.undo(&mut core).is_err());
+        assert!(history.undo(&mut core).unwrap().is_none());
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! Handles reading and writing to target memory.
 
+pub mod cache;
+pub mod checksum;
+pub mod history;
+pub mod snapshot;
+
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::MemoryInterface;
 use anyhow::{Context, Result};
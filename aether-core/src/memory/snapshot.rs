@@ -0,0 +1,166 @@
+//! Memory snapshots and byte diffs.
+//!
+//! Snapshot a region, let the target do something, snapshot it again and see
+//! which bytes changed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of snapshots kept; the oldest are dropped first.
+pub const MAX_SNAPSHOTS: usize = 32;
+
+/// Largest region a single snapshot may cover, so the kept snapshots stay
+/// bounded at `MAX_SNAPSHOTS * MAX_SNAPSHOT_LEN` bytes.
+pub const MAX_SNAPSHOT_LEN: usize = 256 * 1024;
+
+/// Contents of a memory region at one point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    /// Session-unique, increasing from 1.
+    pub id: u32,
+    pub address: u64,
+    pub data: Vec<u8>,
+    /// Seconds since the session started.
+    pub timestamp: f64,
+}
+
+impl MemorySnapshot {
+    /// One past the last address covered.
+    pub fn end(&self) -> u64 {
+        self.address + self.data.len() as u64
+    }
+}
+
+/// A byte that differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteChange {
+    /// Offset from [`MemoryDiff::address`].
+    pub offset: usize,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// The changes between two snapshots over the range both of them cover.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryDiff {
+    /// Start of the compared range.
+    pub address: u64,
+    /// Bytes compared; zero when the snapshots do not overlap.
+    pub len: usize,
+    pub changes: Vec<ByteChange>,
+}
+
+impl MemoryDiff {
+    /// Whether the byte at `address` changed.
+    pub fn changed(&self, address: u64) -> bool {
+        address
+            .checked_sub(self.address)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .is_some_and(|offset| self.changes.binary_search_by_key(&offset, |c| c.offset).is_ok())
+    }
+}
+
+/// Bytes that differ between `old` and `new`, in offset order. Only the
+/// length both buffers share is compared.
+pub fn diff_bytes(old: &[u8], new: &[u8]) -> Vec<ByteChange> {
+    old.iter()
+        .zip(new)
+        .enumerate()
+        .filter(|(_, (o, n))| o != n)
+        .map(|(offset, (&old, &new))| ByteChange { offset, old, new })
+        .collect()
+}
+
+/// What changed from snapshot `a` to snapshot `b`. Snapshots of different
+/// regions are compared over the addresses both cover.
+pub fn diff_memory_snapshots(a: &MemorySnapshot, b: &MemorySnapshot) -> MemoryDiff {
+    let start = a.address.max(b.address);
+    let end = a.end().min(b.end());
+    if start >= end {
+        return MemoryDiff { address: start, ..Default::default() };
+    }
+    let slice = |s: &MemorySnapshot| {
+        let from = (start - s.address) as usize;
+        &s.data[from..from + (end - start) as usize]
+    };
+    MemoryDiff {
+        address: start,
+        len: (end - start) as usize,
+        changes: diff_bytes(slice(a), slice(b)),
+    }
+}
+
+/// Bounded store of the snapshots taken during a session.
+#[derive(Debug, Default)]
+pub struct SnapshotStore {
+    snapshots: VecDeque<MemorySnapshot>,
+    next_id: u32,
+}
+
+impl SnapshotStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `data` read from `address` and return the stored snapshot.
+    pub fn insert(&mut self, address: u64, data: Vec<u8>, timestamp: f64) -> MemorySnapshot {
+        self.next_id += 1;
+        let snapshot = MemorySnapshot { id: self.next_id, address, data, timestamp };
+        if self.snapshots.len() == MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot.clone());
+        snapshot
+    }
+
+    pub fn get(&self, id: u32) -> Option<&MemorySnapshot> {
+        self.snapshots.iter().find(|s| s.id == id)
+    }
+
+    /// Compare two stored snapshots, or `None` if either has been dropped.
+    pub fn diff(&self, a: u32, b: u32) -> Option<MemoryDiff> {
+        Some(diff_memory_snapshots(self.get(a)?, self.get(b)?))
+    }
+
+    /// Forget all snapshots, e.g. after the firmware was replaced.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_bytes_reports_changed_offsets() {
+        let before = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let after = [0x00, 0x11, 0xAA, 0x33, 0x44, 0xFF];
+        assert_eq!(
+            diff_bytes(&before, &after),
+            vec![
+                ByteChange { offset: 2, old: 0x22, new: 0xAA },
+                ByteChange { offset: 5, old: 0x55, new: 0xFF },
+            ]
+        );
+        assert!(diff_bytes(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_diff_covers_the_overlap() {
+        let mut store = SnapshotStore::new();
+        let a = store.insert(0x2000_0000, vec![1, 2, 3, 4, 5, 6, 7, 8], 0.0).id;
+        // Starts four bytes later and runs four bytes past the first snapshot.
+        let b = store.insert(0x2000_0004, vec![5, 0, 7, 8, 9, 9, 9, 9], 1.0).id;
+
+        let diff = store.diff(a, b).unwrap();
+        assert_eq!(diff.address, 0x2000_0004);
+        assert_eq!(diff.len, 4);
+        assert_eq!(diff.changes, vec![ByteChange { offset: 1, old: 6, new: 0 }]);
+        assert!(diff.changed(0x2000_0005));
+        assert!(!diff.changed(0x2000_0004));
+        assert!(!diff.changed(0x2000_0000));
+
+        assert!(store.diff(a, 99).is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! Session health metrics.
+//!
+//! Lets long-running automation notice backpressure: commands piling up in the
+//! queue, events overwritten before a slow subscriber read them, a slow probe.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionMetrics {
+    /// Commands waiting in the session's queue.
+    pub queue_depth: usize,
+    /// Events overwritten before the slowest subscriber read them, since the
+    /// session started.
+    pub dropped: u64,
+    /// Duration of the last status read, one probe round trip, in microseconds.
+    pub probe_latency_us: Option<u64>,
+    /// RTT bytes received per second since the previous report.
+    pub rtt_bps: f64,
+}
+
+/// Collects the measurements behind [`SessionMetrics`] and decides when to
+/// report them periodically.
+#[derive(Debug)]
+pub struct MetricsTracker {
+    interval: Option<Duration>,
+    last_report: Instant,
+    rtt_bytes: u64,
+    probe_latency: Option<Duration>,
+}
+
+impl MetricsTracker {
+    pub fn new(now: Instant) -> Self {
+        Self { interval: None, last_report: now, rtt_bytes: 0, probe_latency: None }
+    }
+
+    /// Report every `interval`, or only on request with `None`.
+    pub fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    pub fn record_rtt(&mut self, bytes: usize) {
+        self.rtt_bytes += bytes as u64;
+    }
+
+    pub fn record_probe_latency(&mut self, latency: Duration) {
+        self.probe_latency = Some(latency);
+    }
+
+    /// Build a report and start a new RTT rate window.
+    pub fn report(&mut self, now: Instant, queue_depth: usize, dropped: u64) -> SessionMetrics {
+        let elapsed = now.duration_since(self.last_report).as_secs_f64();
+        let rtt_bps = if elapsed > 0.0 { self.rtt_bytes as f64 / elapsed } else { 0.0 };
+        self.last_report = now;
+        self.rtt_bytes = 0;
+        SessionMetrics {
+            queue_depth,
+            dropped,
+            probe_latency_us: self.probe_latency.map(|d| d.as_micros() as u64),
+            rtt_bps,
+        }
+    }
+
+    /// A report if periodic reporting is on and the interval has elapsed.
+    pub fn poll(
+        &mut self,
+        now: Instant,
+        queue_depth: usize,
+        dropped: u64,
+    ) -> Option<SessionMetrics> {
+        let interval = self.interval?;
+        if now.duration_since(self.last_report) < interval {
+            return None;
+        }
+        Some(self.report(now, queue_depth, dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{DebugCommand, DebugEvent, EventSender};
+
+    #[test]
+    fn test_metrics_reflect_queue_depth_and_drops() {
+        // A subscriber that never reads falls behind a 4-event buffer.
+        let evt_tx = EventSender::new(4);
+        let _slow = evt_tx.subscribe();
+        for _ in 0..6 {
+            evt_tx.send(DebugEvent::Resumed).unwrap();
+        }
+        assert_eq!(evt_tx.dropped(), 2);
+
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        for _ in 0..3 {
+            cmd_tx.send(DebugCommand::PollStatus).unwrap();
+        }
+
+        let start = Instant::now();
+        let mut metrics = MetricsTracker::new(start);
+        metrics.record_rtt(1500);
+        metrics.record_rtt(500);
+        metrics.record_probe_latency(Duration::from_micros(850));
+
+        let report = metrics.report(start + Duration::from_secs(2), cmd_rx.len(), evt_tx.dropped());
+        assert_eq!(
+            report,
+            SessionMetrics {
+                queue_depth: 3,
+                dropped: 2,
+                probe_latency_us: Some(850),
+                rtt_bps: 1000.0
+            }
+        );
+
+        // Periodic reports are off until an interval is set.
+        let later = start + Duration::from_secs(3);
+        assert_eq!(metrics.poll(later, 0, 2), None);
+        metrics.set_interval(Some(Duration::from_millis(500)));
+        assert_eq!(metrics.poll(later, 0, 2).map(|m| m.rtt_bps), Some(0.0));
+        assert_eq!(metrics.poll(later + Duration::from_millis(100), 0, 2), None);
+    }
+}
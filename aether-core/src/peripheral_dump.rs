@@ -0,0 +1,212 @@
+//! Peripheral dump module.
+//!
+//! Captures the registers of every SVD peripheral for bug reports and state
+//! comparisons, a few peripherals per session-loop iteration so the loop keeps
+//! serving commands while the dump runs. Two dumps can be diffed field by field.
+
+use crate::svd::RegisterInfo;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+
+/// Number of peripherals read per session-loop iteration.
+pub const PERIPHERAL_DUMP_CHUNK: usize = 8;
+
+/// Register values of all peripherals.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeripheralDump {
+    /// Peripheral name -> registers with their values and field layout.
+    pub peripherals: BTreeMap<String, Vec<RegisterInfo>>,
+}
+
+impl PeripheralDump {
+    /// Render the dump as a plain-text report.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (peripheral, registers) in &self.peripherals {
+            let _ = writeln!(out, "{}", peripheral);
+            for register in registers {
+                match register.value {
+                    Some(v) => {
+                        let _ = writeln!(out, "  {:<16} 0x{:08X}", register.name, v);
+                    }
+                    None => {
+                        let _ = writeln!(out, "  {:<16} <unreadable>", register.name);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// An in-progress dump of a list of peripherals.
+#[derive(Debug, Default)]
+pub struct PeripheralDumpJob {
+    pending: VecDeque<String>,
+    dump: PeripheralDump,
+}
+
+impl PeripheralDumpJob {
+    pub fn new(peripherals: Vec<String>) -> Self {
+        Self { pending: peripherals.into(), dump: PeripheralDump::default() }
+    }
+
+    /// Read the next `chunk` peripherals with `read`, returning the finished
+    /// dump once every peripheral has been read.
+    ///
+    /// Peripherals that fail to read are kept in the dump with no registers.
+    pub fn step<F>(&mut self, chunk: usize, mut read: F) -> Option<PeripheralDump>
+    where
+        F: FnMut(&str) -> Result<Vec<RegisterInfo>>,
+    {
+        for _ in 0..chunk {
+            let Some(name) = self.pending.pop_front() else {
+                break;
+            };
+            let registers = read(&name).unwrap_or_default();
+            self.dump.peripherals.insert(name, registers);
+        }
+        self.pending.is_empty().then(|| std::mem::take(&mut self.dump))
+    }
+}
+
+/// A register field whose value differs between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub peripheral: String,
+    pub register: String,
+    /// Field name, or the register name for registers without fields.
+    pub field: String,
+    pub old: u64,
+    pub new: u64,
+}
+
+/// Compare two snapshots field by field.
+///
+/// Registers missing or unreadable in either snapshot are skipped.
+pub fn diff_peripheral_snapshots(a: &PeripheralDump, b: &PeripheralDump) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    for (peripheral, new_regs) in &b.peripherals {
+        let Some(old_regs) = a.peripherals.get(peripheral) else {
+            continue;
+        };
+        for reg in new_regs {
+            let Some(old_reg) = old_regs.iter().find(|r| r.name == reg.name) else {
+                continue;
+            };
+            let (Some(old), Some(new)) = (old_reg.value, reg.value) else {
+                continue;
+            };
+            if old == new {
+                continue;
+            }
+            let change = |field: &str, old, new| FieldChange {
+                peripheral: peripheral.clone(),
+                register: reg.name.clone(),
+                field: field.to_string(),
+                old,
+                new,
+            };
+            if reg.fields.is_empty() {
+                changes.push(change(&reg.name, old, new));
+            }
+            for field in &reg.fields {
+                let (old_field, new_field) = (field.decode(old), field.decode(new));
+                if old_field != new_field {
+                    changes.push(change(&field.name, old_field, new_field));
+                }
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::FieldInfo;
+
+    fn field(name: &str, bit_offset: u32, bit_width: u32) -> FieldInfo {
+        FieldInfo {
+            name: name.to_string(),
+            description: None,
+            bit_offset,
+            bit_width,
+            write_one_to_clear: false,
+        }
+    }
+
+    fn mock_register(name: &str, value: u64) -> RegisterInfo {
+        RegisterInfo {
+            name: name.to_string(),
+            address_offset: 0,
+            description: None,
+            size: 32,
+            reset_value: None,
+            write_one_to_clear: false,
+            fields: Vec::new(),
+            value: Some(value),
+        }
+    }
+
+    #[test]
+    fn test_dump_covers_all_peripherals() {
+        let names: Vec<String> = (0..5).map(|i| format!("PERIPH{}", i)).collect();
+        let mut job = PeripheralDumpJob::new(names.clone());
+
+        let read = |name: &str| {
+            if name == "PERIPH3" {
+                anyhow::bail!("bus fault");
+            }
+            Ok(vec![mock_register("CR", 0x1), mock_register("SR", 0xC0)])
+        };
+
+        // Two peripherals per step: the dump completes on the third step.
+        assert!(job.step(2, read).is_none());
+        assert!(job.step(2, read).is_none());
+        let dump = job.step(2, read).unwrap();
+
+        let dumped: Vec<_> = dump.peripherals.keys().cloned().collect();
+        assert_eq!(dumped, names);
+        assert_eq!(dump.peripherals["PERIPH0"][1].value, Some(0xC0));
+        assert!(dump.peripherals["PERIPH3"].is_empty());
+        assert!(dump.to_text().contains("  SR               0x000000C0"));
+    }
+
+    #[test]
+    fn test_diff_peripheral_snapshots() {
+        let snapshot = |cr: u64, sr: u64, dr: u64| {
+            let mut cr_reg = mock_register("CR", cr);
+            cr_reg.fields = vec![field("EN", 0, 1), field("MODE", 1, 2)];
+            let mut sr_reg = mock_register("SR", sr);
+            sr_reg.fields = vec![field("RXNE", 5, 1), field("TXE", 7, 1)];
+            let mut dump = PeripheralDump::default();
+            dump.peripherals.insert("USART1".to_string(), vec![cr_reg, sr_reg]);
+            dump.peripherals.insert("CRC".to_string(), vec![mock_register("DR", dr)]);
+            dump
+        };
+
+        let before = snapshot(0b001, 0x80, 0xFFFF_FFFF);
+        let after = snapshot(0b101, 0xA0, 0x1234_5678);
+        let changes = diff_peripheral_snapshots(&before, &after);
+
+        let change = |peripheral: &str, register: &str, field: &str, old, new| FieldChange {
+            peripheral: peripheral.to_string(),
+            register: register.to_string(),
+            field: field.to_string(),
+            old,
+            new,
+        };
+        assert_eq!(
+            changes,
+            vec![
+                change("CRC", "DR", "DR", 0xFFFF_FFFF, 0x1234_5678),
+                change("USART1", "CR", "MODE", 0b00, 0b10),
+                change("USART1", "SR", "RXNE", 0, 1),
+            ]
+        );
+        assert!(diff_peripheral_snapshots(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Plot module.
+//!
+//! Keeps a bounded history of sampled plot values in the core so that
+//! long-running sessions don't accumulate unbounded state.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Default number of points kept per plot, matching the UI cap.
+pub const DEFAULT_MAX_POINTS: usize = 100_000;
+
+/// Retention policy for a single plot.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlotRetention {
+    /// Maximum number of points kept (oldest are dropped first).
+    pub max_points: Option<usize>,
+    /// Maximum age of a point in seconds, relative to the newest sample.
+    pub max_duration: Option<f64>,
+}
+
+impl Default for PlotRetention {
+    fn default() -> Self {
+        Self { max_points: Some(DEFAULT_MAX_POINTS), max_duration: None }
+    }
+}
+
+/// Aggregate over the retained points of a plot.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlotSummary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Sampled history of a single plot.
+#[derive(Debug, Clone, Default)]
+pub struct PlotSeries {
+    retention: PlotRetention,
+    points: VecDeque<(f64, f64)>,
+}
+
+impl PlotSeries {
+    pub fn new(retention: PlotRetention) -> Self {
+        Self { retention, points: VecDeque::new() }
+    }
+
+    pub fn retention(&self) -> PlotRetention {
+        self.retention
+    }
+
+    pub fn set_retention(&mut self, retention: PlotRetention) {
+        self.retention = retention;
+        self.prune();
+    }
+
+    /// Append a sample and apply the retention policy.
+    pub fn push(&mut self, timestamp: f64, value: f64) {
+        self.points.push_back((timestamp, value));
+        self.prune();
+    }
+
+    /// Drop the oldest points that fall outside the retention window.
+    pub fn prune(&mut self) {
+        if let Some(max_duration) = self.retention.max_duration {
+            if let Some(&(newest, _)) = self.points.back() {
+                while let Some(&(ts, _)) = self.points.front() {
+                    if newest - ts > max_duration {
+                        self.points.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(max_points) = self.retention.max_points {
+            while self.points.len() > max_points {
+                self.points.pop_front();
+            }
+        }
+    }
+
+    pub fn points(&self) -> &VecDeque<(f64, f64)> {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Prune, then aggregate over the remaining points.
+    pub fn summary(&mut self) -> Option<PlotSummary> {
+        self.prune();
+        if self.points.is_empty() {
+            return None;
+        }
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        let mut sum = 0.0;
+        for &(_, v) in &self.points {
+            min = min.min(v);
+            max = max.max(v);
+            sum += v;
+        }
+        let count = self.points.len();
+        Some(PlotSummary { count, min, max, mean: sum / count as f64 })
+    }
+}
+
+/// Per-session store of plot histories, keyed by plot name.
+#[derive(Debug, Default)]
+pub struct PlotStore {
+    series: HashMap<String, PlotSeries>,
+}
+
+impl PlotStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, retention: PlotRetention) {
+        self.series.insert(name.to_string(), PlotSeries::new(retention));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.series.remove(name);
+    }
+
+    /// Update the retention policy of an existing plot. Returns false if unknown.
+    pub fn set_retention(&mut self, name: &str, retention: PlotRetention) -> bool {
+        if let Some(series) = self.series.get_mut(name) {
+            series.set_retention(retention);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn push(&mut self, name: &str, timestamp: f64, value: f64) {
+        if let Some(series) = self.series.get_mut(name) {
+            series.push(timestamp, value);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PlotSeries> {
+        self.series.get(name)
+    }
+
+    pub fn summary(&mut self, name: &str) -> Option<PlotSummary> {
+        self.series.get_mut(name).and_then(PlotSeries::summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_prunes_oldest_points() {
+        let mut store = PlotStore::new();
+        store.add("count", PlotRetention { max_points: Some(3), max_duration: None });
+        for i in 0..5 {
+            store.push("count", f64::from(i), f64::from(i) * 10.0);
+        }
+        let series = store.get("count").unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.points().front(), Some(&(2.0, 20.0)));
+
+        store.add("window", PlotRetention { max_points: None, max_duration: Some(1.5) });
+        for i in 0..5 {
+            store.push("window", f64::from(i), 1.0);
+        }
+        let series = store.get("window").unwrap();
+        let timestamps: Vec<f64> = series.points().iter().map(|p| p.0).collect();
+        assert_eq!(timestamps, vec![3.0, 4.0]);
+
+        let summary = store.summary("count").unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.min, 20.0);
+        assert_eq!(summary.max, 40.0);
+    }
+}
@@ -8,7 +8,7 @@ pub use probe_rs::probe::WireProtocol;
 use probe_rs::probe::{DebugProbeInfo, Probe};
 
 /// Information about an available debug probe.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ProbeInfo {
     pub vendor_id: u16,
     pub product_id: u16,
@@ -18,7 +18,7 @@ pub struct ProbeInfo {
 }
 
 /// Type of debug probe.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ProbeType {
     StLink,
     JLink,
@@ -82,6 +82,143 @@ pub fn map_probe_error(err: &anyhow::Error) -> String {
     }
 }
 
+/// Chips tried when auto-detection fails with every protocol.
+const HEURISTIC_CHIPS: [&str; 3] = ["STM32L476RGTx", "STM32F407VGTx", "Cortex-M"];
+
+/// One way of attaching tried while negotiating a connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AttachStrategy<'a> {
+    protocol: WireProtocol,
+    under_reset: bool,
+    chip: &'a str,
+}
+
+impl AttachStrategy<'_> {
+    fn describe(&self) -> String {
+        let reset = if self.under_reset { " under reset" } else { "" };
+        let protocol = format!("{:?}", self.protocol).to_uppercase();
+        format!("{}{} ({})", protocol, reset, self.chip)
+    }
+}
+
+/// The strategies to try, in order.
+///
+/// A user-specified protocol is tried alone; otherwise SWD, then JTAG. With
+/// auto-detection each protocol is retried under reset, and a few common chips
+/// are tried last.
+fn attach_strategies(
+    target_name: &str,
+    protocol: Option<WireProtocol>,
+    under_reset: bool,
+) -> Vec<AttachStrategy<'_>> {
+    let auto = target_name.eq_ignore_ascii_case("auto");
+    let protocols = match protocol {
+        Some(proto) => vec![proto],
+        None => vec![WireProtocol::Swd, WireProtocol::Jtag],
+    };
+
+    let mut strategies = Vec::new();
+    for protocol in protocols {
+        strategies.push(AttachStrategy { protocol, under_reset, chip: target_name });
+        if !under_reset && auto {
+            strategies.push(AttachStrategy { protocol, under_reset: true, chip: target_name });
+        }
+    }
+    if protocol.is_none() && auto {
+        strategies.extend(HEURISTIC_CHIPS.iter().map(|&chip| AttachStrategy {
+            protocol: WireProtocol::Swd,
+            under_reset: false,
+            chip,
+        }));
+    }
+    strategies
+}
+
+/// Try each strategy until one attaches. If none does, the error is a
+/// [`DebugError::AttachFailed`](crate::DebugError::AttachFailed) recording
+/// every attempt.
+fn negotiate<T>(
+    strategies: &[AttachStrategy<'_>],
+    mut attempt: impl FnMut(&AttachStrategy<'_>) -> Result<T>,
+) -> Result<T> {
+    let mut attempts = Vec::new();
+    for strategy in strategies {
+        match attempt(strategy) {
+            Ok(attached) => {
+                log::info!("Attached with {}", strategy.describe());
+                return Ok(attached);
+            }
+            Err(e) => {
+                log::warn!("{} failed: {:#}", strategy.describe(), e);
+                attempts.push(crate::AttachAttempt {
+                    stage: strategy.describe(),
+                    error: format!("{:#}", e),
+                });
+            }
+        }
+    }
+    Err(crate::DebugError::AttachFailed { attempts }.into())
+}
+
+/// How a chip attached with `selector` was identified.
+fn detection_method(selector: &str) -> crate::DetectionMethod {
+    if selector.eq_ignore_ascii_case("auto") {
+        crate::DetectionMethod::Auto
+    } else {
+        crate::DetectionMethod::Requested
+    }
+}
+
+/// Default SWD/JTAG clock used during attach, in kHz.
+pub const DEFAULT_SPEED_KHZ: u32 = 1000;
+
+/// How long nRST is held low when recovering a failed attach.
+const RESET_PULSE: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The probe's target reset line, separated out so recovery can be tested
+/// without hardware.
+trait ResetLine {
+    fn assert_reset(&mut self) -> Result<()>;
+    fn deassert_reset(&mut self) -> Result<()>;
+}
+
+impl ResetLine for Probe {
+    fn assert_reset(&mut self) -> Result<()> {
+        self.target_reset_assert().context("Failed to assert nRST")
+    }
+
+    fn deassert_reset(&mut self) -> Result<()> {
+        self.target_reset_deassert().context("Failed to release nRST")
+    }
+}
+
+/// Run `connect`; if it fails, pulse nRST on the probe from `open` and run it
+/// once more. The original error is kept when the pulse itself fails.
+fn connect_with_reset_recovery<P: ResetLine, T>(
+    mut connect: impl FnMut() -> Result<T>,
+    open: impl FnOnce() -> Result<P>,
+    mut progress: impl FnMut(String),
+) -> Result<T> {
+    let err = match connect() {
+        Ok(attached) => return Ok(attached),
+        Err(e) => e,
+    };
+    progress("Attach failed, pulsing nRST".to_string());
+    let pulse = open().and_then(|mut probe| {
+        probe.assert_reset()?;
+        std::thread::sleep(RESET_PULSE);
+        probe.deassert_reset()
+        // The probe is closed here so the retry can open it again.
+    });
+    if let Err(e) = pulse {
+        log::warn!("nRST recovery failed: {:#}", e);
+        progress(format!("Could not pulse nRST: {:#}", e));
+        return Err(err);
+    }
+    progress("Retrying attach after reset".to_string());
+    connect()
+}
+
 /// Probe manager for enumerating and connecting to debug probes.
 pub struct ProbeManager {
     lister: Lister,
@@ -107,6 +244,23 @@ impl ProbeManager {
         probe_info.open().context("Failed to open probe")
     }
 
+    /// Find the index of a probe by its serial number.
+    pub fn find_probe_by_serial(&self, serial: &str) -> Result<usize> {
+        let probes = self.lister.list_all();
+        probes
+            .iter()
+            .position(|p| p.serial_number.as_deref() == Some(serial))
+            .with_context(|| format!("No probe with serial {}", serial))
+    }
+
+    /// Target supply voltage as measured by the probe, `None` if it cannot measure it.
+    ///
+    /// The probe must not be in use by a session, so this is read before attaching.
+    pub fn target_voltage(&self, index: usize) -> Result<Option<f32>> {
+        let mut probe = self.open_probe(index)?;
+        probe.get_target_voltage().context("Failed to read target voltage")
+    }
+
     /// Open the first available probe.
     pub fn open_first_probe(&self) -> Result<Probe> {
         let probes = self.lister.list_all();
@@ -123,12 +277,36 @@ impl Default for ProbeManager {
 }
 
 /// Information about the connected target chip.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TargetInfo {
     pub name: String,
     pub flash_size: u64,
     pub ram_size: u64,
     pub architecture: String,
+    /// Byte order used to decode target memory.
+    #[serde(default)]
+    pub endian: crate::Endian,
+    /// The chip selector the attach asked for, e.g. `auto`; `name` is what attached.
+    #[serde(default)]
+    pub requested: String,
+    #[serde(default)]
+    pub detection_method: crate::DetectionMethod,
+}
+
+impl TargetInfo {
+    /// Record that the attach asked for `requested`. Attaching as any other
+    /// chip means auto-detection failed and a heuristic fallback was used.
+    fn requested_as(mut self, requested: &str) -> Self {
+        if !self.requested.eq_ignore_ascii_case(requested) {
+            log::warn!(
+                "Auto-detection failed, attached as {}; select the chip explicitly if this is wrong",
+                self.name
+            );
+            self.requested = requested.to_string();
+            self.detection_method = crate::DetectionMethod::Heuristic;
+        }
+        self
+    }
 }
 
 impl ProbeManager {
@@ -196,6 +374,11 @@ impl ProbeManager {
             flash_size,
             ram_size,
             architecture: format!("{:?}", target.architecture()),
+            // Every core probe-rs drives runs little-endian; big-endian images
+            // are still detected from the ELF when symbols are loaded.
+            endian: crate::Endian::Little,
+            requested: target_name.to_string(),
+            detection_method: detection_method(target_name),
         };
 
         Ok((info, session))
@@ -208,104 +391,62 @@ impl ProbeManager {
         target_name: &str,
         protocol: Option<WireProtocol>,
         under_reset: bool,
+    ) -> Result<(TargetInfo, probe_rs::Session)> {
+        self.connect_with_speed(probe_index, target_name, protocol, under_reset, None)
+    }
+
+    /// Connect to a target with an explicit probe clock (kHz).
+    /// `None` keeps the probe default for a user-specified protocol and
+    /// `DEFAULT_SPEED_KHZ` during negotiation.
+    pub fn connect_with_speed(
+        &self,
+        probe_index: usize,
+        target_name: &str,
+        protocol: Option<WireProtocol>,
+        under_reset: bool,
+        speed_khz: Option<u32>,
     ) -> Result<(TargetInfo, probe_rs::Session)> {
         let probes = self.lister.list_all();
         let probe_info = probes.get(probe_index).context("Probe index out of range")?;
+        let speed = speed_khz.unwrap_or(DEFAULT_SPEED_KHZ);
 
-        if let Some(proto) = protocol {
-            // User specified protocol
+        negotiate(&attach_strategies(target_name, protocol, under_reset), |strategy| {
+            log::info!("Trying {}...", strategy.describe());
             let mut probe = probe_info.open()?;
-            probe.select_protocol(proto)?;
-
-            match self.detect_target_internal(probe, target_name, under_reset) {
-                Ok(res) => Ok(res),
-                Err(e) if !under_reset && target_name.eq_ignore_ascii_case("auto") => {
-                    log::warn!(
-                        "Specified protocol ({:?}) attach failed. Retrying under reset...",
-                        proto
-                    );
-                    let mut probe = probe_info.open()?;
-                    probe.select_protocol(proto)?;
-                    self.detect_target_internal(probe, target_name, true)
-                }
-                Err(e) => Err(e),
-            }
-        } else {
-            // Automated negotiation: Try SWD (Normal -> Reset), then JTAG (Normal -> Reset)
-            let protocols = [WireProtocol::Swd, WireProtocol::Jtag];
-            let mut last_error = None;
-
-            for &proto in &protocols {
-                log::info!("Trying protocol: {:?}...", proto);
-
-                // 1. Normal Attach
-                let mut probe = match probe_info.open() {
-                    Ok(p) => p,
-                    Err(e) => {
-                        log::warn!("Failed to open probe for {:?}: {}", proto, e);
-                        continue;
-                    }
-                };
-                let _ = probe.select_protocol(proto);
-                let _ = probe.set_speed(1000); // Try lower speed for compatibility
-                match self.detect_target_internal(probe, target_name, under_reset) {
-                    Ok(res) => {
-                        log::info!("Successfully attached with {:?} (Normal)", proto);
-                        return Ok(res);
-                    }
-                    Err(e) => {
-                        log::warn!("{:?} normal attach failed: {}", proto, e);
-                        last_error = Some(e);
-                    }
-                }
-
-                // 2. Fallback to Reset (if not already under reset and name is "auto")
-                if !under_reset && target_name.eq_ignore_ascii_case("auto") {
-                    log::info!("Trying {:?} under reset...", proto);
-                    let mut probe = match probe_info.open() {
-                        Ok(p) => p,
-                        Err(e) => {
-                            log::warn!("Failed to open probe for {:?} (Reset): {}", proto, e);
-                            continue;
-                        }
-                    };
-                    let _ = probe.select_protocol(proto);
-                    let _ = probe.set_speed(1000);
-                    match self.detect_target_internal(probe, target_name, true) {
-                        Ok(res) => {
-                            log::info!("Successfully attached with {:?} (Reset)", proto);
-                            return Ok(res);
-                        }
-                        Err(e) => {
-                            log::warn!("{:?} reset attach failed: {}", proto, e);
-                            last_error = Some(e);
-                        }
-                    }
-                }
-            }
-
-            // 3. Final Heuristic Fallback: Try common chips and Generic
-            if target_name.eq_ignore_ascii_case("auto") {
-                log::info!("Auto-detection exhausted. Trying heuristic fallbacks...");
-                let heuristics = ["STM32L476RGTx", "STM32F407VGTx", "Cortex-M"];
-                for chip in heuristics {
-                    log::info!("Trying heuristic: {}...", chip);
-                    let mut probe = match probe_info.open() {
-                        Ok(p) => p,
-                        Err(_) => continue,
-                    };
-                    let _ = probe.select_protocol(WireProtocol::Swd);
-                    let _ = probe.set_speed(1000);
-                    if let Ok(res) = self.detect_target_internal(probe, chip, false) {
-                        log::info!("Heuristic SUCCESS: Identified as {}", chip);
-                        return Ok(res);
-                    }
+            if protocol.is_some() {
+                // A user-specified protocol and clock must be honored.
+                probe.select_protocol(strategy.protocol)?;
+                if let Some(khz) = speed_khz {
+                    probe.set_speed(khz)?;
                 }
+            } else {
+                let _ = probe.select_protocol(strategy.protocol);
+                let _ = probe.set_speed(speed); // Try lower speed for compatibility
             }
+            let (info, session) =
+                self.detect_target_internal(probe, strategy.chip, strategy.under_reset)?;
+            Ok((info.requested_as(target_name), session))
+        })
+    }
 
-            Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Discovery failed")))
-                .context("Zero-config attachment failed")
-        }
+    /// Like [`connect_with_speed`](Self::connect_with_speed), but if every
+    /// strategy fails, toggle the probe's nRST line and try again once. Some
+    /// targets only re-attach after a hardware reset. Steps are reported
+    /// through `progress`.
+    pub fn connect_with_reset_recovery(
+        &self,
+        probe_index: usize,
+        target_name: &str,
+        protocol: Option<WireProtocol>,
+        under_reset: bool,
+        speed_khz: Option<u32>,
+        progress: impl FnMut(String),
+    ) -> Result<(TargetInfo, probe_rs::Session)> {
+        connect_with_reset_recovery(
+            || self.connect_with_speed(probe_index, target_name, protocol, under_reset, speed_khz),
+            || self.open_probe(probe_index),
+            progress,
+        )
     }
 
     /// Detect the target chip connected to the opened probe.
@@ -325,6 +466,147 @@ impl ProbeManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_failed_negotiation_records_each_stage() {
+        let strategies = attach_strategies("auto", None, false);
+        let err = negotiate(&strategies, |s| -> Result<()> {
+            anyhow::bail!("no response on {:?}", s.protocol)
+        })
+        .unwrap_err();
+
+        let Some(crate::DebugError::AttachFailed { attempts }) = err.downcast_ref() else {
+            panic!("expected AttachFailed, got {:#}", err);
+        };
+        let stages: Vec<&str> = attempts.iter().map(|a| a.stage.as_str()).collect();
+        assert_eq!(
+            stages,
+            vec![
+                "SWD (auto)",
+                "SWD under reset (auto)",
+                "JTAG (auto)",
+                "JTAG under reset (auto)",
+                "SWD (STM32L476RGTx)",
+                "SWD (STM32F407VGTx)",
+                "SWD (Cortex-M)",
+            ]
+        );
+        assert_eq!(attempts[2].error, "no response on Jtag");
+        assert!(crate::DebugError::classify(&err).guidance().unwrap().contains("wiring"));
+
+        // An explicit chip and protocol leave nothing to negotiate.
+        let strategies = attach_strategies("nRF52840_xxAA", Some(WireProtocol::Swd), false);
+        assert_eq!(strategies.len(), 1);
+
+        // The first success ends the negotiation.
+        let mut tried = 0;
+        let attached = negotiate(&attach_strategies("auto", None, false), |s| {
+            tried += 1;
+            if s.under_reset {
+                Ok(s.describe())
+            } else {
+                anyhow::bail!("no response")
+            }
+        });
+        assert_eq!(attached.unwrap(), "SWD under reset (auto)");
+        assert_eq!(tried, 2);
+    }
+
+    #[derive(Default)]
+    struct MockResetLine {
+        calls: std::rc::Rc<std::cell::RefCell<Vec<&'static str>>>,
+    }
+
+    impl ResetLine for MockResetLine {
+        fn assert_reset(&mut self) -> Result<()> {
+            self.calls.borrow_mut().push("assert");
+            Ok(())
+        }
+
+        fn deassert_reset(&mut self) -> Result<()> {
+            self.calls.borrow_mut().push("deassert");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reset_recovery_pulses_nrst_before_retrying() {
+        let probe = MockResetLine::default();
+        let calls = probe.calls.clone();
+        let mut messages = Vec::new();
+        let attached = connect_with_reset_recovery(
+            || {
+                // Attaches only once nRST has been released.
+                let released = calls.borrow().contains(&"deassert");
+                calls.borrow_mut().push("connect");
+                if released {
+                    Ok("attached")
+                } else {
+                    anyhow::bail!("no response")
+                }
+            },
+            || Ok(probe),
+            |message| messages.push(message),
+        );
+
+        assert_eq!(attached.unwrap(), "attached");
+        assert_eq!(*calls.borrow(), vec!["connect", "assert", "deassert", "connect"]);
+        assert_eq!(messages, vec!["Attach failed, pulsing nRST", "Retrying attach after reset"]);
+
+        // A first attempt that succeeds never touches the reset line.
+        let probe = MockResetLine::default();
+        let calls = probe.calls.clone();
+        connect_with_reset_recovery(|| Ok(()), || Ok(probe), |_| {}).unwrap();
+        assert!(calls.borrow().is_empty());
+
+        // Without a probe to pulse, the original error is reported.
+        let err = connect_with_reset_recovery::<MockResetLine, ()>(
+            || anyhow::bail!("no response"),
+            || anyhow::bail!("probe busy"),
+            |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "no response");
+    }
+
+    #[test]
+    fn test_auto_attach_reports_detection_method() {
+        // Stand-in for detect_target_internal: `detected` is what the probe found.
+        let attach = |strategy: &AttachStrategy<'_>, detected: &str| TargetInfo {
+            name: detected.to_string(),
+            flash_size: 0,
+            ram_size: 0,
+            architecture: "Armv7em".to_string(),
+            endian: crate::Endian::Little,
+            requested: strategy.chip.to_string(),
+            detection_method: detection_method(strategy.chip),
+        };
+
+        let info = negotiate(&attach_strategies("auto", None, false), |s| {
+            Ok(attach(s, "STM32G474RETx").requested_as("auto"))
+        })
+        .unwrap();
+        assert_eq!(info.name, "STM32G474RETx");
+        assert_eq!(info.requested, "auto");
+        assert_eq!(info.detection_method, crate::DetectionMethod::Auto);
+
+        // Only a fallback chip attached.
+        let info = negotiate(&attach_strategies("auto", None, false), |s| {
+            if s.chip == "auto" {
+                anyhow::bail!("no chip found")
+            }
+            Ok(attach(s, s.chip).requested_as("auto"))
+        })
+        .unwrap();
+        assert_eq!(info.name, "STM32L476RGTx");
+        assert_eq!(info.requested, "auto");
+        assert_eq!(info.detection_method, crate::DetectionMethod::Heuristic);
+
+        let strategies = attach_strategies("nRF52840_xxAA", None, false);
+        let info = negotiate(&strategies, |s| Ok(attach(s, s.chip).requested_as("nRF52840_xxAA")))
+            .unwrap();
+        assert_eq!(info.detection_method, crate::DetectionMethod::Requested);
+    }
+
     #[test]
     fn test_probe_info_names() {
         let cases = vec![
@@ -385,6 +667,9 @@ mod tests {
             flash_size: 1024 * 1024,
             ram_size: 192 * 1024,
             architecture: "Armv7em".to_string(),
+            endian: crate::Endian::Little,
+            requested: "STM32F407VGTx".to_string(),
+            detection_method: crate::DetectionMethod::Requested,
         };
         assert_eq!(info.name, "STM32F407VGTx");
         assert_eq!(info.flash_size, 1048576);
@@ -397,6 +682,9 @@ mod tests {
             flash_size: 0,
             ram_size: 0,
             architecture: "Unknown".to_string(),
+            endian: crate::Endian::Big,
+            requested: "auto".to_string(),
+            detection_method: crate::DetectionMethod::Auto,
         };
         assert_eq!(info.flash_size, 0);
         assert_eq!(info.ram_size, 0);
@@ -0,0 +1,165 @@
+//! Probe ownership module.
+//!
+//! Hands the probe from the session thread to another user, such as a flash
+//! download that opens it at a faster clock, and back again, so that the two
+//! never drive the probe at the same time.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How long a borrower waits by default for the session to let go of the probe.
+pub const DEFAULT_HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Who holds the probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOwner {
+    /// The session thread.
+    Session,
+    /// A borrower is waiting for the session to close the probe.
+    ReleaseRequested,
+    /// The session closed the probe and a [`ProbeLoan`] holds it.
+    Lent,
+    /// The loan ended; the session has not taken the probe back yet.
+    Returned,
+}
+
+/// Why [`ProbeLock::acquire`] did not get the probe.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProbeLockError {
+    #[error("the probe is already lent out")]
+    Busy,
+    #[error("the session did not release the probe within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Shared handoff token between the session thread and borrowers of its probe.
+///
+/// A borrower calls [`acquire`](Self::acquire); the session thread sees
+/// [`release_requested`](Self::release_requested), closes the probe and calls
+/// [`release`](Self::release). When the returned [`ProbeLoan`] is dropped the
+/// session calls [`reclaim`](Self::reclaim) and may re-open the probe.
+#[derive(Debug, Clone)]
+pub struct ProbeLock {
+    state: Arc<(Mutex<ProbeOwner>, Condvar)>,
+}
+
+impl Default for ProbeLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProbeLock {
+    pub fn new() -> Self {
+        Self { state: Arc::new((Mutex::new(ProbeOwner::Session), Condvar::new())) }
+    }
+
+    pub fn owner(&self) -> ProbeOwner {
+        *self.lock()
+    }
+
+    /// Ask the session for the probe and wait up to `timeout` until it has
+    /// closed it. The probe goes back to the session when the loan is dropped.
+    pub fn acquire(&self, timeout: Duration) -> Result<ProbeLoan, ProbeLockError> {
+        let (_, changed) = &*self.state;
+        let mut owner = self.lock();
+        match *owner {
+            ProbeOwner::Session => *owner = ProbeOwner::ReleaseRequested,
+            // Still closed from the previous loan.
+            ProbeOwner::Returned => *owner = ProbeOwner::Lent,
+            ProbeOwner::ReleaseRequested | ProbeOwner::Lent => return Err(ProbeLockError::Busy),
+        }
+        let (mut owner, _) = changed
+            .wait_timeout_while(owner, timeout, |o| *o == ProbeOwner::ReleaseRequested)
+            .unwrap_or_else(|e| e.into_inner());
+        if *owner == ProbeOwner::ReleaseRequested {
+            *owner = ProbeOwner::Session;
+            return Err(ProbeLockError::Timeout(timeout));
+        }
+        Ok(ProbeLoan { lock: self.clone() })
+    }
+
+    /// Session side: a borrower is waiting for the probe.
+    pub fn release_requested(&self) -> bool {
+        self.owner() == ProbeOwner::ReleaseRequested
+    }
+
+    /// Session side: the probe is closed, hand it to the waiting borrower.
+    pub fn release(&self) {
+        self.transition(ProbeOwner::ReleaseRequested, ProbeOwner::Lent);
+    }
+
+    /// Session side: take the probe back once the loan has ended. Returns
+    /// `false` while it is still lent out or was never lent.
+    pub fn reclaim(&self) -> bool {
+        self.transition(ProbeOwner::Returned, ProbeOwner::Session)
+    }
+
+    fn transition(&self, from: ProbeOwner, to: ProbeOwner) -> bool {
+        let mut owner = self.lock();
+        if *owner != from {
+            return false;
+        }
+        *owner = to;
+        self.state.1.notify_all();
+        true
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProbeOwner> {
+        self.state.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Exclusive use of the probe, returned to the session on drop.
+#[derive(Debug)]
+pub struct ProbeLoan {
+    lock: ProbeLock,
+}
+
+impl Drop for ProbeLoan {
+    fn drop(&mut self) {
+        self.lock.transition(ProbeOwner::Lent, ProbeOwner::Returned);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_handoff_round_trip() {
+        let lock = ProbeLock::new();
+        assert!(!lock.reclaim());
+
+        // The borrower blocks until the session has released the probe.
+        let borrower = {
+            let lock = lock.clone();
+            std::thread::spawn(move || lock.acquire(Duration::from_secs(5)))
+        };
+        while !lock.release_requested() {
+            std::thread::yield_now();
+        }
+        lock.release();
+        let loan = borrower.join().unwrap().unwrap();
+        assert_eq!(lock.owner(), ProbeOwner::Lent);
+
+        // Only one loan at a time, and the session cannot take it back early.
+        assert_eq!(lock.acquire(Duration::from_millis(10)).unwrap_err(), ProbeLockError::Busy);
+        assert!(!lock.reclaim());
+
+        drop(loan);
+        assert_eq!(lock.owner(), ProbeOwner::Returned);
+        assert!(lock.reclaim());
+        assert_eq!(lock.owner(), ProbeOwner::Session);
+    }
+
+    #[test]
+    fn test_acquire_times_out_without_session() {
+        let lock = ProbeLock::new();
+        let timeout = Duration::from_millis(20);
+        assert_eq!(lock.acquire(timeout).unwrap_err(), ProbeLockError::Timeout(timeout));
+        // The request is withdrawn so the session keeps the probe.
+        assert_eq!(lock.owner(), ProbeOwner::Session);
+        assert!(!lock.release_requested());
+    }
+}
@@ -0,0 +1,255 @@
+//! Reconnect module.
+//!
+//! Tracks the probe a session was attached through so that the session can
+//! re-open it by serial number after the probe re-enumerates (e.g. a USB reset
+//! after flashing or a watchdog).
+
+use crate::probe::{ProbeInfo, WireProtocol};
+use crate::DebugCommand;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// Number of consecutive core access failures before the probe is considered gone.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+/// Minimum delay between two reconnect attempts.
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Everything needed to re-attach to the same probe and target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectTarget {
+    pub serial: String,
+    pub chip: String,
+    pub protocol: Option<WireProtocol>,
+    pub under_reset: bool,
+    pub speed_khz: Option<u32>,
+}
+
+impl ReconnectTarget {
+    /// The attach command for this target through the probe with the stored
+    /// serial, or `None` if that probe is not in `probes`.
+    pub fn attach_command(&self, probes: &[ProbeInfo]) -> Option<DebugCommand> {
+        let probe_index =
+            probes.iter().position(|p| p.serial_number.as_deref() == Some(&self.serial))?;
+        Some(DebugCommand::Attach {
+            probe_index,
+            chip: self.chip.clone(),
+            protocol: self.protocol,
+            under_reset: self.under_reset,
+            speed_khz: self.speed_khz,
+        })
+    }
+}
+
+/// Opt-in supervisor that detects a lost probe and drives re-attachment.
+#[derive(Debug)]
+pub struct ReconnectSupervisor {
+    enabled: bool,
+    target: Option<ReconnectTarget>,
+    failure_threshold: u32,
+    consecutive_failures: u32,
+    retry_interval: Duration,
+    last_attempt: Option<Instant>,
+}
+
+impl Default for ReconnectSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReconnectSupervisor {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            target: None,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            consecutive_failures: 0,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            last_attempt: None,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Remember the probe/target of the current attachment.
+    pub fn set_target(&mut self, target: Option<ReconnectTarget>) {
+        self.target = target;
+        self.consecutive_failures = 0;
+        self.last_attempt = None;
+    }
+
+    pub fn target(&self) -> Option<&ReconnectTarget> {
+        self.target.as_ref()
+    }
+
+    /// Record a successful core access.
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Record a failed core access. Returns true once the probe is considered disconnected.
+    pub fn record_failure(&mut self) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.is_disconnected()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.target.is_some() && self.consecutive_failures >= self.failure_threshold
+    }
+
+    /// Try to re-open the probe if reconnecting is enabled, the probe is
+    /// considered lost and the retry interval has elapsed.
+    ///
+    /// `reopen` is called with the stored target and should locate the probe by
+    /// serial and attach. On success the failure counter is reset.
+    pub fn poll<T, F>(&mut self, now: Instant, reopen: F) -> Option<T>
+    where
+        F: FnOnce(&ReconnectTarget) -> Result<T>,
+    {
+        if !self.enabled || !self.is_disconnected() {
+            return None;
+        }
+        if let Some(last) = self.last_attempt {
+            if now.duration_since(last) < self.retry_interval {
+                return None;
+            }
+        }
+        self.last_attempt = Some(now);
+
+        let target = self.target.as_ref()?;
+        match reopen(target) {
+            Ok(v) => {
+                log::info!("Reconnected to probe {}", target.serial);
+                self.consecutive_failures = 0;
+                self.last_attempt = None;
+                Some(v)
+            }
+            Err(e) => {
+                log::debug!("Reconnect to probe {} failed: {}", target.serial, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::breakpoint::BreakpointManager;
+    use crate::fake::FakeSession;
+    use crate::DebugEvent;
+
+    #[test]
+    fn test_disconnect_then_reappear_reconnects_and_restores_breakpoints() {
+        let (evt_tx, mut evt_rx) = tokio::sync::broadcast::channel(16);
+        let mut breakpoints = BreakpointManager::new();
+        breakpoints.set_breakpoint(&mut FakeSession::new(), 0x0800_0100).unwrap();
+        breakpoints.set_breakpoint(&mut FakeSession::new(), 0x0800_0200).unwrap();
+
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_enabled(true);
+        supervisor.set_target(Some(ReconnectTarget {
+            serial: "066DFF555".to_string(),
+            chip: "STM32L476RGTx".to_string(),
+            protocol: None,
+            under_reset: false,
+            speed_khz: None,
+        }));
+
+        // Probe drops off the bus.
+        assert!(!supervisor.record_failure());
+        assert!(!supervisor.record_failure());
+        assert!(supervisor.record_failure());
+
+        // First attempt: the probe has not re-enumerated yet.
+        let start = Instant::now();
+        let res: Option<FakeSession> =
+            supervisor.poll(start, |_| Err(anyhow::anyhow!("No probe with serial 066DFF555")));
+        assert!(res.is_none());
+
+        // Within the retry interval nothing is attempted.
+        let res: Option<FakeSession> =
+            supervisor.poll(start, |_| panic!("must not retry before the interval"));
+        assert!(res.is_none());
+
+        // Probe re-appears under a new index with the same serial.
+        let later = start + DEFAULT_RETRY_INTERVAL;
+        let mut core = supervisor
+            .poll(later, |t| {
+                assert_eq!(t.serial, "066DFF555");
+                Ok(FakeSession::new())
+            })
+            .unwrap();
+        let restored = breakpoints.replay(&mut core);
+        let _ = evt_tx.send(DebugEvent::Reconnected(crate::probe::TargetInfo {
+            name: "STM32L476RGTx".to_string(),
+            flash_size: 0,
+            ram_size: 0,
+            architecture: "Arm".to_string(),
+            endian: crate::Endian::Little,
+            requested: "STM32L476RGTx".to_string(),
+            detection_method: crate::DetectionMethod::Requested,
+        }));
+
+        assert_eq!(restored, vec![0x0800_0100, 0x0800_0200]);
+        assert_eq!(core.breakpoints(), vec![0x0800_0100, 0x0800_0200]);
+        assert!(!supervisor.is_disconnected());
+        assert!(matches!(evt_rx.try_recv().unwrap(), DebugEvent::Reconnected(_)));
+    }
+
+    #[test]
+    fn test_disabled_supervisor_never_reconnects() {
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_target(Some(ReconnectTarget {
+            serial: "A".to_string(),
+            chip: "auto".to_string(),
+            protocol: None,
+            under_reset: false,
+            speed_khz: None,
+        }));
+        for _ in 0..DEFAULT_FAILURE_THRESHOLD {
+            supervisor.record_failure();
+        }
+        let res: Option<()> = supervisor.poll(Instant::now(), |_| Ok(()));
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn test_attach_command_replays_stored_parameters() {
+        let probe = |serial: &str| ProbeInfo {
+            vendor_id: 0x0483,
+            product_id: 0x374B,
+            serial_number: Some(serial.to_string()),
+            identifier: "STLink V2-1".to_string(),
+            probe_type: crate::probe::ProbeType::StLink,
+        };
+        let target = ReconnectTarget {
+            serial: "066DFF555".to_string(),
+            chip: "STM32L476RGTx".to_string(),
+            protocol: Some(WireProtocol::Swd),
+            under_reset: true,
+            speed_khz: Some(4000),
+        };
+
+        // The probe re-enumerated behind another one.
+        let probes = [probe("0670FF48"), probe("066DFF555")];
+        match target.attach_command(&probes) {
+            Some(DebugCommand::Attach { probe_index, chip, protocol, under_reset, speed_khz }) => {
+                assert_eq!(probe_index, 1);
+                assert_eq!(chip, "STM32L476RGTx");
+                assert_eq!(protocol, Some(WireProtocol::Swd));
+                assert!(under_reset);
+                assert_eq!(speed_khz, Some(4000));
+            }
+            other => panic!("Expected Attach, got {:?}", other),
+        }
+
+        assert!(target.attach_command(&[probe("0670FF48")]).is_none());
+    }
+}
@@ -58,4 +58,18 @@ impl RtosAware for Embassy {
 
         Ok(tasks)
     }
+
+    /// The executor's poll loop, entered each time it runs ready tasks.
+    fn switch_function(&self, symbols: &SymbolManager) -> Option<u64> {
+        symbols.function_address_matching(|name| name.contains("12SyncExecutor4poll"))
+    }
+
+    /// The executor, the only task Embassy reports.
+    fn current_task(
+        &self,
+        _core: &mut dyn MemoryInterface,
+        symbols: &SymbolManager,
+    ) -> Result<Option<u32>> {
+        Ok(symbols.lookup_symbol("__embassy_executor_global").map(|addr| addr as u32))
+    }
 }
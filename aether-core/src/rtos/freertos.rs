@@ -2,11 +2,29 @@ use super::RtosAware;
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::MemoryInterface;
 use crate::symbols::SymbolManager;
-use crate::{TaskInfo, TaskState};
+use crate::{SyncObjectInfo, SyncObjectKind, TaskInfo, TaskState};
 use anyhow::Result;
 #[cfg(feature = "hardware")]
 use probe_rs::MemoryInterface;
 
+/// Size of a `QueueRegistryItem_t` (name pointer + handle) on 32-bit targets.
+const REGISTRY_ITEM_SIZE: u64 = 8;
+/// Offset of `uxPriority` in `TCB_t` (see `read_tcb`).
+const TCB_PRIORITY: u64 = 44;
+/// Registry length assumed when the symbol size is unavailable (FreeRTOS demo default).
+const DEFAULT_REGISTRY_SIZE: u64 = 8;
+
+// Queue_t offsets (32-bit):
+// pcHead (0), pcWriteTo (4), u (8, 8 bytes), xTasksWaitingToSend (16), xTasksWaitingToReceive (36),
+// uxMessagesWaiting (56), uxLength (60), uxItemSize (64)
+const QUEUE_HEAD: u64 = 0;
+const QUEUE_MUTEX_HOLDER: u64 = 8;
+const QUEUE_WAITING_TO_SEND: u64 = 16;
+const QUEUE_WAITING_TO_RECEIVE: u64 = 36;
+const QUEUE_MESSAGES_WAITING: u64 = 56;
+const QUEUE_LENGTH: u64 = 60;
+const QUEUE_ITEM_SIZE: u64 = 64;
+
 pub struct FreeRtos;
 
 impl Default for FreeRtos {
@@ -27,14 +45,26 @@ impl FreeRtos {
         state: TaskState,
         tasks: &mut Vec<TaskInfo>,
     ) -> Result<()> {
+        for tcb_addr in self.list_owners(core, list_addr)? {
+            if let Ok(task) = self.read_tcb(core, tcb_addr as u64, state) {
+                tasks.push(task);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Owners (TCB addresses) of the items in a `List_t`.
+    fn list_owners(&self, core: &mut dyn MemoryInterface, list_addr: u64) -> Result<Vec<u32>> {
         // FreeRTOS List_t structure (simplified):
         // uxNumberOfItems (u32)
         // pxIndex (pointer)
         // xListEnd (MiniListItem_t)
 
+        let mut owners = Vec::new();
         let num_items: u32 = core.read_word_32(list_addr)?;
         if num_items == 0 {
-            return Ok(());
+            return Ok(owners);
         }
 
         // xListEnd starts at offset 8 (after 4-byte count and 4-byte pointer)
@@ -52,16 +82,115 @@ impl FreeRtos {
             let tcb_addr: u32 = core.read_word_32(current_item_addr as u64 + 12)?;
 
             if tcb_addr != 0 {
-                if let Ok(task) = self.read_tcb(core, tcb_addr as u64, state) {
-                    tasks.push(task);
-                }
+                owners.push(tcb_addr);
             }
 
             // Move to next item
             current_item_addr = core.read_word_32(current_item_addr as u64 + 4)?;
         }
 
-        Ok(())
+        Ok(owners)
+    }
+
+    /// Walk `xQueueRegistry` (requires `configQUEUE_REGISTRY_SIZE > 0`) and read each
+    /// registered queue, semaphore or mutex.
+    pub fn read_queue_registry(
+        &self,
+        core: &mut dyn MemoryInterface,
+        registry_addr: u64,
+        entries: u64,
+    ) -> Result<Vec<SyncObjectInfo>> {
+        let mut objects = Vec::new();
+        for i in 0..entries {
+            let item_addr = registry_addr + i * REGISTRY_ITEM_SIZE;
+            let name_ptr = core.read_word_32(item_addr)?;
+            let handle = core.read_word_32(item_addr + 4)?;
+            if handle == 0 {
+                continue; // Free slot
+            }
+
+            let mut name_bytes = [0u8; 16];
+            if name_ptr != 0 {
+                core.read_8(name_ptr as u64, &mut name_bytes)?;
+            }
+            let name = String::from_utf8_lossy(&name_bytes)
+                .split('\0')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+
+            objects.push(self.read_queue(core, handle, name)?);
+        }
+        Ok(objects)
+    }
+
+    fn read_queue(
+        &self,
+        core: &mut dyn MemoryInterface,
+        handle: u32,
+        name: String,
+    ) -> Result<SyncObjectInfo> {
+        let queue = handle as u64;
+        let head = core.read_word_32(queue + QUEUE_HEAD)?;
+        let count = core.read_word_32(queue + QUEUE_MESSAGES_WAITING)?;
+        let length = core.read_word_32(queue + QUEUE_LENGTH)?;
+        let item_size = core.read_word_32(queue + QUEUE_ITEM_SIZE)?;
+
+        // Mutexes have no storage (pcHead == NULL); semaphores have zero-sized items.
+        let kind = if head == 0 {
+            SyncObjectKind::Mutex
+        } else if item_size == 0 && length == 1 {
+            SyncObjectKind::BinarySemaphore
+        } else if item_size == 0 {
+            SyncObjectKind::CountingSemaphore
+        } else {
+            SyncObjectKind::Queue
+        };
+
+        let waiting_to_send = self.list_owners(core, queue + QUEUE_WAITING_TO_SEND)?;
+        let waiting_to_receive = self.list_owners(core, queue + QUEUE_WAITING_TO_RECEIVE)?;
+
+        let mut owner = None;
+        let mut priority_inversion = false;
+        if kind == SyncObjectKind::Mutex {
+            let holder = core.read_word_32(queue + QUEUE_MUTEX_HOLDER)?;
+            if holder != 0 {
+                owner = Some(holder);
+                priority_inversion =
+                    self.is_priority_inverted(core, holder, &waiting_to_receive)?;
+            }
+        }
+
+        Ok(SyncObjectInfo {
+            name,
+            handle,
+            kind,
+            count,
+            length,
+            waiting_to_send,
+            waiting_to_receive,
+            owner,
+            priority_inversion,
+        })
+    }
+
+    /// Whether a mutex holder runs at a lower priority than any task blocked on it.
+    ///
+    /// With priority inheritance the holder is boosted to the waiter's priority, so
+    /// this only fires when inheritance is not in effect.
+    fn is_priority_inverted(
+        &self,
+        core: &mut dyn MemoryInterface,
+        holder: u32,
+        waiters: &[u32],
+    ) -> Result<bool> {
+        let holder_priority = core.read_word_32(holder as u64 + TCB_PRIORITY)?;
+        for &waiter in waiters {
+            if core.read_word_32(waiter as u64 + TCB_PRIORITY)? > holder_priority {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
     fn read_tcb(
@@ -78,7 +207,7 @@ impl FreeRtos {
         // pcTaskName (offset 52, size configMAX_TASK_NAME_LEN)
 
         let top_of_stack: u32 = core.read_word_32(tcb_addr)?;
-        let priority: u32 = core.read_word_32(tcb_addr + 44)?;
+        let priority: u32 = core.read_word_32(tcb_addr + TCB_PRIORITY)?;
         let stack_start: u32 = core.read_word_32(tcb_addr + 48)?;
 
         let mut name_bytes = [0u8; 16];
@@ -190,6 +319,34 @@ impl RtosAware for FreeRtos {
 
         Ok(tasks)
     }
+
+    fn get_sync_objects(
+        &self,
+        core: &mut dyn MemoryInterface,
+        symbols: &SymbolManager,
+    ) -> Result<Vec<SyncObjectInfo>> {
+        let (registry_addr, size) =
+            symbols.lookup_symbol_with_size("xQueueRegistry").ok_or_else(|| {
+                anyhow::anyhow!("xQueueRegistry not found (is configQUEUE_REGISTRY_SIZE > 0?)")
+            })?;
+        let entries = if size > 0 { size / REGISTRY_ITEM_SIZE } else { DEFAULT_REGISTRY_SIZE };
+        self.read_queue_registry(core, registry_addr, entries)
+    }
+
+    fn switch_function(&self, symbols: &SymbolManager) -> Option<u64> {
+        symbols.function_address("vTaskSwitchContext")
+    }
+
+    fn current_task(
+        &self,
+        core: &mut dyn MemoryInterface,
+        symbols: &SymbolManager,
+    ) -> Result<Option<u32>> {
+        let Some(current_tcb_ptr) = symbols.lookup_symbol("pxCurrentTCB") else {
+            return Ok(None);
+        };
+        Ok(Some(core.read_word_32(current_tcb_ptr)?).filter(|&tcb| tcb != 0))
+    }
 }
 
 #[cfg(test)]
@@ -383,4 +540,80 @@ mod tests {
         assert_eq!(tasks[0].stack_usage, 0x10); // 0x3010 - 0x3000
         assert_eq!(tasks[0].stack_size, 4); // High water mark: 4 unused bytes
     }
+
+    #[test]
+    fn test_freertos_queue_registry() {
+        let mut mock = MockMemory::new();
+
+        // xQueueRegistry at 0x5000 with two used slots and one free slot.
+        mock.set_word_32(0x5000, 0x5100); // pcQueueName
+        mock.set_word_32(0x5004, 0x6000); // xHandle
+        mock.set_word_32(0x5008, 0);
+        mock.set_word_32(0x500C, 0);
+        mock.set_word_32(0x5010, 0x5110);
+        mock.set_word_32(0x5014, 0x7000);
+        mock.set_bytes(0x5100, b"RxQueue\0");
+        mock.set_bytes(0x5110, b"BusLock\0");
+
+        // Queue at 0x6000: 3 of 8 items of 4 bytes, one task blocked receiving.
+        mock.set_word_32(0x6000, 0x6100); // pcHead
+        mock.set_word_32(0x6000 + 56, 3);
+        mock.set_word_32(0x6000 + 60, 8);
+        mock.set_word_32(0x6000 + 64, 4);
+        // xTasksWaitingToReceive (0x6024): one item at 0x6200 owned by TCB 0x4000
+        mock.set_word_32(0x6024, 1);
+        mock.set_word_32(0x6024 + 12, 0x6200);
+        mock.set_word_32(0x6200 + 4, 0x6024 + 8);
+        mock.set_word_32(0x6200 + 12, 0x4000);
+
+        // Mutex at 0x7000: pcHead == NULL, taken (count 0), no waiters.
+        mock.set_word_32(0x7000 + 56, 0);
+        mock.set_word_32(0x7000 + 60, 1);
+
+        let objects = FreeRtos::new().read_queue_registry(&mut mock, 0x5000, 3).unwrap();
+        assert_eq!(objects.len(), 2);
+
+        assert_eq!(objects[0].name, "RxQueue");
+        assert_eq!(objects[0].kind, SyncObjectKind::Queue);
+        assert_eq!((objects[0].count, objects[0].length), (3, 8));
+        assert_eq!(objects[0].waiting_to_receive, vec![0x4000]);
+        assert!(objects[0].waiting_to_send.is_empty());
+
+        assert_eq!(objects[1].name, "BusLock");
+        assert_eq!(objects[1].handle, 0x7000);
+        assert_eq!(objects[1].kind, SyncObjectKind::Mutex);
+        assert_eq!(objects[1].count, 0);
+    }
+
+    #[test]
+    fn test_freertos_mutex_priority_inversion() {
+        let mut mock = MockMemory::new();
+
+        // Registry with a single mutex at 0x7000.
+        mock.set_word_32(0x5000, 0x5100);
+        mock.set_word_32(0x5004, 0x7000);
+        mock.set_bytes(0x5100, b"SpiLock\0");
+
+        // Mutex held by low-priority TCB 0x4000, high-priority TCB 0x4100 blocked on it.
+        mock.set_word_32(0x7000 + 8, 0x4000); // xMutexHolder
+        mock.set_word_32(0x7000 + 60, 1);
+        mock.set_word_32(0x7024, 1);
+        mock.set_word_32(0x7024 + 12, 0x7200);
+        mock.set_word_32(0x7200 + 4, 0x7024 + 8);
+        mock.set_word_32(0x7200 + 12, 0x4100);
+        mock.set_word_32(0x4000 + 44, 1);
+        mock.set_word_32(0x4100 + 44, 5);
+
+        let freertos = FreeRtos::new();
+        let objects = freertos.read_queue_registry(&mut mock, 0x5000, 1).unwrap();
+        assert_eq!(objects[0].kind, SyncObjectKind::Mutex);
+        assert_eq!(objects[0].owner, Some(0x4000));
+        assert_eq!(objects[0].waiting_to_receive, vec![0x4100]);
+        assert!(objects[0].priority_inversion);
+
+        // Once the holder inherits the waiter's priority the flag clears.
+        mock.set_word_32(0x4000 + 44, 5);
+        let objects = freertos.read_queue_registry(&mut mock, 0x5000, 1).unwrap();
+        assert!(!objects[0].priority_inversion);
+    }
 }
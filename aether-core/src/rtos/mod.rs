@@ -4,7 +4,7 @@ pub mod freertos;
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::MemoryInterface;
 use crate::symbols::SymbolManager;
-use crate::TaskInfo;
+use crate::{SyncObjectInfo, TaskInfo};
 use anyhow::Result;
 #[cfg(feature = "hardware")]
 use probe_rs::MemoryInterface;
@@ -16,6 +16,30 @@ pub trait RtosAware: Send {
         core: &mut dyn MemoryInterface,
         symbols: &SymbolManager,
     ) -> Result<Vec<TaskInfo>>;
+
+    /// List named queues, semaphores and mutexes. Not every RTOS exposes them.
+    fn get_sync_objects(
+        &self,
+        _core: &mut dyn MemoryInterface,
+        _symbols: &SymbolManager,
+    ) -> Result<Vec<SyncObjectInfo>> {
+        Ok(Vec::new())
+    }
+
+    /// Entry address of the function the scheduler runs to switch tasks, for
+    /// breaking on task switches.
+    fn switch_function(&self, _symbols: &SymbolManager) -> Option<u64> {
+        None
+    }
+
+    /// Handle of the task currently selected to run, as used in [`TaskInfo::handle`].
+    fn current_task(
+        &self,
+        _core: &mut dyn MemoryInterface,
+        _symbols: &SymbolManager,
+    ) -> Result<Option<u32>> {
+        Ok(None)
+    }
 }
 
 pub fn detect_rtos(symbols: &SymbolManager) -> Option<Box<dyn RtosAware>> {
@@ -6,6 +6,34 @@ use probe_rs::rtt::Rtt;
 #[cfg(feature = "hardware")]
 use probe_rs::Core;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How long to keep retrying RTT attach once the flashed target runs.
+pub const AUTO_ATTACH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Minimum delay between two auto-attach attempts.
+pub const AUTO_ATTACH_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Symbol of the SEGGER RTT control block.
+pub const CONTROL_BLOCK_SYMBOL: &str = "_SEGGER_RTT";
+
+/// Where to look for the RTT control block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RttLocation {
+    /// Scan target RAM for the control block signature.
+    Scan,
+    /// The control block is at this exact address.
+    At(u64),
+}
+
+impl RttLocation {
+    /// Use the address of `_SEGGER_RTT` when the symbol is known, otherwise scan.
+    pub fn from_symbols<F>(lookup: F) -> Self
+    where
+        F: FnOnce(&str) -> Option<u64>,
+    {
+        lookup(CONTROL_BLOCK_SYMBOL).map_or(Self::Scan, Self::At)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RttChannelInfo {
@@ -52,10 +80,45 @@ impl RttManager {
         }
     }
 
+    /// Attach to the control block at a known address, skipping the RAM scan.
+    pub fn attach_at(&mut self, core: &mut Core, address: u64) -> Result<()> {
+        #[cfg(feature = "hardware")]
+        {
+            match Rtt::attach_at(core, address) {
+                Ok(rtt) => {
+                    self.rtt = Some(rtt);
+                    log::info!("Attached to RTT control block @ 0x{:08X}", address);
+                    Ok(())
+                }
+                Err(e) => {
+                    Err(anyhow::anyhow!("Failed to attach to RTT @ 0x{:08X}: {}", address, e))
+                }
+            }
+        }
+        #[cfg(not(feature = "hardware"))]
+        {
+            let _ = (core, address);
+            Ok(())
+        }
+    }
+
+    /// Attach by scanning or at a fixed address.
+    pub fn attach_to(&mut self, core: &mut Core, location: RttLocation) -> Result<()> {
+        match location {
+            RttLocation::Scan => self.attach(core),
+            RttLocation::At(address) => self.attach_at(core, address),
+        }
+    }
+
     pub fn is_attached(&self) -> bool {
         self.rtt.is_some()
     }
 
+    /// Forget the control block, e.g. after the firmware was replaced.
+    pub fn detach(&mut self) {
+        self.rtt = None;
+    }
+
     pub fn get_up_channels(&mut self) -> Vec<RttChannelInfo> {
         #[cfg(feature = "hardware")]
         {
@@ -144,6 +207,94 @@ impl RttManager {
     }
 }
 
+/// Outcome of an [`RttAutoAttach`] attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoAttachState {
+    /// The control block is not initialized yet; another attempt will follow.
+    Pending,
+    /// Attached; the channels can be listed.
+    Found,
+    /// Gave up without finding a control block.
+    TimedOut,
+}
+
+/// Retries RTT attach after a flash until the firmware has set up its control block.
+///
+/// Freshly flashed firmware initializes the control block some time after it starts
+/// running, so a single attach right after flashing usually fails. The timeout only
+/// starts counting once the core is seen running.
+#[derive(Debug)]
+pub struct RttAutoAttach {
+    armed: bool,
+    deadline: Option<Instant>,
+    last_attempt: Option<Instant>,
+    timeout: Duration,
+    retry_interval: Duration,
+}
+
+impl Default for RttAutoAttach {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RttAutoAttach {
+    pub fn new() -> Self {
+        Self {
+            armed: false,
+            deadline: None,
+            last_attempt: None,
+            timeout: AUTO_ATTACH_TIMEOUT,
+            retry_interval: AUTO_ATTACH_RETRY_INTERVAL,
+        }
+    }
+
+    /// Start retrying, typically once a flash completed.
+    pub fn arm(&mut self) {
+        self.armed = true;
+        self.deadline = None;
+        self.last_attempt = None;
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Attempt to attach if armed, the core is running and the retry interval has
+    /// elapsed. Returns `None` when no attempt was made.
+    pub fn poll<F>(&mut self, now: Instant, running: bool, attach: F) -> Option<AutoAttachState>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if !self.armed || !running {
+            return None;
+        }
+        let deadline = *self.deadline.get_or_insert(now + self.timeout);
+        if let Some(last) = self.last_attempt {
+            if now.duration_since(last) < self.retry_interval {
+                return None;
+            }
+        }
+        self.last_attempt = Some(now);
+
+        match attach() {
+            Ok(()) => {
+                self.armed = false;
+                Some(AutoAttachState::Found)
+            }
+            Err(e) if now >= deadline => {
+                log::warn!("Giving up on RTT attach: {}", e);
+                self.armed = false;
+                Some(AutoAttachState::TimedOut)
+            }
+            Err(e) => {
+                log::debug!("RTT control block not ready: {}", e);
+                Some(AutoAttachState::Pending)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,4 +304,49 @@ mod tests {
         let mgr = RttManager::new();
         assert!(!mgr.is_attached());
     }
+
+    #[test]
+    fn test_location_uses_segger_rtt_symbol() {
+        let location =
+            RttLocation::from_symbols(|name| (name == CONTROL_BLOCK_SYMBOL).then_some(0x2000_0400));
+        assert_eq!(location, RttLocation::At(0x2000_0400));
+        assert_eq!(RttLocation::from_symbols(|_| None), RttLocation::Scan);
+    }
+
+    #[test]
+    fn test_auto_attach_pending_then_found() {
+        let mut auto = RttAutoAttach::new();
+        let start = Instant::now();
+        assert_eq!(auto.poll(start, true, || Ok(())), None);
+
+        auto.arm();
+        // Halted after flashing: no attempt yet.
+        assert_eq!(auto.poll(start, false, || panic!("attached while halted")), None);
+
+        let not_ready = || Err(anyhow::anyhow!("control block not found"));
+        assert_eq!(auto.poll(start, true, not_ready), Some(AutoAttachState::Pending));
+        // Within the retry interval.
+        assert_eq!(auto.poll(start + Duration::from_millis(10), true, not_ready), None);
+        let t = start + AUTO_ATTACH_RETRY_INTERVAL;
+        assert_eq!(auto.poll(t, true, not_ready), Some(AutoAttachState::Pending));
+
+        let t = t + AUTO_ATTACH_RETRY_INTERVAL;
+        assert_eq!(auto.poll(t, true, || Ok(())), Some(AutoAttachState::Found));
+        assert!(!auto.is_armed());
+        assert_eq!(auto.poll(t + AUTO_ATTACH_RETRY_INTERVAL, true, || Ok(())), None);
+    }
+
+    #[test]
+    fn test_auto_attach_times_out() {
+        let mut auto = RttAutoAttach::new();
+        auto.arm();
+        let start = Instant::now();
+        let not_ready = || Err(anyhow::anyhow!("control block not found"));
+        assert_eq!(auto.poll(start, true, not_ready), Some(AutoAttachState::Pending));
+        assert_eq!(
+            auto.poll(start + AUTO_ATTACH_TIMEOUT, true, not_ready),
+            Some(AutoAttachState::TimedOut)
+        );
+        assert!(!auto.is_armed());
+    }
 }
@@ -3,7 +3,6 @@
 //! Handles the debug session in a background thread, processing commands
 //! and sending events back to the main thread.
 
-use crate::debug::DebugManager;
 use crate::CoreStatus;
 use crate::VarType;
 use anyhow::{Context as _, Result};
@@ -11,9 +10,10 @@ use crossbeam_channel::{Receiver, Sender};
 #[cfg(feature = "hardware")]
 use probe_rs::flashing::{FlashProgress, ProgressEvent};
 #[cfg(feature = "hardware")]
-use probe_rs::{MemoryInterface, Session};
+use probe_rs::Session;
 #[cfg(feature = "hardware")]
 use probe_rs_debug::SteppingMode;
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "hardware")]
 use std::collections::HashMap;
 use std::thread;
@@ -21,28 +21,105 @@ use std::time::Duration;
 #[cfg(feature = "hardware")]
 use std::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DebugCommand {
     Halt,
     Resume,
     Step,
+    /// Step over a source statement, or over one instruction without debug info.
     StepOver,
+    /// Step one instruction, running a call to completion.
+    StepOverInstruction,
     StepInto,
     StepOut,
     ReadRegister(u16),
     WriteRegister(u16, u64),
+    /// Read CONTROL, FAULTMASK, BASEPRI or PRIMASK by name.
+    ReadSpecialReg(String),
+    /// Write a special register by name; refused unless the core is halted.
+    WriteSpecialReg(String, u32),
+    /// Read xPSR and decode its flags and exception number, answered by `Flags`.
+    ReadFlags,
+    /// Report whether the MSP or PSP is in use, answered by `ActiveStackPointer`.
+    GetActiveStackPointer,
+    /// Switch Thread mode to the MSP or PSP; refused unless halted in Thread mode.
+    SetActiveStackPointer(crate::debug::stack_pointer::StackPointer),
     ReadMemory(u64, usize),
+    /// Read `len` bytes at a bookmark, resolving symbol targets against the
+    /// loaded symbols; answered by `BookmarkResolved`, then `MemoryData`.
+    ReadBookmark {
+        target: crate::config::BookmarkTarget,
+        len: usize,
+    },
     WriteMemory(u64, Vec<u8>),
+    /// Checksum `len` bytes of target memory, answered by `Checksum`. The range
+    /// is read in the background, a few chunks per loop iteration. Refused
+    /// with `OperationFailed` when nothing is attached or background work is
+    /// paused; a checksum already running continues after `ResumeBackground`.
+    ChecksumRegion {
+        address: u64,
+        len: u64,
+        algo: crate::memory::checksum::ChecksumAlgo,
+    },
+    /// Compare the build ID stored on the target with the loaded ELF's,
+    /// answered by `ImageVerified`.
+    VerifyImageMatchesSymbols,
+    /// Read `count` vector table entries (default `DEFAULT_VECTOR_COUNT`) at
+    /// `base`, or at the active table (VTOR, or the start of flash if VTOR cannot
+    /// be read), answered by `VectorTable`.
+    ReadVectorTable {
+        base: Option<u64>,
+        count: Option<usize>,
+    },
     Disassemble(u64, usize),
+    /// Disassemble the whole function containing the address.
+    DisassembleFunction(u64),
+    /// Disassemble the function with this symbol name.
+    DisassembleSymbol(String),
+    /// Disassemble an address range with the instructions grouped under their
+    /// source lines, answered by `SourceDisassembly`.
+    DisassembleWithSource(std::ops::Range<u64>),
     SetBreakpoint(u64),
     ClearBreakpoint(u64),
+    SetBreakpointEnabled {
+        address: u64,
+        enabled: bool,
+    },
     ListBreakpoints,
+    /// Breakpoint that reads `reads` (register names or global variables) on each hit,
+    /// reports them as `TracepointHit` and resumes. Removed with `ClearBreakpoint`.
+    SetTracepoint {
+        address: u64,
+        reads: Vec<String>,
+    },
+    /// Read the PC and resolve its function and source line, answered by `Location`.
+    WhereAmI,
     LoadSvd(std::path::PathBuf),
     LoadSymbols(std::path::PathBuf),
+    /// Look for sources the ELF records under `from` in `to` instead.
+    AddSourceMapping {
+        from: std::path::PathBuf,
+        to: std::path::PathBuf,
+    },
     LookupSource(u64),
+    /// Read a source file's text from the ELF, for when it is not on disk;
+    /// answered by `EmbeddedSource`.
+    ReadEmbeddedSource(std::path::PathBuf),
+    /// List the source files in the ELF's line tables, answered by `SourceFiles`.
+    ListSourceFiles,
     ToggleBreakpointAtSource(std::path::PathBuf, u32),
+    /// Set a breakpoint on the first instruction of `file:line`, reporting the
+    /// address as `SourceBreakpointResolved` once it is armed.
+    SetBreakpointAtSource {
+        file: std::path::PathBuf,
+        line: u32,
+    },
     GetPeripherals,
     GetRegisters(String),
+    /// Read every peripheral's registers, answered by `PeripheralDump`. Refused
+    /// with `OperationFailed` when nothing is attached or background work is
+    /// paused; a dump already running continues after `ResumeBackground`.
+    DumpAllPeripherals,
     ReadPeripheralValues(String),
     WritePeripheralField {
         peripheral: String,
@@ -50,7 +127,10 @@ pub enum DebugCommand {
         field: String,
         value: u64,
     },
+    /// Attach to RTT, at `_SEGGER_RTT` if the loaded ELF has it, otherwise by scanning.
     RttAttach,
+    /// Attach to the RTT control block at an exact address.
+    RttAttachAt(u64),
     RttWrite {
         channel: usize,
         data: Vec<u8>,
@@ -61,24 +141,84 @@ pub enum DebugCommand {
         var_type: VarType,
     },
     RemovePlot(String),
+    SetPlotRetention {
+        name: String,
+        retention: crate::plot::PlotRetention,
+    },
+    /// Prune a plot to its retention window and aggregate the points left,
+    /// answered by `PlotSummary`.
+    GetPlotSummary(String),
     WatchVariable(String),
+    /// Resolve several watch expressions at once, answered by a single `WatchUpdate`.
+    RefreshWatches(Vec<String>),
+    /// Number of pointer levels shown as child nodes of a variable (default
+    /// [`crate::symbols::DEFAULT_POINTER_DEPTH`]); 0 turns dereferencing off.
+    SetPointerDepth(usize),
+    /// Override how a watched variable's value is displayed.
+    SetVariableFormat {
+        name: String,
+        format: crate::symbols::VariableFormat,
+    },
+    /// Periodically re-read watched variables while the core is running.
+    SetLiveWatch(bool),
+    /// Periodically re-read a peripheral's registers; `None` stops polling.
+    SetPeripheralPoll {
+        peripheral: Option<String>,
+        interval: Duration,
+    },
+    /// Periodically re-read `size` bytes at a memory-mapped register, reporting
+    /// changes with `RegisterChanged`.
+    WatchRegister {
+        address: u64,
+        size: usize,
+    },
+    UnwatchRegister(u64),
     GetTasks,
+    /// List RTOS queues, semaphores and mutexes.
+    GetSyncObjects,
+    /// Report every RTOS task switch as `TaskSwitch`, then resume or halt per the
+    /// mode (`None` disables). Needs the RTOS's switch function in the symbols.
+    SetTaskSwitchBreak(Option<crate::debug::task_switch::TaskSwitchMode>),
+    /// Read the SysTick configuration and core clock.
+    ReadSysTick,
     GetStack,
+    /// Stop unwinding after this many frames; the stack then ends with a
+    /// [`crate::stack::TRUNCATED_FRAME`] frame.
+    SetUnwindDepth(usize),
     EnableTrace(crate::trace::TraceConfig),
     Exit,
     StartFlashing(std::path::PathBuf),
     EnableSemihosting,
     EnableItm {
         baud_rate: u32,
+        /// Pin encoding the probe decodes; UART unless the target drives Manchester.
+        #[serde(default)]
+        mode: crate::itm::SwoMode,
+        #[serde(default)]
+        sink: crate::itm::ItmSink,
+    },
+    /// Send the data written to an ITM stimulus port to `route`.
+    SetItmPortRoute {
+        port: u8,
+        route: crate::itm::ItmPortRoute,
     },
     ListProbes,
+    /// Measure the target supply voltage through a probe that is not attached.
+    ReadTargetVoltage(usize),
     Attach {
         probe_index: usize,
         chip: String,
         protocol: Option<crate::probe::WireProtocol>,
         under_reset: bool,
+        /// Probe clock in kHz; `None` leaves it to the probe.
+        speed_khz: Option<u32>,
     },
-    Reset,
+    Reset(crate::debug::reset::ResetKind),
+    /// Reset, run to a temporary breakpoint on `main` and halt there.
+    RunToMain,
+    /// Keep the active target's debug domain powered in low-power modes and retry
+    /// halts (`None` disables). The register is chip-specific.
+    SetLowPowerDebug(Option<crate::debug::low_power::LowPowerDebug>),
     AttachSubSession {
         name: String,
         probe_index: usize,
@@ -92,6 +232,123 @@ pub enum DebugCommand {
         slave: String,
     },
     ShadowStep,
+    /// Opt in to transparently re-attaching after the probe re-enumerates.
+    SetAutoReconnect(bool),
+    /// Opt in to pulsing the probe's nRST line and retrying once when `Attach`
+    /// fails. Intrusive: the target restarts from reset.
+    SetAttachResetRecovery(bool),
+    /// Drop cached memory and register reads, e.g. after the target changed behind our back.
+    InvalidateCache,
+    /// Report session health, answered by `Metrics`.
+    GetMetrics,
+    /// Report the loaded ELF's build identifier, answered by `BuildId`.
+    GetBuildId,
+    /// Also report `Metrics` every interval; `None` stops.
+    SetMetricsInterval(Option<Duration>),
+    /// Restore the value replaced by the most recent memory or register write.
+    UndoWrite,
+    /// Read `len` bytes at `address` and keep them for diffing, answered by
+    /// `MemorySnapshot`.
+    SnapshotMemory {
+        address: u64,
+        len: usize,
+    },
+    /// Compare two kept snapshots by id, answered by `MemoryDiff`.
+    DiffMemorySnapshots {
+        a: u32,
+        b: u32,
+    },
+    /// Re-arm breakpoints and re-attach RTT when the target resets on its own.
+    SetResetResync(bool),
+    /// Stop all periodic probe traffic (status, RTT, trace, plot, watch and
+    /// peripheral polls) until `ResumeBackground`, e.g. around a flash or a
+    /// timing measurement. Answered by `BackgroundPaused(true)`.
+    PauseBackground,
+    /// Restart the periodic polls, answered by `BackgroundPaused(false)`.
+    ResumeBackground,
+    /// Halt the core as soon as it takes the selected exceptions (DEMCR vector catch).
+    SetVectorCatch(crate::debug::vector_catch::VectorCatch),
+    /// Read a debug register (DHCSR, DEMCR, DWT, FPB, ...) by name or `0x` address,
+    /// answered by `DebugRegister`. See [`crate::debug::debug_regs`].
+    ReadDebugRegister(String),
+    /// Write a known, writable debug register, answered by `DebugRegister` with
+    /// the value read back.
+    WriteDebugRegister {
+        register: String,
+        value: u32,
+    },
+}
+
+/// How often `TraceStats` is sent while ITM is enabled.
+pub const TRACE_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Instructions disassembled by `DisassembleFunction` when the function range is unknown.
+pub const DISASSEMBLE_FALLBACK_COUNT: usize = 32;
+/// Largest function `DisassembleFunction` reads, in bytes.
+pub const DISASSEMBLE_FUNCTION_MAX_BYTES: u64 = 16 * 1024;
+
+/// Byte range `DisassembleFunction` reads for `address`: the enclosing function if
+/// the symbols know it, otherwise a fixed window starting at `address`.
+pub fn function_disassembly_range(
+    symbols: &crate::symbols::SymbolManager,
+    address: u64,
+) -> std::ops::Range<u64> {
+    match symbols.function_range(address) {
+        Some(range) if range.end - range.start <= DISASSEMBLE_FUNCTION_MAX_BYTES => range,
+        _ => address..address + DISASSEMBLE_FALLBACK_COUNT as u64 * 4,
+    }
+}
+
+/// The `DisassembleFunction` command a `DisassembleSymbol(name)` stands for.
+pub fn resolve_disassemble_symbol(
+    symbols: &crate::symbols::SymbolManager,
+    name: &str,
+) -> Result<DebugCommand, crate::DebugError> {
+    symbols
+        .function_address(name)
+        .map(DebugCommand::DisassembleFunction)
+        .ok_or_else(|| crate::DebugError::UnknownSymbol(name.to_string()))
+}
+
+/// Address of the first instruction of `file:line`, for `SetBreakpointAtSource`.
+pub fn resolve_source_line(
+    symbols: &crate::symbols::SymbolManager,
+    file: &std::path::Path,
+    line: u32,
+) -> Result<u64, crate::DebugError> {
+    symbols
+        .get_address(file, line)
+        .ok_or_else(|| crate::DebugError::NoCodeAtLine { file: file.display().to_string(), line })
+}
+
+/// Why a background job cannot start: jobs advance only in the idle poll,
+/// which needs an attached core and is skipped while background work is paused.
+fn background_job_refusal(has_session: bool, paused: bool) -> Option<anyhow::Error> {
+    if !has_session {
+        Some(anyhow::anyhow!("No active session"))
+    } else if paused {
+        Some(anyhow::anyhow!("Background work is paused"))
+    } else {
+        None
+    }
+}
+
+/// The `ReadMemory` command a `ReadBookmark` stands for.
+pub fn resolve_bookmark_read(
+    symbols: &crate::symbols::SymbolManager,
+    target: &crate::config::BookmarkTarget,
+    len: usize,
+) -> Result<DebugCommand, crate::DebugError> {
+    target
+        .resolve(|name| symbols.lookup_symbol(name))
+        .map(|address| DebugCommand::ReadMemory(address, len))
+        .ok_or_else(|| {
+            let name = match target {
+                crate::config::BookmarkTarget::Symbol { name, .. } => name.clone(),
+                crate::config::BookmarkTarget::Address(_) => target.to_string(),
+            };
+            crate::DebugError::UnknownSymbol(name)
+        })
 }
 
 struct PlotConfig {
@@ -100,22 +357,91 @@ struct PlotConfig {
     var_type: VarType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DebugEvent {
     Halted {
         pc: u64,
     },
     Resumed,
     RegisterValue(u16, u64),
+    SpecialRegisterValue(crate::debug::special_regs::SpecialRegister, u32),
+    ActiveStackPointer {
+        which: crate::debug::stack_pointer::StackPointer,
+        value: u64,
+    },
+    Flags(crate::debug::psr::PsrFlags),
     MemoryData(u64, Vec<u8>),
+    Checksum {
+        address: u64,
+        len: u64,
+        algo: crate::memory::checksum::ChecksumAlgo,
+        value: u32,
+    },
+    ImageVerified(crate::symbols::build_id::ImageVerification),
     Disassembly(Vec<crate::disasm::InstructionInfo>),
-    Breakpoints(Vec<u64>),
+    SourceDisassembly(Vec<crate::disasm::SourceLineBlock>),
+    VectorTable(Vec<crate::debug::vector_table::VectorEntry>),
+    /// Breakpoint addresses with their enabled state.
+    Breakpoints(Vec<(u64, bool)>),
     SvdLoaded,
     Peripherals(Vec<crate::svd::PeripheralInfo>),
     Registers(Vec<crate::svd::RegisterInfo>),
+    PeripheralDump(crate::peripheral_dump::PeripheralDump),
     SymbolsLoaded,
+    /// Build identifier of the loaded ELF, see [`crate::SymbolManager::build_id`].
+    BuildId(Option<String>),
     SourceLocation(crate::symbols::SourceInfo),
+    SourceFiles(Vec<std::path::PathBuf>),
+    /// `None` if the ELF does not embed the file's source.
+    EmbeddedSource {
+        file: std::path::PathBuf,
+        text: Option<String>,
+    },
+    /// Where the core is stopped.
+    Location {
+        pc: u64,
+        function: Option<String>,
+        file: Option<std::path::PathBuf>,
+        line: Option<u32>,
+    },
     BreakpointLocations(Vec<crate::symbols::SourceInfo>),
+    SourceBreakpointResolved {
+        file: std::path::PathBuf,
+        line: u32,
+        address: u64,
+    },
+    /// A `ReadBookmark` target resolved to `address`; its `MemoryData` follows.
+    BookmarkResolved {
+        target: crate::config::BookmarkTarget,
+        address: u64,
+    },
+    /// A requested reset was performed; `Halted` follows.
+    ResetPerformed(crate::debug::reset::ResetKind),
+    /// The target reset without a reset being requested (watchdog, brown-out,
+    /// ...). `pc` is the reset handler it halted at, or `None` if it kept running.
+    TargetReset {
+        pc: Option<u64>,
+    },
+    /// A single step finished; `instruction` is the one that executed at `from_pc`.
+    Stepped {
+        from_pc: u64,
+        to_pc: u64,
+        instruction: Option<crate::disasm::InstructionInfo>,
+    },
+    /// A watched memory-mapped register changed value.
+    RegisterChanged {
+        address: u64,
+        old: u64,
+        new: u64,
+    },
+    /// Vector catch configuration read back from the target.
+    VectorCatch(crate::debug::vector_catch::VectorCatch),
+    DebugRegister(crate::debug::debug_regs::DebugRegisterValue),
+    /// A tracepoint was hit; the core has already been resumed.
+    TracepointHit {
+        address: u64,
+        values: crate::debug::tracepoint::TracepointValues,
+    },
     RttChannels {
         up_channels: Vec<crate::rtt::RttChannelInfo>,
         down_channels: Vec<crate::rtt::RttChannelInfo>,
@@ -126,10 +452,20 @@ pub enum DebugEvent {
         timestamp: f64,
         value: f64,
     },
+    /// `None` when the plot has no retained points.
+    PlotSummary {
+        name: String,
+        summary: Option<crate::plot::PlotSummary>,
+    },
     #[cfg(feature = "hardware")]
     Tasks(Vec<crate::TaskInfo>),
     #[cfg(not(feature = "hardware"))]
     Tasks(Vec<crate::TaskInfo>),
+    SyncObjects(Vec<crate::SyncObjectInfo>),
+    TimebaseInfo {
+        core_hz: u32,
+        tick_hz: f64,
+    },
     TaskSwitch {
         from: Option<u32>,
         to: u32,
@@ -140,14 +476,49 @@ pub enum DebugEvent {
     #[cfg(not(feature = "hardware"))]
     Stack(Vec<crate::stack::StackFrame>),
     TraceData(Vec<u8>),
+    Metrics(crate::metrics::SessionMetrics),
+    /// Whether periodic probe traffic is paused.
+    BackgroundPaused(bool),
+    /// The probe was handed to a borrower (`true`) or given back (`false`);
+    /// see [`SessionHandle::probe_lock`].
+    ProbeLent(bool),
     Status(CoreStatus),
     Error(String),
+    /// A probe operation failed for a classified reason.
+    OperationFailed {
+        operation: String,
+        error: crate::DebugError,
+    },
     FlashProgress(f32),
     FlashStatus(String),
     FlashDone,
+    /// The memory/register write log, oldest first; sent after every write or undo.
+    WriteLog(Vec<crate::memory::history::WriteRecord>),
+    /// A region read by `SnapshotMemory` and kept under its id.
+    MemorySnapshot(crate::memory::snapshot::MemorySnapshot),
+    /// The bytes that changed from snapshot `a` to snapshot `b`.
+    MemoryDiff {
+        a: u32,
+        b: u32,
+        diff: crate::memory::snapshot::MemoryDiff,
+    },
     VariableResolved(crate::symbols::TypeInfo),
+    WatchUpdate(Vec<crate::symbols::TypeInfo>),
     SemihostingOutput(String),
     ItmPacket(Vec<u8>),
+    /// Text written to an ITM stimulus port routed to the console.
+    ItmConsole {
+        port: u8,
+        text: String,
+    },
+    /// SWO stream health, sent every `TRACE_STATS_INTERVAL` while ITM is enabled.
+    TraceStats(crate::itm::TraceStats),
+    /// Exception entry/exit decoded from DWT exception trace.
+    Isr {
+        exception: u16,
+        action: crate::itm::ExceptionAction,
+        timestamp: f64,
+    },
     #[cfg(feature = "hardware")]
     Probes(Vec<crate::probe::ProbeInfo>),
     #[cfg(not(feature = "hardware"))]
@@ -166,14 +537,350 @@ pub enum DebugEvent {
         slave_val: u64,
         info: String,
     },
+    Reconnected(crate::probe::TargetInfo),
+    /// A step of attach recovery, e.g. the nRST pulse after a failed attach.
+    AttachProgress(String),
+    /// Target supply voltage in volts; `None` if the probe cannot measure it.
+    TargetVoltage(Option<f32>),
+}
+
+impl DebugEvent {
+    /// Classify the error of a failed probe operation.
+    pub fn operation_failed(operation: impl Into<String>, err: &anyhow::Error) -> Self {
+        Self::OperationFailed {
+            operation: operation.into(),
+            error: crate::DebugError::classify(err),
+        }
+    }
+
+    /// Report a target voltage reading.
+    pub fn target_voltage(reading: Result<Option<f32>>) -> Self {
+        match reading {
+            Ok(volts) => Self::TargetVoltage(volts),
+            Err(e) => Self::operation_failed("Read target voltage", &e),
+        }
+    }
+
+    /// Resolve `pc` to a `Location` event with whatever the symbols provide.
+    pub fn location(symbols: &crate::symbols::SymbolManager, pc: u64) -> Self {
+        let source = symbols.lookup(pc).filter(|s| s.line != 0);
+        Self::Location {
+            pc,
+            function: symbols.function_at(pc),
+            file: source.as_ref().map(|s| s.file.clone()),
+            line: source.map(|s| s.line),
+        }
+    }
+}
+
+/// Serialize a debug event as a single line of JSON.
+pub fn event_to_json(event: &DebugEvent) -> serde_json::Result<String> {
+    serde_json::to_string(event)
+}
+
+/// Write every event received on `rx` to `writer` as newline-delimited JSON.
+///
+/// Blocks until the session's event channel closes. Lagged events are skipped.
+pub fn write_events_json<W: std::io::Write>(
+    mut rx: tokio::sync::broadcast::Receiver<DebugEvent>,
+    mut writer: W,
+) -> std::io::Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+    loop {
+        match rx.blocking_recv() {
+            Ok(event) => {
+                let line = event_to_json(&event)?;
+                writeln!(writer, "{}", line)?;
+                writer.flush()?;
+            }
+            Err(RecvError::Lagged(n)) => {
+                log::warn!("JSON event writer lagged, skipped {} events", n);
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// A debug event tagged with its position in the session's event stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    /// Monotonically increasing, starting at 1. A jump means events were dropped.
+    pub seq: u64,
+    pub event: DebugEvent,
+}
+
+/// Broadcasts session events, numbering each one before it is sent.
+///
+/// Events go out both plain, for existing subscribers, and as [`SequencedEvent`]s
+/// for clients that need ordering or gap detection.
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    tx: tokio::sync::broadcast::Sender<DebugEvent>,
+    seq_tx: tokio::sync::broadcast::Sender<SequencedEvent>,
+    next_seq: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    capacity: usize,
+    dropped: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl EventSender {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = tokio::sync::broadcast::channel(capacity);
+        let (seq_tx, _) = tokio::sync::broadcast::channel(capacity);
+        Self {
+            tx,
+            seq_tx,
+            next_seq: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1)),
+            capacity,
+            dropped: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0)),
+        }
+    }
+
+    /// Assign the next sequence number and broadcast the event.
+    ///
+    /// Returns the number of subscribers reached and, like `broadcast::Sender::send`,
+    /// errors when there are none.
+    pub fn send(
+        &self,
+        event: DebugEvent,
+    ) -> Result<usize, tokio::sync::broadcast::error::SendError<DebugEvent>> {
+        let seq = self.next_seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // A full buffer means this send evicts an event some subscriber has not read.
+        if self.tx.len() >= self.capacity || self.seq_tx.len() >= self.capacity {
+            self.dropped.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        let sequenced = self.seq_tx.send(SequencedEvent { seq, event: event.clone() }).unwrap_or(0);
+        match self.tx.send(event) {
+            Ok(plain) => Ok(plain + sequenced),
+            Err(_) if sequenced > 0 => Ok(sequenced),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<DebugEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn subscribe_sequenced(&self) -> tokio::sync::broadcast::Receiver<SequencedEvent> {
+        self.seq_tx.subscribe()
+    }
+
+    /// Events evicted before every subscriber read them.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// A core the session loop drives: run control, registers, memory and
+/// breakpoints.
+///
+/// Features only a probe provides (RTT, SVD, unwinding, ...) go through
+/// [`TargetCore::probe_core`]; targets without one answer them with an error.
+#[cfg(feature = "hardware")]
+pub trait TargetCore<'p>:
+    crate::debug::status::StatusSource
+    + crate::memory::history::WriteTarget
+    + crate::debug::step_over::StepControl
+    + crate::debug::low_power::HaltControl
+{
+    fn pc(&mut self) -> Result<u64>;
+
+    /// The probe-rs core, or `None` for targets without a probe.
+    fn probe_core(&mut self) -> Option<&mut probe_rs::Core<'p>> {
+        None
+    }
+}
+
+/// A target the session loop runs against: a probe-rs [`Session`], or the
+/// in-memory [`crate::fake::FakeSession`] for tests.
+#[cfg(feature = "hardware")]
+pub trait DebugTarget: Send + Sized + 'static {
+    type Core<'a>: TargetCore<'a>
+    where
+        Self: 'a;
+
+    /// Wrap a session opened by `Attach` or a reconnect, or `None` if this kind
+    /// of target cannot hold one.
+    fn from_probe(session: Session) -> Option<Self>;
+    fn core(&mut self, index: usize) -> Result<Self::Core<'_>>;
+    /// Architecture name as the disassembler expects it, e.g. `"Arm"`.
+    fn architecture(&self) -> String;
+    /// Start of the first flash region, if the target has one.
+    fn flash_base(&self) -> Option<u64>;
+
+    /// The probe-rs session for trace, ITM and flashing, or `None` for targets
+    /// without a probe.
+    fn probe(&mut self) -> Option<&mut Session> {
+        None
+    }
+}
+
+#[cfg(feature = "hardware")]
+impl<'p> TargetCore<'p> for probe_rs::Core<'p> {
+    fn pc(&mut self) -> Result<u64> {
+        Ok(self.read_core_reg(self.program_counter())?)
+    }
+
+    fn probe_core(&mut self) -> Option<&mut probe_rs::Core<'p>> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "hardware")]
+impl DebugTarget for Session {
+    type Core<'a> = probe_rs::Core<'a>;
+
+    fn from_probe(session: Session) -> Option<Self> {
+        Some(session)
+    }
+
+    fn core(&mut self, index: usize) -> Result<probe_rs::Core<'_>> {
+        Ok(Session::core(self, index)?)
+    }
+
+    fn architecture(&self) -> String {
+        format!("{:?}", self.target().architecture())
+    }
+
+    fn flash_base(&self) -> Option<u64> {
+        crate::flash::flash_base(&self.target())
+    }
+
+    fn probe(&mut self) -> Option<&mut Session> {
+        Some(self)
+    }
+}
+
+/// The probe-rs session of target `name`, for features only a probe provides.
+#[cfg(feature = "hardware")]
+fn probe_session<'a, T: DebugTarget>(
+    sessions: &'a mut HashMap<String, T>,
+    name: &str,
+) -> Result<&'a mut Session> {
+    sessions
+        .get_mut(name)
+        .with_context(|| format!("No active session for {}", name))?
+        .probe()
+        .context("Not supported by this target")
+}
+
+/// Step by source statement, returning the new PC.
+#[cfg(feature = "hardware")]
+fn step_statement<'p>(
+    core: &mut impl TargetCore<'p>,
+    mode: SteppingMode,
+    debug_info: &probe_rs_debug::DebugInfo,
+) -> Result<u64> {
+    let core = core.probe_core().context("Not supported by this target")?;
+    let (_status, pc) = mode.step(core, debug_info).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+    Ok(pc)
+}
+
+/// How long a request waits for its answer by default: long enough for an
+/// attach that tries every protocol and reset mode.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Why [`SessionHandle::request`] got no answer.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RequestError {
+    #[error("the session thread has stopped")]
+    Closed,
+    #[error("the event stream lagged")]
+    Lagged,
+    #[error("no matching event within {0:?}")]
+    Timeout(Duration),
+    #[error("core error: {0}")]
+    Core(String),
+    #[error("{operation} failed: {error}")]
+    Failed { operation: String, error: crate::DebugError },
+}
+
+/// Wait on `rx` for the first event `matcher` accepts. An `Error` or
+/// `OperationFailed` event before it ends the wait.
+pub async fn wait_for_event<F>(
+    rx: &mut tokio::sync::broadcast::Receiver<DebugEvent>,
+    matcher: F,
+    timeout: Duration,
+) -> Result<DebugEvent, RequestError>
+where
+    F: Fn(&DebugEvent) -> bool,
+{
+    let wait = async {
+        loop {
+            let event = rx.recv().await.map_err(|e| match e {
+                tokio::sync::broadcast::error::RecvError::Closed => RequestError::Closed,
+                tokio::sync::broadcast::error::RecvError::Lagged(_) => RequestError::Lagged,
+            })?;
+            if matcher(&event) {
+                return Ok(event);
+            }
+            match event {
+                DebugEvent::Error(message) => return Err(RequestError::Core(message)),
+                DebugEvent::OperationFailed { operation, error } => {
+                    return Err(RequestError::Failed { operation, error })
+                }
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(timeout, wait).await.unwrap_or(Err(RequestError::Timeout(timeout)))
+}
+
+/// What [`read_rtt_until`] collected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RttReadUntil {
+    /// Everything received on the channel, through the end of the chunk that
+    /// completed the delimiter.
+    pub data: Vec<u8>,
+    /// `false` if the timeout expired first.
+    pub found: bool,
+}
+
+/// Collect data from RTT up channel `channel` on `rx` until it contains
+/// `delimiter` (e.g. a console prompt) or `timeout` expires. The delimiter may
+/// be split across chunks.
+pub async fn read_rtt_until(
+    rx: &mut tokio::sync::broadcast::Receiver<DebugEvent>,
+    channel: usize,
+    delimiter: &[u8],
+    timeout: Duration,
+) -> Result<RttReadUntil, RequestError> {
+    let mut data = Vec::new();
+    if delimiter.is_empty() {
+        return Ok(RttReadUntil { data, found: true });
+    }
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let event = match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Err(_) => return Ok(RttReadUntil { data, found: false }),
+            Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) => {
+                return Err(RequestError::Closed)
+            }
+            Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => {
+                return Err(RequestError::Lagged)
+            }
+            Ok(Ok(event)) => event,
+        };
+        if let DebugEvent::RttData(ch, chunk) = event {
+            if ch != channel {
+                continue;
+            }
+            // Only the tail of the old data can start a match.
+            let start = data.len().saturating_sub(delimiter.len() - 1);
+            data.extend_from_slice(&chunk);
+            if data[start..].windows(delimiter.len()).any(|w| w == delimiter) {
+                return Ok(RttReadUntil { data, found: true });
+            }
+        }
+    }
 }
 
 /// A handle to the debug session running in a background thread.
 pub struct SessionHandle {
     command_tx: Sender<DebugCommand>,
-    event_tx: tokio::sync::broadcast::Sender<DebugEvent>,
-    #[allow(dead_code)] // Kept for future graceful shutdown
-    thread_handle: Option<thread::JoinHandle<()>>,
+    event_tx: EventSender,
+    thread_handle: std::sync::Mutex<Option<thread::JoinHandle<()>>>,
+    command_log: std::sync::Mutex<Option<crate::command_log::CommandRecorder>>,
+    probe_lock: crate::probe_lock::ProbeLock,
 }
 
 impl SessionHandle {
@@ -182,471 +889,1506 @@ impl SessionHandle {
         self.event_tx.subscribe()
     }
 
+    /// Subscribe to debug events with their sequence numbers
+    pub fn subscribe_sequenced(&self) -> tokio::sync::broadcast::Receiver<SequencedEvent> {
+        self.event_tx.subscribe_sequenced()
+    }
+
     /// Internal helper to create a SessionHandle for testing
-    pub fn new_test() -> (Self, Receiver<DebugCommand>, tokio::sync::broadcast::Sender<DebugEvent>)
-    {
+    pub fn new_test() -> (Self, Receiver<DebugCommand>, EventSender) {
         let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
-        let (evt_tx, _) = tokio::sync::broadcast::channel(1024);
+        let evt_tx = EventSender::new(1024);
 
-        (Self { command_tx: cmd_tx, event_tx: evt_tx.clone(), thread_handle: None }, cmd_rx, evt_tx)
+        (
+            Self {
+                command_tx: cmd_tx,
+                event_tx: evt_tx.clone(),
+                thread_handle: std::sync::Mutex::new(None),
+                command_log: std::sync::Mutex::new(None),
+                probe_lock: crate::probe_lock::ProbeLock::new(),
+            },
+            cmd_rx,
+            evt_tx,
+        )
     }
 
     #[cfg(feature = "hardware")]
     pub fn new(session: Option<Session>) -> Result<Self> {
+        Ok(Self::spawn(session, None))
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    pub fn with_reconnect(
+        session: crate::probe_rs::Session,
+        _reconnect: Option<crate::reconnect::ReconnectTarget>,
+    ) -> Self {
+        Self::spawn_stub(Some(session))
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    pub fn new(session: Option<crate::probe_rs::Session>) -> Result<Self> {
+        Ok(Self::spawn_stub(session))
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    fn spawn_stub(_session: Option<crate::probe_rs::Session>) -> Self {
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        let evt_tx = EventSender::new(100);
+
+        let thread_handle = thread::spawn(move || loop {
+            if let Ok(cmd) = cmd_rx.recv() {
+                if matches!(cmd, DebugCommand::Exit) {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            command_tx: cmd_tx,
+            event_tx: evt_tx,
+            thread_handle: std::sync::Mutex::new(Some(thread_handle)),
+            command_log: std::sync::Mutex::new(None),
+            probe_lock: crate::probe_lock::ProbeLock::new(),
+        }
+    }
+
+    /// Run the session loop against any [`DebugTarget`], e.g. an in-memory
+    /// [`crate::fake::FakeSession`] in tests.
+    #[cfg(feature = "hardware")]
+    pub fn with_target<T: DebugTarget>(target: T) -> Self {
+        Self::spawn(Some(target), None)
+    }
+
+    /// Run the session loop on an attached target that auto-reconnect (see
+    /// [`DebugCommand::SetAutoReconnect`]) re-opens through `reconnect` once the
+    /// probe is lost.
+    #[cfg(feature = "hardware")]
+    pub fn with_reconnect<T: DebugTarget>(
+        target: T,
+        reconnect: Option<crate::reconnect::ReconnectTarget>,
+    ) -> Self {
+        Self::spawn(Some(target), reconnect)
+    }
+
+    #[cfg(feature = "hardware")]
+    fn spawn<T: DebugTarget>(
+        session: Option<T>,
+        reconnect: Option<crate::reconnect::ReconnectTarget>,
+    ) -> Self {
         let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
         // create a broadcast channel with capacity 100
-        let (evt_tx, _) = tokio::sync::broadcast::channel(100);
+        let evt_tx = EventSender::new(100);
         let evt_tx_thread = evt_tx.clone();
+        let probe_lock = crate::probe_lock::ProbeLock::new();
+        let probe_lock_thread = probe_lock.clone();
 
         let thread_handle = thread::spawn(move || {
-            let mut sessions: HashMap<String, Session> = HashMap::new();
-            let mut active_target = "default".to_string();
-            if let Some(s) = session {
-                sessions.insert(active_target.clone(), s);
+            run_session(session, reconnect, cmd_rx, evt_tx_thread, probe_lock_thread);
+        });
+
+        Self {
+            command_tx: cmd_tx,
+            event_tx: evt_tx,
+            thread_handle: std::sync::Mutex::new(Some(thread_handle)),
+            command_log: std::sync::Mutex::new(None),
+            probe_lock,
+        }
+    }
+
+    /// The token for borrowing this session's probe, e.g. to flash at a faster
+    /// clock. The session closes the probe while it is lent out and reports
+    /// `ProbeLent(false)` once it is back, so the borrower can re-attach.
+    pub fn probe_lock(&self) -> crate::probe_lock::ProbeLock {
+        self.probe_lock.clone()
+    }
+
+    pub fn send(&self, cmd: DebugCommand) -> Result<()> {
+        if let Some(recorder) = self.command_log.lock().unwrap_or_else(|e| e.into_inner()).as_mut()
+        {
+            recorder.record(&cmd);
+        }
+        self.command_tx.send(cmd).context("Failed to send command")
+    }
+
+    /// Start recording the commands sent from now on, for replay with
+    /// [`crate::command_log::replay_log`]. Restarts the log if already enabled.
+    pub fn enable_command_log(&self) {
+        *self.command_log.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(crate::command_log::CommandRecorder::new());
+    }
+
+    /// The commands recorded so far, or `None` if logging is not enabled.
+    pub fn command_log(&self) -> Option<crate::command_log::CommandLog> {
+        self.command_log
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|recorder| recorder.log().clone())
+    }
+
+    /// Send `cmd` and wait up to `timeout` for the event answering it, the first
+    /// one `matcher` accepts; see [`wait_for_event`].
+    ///
+    /// Dropping the returned future cancels the wait, e.g. when an RPC client
+    /// goes away. The command itself still runs.
+    pub async fn request<F>(
+        &self,
+        cmd: DebugCommand,
+        matcher: F,
+        timeout: Duration,
+    ) -> Result<DebugEvent, RequestError>
+    where
+        F: Fn(&DebugEvent) -> bool,
+    {
+        // Subscribe first so a fast answer is not missed.
+        let mut rx = self.subscribe();
+        self.send(cmd).map_err(|_| RequestError::Closed)?;
+        wait_for_event(&mut rx, matcher, timeout).await
+    }
+
+    /// Read RTT up channel `channel` until `delimiter` arrives; see
+    /// [`read_rtt_until`]. Only data arriving after the call is considered, so
+    /// send the console command after starting the read.
+    pub async fn rtt_read_until(
+        &self,
+        channel: usize,
+        delimiter: &[u8],
+        timeout: Duration,
+    ) -> Result<RttReadUntil, RequestError> {
+        let mut rx = self.subscribe();
+        read_rtt_until(&mut rx, channel, delimiter, timeout).await
+    }
+
+    /// Stop the session thread and wait for it, releasing the probe.
+    ///
+    /// Later calls, and commands sent afterwards, have no effect.
+    pub fn shutdown(&self) {
+        let _ = self.command_tx.send(DebugCommand::Exit);
+        let handle = self.thread_handle.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(handle) = handle {
+            if handle.join().is_err() {
+                log::error!("Session thread panicked");
             }
-            let mut shadow_sync: Option<(String, String)> = None;
-
-            let evt_tx = evt_tx_thread; // Shadow for inner scope
-            let debug_manager = DebugManager::new();
-            let _memory_manager = crate::MemoryManager::new();
-            let disasm_manager = crate::disasm::DisassemblyManager::new();
-            let mut breakpoint_manager = crate::debug::BreakpointManager::new();
-            let mut svd_manager = crate::svd::SvdManager::new();
-            let mut rtt_manager = crate::rtt::RttManager::new();
-            let mut symbol_manager = crate::symbols::SymbolManager::new();
-            let mut trace_manager = crate::trace::TraceManager::new();
-            let mut rtos_manager: Option<Box<dyn crate::rtos::RtosAware>> = None;
-            let mut _last_poll = Instant::now();
-            let mut core_status = None;
-            let mut itm_manager = crate::itm::ItmManager::new();
-
-            let mut plots: Vec<PlotConfig> = Vec::new();
-            let mut last_plot_poll = Instant::now();
-            let mut _last_task_handle: Option<u32> = None;
-            let mut _last_status_poll = Instant::now();
-
-            let mut arch =
-                sessions.get(&active_target).map(|s| format!("{:?}", s.target().architecture()));
-            let session_start = Instant::now();
-
-            // Loop for processing commands and events
-            loop {
-                // 1. Trace Polling (needs &mut Session)
-                for s in sessions.values_mut() {
-                    if let Ok(data) = trace_manager.read_data(s) {
-                        if !data.is_empty() {
-                            let _ = evt_tx.send(DebugEvent::TraceData(data));
-                        }
-                    }
-                }
-
-                // 2. Commands (Session or Core)
-                let cmd_opt = cmd_rx.try_recv().ok();
-
-                if let Some(cmd) = cmd_opt {
-                    #[allow(unreachable_patterns)]
-                    match cmd {
-                        DebugCommand::EnableTrace(config) => {
-                            if let Some(s) = sessions.get_mut(&active_target) {
-                                if let Err(e) = trace_manager.enable(s, config) {
-                                    let _ = evt_tx.send(DebugEvent::Error(format!(
-                                        "Failed to enable trace: {}",
-                                        e
-                                    )));
-                                }
-                            } else {
-                                let _ = evt_tx.send(DebugEvent::Error(format!(
-                                    "No active session for {}",
-                                    active_target
-                                )));
-                            }
-                            continue;
-                        }
-                        DebugCommand::Exit => return,
-                        DebugCommand::StartFlashing(path) => {
-                            if let Some(s) = sessions.get_mut(&active_target) {
-                                let flash_manager = crate::flash::FlashManager::new();
-                                let tx_clone = evt_tx.clone();
-                                let total_size =
-                                    std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
-                                let current_size =
-                                    std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
-                                let progress = FlashProgress::new(move |event| {
-                                    let size_ref = total_size.clone();
-                                    let current_ref = current_size.clone();
-                                    let update = match event {
-                                        ProgressEvent::AddProgressBar { total, .. } => {
-                                            if let Some(t) = total {
-                                                size_ref.fetch_add(
-                                                    t,
-                                                    std::sync::atomic::Ordering::Relaxed,
-                                                );
-                                            }
-                                            return;
-                                        }
-                                        ProgressEvent::Started(op) => {
-                                            DebugEvent::FlashStatus(format!("{:?}", op))
-                                        }
-                                        ProgressEvent::Progress { size, .. } => {
-                                            let total =
-                                                size_ref.load(std::sync::atomic::Ordering::Relaxed);
-                                            let current = current_ref.fetch_add(
-                                                size,
-                                                std::sync::atomic::Ordering::Relaxed,
-                                            ) + size;
-                                            if total > 0 {
-                                                DebugEvent::FlashProgress(
-                                                    current as f32 / total as f32,
-                                                )
-                                            } else {
-                                                DebugEvent::FlashProgress(0.0)
-                                            }
-                                        }
-                                        ProgressEvent::Finished(_) => {
-                                            DebugEvent::FlashStatus("Finished".to_string())
-                                        }
-                                        ProgressEvent::Failed(_) => {
-                                            DebugEvent::Error("Flash failed".to_string())
-                                        }
-                                        _ => return,
-                                    };
-                                    let _ = tx_clone.send(update);
+        }
+    }
+}
+
+/// The session loop: runs commands against `session` and polls it while idle,
+/// until `Exit`.
+#[cfg(feature = "hardware")]
+fn run_session<T: DebugTarget>(
+    session: Option<T>,
+    reconnect_target: Option<crate::reconnect::ReconnectTarget>,
+    cmd_rx: Receiver<DebugCommand>,
+    evt_tx: EventSender,
+    probe_lock: crate::probe_lock::ProbeLock,
+) {
+    let mut sessions: HashMap<String, T> = HashMap::new();
+    let mut active_target = "default".to_string();
+    if let Some(s) = session {
+        sessions.insert(active_target.clone(), s);
+    }
+    let mut shadow_sync: Option<(String, String)> = None;
+
+    let _memory_manager = crate::MemoryManager::new();
+    let disasm_manager = crate::disasm::DisassemblyManager::new();
+    let mut breakpoint_manager = crate::debug::BreakpointManager::new();
+    let mut tracepoints = crate::debug::tracepoint::TracepointManager::new();
+    let mut task_switch_break: Option<crate::debug::task_switch::TaskSwitchBreak> = None;
+    let mut reset_detector = crate::debug::reset::ResetDetector::new();
+    let mut reset_resync = false;
+    let mut attach_reset_recovery = false;
+    let mut svd_manager = crate::svd::SvdManager::new();
+    let mut rtt_manager = crate::rtt::RttManager::new();
+    let mut rtt_auto_attach = crate::rtt::RttAutoAttach::new();
+    let mut symbol_manager = crate::symbols::SymbolManager::new();
+    let mut trace_manager = crate::trace::TraceManager::new();
+    let mut rtos_manager: Option<Box<dyn crate::rtos::RtosAware>> = None;
+    let mut _last_poll = Instant::now();
+    let mut status_poller = crate::debug::status::StatusPoller::new();
+    let mut read_cache = crate::memory::cache::ReadCache::new();
+    let mut write_history = crate::memory::history::WriteHistory::new();
+    let mut snapshots = crate::memory::snapshot::SnapshotStore::new();
+    let mut itm_manager = crate::itm::ItmManager::new();
+    let mut itm_epoch = 0.0;
+    let mut itm_routes = crate::itm::ItmRoutes::default();
+    let mut last_trace_stats = Instant::now();
+    let mut background_paused = false;
+    let mut unwind_depth = crate::stack::DEFAULT_MAX_UNWIND_DEPTH;
+    let mut metrics = crate::metrics::MetricsTracker::new(Instant::now());
+    let mut reconnect = crate::reconnect::ReconnectSupervisor::new();
+    reconnect.set_target(reconnect_target);
+    let mut live_watch = crate::watch::LiveWatch::new();
+    let mut peripheral_poll = crate::watch::PeripheralPoll::new();
+    let mut register_watch = crate::watch::RegisterWatch::new();
+    let mut peripheral_dump: Option<crate::peripheral_dump::PeripheralDumpJob> = None;
+    let mut checksum_job: Option<crate::memory::checksum::ChecksumJob> = None;
+    let mut low_power_debug: HashMap<String, crate::debug::low_power::LowPowerDebug> =
+        HashMap::new();
+
+    let mut plots: Vec<PlotConfig> = Vec::new();
+    let mut plot_store = crate::plot::PlotStore::new();
+    let mut last_plot_poll = Instant::now();
+    let mut _last_task_handle: Option<u32> = None;
+    let mut _last_status_poll = Instant::now();
+
+    let mut arch = sessions.get(&active_target).map(|s| s.architecture());
+    let session_start = Instant::now();
+
+    // Loop for processing commands and events
+    loop {
+        // 0. Probe handoff: close the probe for a borrower (e.g. a flash
+        // download) and wait for it to come back. State other than the
+        // probe sessions is kept; the owner re-attaches afterwards.
+        if probe_lock.release_requested() {
+            sessions.retain(|_, s| s.probe().is_none());
+            read_cache.invalidate();
+            status_poller.reset();
+            probe_lock.release();
+            let _ = evt_tx.send(DebugEvent::ProbeLent(true));
+        }
+        if probe_lock.reclaim() {
+            let _ = evt_tx.send(DebugEvent::ProbeLent(false));
+        }
+
+        // 1. Trace Polling (needs &mut Session)
+        for s in sessions.values_mut().filter_map(|s| s.probe()).filter(|_| !background_paused) {
+            if let Ok(data) = trace_manager.read_data(s) {
+                if !data.is_empty() {
+                    let _ = evt_tx.send(DebugEvent::TraceData(data));
+                }
+            }
+        }
+
+        if let Some(m) = metrics.poll(Instant::now(), cmd_rx.len(), evt_tx.dropped()) {
+            let _ = evt_tx.send(DebugEvent::Metrics(m));
+        }
+
+        // ITM/SWO: raw packets plus exception trace for the timeline
+        if let Some(s) =
+            sessions.get_mut(&active_target).and_then(|s| s.probe()).filter(|_| !background_paused)
+        {
+            if let Ok(data) = itm_manager.read_swo(s) {
+                if !data.is_empty() {
+                    let now = session_start.elapsed().as_secs_f64();
+                    for packet in itm_manager.decode(&data) {
+                        // Target time from local timestamps when the clock is known.
+                        let timestamp =
+                            itm_manager.seconds(packet.timestamp).map_or(now, |t| itm_epoch + t);
+                        match packet.data {
+                            crate::itm::ItmData::Exception(ev) => {
+                                let _ = evt_tx.send(DebugEvent::Isr {
+                                    exception: ev.number,
+                                    action: ev.action,
+                                    timestamp,
                                 });
-                                // Note: We use the session directly here as before
-                                match flash_manager.flash_elf(s, &path, progress) {
-                                    Ok(_) => {
-                                        let _ = evt_tx.send(DebugEvent::FlashDone);
-                                    }
-                                    Err(e) => {
-                                        let _ = evt_tx.send(DebugEvent::Error(format!(
-                                            "Flash failed: {}",
-                                            e
-                                        )));
-                                    }
-                                }
-                            } else {
-                                let _ = evt_tx.send(DebugEvent::Error(format!(
-                                    "No active session for {}",
-                                    active_target
-                                )));
                             }
-                            continue;
-                        }
-                        DebugCommand::EnableSemihosting => {
-                            log::info!("Semihosting enabled");
-                            continue;
-                        }
-                        DebugCommand::EnableItm { baud_rate } => {
-                            if let Some(s) = sessions.get_mut(&active_target) {
-                                if let Err(e) = itm_manager.configure(s, baud_rate) {
-                                    let _ = evt_tx.send(DebugEvent::Error(format!(
-                                        "Failed to enable ITM: {}",
-                                        e
-                                    )));
-                                } else {
-                                    log::info!("ITM enabled at {} baud", baud_rate);
+                            crate::itm::ItmData::Stimulus { port, data } => {
+                                let Some(event) = itm_routes.dispatch(port, &data, timestamp)
+                                else {
+                                    continue;
+                                };
+                                if let DebugEvent::PlotData { name, timestamp, value } = &event {
+                                    plot_store.push(name, *timestamp, *value);
                                 }
-                            } else {
-                                let _ = evt_tx.send(DebugEvent::Error(format!(
-                                    "No active session for {}",
-                                    active_target
-                                )));
+                                let _ = evt_tx.send(event);
                             }
-                            continue;
                         }
-                        DebugCommand::ListProbes => {
-                            let pm = crate::probe::ProbeManager::new();
-                            match pm.list_probes() {
-                                Ok(p) => {
-                                    let _ = evt_tx.send(DebugEvent::Probes(p));
-                                }
-                                Err(e) => {
-                                    let _ = evt_tx.send(DebugEvent::Error(format!(
-                                        "Failed to list probes: {}",
-                                        e
-                                    )));
-                                }
-                            }
-                            continue;
-                        }
-                        DebugCommand::Attach { probe_index, chip, protocol, under_reset } => {
-                            let pm = crate::probe::ProbeManager::new();
-                            match pm.connect(probe_index, &chip, protocol, under_reset) {
-                                Ok((info, s)) => {
-                                    sessions.insert(active_target.clone(), s);
-                                    arch = Some(info.architecture.clone());
-                                    let _ = evt_tx.send(DebugEvent::Attached(info));
-                                }
-                                Err(e) => {
-                                    let _ = evt_tx.send(DebugEvent::Error(format!(
-                                        "Failed to attach: {}",
-                                        crate::probe::map_probe_error(&e)
-                                    )));
+                    }
+                    let _ = evt_tx.send(DebugEvent::ItmPacket(data));
+                }
+            }
+        }
+        if itm_manager.is_enabled() && last_trace_stats.elapsed() >= TRACE_STATS_INTERVAL {
+            last_trace_stats = Instant::now();
+            let _ = evt_tx.send(DebugEvent::TraceStats(itm_manager.stats()));
+        }
+
+        // 2. Commands (Session or Core)
+        let cmd_opt = cmd_rx.try_recv().ok().or_else(|| {
+            if background_paused {
+                None
+            } else {
+                peripheral_poll.poll(Instant::now())
+            }
+        });
+
+        // Symbol names resolve to addresses before a command reaches a core.
+        let cmd_opt = match cmd_opt {
+            Some(DebugCommand::DisassembleSymbol(name)) => {
+                match resolve_disassemble_symbol(&symbol_manager, &name) {
+                    Ok(cmd) => Some(cmd),
+                    Err(e) => {
+                        let _ = evt_tx.send(DebugEvent::operation_failed("Disassemble", &e.into()));
+                        None
+                    }
+                }
+            }
+            Some(DebugCommand::ReadBookmark { target, len }) => {
+                match resolve_bookmark_read(&symbol_manager, &target, len) {
+                    Ok(cmd) => {
+                        if let DebugCommand::ReadMemory(address, _) = cmd {
+                            let _ = evt_tx.send(DebugEvent::BookmarkResolved { target, address });
+                        }
+                        Some(cmd)
+                    }
+                    Err(e) => {
+                        let _ = evt_tx.send(DebugEvent::operation_failed("Read memory", &e.into()));
+                        None
+                    }
+                }
+            }
+            other => other,
+        };
+
+        if let Some(cmd) = cmd_opt {
+            #[allow(unreachable_patterns)]
+            match cmd {
+                DebugCommand::EnableTrace(mut config) => {
+                    match probe_session(&mut sessions, &active_target) {
+                        Ok(s) => {
+                            // Prefer the clock configured in RCC; a wrong value garbles SWO.
+                            let detected = s.core(0).ok().and_then(|mut core| {
+                                crate::trace::detect_core_frequency(&svd_manager, &mut core)
+                            });
+                            match detected {
+                                Some(hz) => {
+                                    if hz != config.core_frequency {
+                                        log::info!(
+                                            "Using detected core clock {} Hz instead of {} Hz",
+                                            hz,
+                                            config.core_frequency
+                                        );
+                                    }
+                                    config.core_frequency = hz;
                                 }
+                                None => log::info!(
+                                    "Core clock detection failed, using {} Hz",
+                                    config.core_frequency
+                                ),
+                            }
+                            if let Err(e) = trace_manager.enable(s, config) {
+                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                    "Failed to enable trace: {}",
+                                    e
+                                )));
                             }
-                            continue;
                         }
-                        DebugCommand::AttachSubSession {
-                            name,
-                            probe_index,
-                            chip,
-                            protocol,
-                            under_reset,
-                        } => {
-                            let pm = crate::probe::ProbeManager::new();
-                            match pm.connect(probe_index, &chip, protocol, under_reset) {
-                                Ok((info, s)) => {
-                                    sessions.insert(name.clone(), s);
-                                    let _ = evt_tx.send(DebugEvent::SubSessionAttached(name, info));
+                        Err(e) => {
+                            let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::Exit => return,
+                DebugCommand::StartFlashing(path) => {
+                    match probe_session(&mut sessions, &active_target) {
+                        Ok(s) => {
+                            let flash_manager = crate::flash::FlashManager::new();
+                            let tx_clone = evt_tx.clone();
+                            let total_size =
+                                std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+                            let current_size =
+                                std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+                            let progress = FlashProgress::new(move |event| {
+                                let size_ref = total_size.clone();
+                                let current_ref = current_size.clone();
+                                let update = match event {
+                                    ProgressEvent::AddProgressBar { total, .. } => {
+                                        if let Some(t) = total {
+                                            size_ref
+                                                .fetch_add(t, std::sync::atomic::Ordering::Relaxed);
+                                        }
+                                        return;
+                                    }
+                                    ProgressEvent::Started(op) => {
+                                        DebugEvent::FlashStatus(format!("{:?}", op))
+                                    }
+                                    ProgressEvent::Progress { size, .. } => {
+                                        let total =
+                                            size_ref.load(std::sync::atomic::Ordering::Relaxed);
+                                        let current = current_ref
+                                            .fetch_add(size, std::sync::atomic::Ordering::Relaxed)
+                                            + size;
+                                        if total > 0 {
+                                            DebugEvent::FlashProgress(current as f32 / total as f32)
+                                        } else {
+                                            DebugEvent::FlashProgress(0.0)
+                                        }
+                                    }
+                                    ProgressEvent::Finished(_) => {
+                                        DebugEvent::FlashStatus("Finished".to_string())
+                                    }
+                                    ProgressEvent::Failed(_) => {
+                                        DebugEvent::Error("Flash failed".to_string())
+                                    }
+                                    _ => return,
+                                };
+                                let _ = tx_clone.send(update);
+                            });
+                            // Note: We use the session directly here as before
+                            match flash_manager.flash_file(s, &path, None, progress) {
+                                Ok(_) => {
+                                    let _ = evt_tx.send(DebugEvent::FlashDone);
+                                    read_cache.invalidate();
+                                    write_history.clear();
+                                    snapshots.clear();
+                                    let _ = evt_tx.send(DebugEvent::WriteLog(Vec::new()));
+                                    // The old control block is gone with the old firmware.
+                                    rtt_manager.detach();
+                                    rtt_auto_attach.arm();
                                 }
                                 Err(e) => {
-                                    let _ = evt_tx.send(DebugEvent::Error(format!(
-                                        "Failed to attach sub-session {}: {}",
-                                        name, e
-                                    )));
+                                    let _ = evt_tx.send(DebugEvent::operation_failed("Flash", &e));
                                 }
                             }
-                            continue;
-                        }
-                        DebugCommand::SetActiveTarget(name) => {
-                            active_target = name;
-                            continue;
-                        }
-                        DebugCommand::ShadowSync { master, slave } => {
-                            shadow_sync = Some((master, slave));
-                            continue;
-                        }
-                        // Core commands
-                        // Core commands
-                        #[allow(unreachable_patterns)]
-                        core_cmd => {
-                            let target_names = if let Some((ref m, ref s)) = shadow_sync {
-                                if matches!(
-                                    core_cmd,
-                                    DebugCommand::Halt
-                                        | DebugCommand::Resume
-                                        | DebugCommand::Step
-                                        | DebugCommand::StepOver
-                                        | DebugCommand::StepInto
-                                        | DebugCommand::StepOut
-                                        | DebugCommand::Reset
-                                        | DebugCommand::ShadowStep
-                                ) {
-                                    vec![m.clone(), s.clone()]
-                                } else {
-                                    vec![active_target.clone()]
-                                }
+                        }
+                        Err(e) => {
+                            let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::EnableSemihosting => {
+                    log::info!("Semihosting enabled");
+                    continue;
+                }
+                DebugCommand::EnableItm { baud_rate, mode, sink } => {
+                    match probe_session(&mut sessions, &active_target) {
+                        Ok(s) => {
+                            let hz = s.core(0).ok().and_then(|mut core| {
+                                crate::trace::detect_core_frequency(&svd_manager, &mut core)
+                            });
+                            itm_manager.set_timestamp_clock(hz);
+                            itm_epoch = session_start.elapsed().as_secs_f64();
+                            let config = crate::itm::ItmConfig { baud_rate, mode, sink };
+                            if let Err(e) = itm_manager.configure(s, &config) {
+                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                    "Failed to enable ITM: {}",
+                                    e
+                                )));
                             } else {
-                                vec![active_target.clone()]
+                                log::info!(
+                                    "ITM enabled to {:?} at {} baud ({:?})",
+                                    sink,
+                                    baud_rate,
+                                    mode
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::SetItmPortRoute { port, route } => {
+                    if let crate::itm::ItmPortRoute::Plot(name) = &route {
+                        plot_store.add(name, crate::plot::PlotRetention::default());
+                    }
+                    itm_routes.set(port, route);
+                    continue;
+                }
+                DebugCommand::ListProbes => {
+                    let pm = crate::probe::ProbeManager::new();
+                    match pm.list_probes() {
+                        Ok(p) => {
+                            let _ = evt_tx.send(DebugEvent::Probes(p));
+                        }
+                        Err(e) => {
+                            let _ = evt_tx
+                                .send(DebugEvent::Error(format!("Failed to list probes: {}", e)));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::ReadTargetVoltage(probe_index) => {
+                    let pm = crate::probe::ProbeManager::new();
+                    let _ = evt_tx.send(DebugEvent::target_voltage(pm.target_voltage(probe_index)));
+                    continue;
+                }
+                DebugCommand::Attach { probe_index, chip, protocol, under_reset, speed_khz } => {
+                    if probe_lock.owner() != crate::probe_lock::ProbeOwner::Session {
+                        let _ = evt_tx.send(DebugEvent::Error(
+                            "The probe is lent out, e.g. for flashing".to_string(),
+                        ));
+                        continue;
+                    }
+                    let pm = crate::probe::ProbeManager::new();
+                    // An unpowered target is the usual reason an attach fails.
+                    if let Ok(volts) = pm.target_voltage(probe_index) {
+                        let _ = evt_tx.send(DebugEvent::TargetVoltage(volts));
+                    }
+                    let attached = if attach_reset_recovery {
+                        pm.connect_with_reset_recovery(
+                            probe_index,
+                            &chip,
+                            protocol,
+                            under_reset,
+                            speed_khz,
+                            |step| {
+                                let _ = evt_tx.send(DebugEvent::AttachProgress(step));
+                            },
+                        )
+                    } else {
+                        pm.connect_with_speed(probe_index, &chip, protocol, under_reset, speed_khz)
+                    };
+                    match attached {
+                        Ok((mut info, s)) => {
+                            let Some(s) = T::from_probe(s) else {
+                                let _ = evt_tx.send(DebugEvent::Error(
+                                    "Not supported by this target: Attach".to_string(),
+                                ));
+                                continue;
                             };
+                            let serial = pm.list_probes().ok().and_then(|p| {
+                                p.get(probe_index).and_then(|p| p.serial_number.clone())
+                            });
+                            reconnect.set_target(serial.map(|serial| {
+                                crate::reconnect::ReconnectTarget {
+                                    serial,
+                                    chip: chip.clone(),
+                                    protocol,
+                                    under_reset,
+                                    speed_khz,
+                                }
+                            }));
+                            sessions.insert(active_target.clone(), s);
+                            arch = Some(info.architecture.clone());
+                            read_cache.invalidate();
+                            // A loaded ELF already determined the byte order.
+                            if symbol_manager.has_symbols() {
+                                info.endian = symbol_manager.endian();
+                            } else {
+                                symbol_manager.set_endian(info.endian);
+                            }
+                            let _ = evt_tx.send(DebugEvent::Attached(info));
+                        }
+                        Err(e) if e.is::<crate::DebugError>() => {
+                            let _ = evt_tx.send(DebugEvent::operation_failed("Attach", &e));
+                        }
+                        Err(e) => {
+                            let _ = evt_tx.send(DebugEvent::Error(format!(
+                                "Failed to attach: {}",
+                                crate::probe::map_probe_error(&e)
+                            )));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::AttachSubSession {
+                    name,
+                    probe_index,
+                    chip,
+                    protocol,
+                    under_reset,
+                } => {
+                    let pm = crate::probe::ProbeManager::new();
+                    match pm.connect(probe_index, &chip, protocol, under_reset) {
+                        Ok((info, s)) => match T::from_probe(s) {
+                            Some(s) => {
+                                sessions.insert(name.clone(), s);
+                                let _ = evt_tx.send(DebugEvent::SubSessionAttached(name, info));
+                            }
+                            None => {
+                                let _ = evt_tx.send(DebugEvent::Error(
+                                    "Not supported by this target: AttachSubSession".to_string(),
+                                ));
+                            }
+                        },
+                        Err(e) => {
+                            let _ = evt_tx.send(DebugEvent::Error(format!(
+                                "Failed to attach sub-session {}: {}",
+                                name, e
+                            )));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::SetActiveTarget(name) => {
+                    active_target = name;
+                    read_cache.invalidate();
+                    continue;
+                }
+                DebugCommand::ShadowSync { master, slave } => {
+                    shadow_sync = Some((master, slave));
+                    continue;
+                }
+                DebugCommand::SetAutoReconnect(enabled) => {
+                    reconnect.set_enabled(enabled);
+                    continue;
+                }
+                DebugCommand::SetAttachResetRecovery(enabled) => {
+                    attach_reset_recovery = enabled;
+                    continue;
+                }
+                DebugCommand::SetUnwindDepth(depth) => {
+                    unwind_depth = depth.max(1);
+                    continue;
+                }
+                DebugCommand::SetResetResync(enabled) => {
+                    reset_resync = enabled;
+                    continue;
+                }
+                DebugCommand::InvalidateCache => {
+                    read_cache.invalidate();
+                    continue;
+                }
+                DebugCommand::AddPlot { name, var_type } => {
+                    if let Some(address) = symbol_manager.lookup_symbol(&name) {
+                        plot_store.add(&name, crate::plot::PlotRetention::default());
+                        plots.push(PlotConfig { name, address, var_type });
+                    } else {
+                        let _ = evt_tx.send(DebugEvent::Error(format!(
+                            "Symbol not found for plot: {}",
+                            name
+                        )));
+                    }
+                    continue;
+                }
+                DebugCommand::SetPointerDepth(levels) => {
+                    symbol_manager.set_pointer_depth(levels);
+                    continue;
+                }
+                DebugCommand::SetVariableFormat { name, format } => {
+                    symbol_manager.set_variable_format(&name, format);
+                    continue;
+                }
+                DebugCommand::SetPeripheralPoll { peripheral, interval } => {
+                    peripheral_poll.configure(peripheral, interval);
+                    continue;
+                }
+                DebugCommand::WatchRegister { address, size } => {
+                    if let Err(e) = register_watch.watch(address, size) {
+                        let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
+                    }
+                    continue;
+                }
+                DebugCommand::UnwatchRegister(address) => {
+                    register_watch.unwatch(address);
+                    continue;
+                }
+                DebugCommand::SetLiveWatch(enabled) => {
+                    // Reading while running needs background memory access (Cortex-M AHB-AP).
+                    if enabled && arch.as_deref() != Some("Arm") {
+                        let _ = evt_tx.send(DebugEvent::Error(format!(
+                            "Live watch is not supported on this target ({})",
+                            arch.as_deref().unwrap_or("not attached")
+                        )));
+                    } else {
+                        live_watch.set_enabled(enabled);
+                    }
+                    continue;
+                }
+                DebugCommand::RemovePlot(name) => {
+                    plots.retain(|p| p.name != name);
+                    plot_store.remove(&name);
+                    continue;
+                }
+                DebugCommand::SetPlotRetention { name, retention } => {
+                    if !plot_store.set_retention(&name, retention) {
+                        let _ = evt_tx.send(DebugEvent::Error(format!("Unknown plot: {}", name)));
+                    }
+                    continue;
+                }
+                DebugCommand::GetPlotSummary(name) => {
+                    let _ = evt_tx.send(if plot_store.get(&name).is_some() {
+                        let summary = plot_store.summary(&name);
+                        DebugEvent::PlotSummary { name, summary }
+                    } else {
+                        DebugEvent::Error(format!("Unknown plot: {}", name))
+                    });
+                    continue;
+                }
+                // Core commands
+                // Core commands
+                #[allow(unreachable_patterns)]
+                core_cmd => {
+                    let target_names = if let Some((ref m, ref s)) = shadow_sync {
+                        if matches!(
+                            core_cmd,
+                            DebugCommand::Halt
+                                | DebugCommand::Resume
+                                | DebugCommand::Step
+                                | DebugCommand::StepOver
+                                | DebugCommand::StepOverInstruction
+                                | DebugCommand::StepInto
+                                | DebugCommand::StepOut
+                                | DebugCommand::Reset(_)
+                                | DebugCommand::ShadowStep
+                        ) {
+                            vec![m.clone(), s.clone()]
+                        } else {
+                            vec![active_target.clone()]
+                        }
+                    } else {
+                        vec![active_target.clone()]
+                    };
+
+                    // Anything that runs the core makes cached reads stale; the
+                    // next status poll re-enables caching once it is halted again.
+                    if matches!(
+                        core_cmd,
+                        DebugCommand::Halt
+                            | DebugCommand::Resume
+                            | DebugCommand::Step
+                            | DebugCommand::StepOver
+                            | DebugCommand::StepOverInstruction
+                            | DebugCommand::StepInto
+                            | DebugCommand::StepOut
+                            | DebugCommand::Reset(_)
+                            | DebugCommand::RunToMain
+                            | DebugCommand::ShadowStep
+                    ) {
+                        read_cache.set_halted(false);
+                    }
+                    if matches!(core_cmd, DebugCommand::Reset(_) | DebugCommand::RunToMain) {
+                        reset_detector.expect_reset();
+                    }
 
-                            let mut halt_pcs = Vec::new();
+                    let mut halt_pcs = Vec::new();
 
-                            for name in &target_names {
-                                let s = match sessions.get_mut(name) {
-                                    Some(s) => s,
-                                    None => {
+                    for name in &target_names {
+                        let s = match sessions.get_mut(name) {
+                            Some(s) => s,
+                            None => {
+                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                    "No active session for {}",
+                                    name
+                                )));
+                                continue;
+                            }
+                        };
+                        let flash_base = s.flash_base();
+                        let mut core = match s.core(0) {
+                            Ok(c) => c,
+                            Err(e) => {
+                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                    "Failed to attach core: {}",
+                                    e
+                                )));
+                                continue;
+                            }
+                        };
+
+                        match &core_cmd {
+                            DebugCommand::Halt => {
+                                let halted = match low_power_debug.get(name) {
+                                    Some(low_power) => crate::debug::low_power::halt_with_retry(
+                                        &mut core,
+                                        low_power,
+                                        crate::debug::low_power::HALT_ATTEMPTS,
+                                        crate::debug::low_power::HALT_RETRY_BACKOFF,
+                                    ),
+                                    None => core
+                                        .halt(crate::debug::HALT_TIMEOUT)
+                                        .context("Failed to halt core"),
+                                };
+                                match halted {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    }
+                                    Err(e) => {
+                                        let _ = evt_tx.send(DebugEvent::operation_failed(
+                                            format!("Halt {}", name),
+                                            &e,
+                                        ));
+                                    }
+                                }
+                            }
+                            DebugCommand::Resume => {
+                                match core.run().context("Failed to resume core") {
+                                    Ok(_) => {
+                                        let _ = evt_tx.send(DebugEvent::Resumed);
+                                    }
+                                    Err(e) => {
+                                        let _ = evt_tx.send(DebugEvent::operation_failed(
+                                            format!("Resume {}", name),
+                                            &e,
+                                        ));
+                                    }
+                                }
+                            }
+                            DebugCommand::Step | DebugCommand::ShadowStep => {
+                                let from_pc = core.pc().ok();
+                                match core.step().context("Failed to step core") {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                        if let Some(from_pc) = from_pc {
+                                            let instruction = arch.as_ref().and_then(|a| {
+                                                disasm_manager
+                                                    .instruction_at(a, from_pc, |buf| {
+                                                        core.read_memory(from_pc, buf)
+                                                    })
+                                                    .ok()
+                                            });
+                                            let _ = evt_tx.send(DebugEvent::Stepped {
+                                                from_pc,
+                                                to_pc: pc,
+                                                instruction,
+                                            });
+                                        }
+                                    }
+                                    Err(e) => {
+                                        let _ = evt_tx.send(DebugEvent::operation_failed(
+                                            format!("Step {}", name),
+                                            &e,
+                                        ));
+                                    }
+                                }
+                            }
+                            DebugCommand::StepOver
+                                if symbol_manager.debug_info().is_some()
+                                    && core.probe_core().is_some() =>
+                            {
+                                let debug_info = symbol_manager.debug_info().unwrap();
+                                match step_statement(
+                                    &mut core,
+                                    SteppingMode::OverStatement,
+                                    debug_info,
+                                ) {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    }
+                                    Err(e) => {
                                         let _ = evt_tx.send(DebugEvent::Error(format!(
-                                            "No active session for {}",
-                                            name
+                                            "StepOver failed for {}: {}",
+                                            name, e
                                         )));
-                                        continue;
                                     }
+                                }
+                            }
+                            DebugCommand::StepOver | DebugCommand::StepOverInstruction => {
+                                let pc = core.pc();
+                                // Without a decodable instruction, fall back to a plain step.
+                                let insn = pc.ok().and_then(|pc| {
+                                    let a = arch.as_ref()?;
+                                    disasm_manager
+                                        .instruction_at(a, pc, |buf| core.read_memory(pc, buf))
+                                        .ok()
+                                });
+                                let stepped = match insn {
+                                    Some(insn) => {
+                                        let keep =
+                                            breakpoint_manager.is_enabled(insn.next_address());
+                                        crate::debug::step_over::step_over_instruction(
+                                            &mut core,
+                                            &insn,
+                                            keep,
+                                            crate::debug::step_over::STEP_OVER_TIMEOUT,
+                                        )
+                                    }
+                                    None => core.step().context("Failed to step core"),
                                 };
-                                let mut core = match s.core(0) {
-                                    Ok(c) => c,
+                                match stepped {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    }
                                     Err(e) => {
                                         let _ = evt_tx.send(DebugEvent::Error(format!(
-                                            "Failed to attach core: {}",
-                                            e
+                                            "StepOver failed for {}: {}",
+                                            name, e
                                         )));
-                                        continue;
+                                        // A call that did not return in time leaves the core running.
+                                        if let Ok(pc) = core.halt(crate::debug::HALT_TIMEOUT) {
+                                            halt_pcs.push((name.clone(), pc));
+                                            let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                        }
                                     }
-                                };
-
-                                match &core_cmd {
-                                    DebugCommand::Halt => match debug_manager.halt(&mut core) {
-                                        Ok(info) => {
-                                            halt_pcs.push((name.clone(), info.pc));
-                                            let _ = evt_tx.send(DebugEvent::Halted { pc: info.pc });
+                                }
+                            }
+                            DebugCommand::StepInto => {
+                                if let Some(debug_info) = symbol_manager.debug_info() {
+                                    match step_statement(
+                                        &mut core,
+                                        SteppingMode::IntoStatement,
+                                        debug_info,
+                                    ) {
+                                        Ok(pc) => {
+                                            halt_pcs.push((name.clone(), pc));
+                                            let _ = evt_tx.send(DebugEvent::Halted { pc });
                                         }
                                         Err(e) => {
                                             let _ = evt_tx.send(DebugEvent::Error(format!(
-                                                "Failed to halt {}: {}",
+                                                "StepInto failed for {}: {}",
                                                 name, e
                                             )));
                                         }
-                                    },
-                                    DebugCommand::Resume => match debug_manager.resume(&mut core) {
-                                        Ok(_) => {
-                                            let _ = evt_tx.send(DebugEvent::Resumed);
+                                    }
+                                }
+                            }
+                            DebugCommand::StepOut => {
+                                if let Some(debug_info) = symbol_manager.debug_info() {
+                                    match step_statement(
+                                        &mut core,
+                                        SteppingMode::OutOfStatement,
+                                        debug_info,
+                                    ) {
+                                        Ok(pc) => {
+                                            halt_pcs.push((name.clone(), pc));
+                                            let _ = evt_tx.send(DebugEvent::Halted { pc });
                                         }
                                         Err(e) => {
                                             let _ = evt_tx.send(DebugEvent::Error(format!(
-                                                "Failed to resume {}: {}",
+                                                "StepOut failed for {}: {}",
                                                 name, e
                                             )));
                                         }
+                                    }
+                                }
+                            }
+                            DebugCommand::Reset(kind) => {
+                                match crate::debug::reset::reset_and_halt(
+                                    &mut core,
+                                    *kind,
+                                    crate::debug::reset::RESET_TIMEOUT,
+                                ) {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::ResetPerformed(*kind));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    }
+                                    Err(e) => {
+                                        let _ = evt_tx.send(DebugEvent::Error(format!(
+                                            "Reset failed for {}: {}",
+                                            name, e
+                                        )));
+                                    }
+                                }
+                            }
+                            DebugCommand::RunToMain => {
+                                let main = symbol_manager.lookup_symbol("main");
+                                let keep = main
+                                    .is_some_and(|addr| breakpoint_manager.is_enabled(addr & !1));
+                                match crate::debug::run_to_main::run_to_main(
+                                    &mut core,
+                                    main,
+                                    keep,
+                                    crate::debug::run_to_main::RUN_TO_MAIN_TIMEOUT,
+                                ) {
+                                    Ok(pc) => {
+                                        halt_pcs.push((name.clone(), pc));
+                                        let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    }
+                                    Err(e) => {
+                                        let _ = evt_tx.send(DebugEvent::Error(format!(
+                                            "Run to main failed for {}: {}",
+                                            name, e
+                                        )));
+                                        // Startup that outlasts the timeout leaves the core running.
+                                        if let Ok(pc) = core.halt(crate::debug::HALT_TIMEOUT) {
+                                            halt_pcs.push((name.clone(), pc));
+                                            let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                        }
+                                    }
+                                }
+                            }
+                            _ => {
+                                // For state-less or inspection commands, only run on one target
+                                // (usually the first one in target_names which is active_target)
+                                match &core_cmd {
+                                    DebugCommand::ReadMemory(addr, size) => {
+                                        match read_cache.read_memory(*addr, *size, |data| {
+                                            core.read_memory(*addr, data)
+                                        }) {
+                                            Ok(data) => {
+                                                let _ = evt_tx
+                                                    .send(DebugEvent::MemoryData(*addr, data));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Read memory at 0x{:08X}", addr),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::WriteMemory(addr, data) => {
+                                        read_cache.invalidate_memory(*addr, data.len());
+                                        let timestamp = session_start.elapsed().as_secs_f64();
+                                        // Reading a peripheral register can clear
+                                        // flags or pop a FIFO.
+                                        let read_old =
+                                            !crate::memory::cache::is_volatile(*addr, data.len())
+                                                && !svd_manager.contains_address(*addr, data.len());
+                                        match write_history.write_memory(
+                                            &mut core, *addr, data, read_old, timestamp,
+                                        ) {
+                                            Ok(()) => {
+                                                let _ = evt_tx.send(DebugEvent::WriteLog(
+                                                    write_history.records(),
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Write memory at 0x{:08X}", addr),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::ReadRegister(id) => {
+                                        match read_cache
+                                            .read_register(*id, || core.read_register(*id))
+                                        {
+                                            Ok(v) => {
+                                                let _ =
+                                                    evt_tx.send(DebugEvent::RegisterValue(*id, v));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Read register {}", id),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::ReadFlags => {
+                                        use crate::debug::stack_pointer::XPSR_REGISTER_ID;
+                                        match read_cache.read_register(XPSR_REGISTER_ID, || {
+                                            crate::memory::history::WriteTarget::read_register(
+                                                &mut core,
+                                                XPSR_REGISTER_ID,
+                                            )
+                                        }) {
+                                            Ok(xpsr) => {
+                                                let _ = evt_tx.send(DebugEvent::Flags(
+                                                    crate::debug::psr::PsrFlags::from_xpsr(
+                                                        xpsr as u32,
+                                                    ),
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Read flags",
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::GetActiveStackPointer
+                                    | DebugCommand::SetActiveStackPointer(_) => {
+                                        use crate::debug::stack_pointer::{
+                                            read_active_stack_pointer, select_stack_pointer,
+                                        };
+                                        let result = match &core_cmd {
+                                            DebugCommand::SetActiveStackPointer(which) => {
+                                                read_cache.invalidate_register(
+                                                    crate::debug::special_regs::EXTRA_REGISTER_ID,
+                                                );
+                                                let halted =
+                                                    core.read_status().is_ok_and(|s| s.is_halted());
+                                                select_stack_pointer(&mut core, halted, *which)
+                                                    .and_then(|()| {
+                                                        read_active_stack_pointer(&mut core)
+                                                    })
+                                            }
+                                            _ => read_active_stack_pointer(&mut core),
+                                        };
+                                        match result {
+                                            Ok((which, value)) => {
+                                                let _ =
+                                                    evt_tx.send(DebugEvent::ActiveStackPointer {
+                                                        which,
+                                                        value,
+                                                    });
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Stack pointer selection",
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::ReadSpecialReg(name) => {
+                                        let result =
+                                            crate::debug::special_regs::SpecialRegister::from_name(name)
+                                                .and_then(|reg| {
+                                                    let value = crate::debug::special_regs::read_special_reg(&mut core, reg)?;
+                                                    Ok((reg, value))
+                                                });
+                                        match result {
+                                            Ok((reg, value)) => {
+                                                let _ = evt_tx.send(
+                                                    DebugEvent::SpecialRegisterValue(reg, value),
+                                                );
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Read {}", name),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::WriteSpecialReg(name, value) => {
+                                        read_cache.invalidate_register(
+                                            crate::debug::special_regs::EXTRA_REGISTER_ID,
+                                        );
+                                        let halted =
+                                            core.read_status().is_ok_and(|s| s.is_halted());
+                                        let timestamp = session_start.elapsed().as_secs_f64();
+                                        let result =
+                                            crate::debug::special_regs::SpecialRegister::from_name(
+                                                name,
+                                            )
+                                            .and_then(
+                                                |reg| {
+                                                    write_history.write_special_reg(
+                                                        &mut core, halted, reg, *value, timestamp,
+                                                    )?;
+                                                    crate::debug::special_regs::read_special_reg(
+                                                        &mut core, reg,
+                                                    )
+                                                    .map(|v| (reg, v))
+                                                },
+                                            );
+                                        match result {
+                                            Ok((reg, value)) => {
+                                                let _ = evt_tx.send(
+                                                    DebugEvent::SpecialRegisterValue(reg, value),
+                                                );
+                                                let _ = evt_tx.send(DebugEvent::WriteLog(
+                                                    write_history.records(),
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Write {}", name),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::WhereAmI => match core.pc() {
+                                        Ok(pc) => {
+                                            let _ = evt_tx
+                                                .send(DebugEvent::location(&symbol_manager, pc));
+                                        }
+                                        Err(e) => {
+                                            let _ = evt_tx.send(DebugEvent::Error(format!(
+                                                "Failed to read PC: {}",
+                                                e
+                                            )));
+                                        }
                                     },
-                                    DebugCommand::Step | DebugCommand::ShadowStep => {
-                                        match debug_manager.step(&mut core) {
-                                            Ok(info) => {
-                                                halt_pcs.push((name.clone(), info.pc));
+                                    DebugCommand::WriteRegister(id, val) => {
+                                        read_cache.invalidate_register(*id);
+                                        let timestamp = session_start.elapsed().as_secs_f64();
+                                        match write_history
+                                            .write_register(&mut core, *id, *val, timestamp)
+                                        {
+                                            Ok(()) => {
+                                                let _ = evt_tx.send(DebugEvent::WriteLog(
+                                                    write_history.records(),
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                                    "Register write failed: {}",
+                                                    e
+                                                )));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::UndoWrite => {
+                                        read_cache.invalidate();
+                                        match write_history.undo(&mut core) {
+                                            Ok(Some(_)) => {
+                                                let _ = evt_tx.send(DebugEvent::WriteLog(
+                                                    write_history.records(),
+                                                ));
+                                            }
+                                            Ok(None) => {
+                                                let _ = evt_tx.send(DebugEvent::Error(
+                                                    "No write to undo".to_string(),
+                                                ));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                                    "Undo failed: {}",
+                                                    e
+                                                )));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::SnapshotMemory { address, len }
+                                        if *len > crate::memory::snapshot::MAX_SNAPSHOT_LEN =>
+                                    {
+                                        let _ = evt_tx.send(DebugEvent::operation_failed(
+                                            format!("Snapshot memory at 0x{:08X}", address),
+                                            &anyhow::anyhow!(
+                                                "{} bytes requested, at most {} allowed",
+                                                len,
+                                                crate::memory::snapshot::MAX_SNAPSHOT_LEN
+                                            ),
+                                        ));
+                                    }
+                                    DebugCommand::SnapshotMemory { address, len } => {
+                                        // Read around the cache: a snapshot must show
+                                        // what the target holds now.
+                                        let mut data = vec![0u8; *len];
+                                        match core.read_memory(*address, &mut data) {
+                                            Ok(()) => {
+                                                let timestamp =
+                                                    session_start.elapsed().as_secs_f64();
+                                                let snapshot =
+                                                    snapshots.insert(*address, data, timestamp);
+                                                let _ = evt_tx
+                                                    .send(DebugEvent::MemorySnapshot(snapshot));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    format!("Snapshot memory at 0x{:08X}", address),
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::ReadVectorTable { base, count } => {
+                                        use crate::debug::vector_table::{
+                                            read_vector_entries, DEFAULT_VECTOR_COUNT,
+                                        };
+                                        let active = || {
+                                            core.read_u32(crate::debug::reset::VTOR)
+                                                .map(u64::from)
+                                                .ok()
+                                                .or(flash_base)
+                                        };
+                                        let result = match base.or_else(active) {
+                                            Some(base) => read_vector_entries(
+                                                base,
+                                                count.unwrap_or(DEFAULT_VECTOR_COUNT),
+                                                |addr, buf| core.read_memory(addr, buf),
+                                                |addr| symbol_manager.function_at(addr),
+                                            ),
+                                            None => Err(anyhow::anyhow!(
+                                                "No base given, VTOR unreadable and the target has no flash"
+                                            )),
+                                        };
+                                        match result {
+                                            Ok(entries) => {
+                                                let _ =
+                                                    evt_tx.send(DebugEvent::VectorTable(entries));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Read vector table",
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::VerifyImageMatchesSymbols => {
+                                        match crate::symbols::build_id::verify_image(
+                                            &symbol_manager,
+                                            |addr, buf| core.read_memory(addr, buf),
+                                        ) {
+                                            Ok(result) => {
                                                 let _ =
-                                                    evt_tx.send(DebugEvent::Halted { pc: info.pc });
+                                                    evt_tx.send(DebugEvent::ImageVerified(result));
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Verify image",
+                                                    &e,
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::Disassemble(addr, count) => {
+                                        let mut code = vec![0u8; count * 4];
+                                        if core.read_memory(*addr, &mut code).is_ok() {
+                                            if let Some(ref a) = arch {
+                                                if let Ok(lines) =
+                                                    disasm_manager.disassemble(a, &code, *addr)
+                                                {
+                                                    let _ =
+                                                        evt_tx.send(DebugEvent::Disassembly(lines));
+                                                }
+                                            }
+                                        }
+                                    }
+                                    DebugCommand::DisassembleFunction(addr) => {
+                                        let range =
+                                            function_disassembly_range(&symbol_manager, *addr);
+                                        let mut code =
+                                            vec![0u8; (range.end - range.start) as usize];
+                                        match core.read_memory(range.start, &mut code) {
+                                            Ok(_) => {
+                                                if let Some(ref a) = arch {
+                                                    if let Ok(lines) = disasm_manager.disassemble(
+                                                        a,
+                                                        &code,
+                                                        range.start,
+                                                    ) {
+                                                        let _ = evt_tx
+                                                            .send(DebugEvent::Disassembly(lines));
+                                                    }
+                                                }
                                             }
                                             Err(e) => {
                                                 let _ = evt_tx.send(DebugEvent::Error(format!(
-                                                    "Failed to step {}: {}",
-                                                    name, e
+                                                    "Failed to read function code: {}",
+                                                    e
                                                 )));
                                             }
                                         }
                                     }
-                                    DebugCommand::StepOver => {
-                                        if let Some(debug_info) = symbol_manager.debug_info() {
-                                            match SteppingMode::OverStatement
-                                                .step(&mut core, debug_info)
-                                            {
-                                                Ok((_status, pc)) => {
-                                                    halt_pcs.push((name.clone(), pc));
-                                                    let _ = evt_tx.send(DebugEvent::Halted { pc });
+                                    DebugCommand::DisassembleWithSource(range) => {
+                                        let end = range
+                                            .end
+                                            .min(range.start + DISASSEMBLE_FUNCTION_MAX_BYTES);
+                                        if let Some(ref a) = arch {
+                                            match disasm_manager.disassemble_with_source(
+                                                a,
+                                                range.start..end,
+                                                &symbol_manager,
+                                                |addr, buf| core.read_memory(addr, buf),
+                                            ) {
+                                                Ok(blocks) => {
+                                                    let _ = evt_tx.send(
+                                                        DebugEvent::SourceDisassembly(blocks),
+                                                    );
                                                 }
                                                 Err(e) => {
                                                     let _ =
-                                                        evt_tx.send(DebugEvent::Error(format!(
-                                                            "StepOver failed for {}: {:?}",
-                                                            name, e
-                                                        )));
+                                                        evt_tx.send(DebugEvent::operation_failed(
+                                                            "Disassemble",
+                                                            &e,
+                                                        ));
                                                 }
                                             }
                                         }
                                     }
-                                    DebugCommand::StepInto => {
-                                        if let Some(debug_info) = symbol_manager.debug_info() {
-                                            match SteppingMode::IntoStatement
-                                                .step(&mut core, debug_info)
-                                            {
-                                                Ok((_status, pc)) => {
-                                                    halt_pcs.push((name.clone(), pc));
-                                                    let _ = evt_tx.send(DebugEvent::Halted { pc });
-                                                }
-                                                Err(e) => {
-                                                    let _ =
-                                                        evt_tx.send(DebugEvent::Error(format!(
-                                                            "StepInto failed for {}: {:?}",
-                                                            name, e
-                                                        )));
-                                                }
-                                            }
+                                    DebugCommand::SetBreakpoint(addr) => {
+                                        if let Err(e) =
+                                            breakpoint_manager.set_breakpoint(&mut core, *addr)
+                                        {
+                                            let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                "Set breakpoint",
+                                                &e,
+                                            ));
                                         }
+                                        let _ = evt_tx.send(DebugEvent::Breakpoints(
+                                            breakpoint_manager.entries(),
+                                        ));
                                     }
-                                    DebugCommand::StepOut => {
-                                        if let Some(debug_info) = symbol_manager.debug_info() {
-                                            match SteppingMode::OutOfStatement
-                                                .step(&mut core, debug_info)
-                                            {
-                                                Ok((_status, pc)) => {
-                                                    halt_pcs.push((name.clone(), pc));
-                                                    let _ = evt_tx.send(DebugEvent::Halted { pc });
-                                                }
-                                                Err(e) => {
-                                                    let _ =
-                                                        evt_tx.send(DebugEvent::Error(format!(
-                                                            "StepOut failed for {}: {:?}",
-                                                            name, e
-                                                        )));
-                                                }
+                                    DebugCommand::SetBreakpointAtSource { file, line } => {
+                                        let result =
+                                            resolve_source_line(&symbol_manager, file, *line)
+                                                .map_err(anyhow::Error::from)
+                                                .and_then(|address| {
+                                                    breakpoint_manager
+                                                        .set_breakpoint(&mut core, address)
+                                                        .map(|()| address)
+                                                });
+                                        match result {
+                                            Ok(address) => {
+                                                let _ = evt_tx.send(
+                                                    DebugEvent::SourceBreakpointResolved {
+                                                        file: file.clone(),
+                                                        line: *line,
+                                                        address,
+                                                    },
+                                                );
+                                            }
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Set breakpoint",
+                                                    &e,
+                                                ));
                                             }
                                         }
+                                        let _ = evt_tx.send(DebugEvent::Breakpoints(
+                                            breakpoint_manager.entries(),
+                                        ));
                                     }
-                                    DebugCommand::Reset => {
-                                        match core.reset_and_halt(Duration::from_millis(100)) {
+                                    DebugCommand::SetTracepoint { address, reads } => {
+                                        match breakpoint_manager.set_breakpoint(&mut core, *address)
+                                        {
                                             Ok(_) => {
-                                                if let Ok(pc_val) =
-                                                    core.read_core_reg(core.program_counter())
-                                                {
-                                                    halt_pcs.push((name.clone(), pc_val));
-                                                    let _ = evt_tx
-                                                        .send(DebugEvent::Halted { pc: pc_val });
-                                                }
+                                                tracepoints.set(*address, reads.clone());
                                             }
                                             Err(e) => {
                                                 let _ = evt_tx.send(DebugEvent::Error(format!(
-                                                    "Reset failed for {}: {}",
-                                                    name, e
+                                                    "Tracepoint failed: {}",
+                                                    e
                                                 )));
                                             }
                                         }
+                                        let _ = evt_tx.send(DebugEvent::Breakpoints(
+                                            breakpoint_manager.entries(),
+                                        ));
                                     }
-                                    _ => {
-                                        // For state-less or inspection commands, only run on one target
-                                        // (usually the first one in target_names which is active_target)
-                                        match &core_cmd {
-                                            DebugCommand::ReadMemory(addr, size) => {
-                                                let mut data = vec![0u8; *size];
-                                                match core.read(*addr, &mut data) {
-                                                    Ok(_) => {
-                                                        let _ = evt_tx.send(
-                                                            DebugEvent::MemoryData(*addr, data),
-                                                        );
-                                                    }
-                                                    Err(e) => {
-                                                        let _ = evt_tx
-                                                            .send(DebugEvent::Error(e.to_string()));
-                                                    }
-                                                }
-                                            }
-                                            DebugCommand::WriteMemory(addr, data) => {
-                                                let _ = core.write_8(*addr, data);
-                                            }
-                                            DebugCommand::ReadRegister(id) => {
-                                                if let Ok(val) = core.read_core_reg(*id) {
-                                                    let v = match val {
-                                                        probe_rs::RegisterValue::U32(v) => v as u64,
-                                                        probe_rs::RegisterValue::U64(v) => v,
-                                                        probe_rs::RegisterValue::U128(v) => {
-                                                            v as u64
-                                                        }
-                                                    };
-                                                    let _ = evt_tx
-                                                        .send(DebugEvent::RegisterValue(*id, v));
-                                                }
-                                            }
-                                            DebugCommand::WriteRegister(id, val) => {
-                                                let _ = core.write_core_reg(*id, *val);
-                                            }
-                                            DebugCommand::Disassemble(addr, count) => {
-                                                let mut code = vec![0u8; count * 4];
-                                                if core.read(*addr, &mut code).is_ok() {
-                                                    if let Some(ref a) = arch {
-                                                        if let Ok(lines) = disasm_manager
-                                                            .disassemble(a, &code, *addr)
-                                                        {
-                                                            let _ = evt_tx.send(
-                                                                DebugEvent::Disassembly(lines),
-                                                            );
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            DebugCommand::SetBreakpoint(addr) => {
-                                                let _ = breakpoint_manager
-                                                    .set_breakpoint(&mut core, *addr);
-                                                let _ = evt_tx.send(DebugEvent::Breakpoints(
-                                                    breakpoint_manager.list(),
-                                                ));
-                                            }
-                                            DebugCommand::ClearBreakpoint(addr) => {
-                                                let _ = breakpoint_manager
-                                                    .clear_breakpoint(&mut core, *addr);
-                                                let _ = evt_tx.send(DebugEvent::Breakpoints(
-                                                    breakpoint_manager.list(),
+                                    DebugCommand::ClearBreakpoint(addr) => {
+                                        tracepoints.remove(*addr);
+                                        let _ =
+                                            breakpoint_manager.clear_breakpoint(&mut core, *addr);
+                                        let _ = evt_tx.send(DebugEvent::Breakpoints(
+                                            breakpoint_manager.entries(),
+                                        ));
+                                    }
+                                    DebugCommand::SetBreakpointEnabled { address, enabled } => {
+                                        if let Err(e) = breakpoint_manager
+                                            .set_enabled(&mut core, *address, *enabled)
+                                        {
+                                            let _ = evt_tx.send(DebugEvent::Error(format!(
+                                                "Breakpoint update failed: {}",
+                                                e
+                                            )));
+                                        }
+                                        let _ = evt_tx.send(DebugEvent::Breakpoints(
+                                            breakpoint_manager.entries(),
+                                        ));
+                                    }
+                                    DebugCommand::SetTaskSwitchBreak(mode) => {
+                                        use crate::debug::task_switch::TaskSwitchBreak;
+                                        let result = task_switch_break
+                                            .take()
+                                            .map_or(Ok(()), |brk| brk.disarm(&mut core))
+                                            .and_then(|()| {
+                                                let Some(mode) = mode else {
+                                                    return Ok(None);
+                                                };
+                                                let entry = rtos_manager
+                                                    .as_deref()
+                                                    .context("No RTOS detected")?
+                                                    .switch_function(&symbol_manager)
+                                                    .context(
+                                                        "RTOS switch function not found in symbols",
+                                                    )?;
+                                                TaskSwitchBreak::arm(&mut core, entry, *mode)
+                                                    .map(Some)
+                                            });
+                                        match result {
+                                            Ok(brk) => task_switch_break = brk,
+                                            Err(e) => {
+                                                let _ = evt_tx.send(DebugEvent::operation_failed(
+                                                    "Task switch break",
+                                                    &e,
                                                 ));
                                             }
+                                        }
+                                    }
+                                    probe_cmd => {
+                                        // The rest needs the probe-rs core; other targets cannot serve it.
+                                        let Some(core) = core.probe_core() else {
+                                            let _ = evt_tx.send(DebugEvent::Error(format!(
+                                                "Not supported by this target: {:?}",
+                                                probe_cmd
+                                            )));
+                                            break;
+                                        };
+                                        match probe_cmd {
                                             DebugCommand::ReadPeripheralValues(name) => {
-                                                if let Ok(regs) = svd_manager
-                                                    .read_peripheral_values(name, &mut core)
+                                                if let Ok(regs) =
+                                                    svd_manager.read_peripheral_values(name, core)
                                                 {
                                                     let _ =
                                                         evt_tx.send(DebugEvent::Registers(regs));
@@ -658,18 +2400,30 @@ impl SessionHandle {
                                                 field,
                                                 value,
                                             } => {
+                                                read_cache.invalidate();
                                                 let _ = svd_manager.write_peripheral_field(
-                                                    &mut core, peripheral, register, field, *value,
+                                                    core, peripheral, register, field, *value,
                                                 );
                                                 if let Ok(regs) = svd_manager
-                                                    .read_peripheral_values(peripheral, &mut core)
+                                                    .read_peripheral_values(peripheral, core)
                                                 {
                                                     let _ =
                                                         evt_tx.send(DebugEvent::Registers(regs));
                                                 }
                                             }
-                                            DebugCommand::RttAttach => {
-                                                if let Err(e) = rtt_manager.attach(&mut core) {
+                                            DebugCommand::RttAttach
+                                            | DebugCommand::RttAttachAt(_) => {
+                                                let location = match &core_cmd {
+                                                    DebugCommand::RttAttachAt(addr) => {
+                                                        crate::rtt::RttLocation::At(*addr)
+                                                    }
+                                                    _ => crate::rtt::RttLocation::from_symbols(
+                                                        |name| symbol_manager.lookup_symbol(name),
+                                                    ),
+                                                };
+                                                if let Err(e) =
+                                                    rtt_manager.attach_to(core, location)
+                                                {
                                                     let _ = evt_tx.send(DebugEvent::Error(
                                                         format!("RTT attach failed: {}", e),
                                                     ));
@@ -682,33 +2436,156 @@ impl SessionHandle {
                                                 }
                                             }
                                             DebugCommand::RttWrite { channel, data } => {
-                                                let _ = rtt_manager
-                                                    .write_channel(&mut core, *channel, data);
+                                                read_cache.invalidate();
+                                                let _ =
+                                                    rtt_manager.write_channel(core, *channel, data);
                                             }
                                             DebugCommand::GetTasks => {
                                                 if let Some(rtos) = &mut rtos_manager {
                                                     if let Ok(tasks) =
-                                                        rtos.get_tasks(&mut core, &symbol_manager)
+                                                        rtos.get_tasks(core, &symbol_manager)
                                                     {
                                                         let _ =
                                                             evt_tx.send(DebugEvent::Tasks(tasks));
                                                     }
                                                 }
                                             }
+                                            DebugCommand::GetSyncObjects => {
+                                                if let Some(rtos) = &mut rtos_manager {
+                                                    match rtos
+                                                        .get_sync_objects(core, &symbol_manager)
+                                                    {
+                                                        Ok(objects) => {
+                                                            let _ = evt_tx.send(
+                                                                DebugEvent::SyncObjects(objects),
+                                                            );
+                                                        }
+                                                        Err(e) => {
+                                                            let _ = evt_tx.send(DebugEvent::Error(
+                                                                format!(
+                                                                    "Failed to read sync objects: {}",
+                                                                    e
+                                                                ),
+                                                            ));
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            DebugCommand::ReadDebugRegister(register) => {
+                                                match crate::debug::debug_regs::read_debug_register(
+                                                    core, register,
+                                                ) {
+                                                    Ok(value) => {
+                                                        let _ = evt_tx
+                                                            .send(DebugEvent::DebugRegister(value));
+                                                    }
+                                                    Err(e) => {
+                                                        let _ = evt_tx.send(
+                                                            DebugEvent::operation_failed(
+                                                                "Read debug register",
+                                                                &e,
+                                                            ),
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                            DebugCommand::WriteDebugRegister {
+                                                register,
+                                                value,
+                                            } => {
+                                                match crate::debug::debug_regs::write_debug_register(
+                                                    core, register, *value,
+                                                ) {
+                                                    Ok(value) => {
+                                                        let _ = evt_tx
+                                                            .send(DebugEvent::DebugRegister(value));
+                                                    }
+                                                    Err(e) => {
+                                                        let _ = evt_tx.send(
+                                                            DebugEvent::operation_failed(
+                                                                "Write debug register",
+                                                                &e,
+                                                            ),
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                            DebugCommand::SetVectorCatch(catch) => {
+                                                match crate::debug::vector_catch::set_vector_catch(
+                                                    core, *catch,
+                                                ) {
+                                                    Ok(current) => {
+                                                        let _ = evt_tx
+                                                            .send(DebugEvent::VectorCatch(current));
+                                                    }
+                                                    Err(e) => {
+                                                        let _ = evt_tx.send(DebugEvent::Error(
+                                                            format!(
+                                                                "Failed to set vector catch: {}",
+                                                                e
+                                                            ),
+                                                        ));
+                                                    }
+                                                }
+                                            }
+                                            DebugCommand::ReadSysTick => {
+                                                // Without SystemCoreClock, fall back to the RCC setup.
+                                                let fallback_hz = symbol_manager
+                                                    .lookup_symbol("SystemCoreClock")
+                                                    .is_none()
+                                                    .then(|| {
+                                                        crate::trace::detect_core_frequency(
+                                                            &svd_manager,
+                                                            &mut *core,
+                                                        )
+                                                    })
+                                                    .flatten();
+                                                match crate::debug::systick::read_timebase(
+                                                    core,
+                                                    &symbol_manager,
+                                                    fallback_hz,
+                                                ) {
+                                                    Ok(tb) => {
+                                                        let _ =
+                                                            evt_tx.send(DebugEvent::TimebaseInfo {
+                                                                core_hz: tb.core_hz,
+                                                                tick_hz: tb.tick_hz,
+                                                            });
+                                                    }
+                                                    Err(e) => {
+                                                        let _ = evt_tx.send(DebugEvent::Error(
+                                                            format!(
+                                                                "Failed to read SysTick: {}",
+                                                                e
+                                                            ),
+                                                        ));
+                                                    }
+                                                }
+                                            }
                                             DebugCommand::GetStack => {
-                                                if let Ok(frames) = crate::stack::unwind_stack(
-                                                    &mut core,
+                                                match crate::stack::unwind_stack(
+                                                    core,
                                                     &symbol_manager,
+                                                    unwind_depth,
                                                 ) {
-                                                    let _ = evt_tx.send(DebugEvent::Stack(frames));
+                                                    Ok(frames) => {
+                                                        let _ =
+                                                            evt_tx.send(DebugEvent::Stack(frames));
+                                                    }
+                                                    Err(e) => {
+                                                        let _ = evt_tx.send(DebugEvent::Error(
+                                                            format!("Unwind Error: {}", e),
+                                                        ));
+                                                    }
                                                 }
                                             }
                                             DebugCommand::WatchVariable(name) => {
+                                                live_watch.watch(name);
                                                 if let Some(addr) =
                                                     symbol_manager.lookup_symbol(name)
                                                 {
                                                     if let Some(info) = symbol_manager
-                                                        .resolve_variable(&mut core, name, addr)
+                                                        .resolve_variable(core, name, addr)
                                                     {
                                                         let _ = evt_tx.send(
                                                             DebugEvent::VariableResolved(info),
@@ -716,218 +2593,499 @@ impl SessionHandle {
                                                     }
                                                 }
                                             }
-                                            DebugCommand::PollStatus => {
-                                                core_status = None;
+                                            DebugCommand::RefreshWatches(names) => {
+                                                live_watch.set_names(names);
+                                                let infos =
+                                                    symbol_manager.resolve_variables(core, names);
+                                                let _ = evt_tx.send(DebugEvent::WatchUpdate(infos));
                                             }
                                             _ => {}
                                         }
-                                        // Break after first target for inspection commands
-                                        break;
                                     }
                                 }
+                                // Break after first target for inspection commands
+                                break;
+                            }
+                        }
+                    }
+
+                    // Perform Parity Check if synced and both halted
+                    if let Some((ref m_name, ref s_name)) = shadow_sync {
+                        if halt_pcs.len() == 2 {
+                            let mut m_pc = 0;
+                            let mut s_pc = 0;
+                            for (n, pc) in &halt_pcs {
+                                if n == m_name {
+                                    m_pc = *pc;
+                                }
+                                if n == s_name {
+                                    s_pc = *pc;
+                                }
                             }
 
-                            // Perform Parity Check if synced and both halted
-                            if let Some((ref m_name, ref s_name)) = shadow_sync {
-                                if halt_pcs.len() == 2 {
-                                    let mut m_pc = 0;
-                                    let mut s_pc = 0;
-                                    for (n, pc) in &halt_pcs {
-                                        if n == m_name {
-                                            m_pc = *pc;
-                                        }
-                                        if n == s_name {
-                                            s_pc = *pc;
+                            if m_pc != s_pc {
+                                let _ = evt_tx.send(DebugEvent::ParityDiverged {
+                                    location: m_pc,
+                                    master_val: m_pc,
+                                    slave_val: s_pc,
+                                    info: "Program Counter Divergence".to_string(),
+                                });
+                            } else {
+                                // 2. Register Check (Exhaustive)
+                                let mut diverged = false;
+
+                                // Check R0-R12, SP, LR, PC (register indices 0-15)
+                                for reg_idx in 0..16 {
+                                    let mut m_val = None;
+                                    if let Some(s_m) = sessions.get_mut(m_name) {
+                                        if let Ok(mut c_m) = s_m.core(0) {
+                                            m_val = c_m.read_register(reg_idx).ok();
                                         }
                                     }
 
-                                    if m_pc != s_pc {
-                                        let _ = evt_tx.send(DebugEvent::ParityDiverged {
-                                            location: m_pc,
-                                            master_val: m_pc,
-                                            slave_val: s_pc,
-                                            info: "Program Counter Divergence".to_string(),
-                                        });
-                                    } else {
-                                        // 2. Register Check (Exhaustive)
-                                        let mut diverged = false;
-
-                                        // Check R0-R12, SP, LR, PC (register indices 0-15)
-                                        for reg_idx in 0..16 {
-                                            let mut m_val = None;
-                                            if let Some(s_m) = sessions.get_mut(m_name) {
-                                                if let Ok(mut c_m) = s_m.core(0) {
-                                                    if let Ok(v) = c_m.read_core_reg(reg_idx) {
-                                                        m_val = Some(match v {
-                                                            probe_rs::RegisterValue::U32(v) => {
-                                                                v as u64
-                                                            }
-                                                            probe_rs::RegisterValue::U64(v) => v,
-                                                            _ => 0,
-                                                        });
-                                                    }
-                                                }
-                                            }
-
-                                            if let Some(mv) = m_val {
-                                                if let Some(s_s) = sessions.get_mut(s_name) {
-                                                    if let Ok(mut c_s) = s_s.core(0) {
-                                                        if let Ok(v) = c_s.read_core_reg(reg_idx) {
-                                                            let sv = match v {
-                                                                probe_rs::RegisterValue::U32(v) => {
-                                                                    v as u64
-                                                                }
-                                                                probe_rs::RegisterValue::U64(v) => {
-                                                                    v
-                                                                }
-                                                                _ => 0,
-                                                            };
-                                                            if mv != sv {
-                                                                let _ = evt_tx.send(DebugEvent::ParityDiverged {
-                                                                    location: m_pc,
-                                                                    master_val: mv,
-                                                                    slave_val: sv,
-                                                                    info: format!("Register R{} Divergence", reg_idx),
-                                                                });
-                                                                diverged = true;
-                                                            }
-                                                        }
+                                    if let Some(mv) = m_val {
+                                        if let Some(s_s) = sessions.get_mut(s_name) {
+                                            if let Ok(mut c_s) = s_s.core(0) {
+                                                if let Ok(sv) = c_s.read_register(reg_idx) {
+                                                    if mv != sv {
+                                                        let _ = evt_tx.send(
+                                                            DebugEvent::ParityDiverged {
+                                                                location: m_pc,
+                                                                master_val: mv,
+                                                                slave_val: sv,
+                                                                info: format!(
+                                                                    "Register R{} Divergence",
+                                                                    reg_idx
+                                                                ),
+                                                            },
+                                                        );
+                                                        diverged = true;
                                                     }
                                                 }
                                             }
-                                            if diverged {
-                                                break;
-                                            }
                                         }
                                     }
+                                    if diverged {
+                                        break;
+                                    }
                                 }
                             }
-                            continue;
                         }
-                        DebugCommand::LoadSymbols(path) => {
-                            if let Err(e) = symbol_manager.load_elf(&path) {
-                                let _ = evt_tx.send(DebugEvent::Error(format!(
-                                    "Failed to load symbols: {}",
-                                    e
-                                )));
-                            } else {
-                                let _ = evt_tx.send(DebugEvent::SymbolsLoaded);
-                                rtos_manager =
-                                    Some(Box::new(crate::rtos::freertos::FreeRtos::new()));
-                            }
-                            continue;
+                    }
+                    continue;
+                }
+                DebugCommand::LoadSymbols(path) => {
+                    if let Err(e) = symbol_manager.load_elf(&path) {
+                        let _ = evt_tx
+                            .send(DebugEvent::Error(format!("Failed to load symbols: {}", e)));
+                    } else {
+                        let _ = evt_tx.send(DebugEvent::SymbolsLoaded);
+                        let _ = evt_tx.send(DebugEvent::BuildId(symbol_manager.build_id()));
+                        rtos_manager = Some(Box::new(crate::rtos::freertos::FreeRtos::new()));
+                    }
+                    continue;
+                }
+                DebugCommand::AddSourceMapping { from, to } => {
+                    symbol_manager.add_source_mapping(from, to);
+                    continue;
+                }
+                DebugCommand::ListSourceFiles => {
+                    let _ = evt_tx.send(DebugEvent::SourceFiles(symbol_manager.source_files()));
+                    continue;
+                }
+                DebugCommand::ReadEmbeddedSource(file) => {
+                    let text = symbol_manager.embedded_source(&file);
+                    let _ = evt_tx.send(DebugEvent::EmbeddedSource { file, text });
+                    continue;
+                }
+                DebugCommand::LoadSvd(path) => {
+                    if let Err(e) = svd_manager.load_svd(path) {
+                        let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
+                    } else {
+                        let _ = evt_tx.send(DebugEvent::SvdLoaded);
+                    }
+                    continue;
+                }
+                DebugCommand::GetPeripherals => {
+                    let _ =
+                        evt_tx.send(DebugEvent::Peripherals(svd_manager.get_peripherals_info()));
+                    continue;
+                }
+                DebugCommand::SetLowPowerDebug(config) => {
+                    match config {
+                        Some(config) => {
+                            low_power_debug.insert(active_target.clone(), config);
                         }
-                        DebugCommand::LoadSvd(path) => {
-                            if let Err(e) = svd_manager.load_svd(path) {
-                                let _ = evt_tx.send(DebugEvent::Error(e.to_string()));
-                            } else {
-                                let _ = evt_tx.send(DebugEvent::SvdLoaded);
-                            }
-                            continue;
+                        None => {
+                            low_power_debug.remove(&active_target);
                         }
-                        DebugCommand::GetPeripherals => {
-                            let _ = evt_tx
-                                .send(DebugEvent::Peripherals(svd_manager.get_peripherals_info()));
-                            continue;
+                    }
+                    continue;
+                }
+                DebugCommand::PollStatus => {
+                    // Served by the next status poll, however many are queued.
+                    status_poller.request();
+                    continue;
+                }
+                DebugCommand::GetMetrics => {
+                    let m = metrics.report(Instant::now(), cmd_rx.len(), evt_tx.dropped());
+                    let _ = evt_tx.send(DebugEvent::Metrics(m));
+                    continue;
+                }
+                DebugCommand::SetMetricsInterval(interval) => {
+                    metrics.set_interval(interval);
+                    continue;
+                }
+                DebugCommand::GetBuildId => {
+                    let _ = evt_tx.send(DebugEvent::BuildId(symbol_manager.build_id()));
+                    continue;
+                }
+                DebugCommand::DiffMemorySnapshots { a, b } => {
+                    let _ = evt_tx.send(match snapshots.diff(a, b) {
+                        Some(diff) => DebugEvent::MemoryDiff { a, b, diff },
+                        None => DebugEvent::Error(format!(
+                            "Memory snapshot {} or {} no longer exists",
+                            a, b
+                        )),
+                    });
+                    continue;
+                }
+                DebugCommand::PauseBackground | DebugCommand::ResumeBackground => {
+                    background_paused = matches!(cmd, DebugCommand::PauseBackground);
+                    log::info!(
+                        "Background polling {}",
+                        if background_paused { "paused" } else { "resumed" }
+                    );
+                    let _ = evt_tx.send(DebugEvent::BackgroundPaused(background_paused));
+                    continue;
+                }
+                DebugCommand::DumpAllPeripherals => {
+                    match background_job_refusal(
+                        sessions.contains_key(&active_target),
+                        background_paused,
+                    ) {
+                        Some(e) => {
+                            let _ =
+                                evt_tx.send(DebugEvent::operation_failed("Dump peripherals", &e));
                         }
-                        DebugCommand::AddPlot { name, var_type } => {
-                            if let Some(address) = symbol_manager.lookup_symbol(&name) {
-                                plots.push(PlotConfig { name, address, var_type });
-                            }
-                            continue;
+                        None => {
+                            peripheral_dump = Some(crate::peripheral_dump::PeripheralDumpJob::new(
+                                svd_manager.list_peripherals(),
+                            ));
+                        }
+                    }
+                    continue;
+                }
+                DebugCommand::ChecksumRegion { address, len, algo } => {
+                    match background_job_refusal(
+                        sessions.contains_key(&active_target),
+                        background_paused,
+                    ) {
+                        Some(e) => {
+                            let _ = evt_tx.send(DebugEvent::operation_failed(
+                                format!("Checksum at 0x{:08X}", address),
+                                &e,
+                            ));
                         }
-                        DebugCommand::RemovePlot(name) => {
-                            plots.retain(|p| p.name != name);
-                            continue;
+                        None => {
+                            checksum_job =
+                                Some(crate::memory::checksum::ChecksumJob::new(address, len, algo));
                         }
-                        _ => {}
                     }
-                } else {
-                    // 3. Polling (Status, RTT, Plots for active_target)
-                    if let Some(s) = sessions.get_mut(&active_target) {
-                        if let Ok(mut core) = s.core(0) {
-                            // Poll Status
-                            if let Ok(status) = core.status() {
-                                if core_status != Some(status) {
-                                    core_status = Some(status);
-                                    let _ = evt_tx.send(DebugEvent::Status(status));
-                                    if status.is_halted() {
-                                        if let Ok(pc) = core.read_core_reg(core.program_counter()) {
-                                            let pc_val = match pc {
+                    continue;
+                }
+                _ => {}
+            }
+        } else if !background_paused {
+            // 3. Polling (Status, RTT, Plots for active_target)
+            if let Some(s) = sessions.get_mut(&active_target) {
+                if let Ok(mut core) = s.core(0) {
+                    // Poll Status
+                    let was_running = status_poller.last().is_some_and(|s| !s.is_halted());
+                    let poll_start = Instant::now();
+                    let status_res = status_poller.poll(&mut core);
+                    metrics.record_probe_latency(poll_start.elapsed());
+                    if status_res.is_ok() {
+                        reconnect.record_success();
+                    } else {
+                        reconnect.record_failure();
+                    }
+                    read_cache.set_halted(status_poller.last().is_some_and(|s| s.is_halted()));
+                    let mut target_reset = None;
+                    if let Ok(Some(status)) = status_res {
+                        let pc = if status.is_halted() { core.pc().ok() } else { None };
+                        // Task switch breaks report the switch, then resume or stop per mode
+                        let mut switch_resumed = false;
+                        if let (Some(pc), Some(brk), Some(rtos), Some(core)) = (
+                            pc,
+                            task_switch_break.as_mut(),
+                            rtos_manager.as_deref(),
+                            core.probe_core(),
+                        ) {
+                            let mut target = crate::debug::task_switch::CoreWithRtos {
+                                core,
+                                symbols: &symbol_manager,
+                                rtos,
+                            };
+                            match brk.on_halt(&mut target, pc) {
+                                Some(Ok(switch)) => {
+                                    if let Some(switch) = switch {
+                                        let _ = evt_tx.send(DebugEvent::TaskSwitch {
+                                            from: switch.from,
+                                            to: switch.to,
+                                            timestamp: session_start.elapsed().as_secs_f64(),
+                                        });
+                                    }
+                                    switch_resumed = switch.is_none()
+                                        || brk.mode()
+                                            == crate::debug::task_switch::TaskSwitchMode::Resume;
+                                }
+                                Some(Err(e)) => {
+                                    let _ = evt_tx.send(DebugEvent::operation_failed(
+                                        "Task switch break",
+                                        &e,
+                                    ));
+                                }
+                                None => {}
+                            }
+                        }
+                        // Tracepoint hits are logged and resumed, not reported as halts
+                        let traced = pc.filter(|_| !switch_resumed).and_then(|pc| {
+                            let mut target = crate::debug::tracepoint::CoreWithSymbols {
+                                core: &mut core,
+                                symbols: &symbol_manager,
+                            };
+                            tracepoints.on_halt(&mut target, pc).map(|hit| (pc, hit))
+                        });
+                        if switch_resumed {
+                            status_poller.mark_running();
+                            read_cache.set_halted(false);
+                        } else if let Some((address, Ok(values))) = traced {
+                            status_poller.mark_running();
+                            read_cache.set_halted(false);
+                            let _ = evt_tx.send(DebugEvent::TracepointHit { address, values });
+                        } else {
+                            if let Some((address, Err(e))) = traced {
+                                let _ = evt_tx.send(DebugEvent::Error(format!(
+                                    "Failed to resume after tracepoint 0x{:08X}: {}",
+                                    address, e
+                                )));
+                            }
+                            // A halt straight after running may be a reset we did not ask for
+                            if let (Some(pc), Some(core)) = (
+                                pc.filter(|_| was_running && arch.as_deref() == Some("Arm")),
+                                core.probe_core(),
+                            ) {
+                                let unexpected = match reset_detector.poll_reset_flag(core) {
+                                    Ok(Some(unexpected)) => unexpected,
+                                    _ => {
+                                        let sp = core.read_core_reg(core.stack_pointer()).ok().map(
+                                            |sp| match sp {
                                                 probe_rs::RegisterValue::U32(v) => v as u64,
                                                 probe_rs::RegisterValue::U64(v) => v,
                                                 _ => 0,
-                                            };
-                                            let _ = evt_tx.send(DebugEvent::Halted { pc: pc_val });
+                                            },
+                                        );
+                                        let vectors = crate::debug::reset::read_vector_table(core);
+                                        match (sp, vectors) {
+                                            (Some(sp), Ok(vectors)) => {
+                                                reset_detector.is_unexpected_reset(&vectors, pc, sp)
+                                            }
+                                            _ => false,
                                         }
                                     }
+                                };
+                                if unexpected {
+                                    target_reset = Some(Some(pc));
                                 }
                             }
+                            let _ = evt_tx.send(DebugEvent::Status(status));
+                            if let Some(pc) = pc {
+                                let _ = evt_tx.send(DebugEvent::Halted { pc });
+                            }
+                        }
+                    }
 
-                            // Poll RTT
-                            if rtt_manager.is_attached() {
-                                for ch in rtt_manager.get_up_channels() {
-                                    if let Ok(data) = rtt_manager.read_channel(&mut core, ch.number)
-                                    {
-                                        if !data.is_empty() {
-                                            let _ =
-                                                evt_tx.send(DebugEvent::RttData(ch.number, data));
-                                        }
-                                    }
+                    // A reset while running never shows up as a halt
+                    let running = status_poller.last().is_some_and(|s| !s.is_halted());
+                    if running && arch.as_deref() == Some("Arm") {
+                        if let Ok(Some(true)) = reset_detector.poll_reset_flag(&mut core) {
+                            target_reset = Some(None);
+                        }
+                    }
+                    if let Some(pc) = target_reset {
+                        let _ = evt_tx.send(DebugEvent::TargetReset { pc });
+                        if reset_resync {
+                            let restored = breakpoint_manager.replay(&mut core);
+                            log::info!("Re-armed {} breakpoints after reset", restored.len());
+                            rtt_manager.detach();
+                            rtt_auto_attach.arm();
+                        }
+                    }
+
+                    // Pick up RTT once freshly flashed firmware set it up
+                    if running {
+                        reset_detector.observe_running();
+                    }
+                    match rtt_auto_attach.poll(Instant::now(), running, || {
+                        let location = crate::rtt::RttLocation::from_symbols(|name| {
+                            symbol_manager.lookup_symbol(name)
+                        });
+                        let core = core.probe_core().context("Not supported by this target")?;
+                        rtt_manager.attach_to(core, location)
+                    }) {
+                        Some(crate::rtt::AutoAttachState::Pending) => {
+                            let _ = evt_tx.send(DebugEvent::FlashStatus(
+                                "RTT Pending... Waiting for target initialization".to_string(),
+                            ));
+                        }
+                        Some(crate::rtt::AutoAttachState::Found) => {
+                            let _ = evt_tx.send(DebugEvent::RttChannels {
+                                up_channels: rtt_manager.get_up_channels(),
+                                down_channels: rtt_manager.get_down_channels(),
+                            });
+                        }
+                        Some(crate::rtt::AutoAttachState::TimedOut) => {
+                            let _ = evt_tx.send(DebugEvent::Error(
+                                "RTT control block not found after flashing".to_string(),
+                            ));
+                        }
+                        None => {}
+                    }
+
+                    // Poll RTT
+                    if let Some(core) = core.probe_core().filter(|_| rtt_manager.is_attached()) {
+                        for ch in rtt_manager.get_up_channels() {
+                            if let Ok(data) = rtt_manager.read_channel(core, ch.number) {
+                                if !data.is_empty() {
+                                    metrics.record_rtt(data.len());
+                                    let _ = evt_tx.send(DebugEvent::RttData(ch.number, data));
                                 }
                             }
+                        }
+                    }
 
-                            // Poll Plots
-                            if last_plot_poll.elapsed() >= Duration::from_millis(100) {
-                                for plot in &plots {
-                                    let val = match plot.var_type {
-                                        crate::VarType::U32 => {
-                                            core.read_word_32(plot.address).ok().map(|v| v as f64)
-                                        }
-                                        crate::VarType::F32 => core
-                                            .read_word_32(plot.address)
-                                            .ok()
-                                            .map(|v| f32::from_bits(v) as f64),
-                                        _ => None,
-                                    };
-                                    if let Some(v) = val {
-                                        let _ = evt_tx.send(DebugEvent::PlotData {
-                                            name: plot.name.clone(),
-                                            timestamp: session_start.elapsed().as_secs_f64(),
-                                            value: v,
-                                        });
-                                    }
+                    // Poll Plots
+                    if last_plot_poll.elapsed() >= Duration::from_millis(100) {
+                        for plot in &plots {
+                            let val = match plot.var_type {
+                                crate::VarType::U32 => {
+                                    core.read_u32(plot.address).ok().map(|v| v as f64)
                                 }
-                                last_plot_poll = Instant::now();
+                                crate::VarType::F32 => core
+                                    .read_u32(plot.address)
+                                    .ok()
+                                    .map(|v| f32::from_bits(v) as f64),
+                                _ => None,
+                            };
+                            if let Some(v) = val {
+                                let timestamp = session_start.elapsed().as_secs_f64();
+                                plot_store.push(&plot.name, timestamp, v);
+                                let _ = evt_tx.send(DebugEvent::PlotData {
+                                    name: plot.name.clone(),
+                                    timestamp,
+                                    value: v,
+                                });
                             }
                         }
+                        last_plot_poll = Instant::now();
                     }
-                }
-                thread::sleep(Duration::from_millis(10));
-            }
-        });
 
-        Ok(Self { command_tx: cmd_tx, event_tx: evt_tx, thread_handle: Some(thread_handle) })
-    }
+                    // Poll live watches while running
+                    if let Some(infos) = live_watch.poll(Instant::now(), !running, |names| {
+                        core.probe_core().map_or_else(Vec::new, |core| {
+                            symbol_manager.resolve_variables(core, names)
+                        })
+                    }) {
+                        for info in infos {
+                            let _ = evt_tx.send(DebugEvent::VariableResolved(info));
+                        }
+                    }
 
-    #[cfg(not(feature = "hardware"))]
-    pub fn new(_session: Option<crate::probe_rs::Session>) -> Result<Self> {
-        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
-        let (evt_tx, _) = tokio::sync::broadcast::channel(100);
+                    // Re-read watched registers
+                    for change in register_watch
+                        .poll(Instant::now(), |address, buf| core.read_memory(address, buf))
+                    {
+                        let _ = evt_tx.send(DebugEvent::RegisterChanged {
+                            address: change.address,
+                            old: change.old,
+                            new: change.new,
+                        });
+                    }
 
-        let thread_handle = thread::spawn(move || loop {
-            if let Ok(cmd) = cmd_rx.recv() {
-                if matches!(cmd, DebugCommand::Exit) {
-                    return;
+                    // Continue a pending peripheral dump
+                    if let Some(job) = &mut peripheral_dump {
+                        if let Some(dump) =
+                            job.step(crate::peripheral_dump::PERIPHERAL_DUMP_CHUNK, |name| {
+                                let core =
+                                    core.probe_core().context("Not supported by this target")?;
+                                svd_manager.read_peripheral_values(name, core)
+                            })
+                        {
+                            let _ = evt_tx.send(DebugEvent::PeripheralDump(dump));
+                            peripheral_dump = None;
+                        }
+                    }
+
+                    // Continue a pending checksum
+                    if let Some(job) = &mut checksum_job {
+                        if let Some(result) = job
+                            .step(crate::memory::checksum::CHECKSUM_CHUNKS_PER_STEP, |addr, buf| {
+                                core.read_memory(addr, buf)
+                            })
+                        {
+                            let _ = evt_tx.send(match result {
+                                Ok(value) => DebugEvent::Checksum {
+                                    address: job.address(),
+                                    len: job.size(),
+                                    algo: job.algo(),
+                                    value,
+                                },
+                                Err(e) => DebugEvent::operation_failed(
+                                    format!("Checksum at 0x{:08X}", job.address()),
+                                    &e,
+                                ),
+                            });
+                            checksum_job = None;
+                        }
+                    }
+                } else {
+                    reconnect.record_failure();
                 }
             }
-        });
-
-        Ok(Self { command_tx: cmd_tx, event_tx: evt_tx, thread_handle: Some(thread_handle) })
-    }
 
-    pub fn send(&self, cmd: DebugCommand) -> Result<()> {
-        self.command_tx.send(cmd).context("Failed to send command")
+            // 4. Re-attach after probe re-enumeration (opt-in)
+            if reconnect.is_enabled()
+                && reconnect.is_disconnected()
+                && probe_lock.owner() == crate::probe_lock::ProbeOwner::Session
+            {
+                sessions.remove(&active_target);
+                let pm = crate::probe::ProbeManager::new();
+                if let Some((mut info, mut s)) = reconnect.poll(Instant::now(), |t| {
+                    let index = pm.find_probe_by_serial(&t.serial)?;
+                    let (info, s) = pm.connect_with_speed(
+                        index,
+                        &t.chip,
+                        t.protocol,
+                        t.under_reset,
+                        t.speed_khz,
+                    )?;
+                    Ok((info, T::from_probe(s).context("Not supported by this target")?))
+                }) {
+                    if let Ok(mut core) = s.core(0) {
+                        let restored = breakpoint_manager.replay(&mut core);
+                        log::info!("Restored {} breakpoints", restored.len());
+                    }
+                    sessions.insert(active_target.clone(), s);
+                    arch = Some(info.architecture.clone());
+                    status_poller.reset();
+                    read_cache.set_halted(false);
+                    info.endian = symbol_manager.endian();
+                    let _ = evt_tx.send(DebugEvent::Reconnected(info));
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
     }
 }
 #[cfg(test)]
@@ -952,6 +3110,138 @@ mod tests {
         assert!(matches!(event, DebugEvent::Resumed));
     }
 
+    #[tokio::test]
+    async fn test_request_times_out_without_matching_event() {
+        let (handle, cmd_rx, event_tx) = SessionHandle::new_test();
+        let timeout = Duration::from_millis(50);
+
+        // Only unrelated events arrive.
+        let chatter = tokio::spawn(async move {
+            for _ in 0..3 {
+                let _ = event_tx.send(DebugEvent::Resumed);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            event_tx
+        });
+        let result = handle
+            .request(
+                DebugCommand::ReadRegister(15),
+                |e| matches!(e, DebugEvent::RegisterValue(15, _)),
+                timeout,
+            )
+            .await;
+        assert_eq!(result.unwrap_err(), RequestError::Timeout(timeout));
+        assert!(matches!(cmd_rx.try_recv(), Ok(DebugCommand::ReadRegister(15))));
+
+        // A failure ends the wait early.
+        let event_tx = chatter.await.unwrap();
+        let request = handle.request(
+            DebugCommand::ReadRegister(15),
+            |e| matches!(e, DebugEvent::RegisterValue(15, _)),
+            Duration::from_secs(5),
+        );
+        let fail = async {
+            tokio::task::yield_now().await;
+            let _ = event_tx.send(DebugEvent::Error("Core is running".to_string()));
+        };
+        let (result, ()) = tokio::join!(request, fail);
+        assert_eq!(result.unwrap_err(), RequestError::Core("Core is running".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_rtt_read_until_accumulates_chunks() {
+        let (handle, _cmd_rx, event_tx) = SessionHandle::new_test();
+        let read = handle.rtt_read_until(0, b"> ", Duration::from_secs(5));
+        let console = async {
+            tokio::task::yield_now().await;
+            let _ = event_tx.send(DebugEvent::RttData(0, b"temp=21".to_vec()));
+            let _ = event_tx.send(DebugEvent::RttData(1, b"> ".to_vec()));
+            let _ = event_tx.send(DebugEvent::Halted { pc: 0x100 });
+            let _ = event_tx.send(DebugEvent::RttData(0, b".5\r\n>".to_vec()));
+            let _ = event_tx.send(DebugEvent::RttData(0, b" ".to_vec()));
+            let _ = event_tx.send(DebugEvent::RttData(0, b"late".to_vec()));
+        };
+        let (result, ()) = tokio::join!(read, console);
+        assert_eq!(
+            result.unwrap(),
+            RttReadUntil { data: b"temp=21.5\r\n> ".to_vec(), found: true }
+        );
+
+        // Without the delimiter the data so far is returned at the timeout.
+        let read = handle.rtt_read_until(0, b"> ", Duration::from_millis(50));
+        let console = async {
+            tokio::task::yield_now().await;
+            let _ = event_tx.send(DebugEvent::RttData(0, b"busy".to_vec()));
+        };
+        let (result, ()) = tokio::join!(read, console);
+        assert_eq!(result.unwrap(), RttReadUntil { data: b"busy".to_vec(), found: false });
+    }
+
+    #[test]
+    fn test_event_json_round_trip() {
+        let events = vec![
+            DebugEvent::Halted { pc: 0x0800_0123 },
+            DebugEvent::MemoryData(0x2000_0000, vec![1, 2, 3]),
+            DebugEvent::PlotData { name: "temp".to_string(), timestamp: 1.5, value: 42.0 },
+            DebugEvent::Error("boom".to_string()),
+        ];
+        for event in events {
+            let json = event_to_json(&event).unwrap();
+            assert!(!json.contains('\n'));
+            let decoded: DebugEvent = serde_json::from_str(&json).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", event));
+        }
+
+        let cmd = DebugCommand::ReadMemory(0x2000_0000, 16);
+        let json = serde_json::to_string(&cmd).unwrap();
+        let decoded: DebugCommand = serde_json::from_str(&json).unwrap();
+        assert!(matches!(decoded, DebugCommand::ReadMemory(0x2000_0000, 16)));
+    }
+
+    #[test]
+    fn test_write_events_json_is_line_delimited() {
+        let (handle, _cmd_rx, event_tx) = SessionHandle::new_test();
+        let rx = handle.subscribe();
+        event_tx.send(DebugEvent::Resumed).unwrap();
+        event_tx.send(DebugEvent::Halted { pc: 4 }).unwrap();
+        drop(handle);
+        drop(event_tx);
+
+        let mut out = Vec::new();
+        write_events_json(rx, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines, vec!["\"Resumed\"", "{\"Halted\":{\"pc\":4}}"]);
+    }
+
+    #[test]
+    fn test_emitted_events_have_increasing_seq() {
+        let (handle, _cmd_rx, event_tx) = SessionHandle::new_test();
+        let _plain = handle.subscribe();
+        let mut rx = handle.subscribe_sequenced();
+        event_tx.send(DebugEvent::Resumed).unwrap();
+        event_tx.send(DebugEvent::Halted { pc: 4 }).unwrap();
+        event_tx.send(DebugEvent::Resumed).unwrap();
+
+        let seqs: Vec<u64> = (0..3).map(|_| rx.try_recv().unwrap().seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert!(matches!(rx.try_recv(), Err(tokio::sync::broadcast::error::TryRecvError::Empty)));
+    }
+
+    #[test]
+    fn test_target_voltage_event() {
+        assert!(matches!(
+            DebugEvent::target_voltage(Ok(Some(3.3))),
+            DebugEvent::TargetVoltage(Some(v)) if v == 3.3
+        ));
+        // Probes without a VTarget sense line report no value rather than an error.
+        assert!(matches!(DebugEvent::target_voltage(Ok(None)), DebugEvent::TargetVoltage(None)));
+        assert!(matches!(
+            DebugEvent::target_voltage(Err(anyhow::anyhow!("USB error: Resource busy"))),
+            DebugEvent::OperationFailed { error: crate::DebugError::ProbeBusy, .. }
+        ));
+    }
+
     #[test]
     fn test_debug_event_clone() {
         let event = DebugEvent::Halted { pc: 0x1234 };
@@ -1,6 +1,9 @@
+use crate::debug::stack_pointer::{exc_return_stack, StackPointer, PSP_REGISTER_ID};
+use crate::memory::history::WriteTarget;
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::{Core, MemoryInterface};
 use crate::symbols::SymbolManager;
+use anyhow::{bail, Result};
 use gimli::{BaseAddresses, DebugFrame, RunTimeEndian, UnwindContext, UnwindSection};
 use object::{Object, ObjectSection};
 #[cfg(feature = "hardware")]
@@ -42,15 +45,33 @@ impl From<&ProbeStackFrame> for StackFrame {
     }
 }
 
+/// Frames walked before giving up, so a corrupt stack cannot loop forever.
+pub const DEFAULT_MAX_UNWIND_DEPTH: usize = 64;
+
+/// Name of the frame appended when the depth limit cut the stack short.
+pub const TRUNCATED_FRAME: &str = "<truncated>";
+
+/// Offsets of the stacked PC and xPSR in an exception frame.
+const EXC_FRAME_PC: u64 = 0x18;
+const EXC_FRAME_XPSR: u64 = 0x1C;
+/// Size of the basic exception frame (R0-R3, R12, LR, PC, xPSR) and of the
+/// extended frame that adds S0-S15 and FPSCR.
+const EXC_FRAME_SIZE: u64 = 0x20;
+const EXC_FRAME_FP_SIZE: u64 = 0x68;
+/// EXC_RETURN bit 4: set for a basic frame without FP registers.
+const EXC_RETURN_BASIC_FRAME: u32 = 1 << 4;
+/// Stacked xPSR bit 9: a padding word was pushed to align the frame.
+const XPSR_FRAME_PADDED: u32 = 1 << 9;
+
+/// Unwind the halted core's stack, at most `max_depth` frames deep.
 pub fn unwind_stack(
     core: &mut Core,
     symbol_manager: &SymbolManager,
-) -> Result<Vec<StackFrame>, String> {
+    max_depth: usize,
+) -> Result<Vec<StackFrame>> {
     // 1. Initial State
-    let mut frames = Vec::new();
-
     // Get current registers
-    let pc_val: u64 = match core.read_core_reg(core.program_counter()).map_err(|e| e.to_string())? {
+    let pc_val: u64 = match core.read_core_reg(core.program_counter())? {
         #[cfg(feature = "hardware")]
         probe_rs::RegisterValue::U32(v) => v as u64,
         #[cfg(feature = "hardware")]
@@ -61,7 +82,7 @@ pub fn unwind_stack(
         crate::RegisterValue::U64(v) => v,
         _ => 0,
     };
-    let sp_val: u64 = match core.read_core_reg(core.stack_pointer()).map_err(|e| e.to_string())? {
+    let sp_val: u64 = match core.read_core_reg(core.stack_pointer())? {
         #[cfg(feature = "hardware")]
         probe_rs::RegisterValue::U32(v) => v as u64,
         #[cfg(feature = "hardware")]
@@ -73,7 +94,7 @@ pub fn unwind_stack(
         _ => 0,
     };
     // We might need LR for leaf functions or if DWARF is missing
-    let lr_val: u64 = match core.read_core_reg(core.return_address()).map_err(|e| e.to_string())? {
+    let lr_val: u64 = match core.read_core_reg(core.return_address())? {
         #[cfg(feature = "hardware")]
         probe_rs::RegisterValue::U32(v) => v as u64,
         #[cfg(feature = "hardware")]
@@ -86,23 +107,11 @@ pub fn unwind_stack(
     };
 
     // Current frame (Top of Stack)
-    // Try to resolve function name for PC
-    let func_name = if let Some(info) = symbol_manager.lookup(pc_val) {
-        info.function.unwrap_or_else(|| format!("0x{:08x}", pc_val))
-    } else {
-        format!("0x{:08x}", pc_val)
-    };
-
-    let source_loc = symbol_manager.lookup(pc_val);
-
-    frames.push(StackFrame {
-        id: 0,
-        function_name: func_name,
-        source_file: source_loc.as_ref().map(|l| l.file.to_string_lossy().to_string()),
-        line: source_loc.as_ref().map(|l| l.line as u64),
-        pc: pc_val as u32,
-        sp: sp_val as u32,
-    });
+    let mut frames = vec![resolve_frame(
+        symbol_manager,
+        0,
+        FrameRegs { pc: pc_val, sp: sp_val, interrupted: false },
+    )];
 
     // 2. Load ELF and DWARF for Unwinding
     let elf_data = if let Some(data) = symbol_manager.elf_data() {
@@ -112,7 +121,7 @@ pub fn unwind_stack(
         return Ok(frames);
     };
 
-    let obj = object::File::parse(elf_data).map_err(|e| e.to_string())?;
+    let obj = object::File::parse(elf_data)?;
     let endian = if obj.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
 
     // Try .debug_frame, then .eh_frame
@@ -128,108 +137,277 @@ pub fn unwind_stack(
 
     // Register state state (DWARF register numbers)
     // Cortex-M: 13=SP, 14=LR, 15=PC
-    let mut current_pc = pc_val;
-    let mut current_sp = sp_val;
     let current_lr = lr_val;
 
-    // Limit depth
-    for _ in 0..20 {
-        let mut unwound = false;
+    let walk = walk_frames(
+        FrameRegs { pc: pc_val, sp: sp_val, interrupted: false },
+        max_depth,
+        |current| {
+            // Stop if we hit typical end-of-stack markers (e.g. 0xFFFFFFFF)
+            if current.pc == 0xFFFFFFFF {
+                return None;
+            }
+            let current_pc = current.pc;
+            let current_sp = current.sp;
 
-        if let Some(section_data) = &debug_frame_section {
+            let section_data = debug_frame_section.as_ref()?;
             let debug_frame = DebugFrame::new(section_data, endian);
             let mut bases = BaseAddresses::default();
             bases = bases.set_text(0); // Assuming 0 for now
-            if let Ok(fde) =
-                debug_frame.fde_for_address(&bases, current_pc, |f, b, o| f.cie_from_offset(b, o))
-            {
-                if let Ok(row) =
-                    fde.unwind_info_for_address(&debug_frame, &bases, &mut ctx, current_pc)
-                {
-                    // Evaluate CFA (Canonical Frame Address) - usually SP of caller
-                    let cfa = match row.cfa() {
-                        gimli::CfaRule::RegisterAndOffset { register, offset } => {
-                            let reg_val = if register.0 == 13 { current_sp } else { 0 }; // TODO: Handle other regs
-                            (reg_val as i64 + offset) as u64
-                        }
-                        _ => current_sp, // Fallback
-                    };
-
-                    // Evaluate Return Address (RA) -> PC of caller
-                    // Usually stores in LR (14) or on stack
-                    let ra_rule = row.register(gimli::Register(14)); // LR
-                    let caller_pc = match ra_rule {
-                        gimli::RegisterRule::Undefined => {
-                            // If Undefined, maybe we are at bottom or uses LR directly
-                            if current_lr != 0 {
-                                current_lr
-                            } else {
-                                0
-                            }
-                        }
-                        gimli::RegisterRule::SameValue => current_lr,
-                        gimli::RegisterRule::Offset(offset) => {
-                            // Saved at CFA + offset
-                            let addr = (cfa as i64 + offset) as u64;
-                            match core.read_word_32(addr) {
-                                Ok(val) => val as u64,
-                                Err(_) => 0,
-                            }
-                        }
-                        gimli::RegisterRule::ValOffset(offset) => (cfa as i64 + offset) as u64,
-                        gimli::RegisterRule::Register(reg) => {
-                            if reg.0 == 14 {
-                                current_lr
-                            } else {
-                                0
-                            } // Simplified
-                        }
-                        _ => 0,
-                    };
-
-                    if caller_pc == 0 || caller_pc == current_pc {
-                        break; // Stop unwinding
-                    }
+            let fde = debug_frame
+                .fde_for_address(&bases, current_pc, |f, b, o| f.cie_from_offset(b, o))
+                .ok()?;
+            let row =
+                fde.unwind_info_for_address(&debug_frame, &bases, &mut ctx, current_pc).ok()?;
 
-                    // Update state for next frame
-                    current_pc = caller_pc;
-                    current_sp = cfa;
-                    // current_lr should be updated too if possible, but simpler is ok for now
-                    unwound = true;
+            // Evaluate CFA (Canonical Frame Address) - usually SP of caller
+            let cfa = match row.cfa() {
+                gimli::CfaRule::RegisterAndOffset { register, offset } => {
+                    let reg_val = if register.0 == 13 { current_sp } else { 0 }; // TODO: Handle other regs
+                    (reg_val as i64 + offset) as u64
                 }
-            }
-        }
+                _ => current_sp, // Fallback
+            };
 
-        // If DWARF failed, maybe try primitive LR unwinding for one step?
-        if !unwound {
-            // Basic leaf function handling: if we are in a leaf, LR holds the caller
-            // But we simulate this inside loop usually.
-            // If we haven't found DWARF, we assume we can't unwind further safely.
-            break;
-        }
+            // Evaluate Return Address (RA) -> PC of caller
+            // Usually stores in LR (14) or on stack
+            let ra_rule = row.register(gimli::Register(14)); // LR
+            let caller_pc = match ra_rule {
+                gimli::RegisterRule::Undefined => {
+                    // If Undefined, maybe we are at bottom or uses LR directly
+                    if current_lr != 0 {
+                        current_lr
+                    } else {
+                        0
+                    }
+                }
+                gimli::RegisterRule::SameValue => current_lr,
+                gimli::RegisterRule::Offset(offset) => {
+                    // Saved at CFA + offset
+                    let addr = (cfa as i64 + offset) as u64;
+                    match core.read_word_32(addr) {
+                        Ok(val) => val as u64,
+                        Err(_) => 0,
+                    }
+                }
+                gimli::RegisterRule::ValOffset(offset) => (cfa as i64 + offset) as u64,
+                gimli::RegisterRule::Register(reg) => {
+                    if reg.0 == 14 {
+                        current_lr
+                    } else {
+                        0
+                    } // Simplified
+                }
+                _ => 0,
+            };
 
-        // Resolve symbol for new PC
-        let func_name = if let Some(info) = symbol_manager.lookup(current_pc) {
-            info.function.unwrap_or_else(|| format!("0x{:08x}", current_pc))
-        } else {
-            format!("0x{:08x}", current_pc)
-        };
-        let source_loc = symbol_manager.lookup(current_pc);
+            if caller_pc == 0 || caller_pc == current_pc {
+                return None; // Stop unwinding
+            }
+            // Returning through EXC_RETURN: the caller is the interrupted code, whose
+            // registers the exception pushed to the stack EXC_RETURN names.
+            if let Some(stack) = exc_return_stack(caller_pc as u32) {
+                let frame_sp = match stack {
+                    StackPointer::Msp => cfa,
+                    StackPointer::Psp => WriteTarget::read_register(core, PSP_REGISTER_ID).ok()?,
+                };
+                return exception_caller(caller_pc as u32, frame_sp, |addr| {
+                    core.read_word_32(addr).ok()
+                });
+            }
+            // current_lr should be updated too if possible, but simpler is ok for now
+            Some(FrameRegs { pc: caller_pc, sp: cfa, interrupted: false })
+        },
+    )?;
 
+    for regs in walk.frames.into_iter().skip(1) {
+        frames.push(resolve_frame(symbol_manager, frames.len() as u64, regs));
+    }
+    if walk.truncated {
         frames.push(StackFrame {
             id: frames.len() as u64,
-            function_name: func_name,
-            source_file: source_loc.as_ref().map(|l| l.file.to_string_lossy().to_string()),
-            line: source_loc.as_ref().map(|l| l.line as u64),
-            pc: current_pc as u32,
-            sp: current_sp as u32,
+            function_name: TRUNCATED_FRAME.to_string(),
+            source_file: None,
+            line: None,
+            pc: 0,
+            sp: 0,
         });
+    }
+
+    Ok(frames)
+}
 
-        // Stop if we hit typical end-of-stack markers (e.g. 0xFFFFFFFF or 0)
-        if current_pc == 0 || current_pc == 0xFFFFFFFF {
+/// The frame of the code interrupted by an exception, read from the exception
+/// frame at `frame_sp` that was pushed with `exc_return` in LR.
+pub fn exception_caller<F>(exc_return: u32, frame_sp: u64, mut read_word: F) -> Option<FrameRegs>
+where
+    F: FnMut(u64) -> Option<u32>,
+{
+    let pc = read_word(frame_sp + EXC_FRAME_PC)?;
+    let xpsr = read_word(frame_sp + EXC_FRAME_XPSR)?;
+    let mut size =
+        if exc_return & EXC_RETURN_BASIC_FRAME != 0 { EXC_FRAME_SIZE } else { EXC_FRAME_FP_SIZE };
+    if xpsr & XPSR_FRAME_PADDED != 0 {
+        size += 4;
+    }
+    Some(FrameRegs { pc: u64::from(pc & !1), sp: frame_sp + size, interrupted: true })
+}
+
+/// Name and source location of the frame at `regs`.
+fn resolve_frame(symbol_manager: &SymbolManager, id: u64, regs: FrameRegs) -> StackFrame {
+    let source_loc = symbol_manager.lookup(regs.pc);
+    let func_name = source_loc
+        .as_ref()
+        .and_then(|info| info.function.clone())
+        .unwrap_or_else(|| format!("0x{:08x}", regs.pc));
+
+    StackFrame {
+        id,
+        function_name: func_name,
+        source_file: source_loc.as_ref().map(|l| l.file.to_string_lossy().to_string()),
+        line: source_loc.as_ref().map(|l| l.line as u64),
+        pc: regs.pc as u32,
+        sp: regs.sp as u32,
+    }
+}
+
+/// Program counter and stack pointer of one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRegs {
+    pub pc: u64,
+    pub sp: u64,
+    /// Reached through an exception frame, so the stack may have switched
+    /// from the MSP to the PSP.
+    pub interrupted: bool,
+}
+
+/// Frames found by [`walk_frames`], innermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameWalk {
+    pub frames: Vec<FrameRegs>,
+    /// The depth limit was reached while callers remained.
+    pub truncated: bool,
+}
+
+/// Walk the stack from `top`, calling `step` for each frame's caller until it
+/// returns `None` or `max_depth` frames are found.
+///
+/// A misaligned stack pointer in the top frame means the stack cannot be trusted
+/// at all and is an error. Further up, a misaligned stack pointer or one that
+/// does not move towards the stack base ends the walk, except across an
+/// exception frame: with a corrupt stack the frames below it are still worth
+/// showing.
+pub fn walk_frames<F>(top: FrameRegs, max_depth: usize, mut step: F) -> Result<FrameWalk>
+where
+    F: FnMut(FrameRegs) -> Option<FrameRegs>,
+{
+    if top.sp % 4 != 0 {
+        bail!("Stack corrupted (invalid SP: 0x{:08X})", top.sp);
+    }
+
+    let mut frames = vec![top];
+    let mut current = top;
+    while let Some(caller) = step(current) {
+        if caller.sp % 4 != 0 || (caller.sp < current.sp && !caller.interrupted) {
             break;
         }
+        if frames.len() >= max_depth {
+            return Ok(FrameWalk { frames, truncated: true });
+        }
+        frames.push(caller);
+        current = caller;
     }
+    Ok(FrameWalk { frames, truncated: false })
+}
 
-    Ok(frames)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_frames_stops_at_depth_limit() {
+        // Two functions returning to each other forever, each frame 8 bytes up.
+        let walk = walk_frames(
+            FrameRegs { pc: 0x0800_0100, sp: 0x2000_0000, interrupted: false },
+            64,
+            |f| {
+                let pc = if f.pc == 0x0800_0100 { 0x0800_0200 } else { 0x0800_0100 };
+                Some(FrameRegs { pc, sp: f.sp + 8, interrupted: false })
+            },
+        )
+        .unwrap();
+        assert!(walk.truncated);
+        assert_eq!(walk.frames.len(), 64);
+
+        // A cycle that revisits the same stack slot is cut as soon as SP stops moving up.
+        let walk = walk_frames(
+            FrameRegs { pc: 0x0800_0100, sp: 0x2000_0000, interrupted: false },
+            64,
+            |f| {
+                Some(FrameRegs {
+                    pc: f.pc + 4,
+                    sp: 0x2000_0000 + (f.sp + 8) % 16,
+                    interrupted: false,
+                })
+            },
+        )
+        .unwrap();
+        assert!(!walk.truncated);
+        assert_eq!(walk.frames.len(), 2);
+
+        // An unwind that ends on its own is not truncated.
+        let walk = walk_frames(
+            FrameRegs { pc: 0x0800_0100, sp: 0x2000_0000, interrupted: false },
+            64,
+            |f| {
+                (f.sp < 0x2000_0010).then_some(FrameRegs {
+                    pc: f.pc + 4,
+                    sp: f.sp + 8,
+                    interrupted: false,
+                })
+            },
+        )
+        .unwrap();
+        assert!(!walk.truncated);
+        assert_eq!(walk.frames.len(), 3);
+
+        let err = walk_frames(
+            FrameRegs { pc: 0x0800_0100, sp: 0xDEAD_BEEF, interrupted: false },
+            64,
+            |_| None,
+        );
+        assert_eq!(err.unwrap_err().to_string(), "Stack corrupted (invalid SP: 0xDEADBEEF)");
+    }
+
+    #[test]
+    fn test_unwind_through_exception_frame() {
+        // A basic frame on the PSP, interrupted at 0x0800_0400 (Thumb bit set).
+        let psp = 0x2000_0100u64;
+        let read = |address: u64| match address - psp {
+            EXC_FRAME_PC => Some(0x0800_0401),
+            EXC_FRAME_XPSR => Some(0x0100_0000),
+            _ => Some(0),
+        };
+        let caller = exception_caller(0xFFFF_FFFD, psp, read).unwrap();
+        assert_eq!(caller, FrameRegs { pc: 0x0800_0400, sp: psp + 0x20, interrupted: true });
+
+        // An extended FP frame with the alignment padding word.
+        let padded = |address: u64| match address - psp {
+            EXC_FRAME_PC => Some(0x0800_0400),
+            EXC_FRAME_XPSR => Some(0x0100_0200),
+            _ => Some(0),
+        };
+        let caller = exception_caller(0xFFFF_FFED, psp, padded).unwrap();
+        assert_eq!(caller.sp, psp + 0x6C);
+
+        // The handler runs on the MSP above the task's PSP stack; the walk
+        // follows the switch to the lower thread stack.
+        let walk = walk_frames(
+            FrameRegs { pc: 0x0800_0100, sp: 0x2000_8000, interrupted: false },
+            64,
+            |f| (f.pc == 0x0800_0100).then_some(caller),
+        )
+        .unwrap();
+        assert_eq!(walk.frames.len(), 2);
+    }
 }
@@ -39,6 +39,37 @@ impl SvdManager {
         self.device.as_ref()?.peripherals.iter().find(|p| p.name == name)
     }
 
+    /// Find the register definitions of a peripheral, following `derivedFrom`
+    /// (possibly through several peripherals) when it defines none itself.
+    fn resolve_registers<'a>(
+        &'a self,
+        mut p: &'a rs::Peripheral,
+    ) -> Option<&'a Vec<rs::RegisterCluster>> {
+        let max_depth = self.device.as_ref()?.peripherals.len();
+        for _ in 0..=max_depth {
+            if let Some(regs) = &p.registers {
+                return Some(regs);
+            }
+            p = self.get_peripheral(p.derived_from.as_deref()?)?;
+        }
+        // Cyclic derivation.
+        None
+    }
+
+    /// Whether `[address, address + len)` overlaps an address block of a
+    /// peripheral in the loaded SVD.
+    pub fn contains_address(&self, address: u64, len: usize) -> bool {
+        let end = address + len as u64;
+        self.device.as_ref().is_some_and(|d| {
+            d.peripherals.iter().any(|p| {
+                p.address_block.iter().flatten().any(|block| {
+                    let start = p.base_address + u64::from(block.offset);
+                    address < start + u64::from(block.size) && end > start
+                })
+            })
+        })
+    }
+
     /// Get detailed peripheral info.
     pub fn get_peripherals_info(&self) -> Vec<PeripheralInfo> {
         self.device
@@ -50,6 +81,7 @@ impl SvdManager {
                         name: p.name.clone(),
                         base_address: p.base_address,
                         description: p.description.clone(),
+                        group_name: p.group_name.clone(),
                     })
                     .collect()
             })
@@ -62,7 +94,7 @@ impl SvdManager {
             .get_peripheral(peripheral_name)
             .context(format!("Peripheral {} not found", peripheral_name))?;
 
-        let registers = match &p.registers {
+        let registers = match self.resolve_registers(p) {
             Some(regs) => regs,
             None => return Ok(Vec::new()),
         };
@@ -79,6 +111,10 @@ impl SvdManager {
                             description: f.description.clone(),
                             bit_offset: f.bit_offset(),
                             bit_width: f.bit_width(),
+                            // A field without its own setting inherits the register's.
+                            write_one_to_clear: is_one_to_clear(
+                                f.modified_write_values.or(r.modified_write_values),
+                            ),
                         });
                     }
                 }
@@ -88,6 +124,11 @@ impl SvdManager {
                     address_offset: r.address_offset,
                     description: r.description.clone(),
                     size: r.properties.size.unwrap_or(32),
+                    reset_value: r
+                        .properties
+                        .reset_value
+                        .or(p.default_register_properties.reset_value),
+                    write_one_to_clear: fields.iter().any(|f| f.write_one_to_clear),
                     fields,
                     value: None,
                 });
@@ -166,9 +207,7 @@ impl SvdManager {
         .context("Failed to read register for write-modify-read")?;
 
         // 2. Modify field
-        let mask = ((1u64 << field.bit_width) - 1) << field.bit_offset;
-        let masked_new_val = (new_field_value << field.bit_offset) & mask;
-        let next_val = (current_val & !mask) | masked_new_val;
+        let next_val = field_write_value(current_val, reg, field, new_field_value);
 
         // 3. Write back
         match reg.size {
@@ -184,12 +223,29 @@ impl SvdManager {
     }
 }
 
+fn is_one_to_clear(mwv: Option<rs::ModifiedWriteValues>) -> bool {
+    mwv == Some(rs::ModifiedWriteValues::OneToClear)
+}
+
+/// Compute the value of `reg` to write when updating `field`.
+///
+/// Other fields keep their current value, except W1C fields, which are written
+/// as 0: writing back the 1s of their pending bits would clear them.
+pub fn field_write_value(current: u64, reg: &RegisterInfo, field: &FieldInfo, value: u64) -> u64 {
+    let one_to_clear =
+        reg.fields.iter().filter(|f| f.write_one_to_clear).fold(0, |mask, f| mask | f.mask());
+    let mask = field.mask();
+    (current & !one_to_clear & !mask) | ((value << field.bit_offset) & mask)
+}
+
 /// Simplified representation for UI.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PeripheralInfo {
     pub name: String,
     pub base_address: u64,
     pub description: Option<String>,
+    /// SVD `groupName`, e.g. "USART" for USART1..USART6.
+    pub group_name: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -198,6 +254,10 @@ pub struct RegisterInfo {
     pub address_offset: u32,
     pub description: Option<String>,
     pub size: u32,
+    /// Value after reset, from the register or its peripheral's defaults.
+    pub reset_value: Option<u64>,
+    /// Some field is cleared by writing 1 (SVD `modifiedWriteValues` = `oneToClear`).
+    pub write_one_to_clear: bool,
     pub fields: Vec<FieldInfo>,
     pub value: Option<u64>,
 }
@@ -208,24 +268,42 @@ pub struct FieldInfo {
     pub description: Option<String>,
     pub bit_offset: u32,
     pub bit_width: u32,
+    /// Bits are cleared by writing 1, from the field's or the register's
+    /// `modifiedWriteValues`.
+    #[serde(default)]
+    pub write_one_to_clear: bool,
 }
 
 impl FieldInfo {
+    /// The bits of this field within the register.
+    pub fn mask(&self) -> u64 {
+        field_mask(self.bit_offset, self.bit_width)
+    }
+
+    /// Largest value the field holds.
+    pub fn max_value(&self) -> u64 {
+        field_mask(0, self.bit_width)
+    }
+
     /// Decode the value of this field from a register value.
     pub fn decode(&self, reg_value: u64) -> u64 {
-        let mask = ((1u64 << self.bit_width) - 1) << self.bit_offset;
-        (reg_value & mask) >> self.bit_offset
+        (reg_value & self.mask()) >> self.bit_offset
     }
 }
 
+/// Mask of `width` bits starting at `offset`; a 64-bit field covers the whole
+/// register.
+pub fn field_mask(offset: u32, width: u32) -> u64 {
+    u64::MAX.checked_shr(64u32.saturating_sub(width)).unwrap_or(0).checked_shl(offset).unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_field_decoding() {
-        let field =
-            FieldInfo { name: "TEST".to_string(), description: None, bit_offset: 4, bit_width: 4 };
+        let field = field("TEST", 4, 4, false);
 
         // Reg value: 0x0000_00A0 -> Field [4..7] should be A (10)
         assert_eq!(field.decode(0x0000_00A0), 0xA);
@@ -233,8 +311,96 @@ mod tests {
         // Reg value: 0xFFFF_FFAF -> Field [4..7] should be A (10)
         assert_eq!(field.decode(0xFFFF_FFAF), 0xA);
 
-        let multi_bit =
-            FieldInfo { name: "MULTI".to_string(), description: None, bit_offset: 0, bit_width: 8 };
+        let multi_bit = field("MULTI", 0, 8, false);
         assert_eq!(multi_bit.decode(0x1234_5678), 0x78);
+
+        let whole = field("WHOLE", 0, 64, false);
+        assert_eq!(whole.decode(u64::MAX), u64::MAX);
+        assert_eq!(whole.max_value(), u64::MAX);
+    }
+
+    fn field(name: &str, bit_offset: u32, bit_width: u32, write_one_to_clear: bool) -> FieldInfo {
+        FieldInfo {
+            name: name.to_string(),
+            description: None,
+            bit_offset,
+            bit_width,
+            write_one_to_clear,
+        }
+    }
+
+    fn register(fields: Vec<FieldInfo>) -> RegisterInfo {
+        RegisterInfo {
+            name: "SR".to_string(),
+            address_offset: 0,
+            description: None,
+            size: 32,
+            reset_value: None,
+            write_one_to_clear: fields.iter().any(|f| f.write_one_to_clear),
+            fields,
+            value: None,
+        }
+    }
+
+    #[test]
+    fn test_w1c_write_keeps_sibling_bits() {
+        // Status register with three W1C flags in bits 0..=2 and a plain
+        // read-write ENABLE bit 3.
+        let reg = register(vec![
+            field("F0", 0, 1, true),
+            field("F1", 1, 1, true),
+            field("F2", 2, 1, true),
+            field("ENABLE", 3, 1, false),
+        ]);
+        // Writing 1 clears a flag, writing 0 has no effect; ENABLE takes the written value.
+        let write = |current: u64, written: u64| (current & !written & 0b0111) | (written & 0b1000);
+        let status = 0b1011; // Enabled, F0 and F1 pending
+
+        let written = field_write_value(status, &reg, &reg.fields[1], 1);
+        assert_eq!(written, 0b1010, "other flags written as 0, ENABLE kept");
+        assert_eq!(write(status, written), 0b1001, "only the targeted flag is cleared");
+
+        // Writing a plain field leaves every pending flag alone.
+        let written = field_write_value(status, &reg, &reg.fields[3], 0);
+        assert_eq!(written, 0);
+        assert_eq!(write(status, written), 0b0011);
+
+        // Registers without W1C fields merge the field into the current value.
+        let reg = register(vec![field("MODE", 4, 2, false)]);
+        assert_eq!(field_write_value(0xFF0F, &reg, &reg.fields[0], 0b10), 0xFF2F);
+    }
+
+    #[test]
+    fn test_derived_peripheral_registers() {
+        let mut svd = SvdManager::new();
+        svd.load_svd(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/derived.svd")).unwrap();
+
+        let base = svd.get_registers_info("USART1").unwrap();
+        let names: Vec<_> = base.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["SR", "DR"]);
+
+        // USART2 derives from USART1, USART3 from USART2.
+        for derived in ["USART2", "USART3"] {
+            let regs = svd.get_registers_info(derived).unwrap();
+            let names: Vec<_> = regs.iter().map(|r| r.name.as_str()).collect();
+            assert_eq!(names, vec!["SR", "DR"], "{}", derived);
+            assert_eq!(regs[0].fields[0].name, "TXE");
+        }
+    }
+
+    #[test]
+    fn test_register_reset_values() {
+        let mut svd = SvdManager::new();
+        svd.load_svd(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/derived.svd")).unwrap();
+
+        let regs = svd.get_registers_info("USART1").unwrap();
+        let sr = regs.iter().find(|r| r.name == "SR").unwrap();
+        assert_eq!(sr.reset_value, Some(0x0000_00C0));
+        assert_eq!(sr.description.as_deref(), Some("Status register"));
+        assert_eq!(sr.fields[0].description.as_deref(), Some("Transmit data register empty"));
+
+        // DR has no resetValue of its own and inherits the peripheral default.
+        let dr = regs.iter().find(|r| r.name == "DR").unwrap();
+        assert_eq!(dr.reset_value, Some(0));
     }
 }
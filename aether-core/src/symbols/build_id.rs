@@ -0,0 +1,125 @@
+//! Build identifiers and checking the flashed image against the loaded ELF.
+//!
+//! Debugging with symbols from a different build than the one on the board gives
+//! plausible but wrong variables and source lines. If the firmware keeps its
+//! build ID in target memory, reading it back tells the two apart.
+
+use super::SymbolManager;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// String constants searched for a build identifier when there is no GNU build-id note.
+pub const BUILD_ID_SYMBOLS: &[&str] = &["AETHER_BUILD_ID", "GIT_HASH", "BUILD_ID"];
+
+/// Longest build ID string constant read; longer symbols are truncated.
+pub const MAX_BUILD_ID_LEN: u64 = 128;
+
+/// Bytes before the descriptor in a GNU note: name size, descriptor size, type
+/// and the padded name "GNU\0".
+pub(crate) const GNU_NOTE_HEADER: u64 = 16;
+
+/// How the stored bytes turn into a build ID string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildIdEncoding {
+    /// Raw bytes shown as lowercase hex, as in the GNU build-id note.
+    Hex,
+    /// A NUL-terminated ASCII string, e.g. a git hash.
+    Text,
+}
+
+impl BuildIdEncoding {
+    /// The build ID in `bytes`, or `None` if they do not hold one (e.g. erased flash).
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::Hex => Some(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+            Self::Text => {
+                let text = bytes.split(|&b| b == 0).next()?;
+                let text = std::str::from_utf8(text).ok()?.trim();
+                (!text.is_empty() && text.chars().all(|c| c.is_ascii_graphic()))
+                    .then(|| text.to_string())
+            }
+        }
+    }
+}
+
+/// Where the firmware keeps its build ID in target memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildIdLocation {
+    pub address: u64,
+    pub len: u64,
+    pub encoding: BuildIdEncoding,
+}
+
+/// Result of comparing the flashed build ID with the loaded ELF's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageVerification {
+    pub loaded: String,
+    /// `None` if the target holds no readable build ID there.
+    pub flashed: Option<String>,
+}
+
+impl ImageVerification {
+    pub fn matches(&self) -> bool {
+        self.flashed.as_deref() == Some(self.loaded.as_str())
+    }
+}
+
+/// Read the build ID at `location` with `read(address, buf)` and compare it to `loaded`.
+pub fn verify_build_id<F>(
+    loaded: &str,
+    location: BuildIdLocation,
+    mut read: F,
+) -> Result<ImageVerification>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<()>,
+{
+    let mut bytes = vec![0u8; location.len.min(MAX_BUILD_ID_LEN) as usize];
+    read(location.address, &mut bytes)
+        .with_context(|| format!("Failed to read the build ID at 0x{:08X}", location.address))?;
+    Ok(ImageVerification { loaded: loaded.to_string(), flashed: location.encoding.decode(&bytes) })
+}
+
+/// Compare the build ID flashed on the target with the one in `symbols`.
+pub fn verify_image<F>(symbols: &SymbolManager, read: F) -> Result<ImageVerification>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<()>,
+{
+    let (loaded, location) =
+        symbols.build_id_with_location().context("The loaded ELF has no build ID")?;
+    let location = location.context("The firmware does not keep its build ID in target memory")?;
+    verify_build_id(&loaded, location, read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_build_id_match_and_mismatch() {
+        let location =
+            BuildIdLocation { address: 0x0800_0400, len: 12, encoding: BuildIdEncoding::Text };
+        let flash = |contents: &'static [u8]| {
+            move |address: u64, buf: &mut [u8]| {
+                assert_eq!(address, 0x0800_0400);
+                buf.copy_from_slice(&contents[..buf.len()]);
+                Ok(())
+            }
+        };
+
+        let same = verify_build_id("1a2b3c4d", location, flash(b"1a2b3c4d\0\0\0\0")).unwrap();
+        assert!(same.matches());
+
+        let other = verify_build_id("1a2b3c4d", location, flash(b"9f8e7d6c\0\0\0\0")).unwrap();
+        assert_eq!(other.flashed.as_deref(), Some("9f8e7d6c"));
+        assert!(!other.matches());
+
+        // Erased flash holds no build ID.
+        let erased = verify_build_id("1a2b3c4d", location, flash(&[0xFF; 12])).unwrap();
+        assert_eq!(erased.flashed, None);
+        assert!(!erased.matches());
+
+        let note = BuildIdLocation { encoding: BuildIdEncoding::Hex, len: 4, ..location };
+        let hex = verify_build_id("deadbeef", note, flash(&[0xDE, 0xAD, 0xBE, 0xEF])).unwrap();
+        assert!(hex.matches());
+    }
+}
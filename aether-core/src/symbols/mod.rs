@@ -1,14 +1,22 @@
+pub mod build_id;
+
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs_debug::DebugInfo;
+use crate::Endian;
 use anyhow::Result;
+use build_id::{BuildIdEncoding, BuildIdLocation, BUILD_ID_SYMBOLS};
 use gimli::{Abbreviations, AttributeValue, DebugStr, EndianSlice, RunTimeEndian, UnitOffset};
 use object::{Object, ObjectSection, ObjectSymbol};
 #[cfg(feature = "hardware")]
 use probe_rs_debug::DebugInfo;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Upper bound on the bytes read when decoding a C string from target memory.
+pub const MAX_STRING_LEN: usize = 256;
+
 /// Information about a source code location.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceInfo {
@@ -27,15 +35,167 @@ pub struct TypeInfo {
     pub address: Option<u64>,
 }
 
+/// Display format override for a watched variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VariableFormat {
+    /// Format according to the declared type.
+    #[default]
+    Natural,
+    Hex,
+    Dec,
+    Bin,
+    Char,
+}
+
+impl VariableFormat {
+    /// Format a raw value.
+    pub fn format(self, value: u64) -> String {
+        match self {
+            Self::Natural | Self::Dec => value.to_string(),
+            Self::Hex => format!("0x{:X}", value),
+            Self::Bin => format!("0b{:b}", value),
+            Self::Char => match u32::try_from(value).ok().and_then(char::from_u32) {
+                Some(c) => format!("'{}'", c.escape_default()),
+                None => format!("0x{:X}", value),
+            },
+        }
+    }
+}
+
+/// Source path substitutions for debugging a build made elsewhere, like GDB's
+/// `set substitute-path`.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    rules: Vec<(PathBuf, PathBuf)>,
+}
+
+impl SourceMap {
+    /// Rewrite paths under `from` (as recorded in DWARF) to `to` (on this host).
+    /// Replaces an earlier rule for the same prefix.
+    pub fn add(&mut self, from: PathBuf, to: PathBuf) {
+        self.rules.retain(|(existing, _)| *existing != from);
+        self.rules.push((from, to));
+    }
+
+    /// The host path for a DWARF path; the first matching rule wins.
+    pub fn map(&self, path: &Path) -> PathBuf {
+        Self::substitute(self.rules.iter().map(|(from, to)| (from, to)), path)
+    }
+
+    /// The DWARF path for a host path, undoing [`SourceMap::map`].
+    pub fn unmap(&self, path: &Path) -> PathBuf {
+        Self::substitute(self.rules.iter().map(|(from, to)| (to, from)), path)
+    }
+
+    fn substitute<'a>(
+        mut rules: impl Iterator<Item = (&'a PathBuf, &'a PathBuf)>,
+        path: &Path,
+    ) -> PathBuf {
+        rules
+            .find_map(|(from, to)| path.strip_prefix(from).ok().map(|rest| to.join(rest)))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+/// A file entry of a line program.
+struct LineTableFile {
+    /// Full path as recorded at build time.
+    path: PathBuf,
+    /// Name as written in the entry, possibly relative to its directory.
+    name: String,
+    /// Embedded source text, if any.
+    source: Option<String>,
+}
+
+/// Pointer levels followed by default: a linked list shows its next node, not
+/// the whole chain.
+pub const DEFAULT_POINTER_DEPTH: usize = 1;
+
 /// Manager for handling debugging symbols (DWARF).
 pub struct SymbolManager {
     debug_info: Option<DebugInfo>,
     elf_data: Option<Vec<u8>>,
+    /// Code ranges and names of the ELF's function symbols, parsed on load.
+    functions: Vec<(std::ops::Range<u64>, String)>,
+    pointer_depth: usize,
+    formats: HashMap<String, VariableFormat>,
+    endian: Endian,
+    source_map: SourceMap,
+}
+
+/// How closely `candidate` matches `target`: the number of equal trailing path
+/// components, `usize::MAX` if the paths are equal, and 0 if the file names
+/// differ.
+fn path_match_score(candidate: &Path, target: &Path) -> usize {
+    use std::path::Component;
+    let components = |path: &Path| {
+        path.components().filter(|c| !matches!(c, Component::CurDir)).collect::<Vec<_>>()
+    };
+    let (candidate, target) = (components(candidate), components(target));
+    if candidate == target {
+        return usize::MAX;
+    }
+    candidate.iter().rev().zip(target.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+/// Code range and name of every function symbol in `obj`, in symbol table order.
+fn function_symbols(obj: &object::File) -> Vec<(std::ops::Range<u64>, String)> {
+    // Thumb function symbols have bit 0 set.
+    let thumb = obj.architecture() == object::Architecture::Arm;
+    obj.symbols()
+        .filter(|symbol| symbol.kind() == object::SymbolKind::Text)
+        .filter_map(|symbol| {
+            let start = if thumb { symbol.address() & !1 } else { symbol.address() };
+            let name = symbol.name().ok()?.to_string();
+            Some((start..start + symbol.size().max(1), name))
+        })
+        .collect()
 }
 
 impl SymbolManager {
     pub fn new() -> Self {
-        Self { debug_info: None, elf_data: None }
+        Self {
+            debug_info: None,
+            elf_data: None,
+            functions: Vec::new(),
+            pointer_depth: DEFAULT_POINTER_DEPTH,
+            formats: HashMap::new(),
+            endian: Endian::Little,
+            source_map: SourceMap::default(),
+        }
+    }
+
+    /// Show sources recorded under `from` from `to` instead. Kept across ELF reloads.
+    pub fn add_source_mapping(&mut self, from: PathBuf, to: PathBuf) {
+        self.source_map.add(from, to);
+    }
+
+    /// Byte order used to decode variable values. Taken from the ELF on load.
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    /// Set the display format used when resolving the variable `name`.
+    pub fn set_variable_format(&mut self, name: &str, format: VariableFormat) {
+        if format == VariableFormat::Natural {
+            self.formats.remove(name);
+        } else {
+            self.formats.insert(name.to_string(), format);
+        }
+    }
+
+    pub fn variable_format(&self, name: &str) -> VariableFormat {
+        self.formats.get(name).copied().unwrap_or_default()
+    }
+
+    /// Follow up to `levels` pointers on each path when resolving variables,
+    /// showing each pointee as a child node. 0 turns dereferencing off.
+    pub fn set_pointer_depth(&mut self, levels: usize) {
+        self.pointer_depth = levels;
     }
 
     /// Load symbols from an ELF file.
@@ -44,6 +204,11 @@ impl SymbolManager {
         let debug_info = DebugInfo::from_file(path)
             .map_err(|e| anyhow::anyhow!("Failed to parse ELF/DWARF with probe-rs: {:?}", e))?;
 
+        self.functions.clear();
+        if let Ok(obj) = object::File::parse(&*data) {
+            self.endian = if obj.is_little_endian() { Endian::Little } else { Endian::Big };
+            self.functions = function_symbols(&obj);
+        }
         self.debug_info = Some(debug_info);
         self.elf_data = Some(data);
         log::info!("Loaded symbols from {}", path.display());
@@ -59,7 +224,7 @@ impl SymbolManager {
 
         // Convert TypedPathBuf to PathBuf via string representation
         let path_str = location.path.to_string_lossy().to_string();
-        let file = PathBuf::from(path_str);
+        let file = self.source_map.map(Path::new(&path_str));
 
         Some(SourceInfo {
             file,
@@ -74,10 +239,79 @@ impl SymbolManager {
                 #[cfg(not(feature = "hardware"))]
                 crate::probe_rs_debug::ColumnType::LeftEdge => 0,
             }),
-            function: None, // Function name not easily accessible without unwinding
+            function: self.function_at(address),
         })
     }
 
+    /// Name of the function containing `address`, from the ELF symbol table.
+    pub fn function_at(&self, address: u64) -> Option<String> {
+        self.functions
+            .iter()
+            .find(|(range, _)| range.contains(&address))
+            .map(|(_, name)| name.clone())
+    }
+
+    /// Start address of the function `name`, without the Thumb bit.
+    pub fn function_address(&self, name: &str) -> Option<u64> {
+        self.function_address_matching(|symbol| symbol == name)
+    }
+
+    /// Start address of the first function whose raw (possibly mangled) symbol
+    /// name satisfies `matches`, without the Thumb bit.
+    pub fn function_address_matching(&self, matches: impl Fn(&str) -> bool) -> Option<u64> {
+        let data = self.elf_data.as_ref()?;
+        let obj = object::File::parse(&**data).ok()?;
+        let thumb = obj.architecture() == object::Architecture::Arm;
+
+        obj.symbols()
+            .filter(|symbol| symbol.kind() == object::SymbolKind::Text)
+            .find(|symbol| symbol.name().is_ok_and(&matches))
+            .map(|symbol| if thumb { symbol.address() & !1 } else { symbol.address() })
+    }
+
+    /// Code range `[low_pc, high_pc)` of the DWARF subprogram containing `address`.
+    pub fn function_range(&self, address: u64) -> Option<std::ops::Range<u64>> {
+        let data = self.elf_data.as_ref()?;
+        let obj = object::File::parse(&**data).ok()?;
+        let endian =
+            if obj.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
+
+        let debug_info_data = obj.section_by_name(".debug_info")?.uncompressed_data().ok()?;
+        let debug_info = gimli::DebugInfo::new(&debug_info_data, endian);
+        let debug_abbrev_data = obj.section_by_name(".debug_abbrev")?.uncompressed_data().ok()?;
+        let debug_abbrev = gimli::DebugAbbrev::new(&debug_abbrev_data, endian);
+
+        let mut units = debug_info.units();
+        while let Ok(Some(header)) = units.next() {
+            let Ok(abbrev) = header.abbreviations(&debug_abbrev) else {
+                continue;
+            };
+            let mut entries = header.entries(&abbrev);
+            while let Ok(Some((_, entry))) = entries.next_dfs() {
+                if entry.tag() != gimli::DW_TAG_subprogram {
+                    continue;
+                }
+                let Ok(Some(AttributeValue::Addr(low))) = entry.attr_value(gimli::DW_AT_low_pc)
+                else {
+                    continue;
+                };
+                // DWARF 4+ usually encodes high_pc as a length from low_pc.
+                let high = match entry.attr_value(gimli::DW_AT_high_pc) {
+                    Ok(Some(AttributeValue::Addr(high))) => high,
+                    Ok(Some(attr)) => match attr.udata_value() {
+                        Some(len) => low + len,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                if (low..high).contains(&address) {
+                    return Some(low..high);
+                }
+            }
+        }
+        None
+    }
+
     pub fn has_symbols(&self) -> bool {
         self.debug_info.is_some()
     }
@@ -91,94 +325,218 @@ impl SymbolManager {
     }
 
     /// Map a source location to a program counter address.
+    ///
+    /// `target_file` is matched against the line tables' files by trailing path
+    /// components, so `app/util.c` does not pick `lib/util.c` and `main.c` does
+    /// not pick `submain.c`. The closest match with code at the line wins.
     pub fn get_address(&self, target_file: &Path, target_line: u32) -> Option<u64> {
+        let target_file = self.source_map.unmap(target_file);
         let data = self.elf_data.as_ref()?;
         let obj = object::File::parse(&**data).ok()?;
         let endian =
             if obj.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
+        let section = |name: &str| {
+            obj.section_by_name(name)
+                .and_then(|s| s.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[]))
+        };
 
-        // Helper to load sections ensuring we don't drop data prematurely
-        // We iterate specifically here rather than using Dwarf::load heavily
-
-        // 1. Get .debug_line section
-        let debug_line_section = obj.section_by_name(".debug_line")?;
-        let debug_line_data = debug_line_section.uncompressed_data().ok()?;
+        let debug_line_data = section(".debug_line");
         let debug_line = gimli::DebugLine::new(&debug_line_data, endian);
-
-        // 2. Iterate units to find line programs.
-        // PROPER WAY: We need to iterate .debug_info to get unit headers, which give us stmt_list offset in .debug_line
-        let debug_info_section = obj.section_by_name(".debug_info")?;
-        let debug_info_data = debug_info_section.uncompressed_data().ok()?;
+        let debug_info_data = section(".debug_info");
         let debug_info = gimli::DebugInfo::new(&debug_info_data, endian);
-
-        let debug_abbrev_section = obj.section_by_name(".debug_abbrev")?;
-        let debug_abbrev_data = debug_abbrev_section.uncompressed_data().ok()?;
+        let debug_abbrev_data = section(".debug_abbrev");
         let debug_abbrev = gimli::DebugAbbrev::new(&debug_abbrev_data, endian);
+        let debug_str_data = section(".debug_str");
+        let debug_str = gimli::DebugStr::new(&debug_str_data, endian);
+        let debug_line_str_data = section(".debug_line_str");
+        let debug_line_str = gimli::DebugLineStr::new(&debug_line_str_data, endian);
 
-        let debug_str_section = obj
-            .section_by_name(".debug_str")
-            .and_then(|s| s.uncompressed_data().ok())
-            .unwrap_or(Cow::Borrowed(&[]));
-        let debug_str = gimli::DebugStr::new(&debug_str_section, endian);
-
-        let mut iter = debug_info.units();
-        while let Ok(Some(header)) = iter.next() {
-            let abbrev = header.abbreviations(&debug_abbrev).ok()?;
+        let string = |value: AttributeValue<EndianSlice<RunTimeEndian>>| {
+            let slice = match value {
+                AttributeValue::String(slice) => Some(slice),
+                AttributeValue::DebugStrRef(offset) => debug_str.get_str(offset).ok(),
+                AttributeValue::DebugLineStrRef(offset) => debug_line_str.get_str(offset).ok(),
+                _ => None,
+            }?;
+            Some(String::from_utf8_lossy(&slice).into_owned())
+        };
 
-            // Get DW_AT_stmt_list
+        // (match score, address) of the best candidate so far.
+        let mut best: Option<(usize, u64)> = None;
+        let mut units = debug_info.units();
+        while let Ok(Some(header)) = units.next() {
+            let Ok(abbrev) = header.abbreviations(&debug_abbrev) else {
+                continue;
+            };
             let mut tree = header.entries(&abbrev);
-            let root = tree.next_dfs().ok()?.map(|(_, node)| node)?;
-            let stmt_list = root.attr_value(gimli::DW_AT_stmt_list).ok()??;
-
-            let offset = match stmt_list {
-                gimli::AttributeValue::DebugLineRef(offset) => offset,
-                _ => continue,
+            let Some(root) = tree.next_dfs().ok().flatten().map(|(_, node)| node) else {
+                continue;
+            };
+            let Ok(Some(AttributeValue::DebugLineRef(offset))) =
+                root.attr_value(gimli::DW_AT_stmt_list)
+            else {
+                continue;
+            };
+            let comp_dir = root
+                .attr_value(gimli::DW_AT_comp_dir)
+                .ok()
+                .flatten()
+                .and_then(string)
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            let Ok(program) = debug_line.program(offset, header.address_size(), None, None) else {
+                continue;
             };
+            let line_header = program.header();
 
-            let program = debug_line.program(offset, header.address_size(), None, None).ok()?;
-            let header = program.header();
-
-            // Check files matches
-            let mut file_idx = None;
-            let file_index_base = if header.version() < 5 { 1 } else { 0 };
-
-            for (i, file_entry) in header.file_names().iter().enumerate() {
-                let name = file_entry.path_name();
-                let name_str_opt = match name {
-                    AttributeValue::String(slice) => String::from_utf8_lossy(&slice).to_string(),
-                    AttributeValue::DebugStrRef(offset) => {
-                        if let Ok(s) = debug_str.get_str(offset) {
-                            String::from_utf8_lossy(&s).to_string()
-                        } else {
-                            continue;
-                        }
-                    }
-                    _ => continue,
+            // Score every file entry of this unit against the target. Rows index
+            // files from 1 before DWARF 5 and from 0 since; `file` handles both.
+            // DWARF 5 repeats the primary file as entries 0 and 1, with names
+            // and directories usually in .debug_line_str.
+            let mut scores = Vec::new();
+            for index in 0..=line_header.file_names().len() as u64 {
+                let Some(entry) = line_header.file(index) else {
+                    continue;
                 };
-
-                // Approximate matching: check if component ends with target_file
-                if target_file.to_string_lossy().ends_with(&name_str_opt) {
-                    file_idx = Some((i as u64) + file_index_base);
-                    break;
+                let Some(name) = string(entry.path_name()) else {
+                    continue;
+                };
+                let dir = entry.directory(line_header).and_then(string).unwrap_or_default();
+                let score = path_match_score(&comp_dir.join(dir).join(name), &target_file);
+                if score > best.map_or(0, |(best, _)| best) {
+                    scores.push((index, score));
                 }
             }
+            if scores.is_empty() {
+                continue;
+            }
 
-            if let Some(idx) = file_idx {
-                // Found file, iterate rows
-                let mut rows = program.rows();
-                while let Ok(Some((_, row))) = rows.next_row() {
-                    if row.is_stmt() && row.file_index() == idx {
-                        if let Some(line) = row.line() {
-                            if line.get() == target_line as u64 {
-                                return Some(row.address());
-                            }
-                        }
-                    }
+            let mut rows = program.rows();
+            while let Ok(Some((_, row))) = rows.next_row() {
+                if !row.is_stmt() || row.line().map(|l| l.get()) != Some(target_line as u64) {
+                    continue;
+                }
+                let Some(&(_, score)) = scores.iter().find(|(idx, _)| *idx == row.file_index())
+                else {
+                    continue;
+                };
+                if score > best.map_or(0, |(best, _)| best) {
+                    best = Some((score, row.address()));
                 }
             }
         }
 
-        None
+        best.map(|(_, address)| address)
+    }
+
+    /// Source text of `file` embedded in the line tables (clang's `-gembed-source`),
+    /// for when the file is not on disk. When several entries share the file
+    /// name, the one whose path matches `file` most closely wins.
+    pub fn embedded_source(&self, file: &Path) -> Option<String> {
+        let file = self.source_map.unmap(file);
+        self.line_table_files()
+            .into_iter()
+            .filter_map(|entry| {
+                let score = path_match_score(&entry.path, &file);
+                Some((score, entry.source?)).filter(|_| score > 0)
+            })
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, source)| source)
+    }
+
+    /// Every source file referenced by the compilation units' line tables,
+    /// sorted, with source mappings applied. Headers appear alongside the
+    /// compiled files.
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .line_table_files()
+            .into_iter()
+            .map(|entry| self.source_map.map(&entry.path))
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// The file entries of all line programs.
+    fn line_table_files(&self) -> Vec<LineTableFile> {
+        let mut files = Vec::new();
+        let Some(data) = self.elf_data.as_ref() else {
+            return files;
+        };
+        let Ok(obj) = object::File::parse(&**data) else {
+            return files;
+        };
+        let endian =
+            if obj.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
+        let section = |name: &str| {
+            obj.section_by_name(name)
+                .and_then(|s| s.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[]))
+        };
+
+        let debug_line_data = section(".debug_line");
+        let debug_line = gimli::DebugLine::new(&debug_line_data, endian);
+        let debug_info_data = section(".debug_info");
+        let debug_info = gimli::DebugInfo::new(&debug_info_data, endian);
+        let debug_abbrev_data = section(".debug_abbrev");
+        let debug_abbrev = gimli::DebugAbbrev::new(&debug_abbrev_data, endian);
+        let debug_str_data = section(".debug_str");
+        let debug_str = gimli::DebugStr::new(&debug_str_data, endian);
+        let debug_line_str_data = section(".debug_line_str");
+        let debug_line_str = gimli::DebugLineStr::new(&debug_line_str_data, endian);
+
+        let string = |value: AttributeValue<EndianSlice<RunTimeEndian>>| {
+            let slice = match value {
+                AttributeValue::String(slice) => Some(slice),
+                AttributeValue::DebugStrRef(offset) => debug_str.get_str(offset).ok(),
+                AttributeValue::DebugLineStrRef(offset) => debug_line_str.get_str(offset).ok(),
+                _ => None,
+            }?;
+            Some(String::from_utf8_lossy(&slice).into_owned())
+        };
+
+        let mut units = debug_info.units();
+        while let Ok(Some(header)) = units.next() {
+            let Ok(abbrev) = header.abbreviations(&debug_abbrev) else {
+                continue;
+            };
+            let mut tree = header.entries(&abbrev);
+            let Some(root) = tree.next_dfs().ok().flatten().map(|(_, node)| node) else {
+                continue;
+            };
+            let Ok(Some(AttributeValue::DebugLineRef(offset))) =
+                root.attr_value(gimli::DW_AT_stmt_list)
+            else {
+                continue;
+            };
+            let comp_dir = root
+                .attr_value(gimli::DW_AT_comp_dir)
+                .ok()
+                .flatten()
+                .and_then(string)
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            let Ok(program) = debug_line.program(offset, header.address_size(), None, None) else {
+                continue;
+            };
+            let line_header = program.header();
+
+            for entry in line_header.file_names() {
+                let Some(name) = string(entry.path_name()) else {
+                    continue;
+                };
+                // Directory 0 is the compilation directory; before DWARF 5 it is implicit.
+                let dir = entry.directory(line_header).and_then(string).unwrap_or_default();
+                files.push(LineTableFile {
+                    path: comp_dir.join(dir).join(&name),
+                    name,
+                    source: entry.source().and_then(string),
+                });
+            }
+        }
+        files
     }
 
     pub fn lookup_symbol(&self, name: &str) -> Option<u64> {
@@ -195,6 +553,56 @@ impl SymbolManager {
         None
     }
 
+    /// Address and size in bytes of a symbol.
+    pub fn lookup_symbol_with_size(&self, name: &str) -> Option<(u64, u64)> {
+        let data = self.elf_data.as_ref()?;
+        let obj = object::File::parse(&**data).ok()?;
+
+        obj.symbols()
+            .find(|symbol| symbol.name() == Ok(name))
+            .map(|symbol| (symbol.address(), symbol.size()))
+    }
+
+    /// Identifier of the loaded build, to check the target runs the same firmware.
+    pub fn build_id(&self) -> Option<String> {
+        self.build_id_with_location().map(|(id, _)| id)
+    }
+
+    /// The build ID and where it sits in target memory, taken from the same
+    /// source so a check reads back what [`Self::build_id`] reports.
+    ///
+    /// Prefers the GNU build-id note (`--build-id`) when it is loaded with the
+    /// image, then a string constant the firmware defines as `AETHER_BUILD_ID`,
+    /// `GIT_HASH` or `BUILD_ID`. A note that is not loaded still gives the ID,
+    /// but no location.
+    pub fn build_id_with_location(&self) -> Option<(String, Option<BuildIdLocation>)> {
+        let data = self.elf_data.as_ref()?;
+        let obj = object::File::parse(&**data).ok()?;
+
+        let note = obj.build_id().ok().flatten();
+        if let (Some(id), Some(section)) = (note, obj.section_by_name(".note.gnu.build-id")) {
+            if section.address() != 0 {
+                let location = BuildIdLocation {
+                    address: section.address() + build_id::GNU_NOTE_HEADER,
+                    len: id.len() as u64,
+                    encoding: BuildIdEncoding::Hex,
+                };
+                return Some((BuildIdEncoding::Hex.decode(id)?, Some(location)));
+            }
+        }
+
+        let constant = BUILD_ID_SYMBOLS.iter().find_map(|name| {
+            let symbol = obj.symbols().find(|symbol| symbol.name() == Ok(name))?;
+            let len = symbol.size().min(build_id::MAX_BUILD_ID_LEN);
+            let section = obj.section_by_index(symbol.section_index()?).ok()?;
+            let bytes = section.data_range(symbol.address(), len).ok()??;
+            let location =
+                BuildIdLocation { address: symbol.address(), len, encoding: BuildIdEncoding::Text };
+            Some((BuildIdEncoding::Text.decode(bytes)?, Some(location)))
+        });
+        constant.or_else(|| Some((BuildIdEncoding::Hex.decode(note?)?, None)))
+    }
+
     pub fn resolve_variable(
         &self,
         #[cfg(feature = "hardware")] core: &mut dyn probe_rs::MemoryInterface,
@@ -202,17 +610,92 @@ impl SymbolManager {
         name: &str,
         base_address: u64,
     ) -> Option<TypeInfo> {
-        let elf_data = self.elf_data.as_ref()?;
-        let obj = object::File::parse(&**elf_data).ok()?;
+        self.resolve_many(core, &[(name, base_address)]).pop().flatten()
+    }
+
+    /// Resolve several global variables with a single symbol table scan and DWARF pass.
+    ///
+    /// The result has one entry per requested name, in order. Names that cannot be
+    /// resolved yield a placeholder with kind "Unknown".
+    pub fn resolve_variables(
+        &self,
+        #[cfg(feature = "hardware")] core: &mut dyn probe_rs::MemoryInterface,
+        #[cfg(not(feature = "hardware"))] core: &mut dyn crate::probe_rs::MemoryInterface,
+        names: &[String],
+    ) -> Vec<TypeInfo> {
+        let addresses = self.lookup_symbols(names);
+        let targets: Vec<(&str, u64)> =
+            names.iter().zip(&addresses).filter_map(|(n, a)| a.map(|a| (n.as_str(), a))).collect();
+        let mut resolved = self.resolve_many(core, &targets).into_iter();
+
+        names
+            .iter()
+            .zip(addresses)
+            .map(|(name, addr)| {
+                addr.and_then(|_| resolved.next().flatten()).unwrap_or_else(|| TypeInfo {
+                    name: name.clone(),
+                    value_formatted_string: "Not Found".to_string(),
+                    kind: "Unknown".to_string(),
+                    members: None,
+                    address: addr,
+                })
+            })
+            .collect()
+    }
+
+    /// Look up the addresses of several symbols in one pass over the symbol table.
+    pub fn lookup_symbols(&self, names: &[String]) -> Vec<Option<u64>> {
+        let mut result = vec![None; names.len()];
+        let Some(data) = self.elf_data.as_ref() else {
+            return result;
+        };
+        let Ok(obj) = object::File::parse(&**data) else {
+            return result;
+        };
+
+        for symbol in obj.symbols() {
+            if let Ok(sym_name) = symbol.name() {
+                for (i, name) in names.iter().enumerate() {
+                    if result[i].is_none() && sym_name == name {
+                        result[i] = Some(symbol.address());
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn resolve_many(
+        &self,
+        #[cfg(feature = "hardware")] core: &mut dyn probe_rs::MemoryInterface,
+        #[cfg(not(feature = "hardware"))] core: &mut dyn crate::probe_rs::MemoryInterface,
+        targets: &[(&str, u64)],
+    ) -> Vec<Option<TypeInfo>> {
+        let mut results: Vec<Option<TypeInfo>> = vec![None; targets.len()];
+        if targets.is_empty() {
+            return results;
+        }
+        let Some(elf_data) = self.elf_data.as_ref() else {
+            return results;
+        };
+        let Ok(obj) = object::File::parse(&**elf_data) else {
+            return results;
+        };
         let endian =
             if obj.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
 
-        let debug_info_section = obj.section_by_name(".debug_info")?;
-        let debug_info_data = debug_info_section.uncompressed_data().ok()?;
+        let Some(debug_info_data) =
+            obj.section_by_name(".debug_info").and_then(|s| s.uncompressed_data().ok())
+        else {
+            return results;
+        };
         let debug_info = gimli::DebugInfo::new(&debug_info_data, endian);
 
-        let debug_abbrev_section = obj.section_by_name(".debug_abbrev")?;
-        let debug_abbrev_data = debug_abbrev_section.uncompressed_data().ok()?;
+        let Some(debug_abbrev_data) =
+            obj.section_by_name(".debug_abbrev").and_then(|s| s.uncompressed_data().ok())
+        else {
+            return results;
+        };
         let debug_abbrev = gimli::DebugAbbrev::new(&debug_abbrev_data, endian);
 
         let debug_str_section = obj
@@ -221,9 +704,12 @@ impl SymbolManager {
             .unwrap_or(Cow::Borrowed(&[]));
         let debug_str = gimli::DebugStr::new(&debug_str_section, endian);
 
+        let mut remaining = targets.len();
         let mut units = debug_info.units();
         while let Ok(Some(header)) = units.next() {
-            let abbrev = header.abbreviations(&debug_abbrev).ok()?;
+            let Ok(abbrev) = header.abbreviations(&debug_abbrev) else {
+                return results;
+            };
             let mut entries = header.entries(&abbrev);
 
             while let Ok(Some((_, entry))) = entries.next_dfs() {
@@ -244,30 +730,40 @@ impl SymbolManager {
                             }
                         });
 
-                    if let Some(en) = entry_name {
-                        if en == name {
-                            if let Ok(Some(AttributeValue::UnitRef(offset))) =
-                                entry.attr_value(gimli::DW_AT_type)
-                            {
-                                if let Some(mut info) = self.resolve_type_from_offset(
-                                    core,
-                                    &header,
-                                    &abbrev,
-                                    &debug_str,
-                                    offset,
-                                    base_address,
-                                    0,
-                                ) {
-                                    info.name = name.to_string();
-                                    return Some(info);
-                                }
+                    let Some(en) = entry_name else {
+                        continue;
+                    };
+                    for (i, &(name, base_address)) in targets.iter().enumerate() {
+                        if results[i].is_some() || en != name {
+                            continue;
+                        }
+                        if let Ok(Some(AttributeValue::UnitRef(offset))) =
+                            entry.attr_value(gimli::DW_AT_type)
+                        {
+                            if let Some(mut info) = self.resolve_type_from_offset(
+                                core,
+                                &header,
+                                &abbrev,
+                                &debug_str,
+                                offset,
+                                base_address,
+                                self.variable_format(name),
+                                self.pointer_depth,
+                                0,
+                            ) {
+                                info.name = name.to_string();
+                                results[i] = Some(info);
+                                remaining -= 1;
                             }
                         }
                     }
+                    if remaining == 0 {
+                        return results;
+                    }
                 }
             }
         }
-        None
+        results
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -280,6 +776,8 @@ impl SymbolManager {
         debug_str: &DebugStr<EndianSlice<RunTimeEndian>>,
         offset: UnitOffset,
         base_address: u64,
+        format: VariableFormat,
+        pointer_levels: usize,
         depth: usize,
     ) -> Option<TypeInfo> {
         if depth > 10 {
@@ -316,20 +814,19 @@ impl SymbolManager {
                     })
                     .unwrap_or(4);
 
+                let encoding = match entry.attr_value(gimli::DW_AT_encoding).ok().flatten() {
+                    Some(AttributeValue::Encoding(encoding)) => Some(encoding),
+                    _ => None,
+                };
+
                 let mut data = vec![0u8; size as usize];
                 let value_str = if core.read(base_address, &mut data).is_ok() {
                     match size {
-                        1 => format!("{}", data[0]),
-                        2 => format!("{}", u16::from_le_bytes([data[0], data[1]])),
-                        4 => {
-                            format!("{}", u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
-                        }
-                        8 => format!(
-                            "{}",
-                            u64::from_le_bytes([
-                                data[0], data[1], data[2], data[3], data[4], data[5], data[6],
-                                data[7]
-                            ])
+                        1 | 2 | 4 | 8 => format_base_value(
+                            self.endian.read_uint(&data),
+                            data.len(),
+                            encoding,
+                            format,
                         ),
                         _ => format!("0x{:X}", base_address),
                     }
@@ -394,9 +891,24 @@ impl SymbolManager {
                                 debug_str,
                                 type_off,
                                 base_address + member_offset,
+                                format,
+                                pointer_levels,
                                 depth + 1,
                             ) {
                                 member_info.name = member_name;
+                                if let Some((bit_offset, bit_size)) = Self::bitfield_layout(child) {
+                                    member_info.value_formatted_string = read_bitfield(
+                                        core,
+                                        base_address,
+                                        member_offset * 8 + bit_offset,
+                                        bit_size,
+                                    )
+                                    .map_or_else(
+                                        || "Error Reading".to_string(),
+                                        |v| format.format(v),
+                                    );
+                                    member_info.address = Some(base_address + member_offset);
+                                }
                                 members.push(member_info);
                             }
                         }
@@ -470,6 +982,8 @@ impl SymbolManager {
                                                 debug_str,
                                                 type_off,
                                                 base_address,
+                                                format,
+                                                pointer_levels,
                                                 depth + 1,
                                             ) {
                                                 m_info.name = v_member_name;
@@ -488,7 +1002,7 @@ impl SymbolManager {
                         if let Some(addr) = len_member.address {
                             let mut data = [0u8; 8];
                             if core.read(addr, &mut data).is_ok() {
-                                let len = u64::from_le_bytes(data); // Assume LE for now, should use endian
+                                let len = self.endian.read_uint(&data);
                                 return Some(TypeInfo {
                                     name: type_name.clone(),
                                     value_formatted_string: format!("Vec (len: {})", len),
@@ -513,13 +1027,95 @@ impl SymbolManager {
                     address: Some(base_address),
                 })
             }
-            gimli::DW_TAG_pointer_type => Some(TypeInfo {
-                name: format!("*{}", type_name),
-                value_formatted_string: format!("0x{:X}", base_address),
-                kind: "Pointer".to_string(),
-                members: None,
-                address: Some(base_address),
-            }),
+            gimli::DW_TAG_pointer_type => {
+                let size = entry
+                    .attr_value(gimli::DW_AT_byte_size)
+                    .ok()
+                    .flatten()
+                    .and_then(|v| match v {
+                        AttributeValue::Udata(s) => Some(s),
+                        _ => None,
+                    })
+                    .unwrap_or(4);
+
+                let mut data = [0u8; 8];
+                let len = if size == 8 { 8 } else { 4 };
+                let target = if core.read(base_address, &mut data[..len]).is_ok() {
+                    Some(self.endian.read_uint(&data[..len]))
+                } else {
+                    None
+                };
+
+                // Dereference while levels remain; null is skipped.
+                let mut members = None;
+                if let (true, Some(ptr)) = (pointer_levels > 0, target) {
+                    if let Ok(Some(AttributeValue::UnitRef(type_off))) =
+                        entry.attr_value(gimli::DW_AT_type)
+                    {
+                        if ptr != 0 {
+                            if let Some(mut pointee) = self.resolve_type_from_offset(
+                                core,
+                                header,
+                                abbrev,
+                                debug_str,
+                                type_off,
+                                ptr,
+                                format,
+                                pointer_levels - 1,
+                                depth + 1,
+                            ) {
+                                pointee.name = "*".to_string();
+                                members = Some(vec![pointee]);
+                            }
+                        }
+                    }
+                }
+
+                let pointee_is_char = match entry.attr_value(gimli::DW_AT_type) {
+                    Ok(Some(AttributeValue::UnitRef(type_off))) => {
+                        Self::is_char_type(header, abbrev, debug_str, type_off, depth)
+                    }
+                    _ => false,
+                };
+
+                // The raw address stays available through the pointee node.
+                Some(TypeInfo {
+                    name: format!("*{}", type_name),
+                    value_formatted_string: match target {
+                        Some(0) => "NULL".to_string(),
+                        Some(ptr) if pointee_is_char => read_c_string(core, ptr, MAX_STRING_LEN)
+                            .unwrap_or_else(|| format!("0x{:X}", ptr)),
+                        Some(ptr) => format!("0x{:X}", ptr),
+                        None => "Error Reading".to_string(),
+                    },
+                    kind: "Pointer".to_string(),
+                    members,
+                    address: Some(base_address),
+                })
+            }
+            gimli::DW_TAG_array_type => {
+                let element_is_char = match entry.attr_value(gimli::DW_AT_type) {
+                    Ok(Some(AttributeValue::UnitRef(type_off))) => {
+                        Self::is_char_type(header, abbrev, debug_str, type_off, depth)
+                    }
+                    _ => false,
+                };
+                let value_str = if element_is_char {
+                    let len = Self::array_len(header, abbrev, offset).unwrap_or(MAX_STRING_LEN);
+                    read_c_string(core, base_address, len.min(MAX_STRING_LEN))
+                        .unwrap_or_else(|| "Error Reading".to_string())
+                } else {
+                    format!("0x{:X}", base_address)
+                };
+
+                Some(TypeInfo {
+                    name: type_name,
+                    value_formatted_string: value_str,
+                    kind: "Array".to_string(),
+                    members: None,
+                    address: Some(base_address),
+                })
+            }
             gimli::DW_TAG_enumeration_type => {
                 // Handle Option/Result discriminants if they look like it
                 Some(TypeInfo {
@@ -541,6 +1137,8 @@ impl SymbolManager {
                         debug_str,
                         type_off,
                         base_address,
+                        format,
+                        pointer_levels,
                         depth,
                     )
                 } else {
@@ -556,6 +1154,165 @@ impl SymbolManager {
             }),
         }
     }
+
+    /// Whether the type at `offset` is a single-byte character (`char`, `unsigned char`, `u8`),
+    /// looking through typedefs and qualifiers.
+    fn is_char_type(
+        header: &gimli::UnitHeader<EndianSlice<RunTimeEndian>>,
+        abbrev: &Abbreviations,
+        debug_str: &DebugStr<EndianSlice<RunTimeEndian>>,
+        offset: UnitOffset,
+        depth: usize,
+    ) -> bool {
+        if depth > 10 {
+            return false;
+        }
+        let Ok(mut entries) = header.entries_at_offset(abbrev, offset) else {
+            return false;
+        };
+        let Ok(Some((_, entry))) = entries.next_dfs() else {
+            return false;
+        };
+
+        match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                let size = entry.attr_value(gimli::DW_AT_byte_size).ok().flatten();
+                if !matches!(size, Some(AttributeValue::Udata(1))) {
+                    return false;
+                }
+                match entry.attr_value(gimli::DW_AT_encoding).ok().flatten() {
+                    Some(AttributeValue::Encoding(gimli::DW_ATE_signed_char))
+                    | Some(AttributeValue::Encoding(gimli::DW_ATE_unsigned_char)) => true,
+                    // Rust describes u8 as a plain unsigned integer.
+                    Some(AttributeValue::Encoding(gimli::DW_ATE_unsigned)) => {
+                        match entry.attr_value(gimli::DW_AT_name).ok().flatten() {
+                            Some(AttributeValue::String(slice)) => slice.slice() == b"u8",
+                            Some(AttributeValue::DebugStrRef(off)) => {
+                                debug_str.get_str(off).map(|s| s.slice() == b"u8").unwrap_or(false)
+                            }
+                            _ => false,
+                        }
+                    }
+                    _ => false,
+                }
+            }
+            gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type | gimli::DW_TAG_typedef => {
+                match entry.attr_value(gimli::DW_AT_type) {
+                    Ok(Some(AttributeValue::UnitRef(type_off))) => {
+                        Self::is_char_type(header, abbrev, debug_str, type_off, depth + 1)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Bit position (relative to the member location, counted from the LSB) and width of a
+    /// bitfield member, or `None` for ordinary members.
+    ///
+    /// Handles both `DW_AT_data_bit_offset` (DWARF 4+) and the older `DW_AT_bit_offset`, which
+    /// counts from the MSB of a `DW_AT_byte_size` storage unit.
+    fn bitfield_layout(
+        member: &gimli::DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+    ) -> Option<(u64, u64)> {
+        let attr = |name| member.attr_value(name).ok().flatten().and_then(|v| v.udata_value());
+        let bit_size = attr(gimli::DW_AT_bit_size)?;
+        if let Some(data_bit_offset) = attr(gimli::DW_AT_data_bit_offset) {
+            return Some((data_bit_offset, bit_size));
+        }
+        let bit_offset = attr(gimli::DW_AT_bit_offset).unwrap_or(0);
+        let storage_bits = attr(gimli::DW_AT_byte_size).unwrap_or(4) * 8;
+        Some((storage_bits.checked_sub(bit_offset + bit_size)?, bit_size))
+    }
+
+    /// Element count of a one-dimensional array type, from its subrange.
+    fn array_len(
+        header: &gimli::UnitHeader<EndianSlice<RunTimeEndian>>,
+        abbrev: &Abbreviations,
+        offset: UnitOffset,
+    ) -> Option<usize> {
+        let mut children = header.entries_at_offset(abbrev, offset).ok()?;
+        children.next_dfs().ok()?; // Skip self
+
+        let (delta, child) = children.next_dfs().ok()??;
+        if delta != 1 || child.tag() != gimli::DW_TAG_subrange_type {
+            return None;
+        }
+        if let Some(count) = child.attr_value(gimli::DW_AT_count).ok().flatten() {
+            return count.udata_value().map(|c| c as usize);
+        }
+        let upper = child.attr_value(gimli::DW_AT_upper_bound).ok().flatten()?;
+        upper.udata_value().map(|u| u as usize + 1)
+    }
+}
+
+/// Read `bit_size` bits starting `bit_offset` bits (from the LSB) past `address`.
+fn read_bitfield(
+    #[cfg(feature = "hardware")] core: &mut dyn probe_rs::MemoryInterface,
+    #[cfg(not(feature = "hardware"))] core: &mut dyn crate::probe_rs::MemoryInterface,
+    address: u64,
+    bit_offset: u64,
+    bit_size: u64,
+) -> Option<u64> {
+    if bit_size == 0 || bit_size > 64 {
+        return None;
+    }
+    let first_byte = bit_offset / 8;
+    let shift = bit_offset % 8;
+    let len = (shift + bit_size).div_ceil(8) as usize;
+    let mut data = [0u8; 9];
+    core.read(address + first_byte, &mut data[..len]).ok()?;
+
+    let raw = data.iter().rev().fold(0u128, |acc, &b| (acc << 8) | u128::from(b));
+    let mask = (1u128 << bit_size) - 1;
+    Some(((raw >> shift) & mask) as u64)
+}
+
+/// Format the `size`-byte value `raw` of a base type with DWARF `encoding`.
+/// [`VariableFormat::Natural`] follows the encoding (signed, float, boolean);
+/// the other formats show the raw bits.
+fn format_base_value(
+    raw: u64,
+    size: usize,
+    encoding: Option<gimli::DwAte>,
+    format: VariableFormat,
+) -> String {
+    if format != VariableFormat::Natural {
+        return format.format(raw);
+    }
+    match encoding {
+        Some(gimli::DW_ATE_signed) | Some(gimli::DW_ATE_signed_char) => {
+            let shift = 64 - 8 * size as u32;
+            (((raw << shift) as i64) >> shift).to_string()
+        }
+        Some(gimli::DW_ATE_float) if size == 4 => f32::from_bits(raw as u32).to_string(),
+        Some(gimli::DW_ATE_float) if size == 8 => f64::from_bits(raw).to_string(),
+        Some(gimli::DW_ATE_boolean) => (raw != 0).to_string(),
+        _ => raw.to_string(),
+    }
+}
+
+/// Read a NUL-terminated string of at most `max_len` bytes and format it as a quoted literal.
+fn read_c_string(
+    #[cfg(feature = "hardware")] core: &mut dyn probe_rs::MemoryInterface,
+    #[cfg(not(feature = "hardware"))] core: &mut dyn crate::probe_rs::MemoryInterface,
+    address: u64,
+    max_len: usize,
+) -> Option<String> {
+    const CHUNK: usize = 32;
+    let mut bytes = Vec::new();
+    let mut chunk = [0u8; CHUNK];
+    while bytes.len() < max_len {
+        let n = CHUNK.min(max_len - bytes.len());
+        core.read(address + bytes.len() as u64, &mut chunk[..n]).ok()?;
+        if let Some(end) = chunk[..n].iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&chunk[..end]);
+            return Some(format!("{:?}", String::from_utf8_lossy(&bytes)));
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+    }
+    Some(format!("{:?}...", String::from_utf8_lossy(&bytes)))
 }
 
 impl Default for SymbolManager {
@@ -1,10 +1,16 @@
 #[cfg(not(feature = "hardware"))]
 use crate::probe_rs::Session;
+use crate::svd::RegisterInfo;
 use anyhow::Result;
 #[cfg(feature = "hardware")]
 use probe_rs::Session;
 use serde::{Deserialize, Serialize};
 
+/// HSI oscillator frequency on STM32F2/F4/F7 parts.
+pub const STM32_HSI_HZ: u32 = 16_000_000;
+/// Assumed HSE crystal frequency (8 MHz on most ST evaluation boards).
+pub const DEFAULT_HSE_HZ: u32 = 8_000_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TraceConfig {
     pub core_frequency: u32,
@@ -57,9 +63,113 @@ impl TraceManager {
     }
 }
 
+fn rcc_field(regs: &[RegisterInfo], register: &str, field: &str) -> Option<u32> {
+    let reg = regs.iter().find(|r| r.name == register)?;
+    let value = reg.value?;
+    let field = reg.fields.iter().find(|f| f.name == field)?;
+    u32::try_from(field.decode(value)).ok()
+}
+
+/// Derive the core clock (HCLK) from STM32F2/F4/F7-style RCC register values.
+///
+/// Follows `CFGR.SWS` to the HSI, HSE or main PLL (`PLLCFGR.PLLM/PLLN/PLLP/PLLSRC`)
+/// and applies the AHB prescaler `CFGR.HPRE`. Returns `None` if the SVD does not
+/// describe these fields or the values are inconsistent.
+pub fn core_clock_from_rcc(regs: &[RegisterInfo], hse_hz: u32) -> Option<u32> {
+    let sysclk = match rcc_field(regs, "CFGR", "SWS")? {
+        0 => u64::from(STM32_HSI_HZ),
+        1 => u64::from(hse_hz),
+        2 => {
+            let src =
+                if rcc_field(regs, "PLLCFGR", "PLLSRC")? == 1 { hse_hz } else { STM32_HSI_HZ };
+            let m = u64::from(rcc_field(regs, "PLLCFGR", "PLLM")?);
+            let n = u64::from(rcc_field(regs, "PLLCFGR", "PLLN")?);
+            let p = 2 * (u64::from(rcc_field(regs, "PLLCFGR", "PLLP")?) + 1);
+            if m == 0 {
+                return None;
+            }
+            u64::from(src) / m * n / p
+        }
+        _ => return None,
+    };
+
+    // HPRE: 0xxx = /1, 1000 = /2 ... 1111 = /512 (/32 is skipped).
+    let hpre = rcc_field(regs, "CFGR", "HPRE")?;
+    let shift = match hpre {
+        0..=7 => 0,
+        8..=11 => hpre - 7,
+        _ => hpre - 6,
+    };
+    u32::try_from(sysclk >> shift).ok()
+}
+
+/// Estimate the core clock by reading the RCC peripheral described by the loaded SVD.
+#[cfg(feature = "hardware")]
+pub fn detect_core_frequency(
+    svd: &crate::svd::SvdManager,
+    core: &mut probe_rs::Core,
+) -> Option<u32> {
+    let regs = svd.read_peripheral_values("RCC", core).ok()?;
+    core_clock_from_rcc(&regs, DEFAULT_HSE_HZ)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::svd::FieldInfo;
+
+    fn register(name: &str, value: u64, fields: &[(&str, u32, u32)]) -> RegisterInfo {
+        RegisterInfo {
+            name: name.to_string(),
+            address_offset: 0,
+            description: None,
+            size: 32,
+            reset_value: None,
+            write_one_to_clear: false,
+            fields: fields
+                .iter()
+                .map(|&(name, bit_offset, bit_width)| FieldInfo {
+                    name: name.to_string(),
+                    description: None,
+                    bit_offset,
+                    bit_width,
+                    write_one_to_clear: false,
+                })
+                .collect(),
+            value: Some(value),
+        }
+    }
+
+    /// RCC registers as described by the STM32F407 SVD.
+    fn stm32f4_rcc(cfgr: u64, pllcfgr: u64) -> Vec<RegisterInfo> {
+        vec![
+            register("CFGR", cfgr, &[("SW", 0, 2), ("SWS", 2, 2), ("HPRE", 4, 4)]),
+            register(
+                "PLLCFGR",
+                pllcfgr,
+                &[("PLLM", 0, 6), ("PLLN", 6, 9), ("PLLP", 16, 2), ("PLLSRC", 22, 1)],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_core_clock_from_rcc() {
+        // 8 MHz HSE / M=8 * N=336 / P=2 = 168 MHz, PLL selected, AHB /1.
+        let pllcfgr = 8 | (336 << 6) | (1 << 22);
+        let regs = stm32f4_rcc(0b1010, pllcfgr);
+        assert_eq!(core_clock_from_rcc(&regs, DEFAULT_HSE_HZ), Some(168_000_000));
+
+        // Same PLL with AHB /2 (HPRE = 1000).
+        let regs = stm32f4_rcc(0b1000_1010, pllcfgr);
+        assert_eq!(core_clock_from_rcc(&regs, DEFAULT_HSE_HZ), Some(84_000_000));
+
+        // Reset state runs from HSI.
+        let regs = stm32f4_rcc(0, 0x2400_3010);
+        assert_eq!(core_clock_from_rcc(&regs, DEFAULT_HSE_HZ), Some(STM32_HSI_HZ));
+
+        // Chips whose SVD lacks these fields are not guessed.
+        assert_eq!(core_clock_from_rcc(&[register("CFGR", 0, &[])], DEFAULT_HSE_HZ), None);
+    }
 
     #[test]
     fn test_trace_manager_initial_state() {
@@ -0,0 +1,318 @@
+//! Live watch module.
+//!
+//! Schedules periodic re-resolution of watched variables, re-reads of the
+//! selected peripheral and of watched memory-mapped registers, relying on
+//! background memory access through the debug port.
+
+use crate::symbols::TypeInfo;
+use crate::DebugCommand;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// Default interval between two live watch refreshes.
+pub const DEFAULT_LIVE_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// Interval between two reads of the watched registers.
+pub const REGISTER_WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tracks the watched variables and when they were last refreshed.
+#[derive(Debug)]
+pub struct LiveWatch {
+    enabled: bool,
+    interval: Duration,
+    names: Vec<String>,
+    last_poll: Option<Instant>,
+}
+
+impl Default for LiveWatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveWatch {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            interval: DEFAULT_LIVE_WATCH_INTERVAL,
+            names: Vec::new(),
+            last_poll: None,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.last_poll = None;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Add a variable to the watch list.
+    pub fn watch(&mut self, name: &str) {
+        if !self.names.iter().any(|n| n == name) {
+            self.names.push(name.to_string());
+        }
+    }
+
+    /// Replace the watch list.
+    pub fn set_names(&mut self, names: &[String]) {
+        self.names = names.to_vec();
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Resolve the watched variables if live watch is enabled, the core is
+    /// running and the poll interval has elapsed.
+    ///
+    /// Halted cores are skipped since watches are refreshed on halt anyway.
+    pub fn poll<F>(&mut self, now: Instant, halted: bool, resolve: F) -> Option<Vec<TypeInfo>>
+    where
+        F: FnOnce(&[String]) -> Vec<TypeInfo>,
+    {
+        if !self.enabled || halted || self.names.is_empty() {
+            return None;
+        }
+        if let Some(last) = self.last_poll {
+            if now.duration_since(last) < self.interval {
+                return None;
+            }
+        }
+        self.last_poll = Some(now);
+        Some(resolve(&self.names))
+    }
+}
+
+/// Periodically re-reads one peripheral's registers.
+#[derive(Debug, Default)]
+pub struct PeripheralPoll {
+    peripheral: Option<String>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl PeripheralPoll {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Poll `peripheral` every `interval`, or stop polling with `None`.
+    pub fn configure(&mut self, peripheral: Option<String>, interval: Duration) {
+        self.peripheral = peripheral;
+        self.interval = interval;
+        self.last_poll = None;
+    }
+
+    pub fn peripheral(&self) -> Option<&str> {
+        self.peripheral.as_deref()
+    }
+
+    /// Returns the read command to run when the interval has elapsed.
+    pub fn poll(&mut self, now: Instant) -> Option<DebugCommand> {
+        let peripheral = self.peripheral.as_ref()?;
+        if let Some(last) = self.last_poll {
+            if now.duration_since(last) < self.interval {
+                return None;
+            }
+        }
+        self.last_poll = Some(now);
+        Some(DebugCommand::ReadPeripheralValues(peripheral.clone()))
+    }
+}
+
+/// A change seen on a watched register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub address: u64,
+    pub old: u64,
+    pub new: u64,
+}
+
+#[derive(Debug)]
+struct WatchedRegister {
+    address: u64,
+    size: usize,
+    last: Option<u64>,
+}
+
+/// Memory-mapped registers re-read periodically, reporting only changes.
+#[derive(Debug, Default)]
+pub struct RegisterWatch {
+    registers: Vec<WatchedRegister>,
+    last_poll: Option<Instant>,
+}
+
+impl RegisterWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch `size` bytes (1, 2, 4 or 8) at `address`. Watching an address again
+    /// replaces its size.
+    pub fn watch(&mut self, address: u64, size: usize) -> Result<()> {
+        if !matches!(size, 1 | 2 | 4 | 8) {
+            anyhow::bail!("Unsupported register size {} (expected 1, 2, 4 or 8)", size);
+        }
+        self.unwatch(address);
+        self.registers.push(WatchedRegister { address, size, last: None });
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, address: u64) {
+        self.registers.retain(|r| r.address != address);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+
+    /// Re-read the watched registers with `read(address, buf)` once the interval
+    /// has elapsed. The first read of a register only records its value; after
+    /// that, only values that differ from the previous read are returned. Failed
+    /// reads are skipped.
+    pub fn poll<F>(&mut self, now: Instant, mut read: F) -> Vec<RegisterChange>
+    where
+        F: FnMut(u64, &mut [u8]) -> Result<()>,
+    {
+        if self.registers.is_empty() {
+            return Vec::new();
+        }
+        if let Some(last) = self.last_poll {
+            if now.duration_since(last) < REGISTER_WATCH_INTERVAL {
+                return Vec::new();
+            }
+        }
+        self.last_poll = Some(now);
+
+        let mut changes = Vec::new();
+        for reg in &mut self.registers {
+            let mut buf = [0u8; 8];
+            if read(reg.address, &mut buf[..reg.size]).is_err() {
+                continue;
+            }
+            let new = u64::from_le_bytes(buf);
+            match reg.last.replace(new) {
+                Some(old) if old != new => {
+                    changes.push(RegisterChange { address: reg.address, old, new })
+                }
+                _ => {}
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn fake_resolve(names: &[String]) -> Vec<TypeInfo> {
+        names
+            .iter()
+            .map(|n| TypeInfo {
+                name: n.clone(),
+                value_formatted_string: "42".to_string(),
+                kind: "Primitive".to_string(),
+                members: None,
+                address: Some(0x2000_0000),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_live_watch_polls_periodically_while_running() {
+        let mut live = LiveWatch::new();
+        live.watch("counter");
+        let start = Instant::now();
+
+        // Disabled: nothing is resolved.
+        assert!(live.poll(start, false, fake_resolve).is_none());
+
+        live.set_enabled(true);
+        let first = live.poll(start, false, fake_resolve).unwrap();
+        assert_eq!(first[0].name, "counter");
+
+        // Within the interval nothing is resolved.
+        assert!(live.poll(start, false, |_| panic!("polled too early")).is_none());
+
+        // Next interval resolves again.
+        let later = start + DEFAULT_LIVE_WATCH_INTERVAL;
+        assert!(live.poll(later, false, fake_resolve).is_some());
+
+        // Halted cores are left to the halt-time refresh.
+        let halted_later = later + DEFAULT_LIVE_WATCH_INTERVAL;
+        assert!(live.poll(halted_later, true, fake_resolve).is_none());
+    }
+
+    #[test]
+    fn test_register_watch_reports_only_changes() {
+        let mut watch = RegisterWatch::new();
+        assert!(watch.watch(0x4002_0014, 3).is_err());
+        watch.watch(0x4002_0014, 4).unwrap();
+        watch.watch(0x4002_0010, 2).unwrap();
+
+        // Mock memory: ODR and IDR of a GPIO port.
+        let mut memory = HashMap::from([(0x4002_0014u64, 0x0000_0001u64), (0x4002_0010, 0x00FF)]);
+        fn read_from(memory: &HashMap<u64, u64>) -> impl FnMut(u64, &mut [u8]) -> Result<()> + '_ {
+            |address, buf| {
+                let len = buf.len();
+                buf.copy_from_slice(&memory[&address].to_le_bytes()[..len]);
+                Ok(())
+            }
+        }
+        let start = Instant::now();
+
+        // The first read only establishes the baseline.
+        assert!(watch.poll(start, read_from(&memory)).is_empty());
+
+        // Unchanged values are deduplicated.
+        let t1 = start + REGISTER_WATCH_INTERVAL;
+        assert!(watch.poll(t1, read_from(&memory)).is_empty());
+
+        memory.insert(0x4002_0014, 0x0000_0003);
+        // Within the interval nothing is read.
+        assert!(watch.poll(t1, |_, _| panic!("read too early")).is_empty());
+
+        let t2 = t1 + REGISTER_WATCH_INTERVAL;
+        assert_eq!(
+            watch.poll(t2, read_from(&memory)),
+            vec![RegisterChange { address: 0x4002_0014, old: 1, new: 3 }]
+        );
+
+        let t3 = t2 + REGISTER_WATCH_INTERVAL;
+        assert!(watch.poll(t3, read_from(&memory)).is_empty());
+
+        watch.unwatch(0x4002_0014);
+        watch.unwatch(0x4002_0010);
+        assert!(watch.is_empty());
+    }
+
+    #[test]
+    fn test_peripheral_poll_repeats_reads() {
+        let mut poll = PeripheralPoll::new();
+        let start = Instant::now();
+        assert!(poll.poll(start).is_none());
+
+        let interval = Duration::from_millis(100);
+        poll.configure(Some("GPIOA".to_string()), interval);
+        let reads: Vec<_> = (0..5)
+            .filter_map(|i| poll.poll(start + interval * i / 2))
+            .map(|cmd| match cmd {
+                DebugCommand::ReadPeripheralValues(name) => name,
+                other => panic!("unexpected command {:?}", other),
+            })
+            .collect();
+        // Reads at 0ms, 100ms and 200ms; 50ms and 150ms are within the interval.
+        assert_eq!(reads, vec!["GPIOA", "GPIOA", "GPIOA"]);
+
+        poll.configure(None, interval);
+        assert!(poll.poll(start + interval * 10).is_none());
+    }
+}
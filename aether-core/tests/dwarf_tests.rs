@@ -1,12 +1,28 @@
-use aether_core::symbols::SymbolManager;
+use aether_core::symbols::{SymbolManager, TypeInfo, VariableFormat};
+use aether_core::watch::{LiveWatch, DEFAULT_LIVE_WATCH_INTERVAL};
 use probe_rs::MemoryInterface;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Instant;
+
+/// Sparse target memory; unset bytes read as zero.
+#[derive(Default)]
+struct MockMemory {
+    bytes: HashMap<u64, u8>,
+}
+
+impl MockMemory {
+    fn write_le(&mut self, address: u64, data: &[u8]) {
+        for (i, b) in data.iter().enumerate() {
+            self.bytes.insert(address + i as u64, *b);
+        }
+    }
+}
 
-struct MockMemory;
 impl MemoryInterface for MockMemory {
-    fn read(&mut self, _address: u64, data: &mut [u8]) -> Result<(), probe_rs::Error> {
-        for b in data.iter_mut() {
-            *b = 0;
+    fn read(&mut self, address: u64, data: &mut [u8]) -> Result<(), probe_rs::Error> {
+        for (i, b) in data.iter_mut().enumerate() {
+            *b = self.bytes.get(&(address + i as u64)).copied().unwrap_or(0);
         }
         Ok(())
     }
@@ -59,7 +75,7 @@ impl MemoryInterface for MockMemory {
 
 #[test]
 fn test_dwarf_nested_resolution() {
-    let mut core = MockMemory;
+    let mut core = MockMemory::default();
     let mut symbol_manager = SymbolManager::new();
     let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     elf_path.push("tests/fixtures/complex_types.elf");
@@ -102,6 +118,43 @@ fn test_dwarf_nested_resolution() {
     assert!(deep_members.iter().any(|m| m.name == "b"));
 }
 
+#[test]
+fn test_live_watch_poll_reads_target_values() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    let mut symbol_manager = SymbolManager::new();
+    symbol_manager.load_elf(&elf_path).expect("Failed to load elf");
+
+    let addr = symbol_manager.lookup_symbol("target_value").expect("target_value not found");
+    let mut core = MockMemory::default();
+    core.write_le(addr, &7i32.to_le_bytes());
+
+    let mut live = LiveWatch::new();
+    live.set_enabled(true);
+    live.set_names(&["target_value".to_string(), "NOT_A_SYMBOL".to_string()]);
+    let start = Instant::now();
+
+    // One poll resolves every watch in a single batch.
+    let infos = live
+        .poll(start, false, |names| symbol_manager.resolve_variables(&mut core, names))
+        .expect("live watch did not poll");
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].name, "target_value");
+    assert_eq!(infos[0].value_formatted_string, "7");
+    assert_eq!(infos[0].address, Some(addr));
+    assert_eq!(infos[1].name, "NOT_A_SYMBOL");
+    assert_eq!(infos[1].kind, "Unknown");
+
+    // The next poll sees what the running target wrote since.
+    core.write_le(addr, &8i32.to_le_bytes());
+    let infos = live
+        .poll(start + DEFAULT_LIVE_WATCH_INTERVAL, false, |names| {
+            symbol_manager.resolve_variables(&mut core, names)
+        })
+        .expect("live watch did not poll");
+    assert_eq!(infos[0].value_formatted_string, "8");
+}
+
 #[test]
 fn test_dwarf_recursion_safety() {
     // This is more of a safety check. Currently our resolver has a depth limit of 10.
@@ -114,7 +167,7 @@ fn test_dwarf_recursion_safety() {
 
 #[test]
 fn test_dwarf_rust_vec_resolution() {
-    let mut core = MockMemory;
+    let mut core = MockMemory::default();
     let mut symbol_manager = SymbolManager::new();
     let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     elf_path.push("tests/fixtures/rust_types.elf");
@@ -133,7 +186,7 @@ fn test_dwarf_rust_vec_resolution() {
 
 #[test]
 fn test_dwarf_rust_option_resolution() {
-    let mut core = MockMemory;
+    let mut core = MockMemory::default();
     let mut symbol_manager = SymbolManager::new();
     let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     elf_path.push("tests/fixtures/rust_types.elf");
@@ -157,3 +210,386 @@ fn test_dwarf_rust_option_resolution() {
     assert!(members.iter().any(|m| m.name == "None"), "None variant missing");
     assert!(members.iter().any(|m| m.name == "Some"), "Some variant missing");
 }
+
+#[test]
+fn test_dwarf_big_endian_decode() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+    assert_eq!(manager.endian(), aether_core::Endian::Little);
+
+    let ptr_addr = manager.lookup_symbol("p_value").expect("p_value not found");
+    let mut core = MockMemory::default();
+    core.write_le(ptr_addr, &0x2000_0000u64.to_be_bytes());
+    core.write_le(0x2000_0000, &1234i32.to_be_bytes());
+
+    manager.set_endian(aether_core::Endian::Big);
+    let info = manager.resolve_variable(&mut core, "p_value", ptr_addr).unwrap();
+    assert_eq!(info.value_formatted_string, "0x20000000");
+    assert_eq!(info.members.as_ref().expect("pointee missing")[0].value_formatted_string, "1234");
+}
+
+#[test]
+fn test_dwarf_pointer_dereference() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    let ptr_addr = manager.lookup_symbol("p_value").expect("p_value not found");
+    let mut core = MockMemory::default();
+    core.write_le(ptr_addr, &0x2000_0000u64.to_le_bytes());
+    core.write_le(0x2000_0000, &1234i32.to_le_bytes());
+
+    let info = manager.resolve_variable(&mut core, "p_value", ptr_addr).unwrap();
+    assert_eq!(info.kind, "Pointer");
+    assert_eq!(info.value_formatted_string, "0x20000000");
+    let pointee = &info.members.as_ref().expect("pointee missing")[0];
+    assert_eq!(pointee.name, "*");
+    assert_eq!(pointee.value_formatted_string, "1234");
+
+    // Null pointers are not followed.
+    let null_addr = manager.lookup_symbol("p_null").expect("p_null not found");
+    let info = manager.resolve_variable(&mut core, "p_null", null_addr).unwrap();
+    assert_eq!(info.value_formatted_string, "NULL");
+    assert!(info.members.is_none());
+
+    // Dereferencing can be turned off.
+    manager.set_pointer_depth(0);
+    let info = manager.resolve_variable(&mut core, "p_value", ptr_addr).unwrap();
+    assert!(info.members.is_none());
+}
+
+#[test]
+fn test_dwarf_pointer_depth() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    // p_list -> node_a -> node_b -> NULL
+    let list_addr = manager.lookup_symbol("p_list").expect("p_list not found");
+    let mut core = MockMemory::default();
+    core.write_le(list_addr, &0x2000_0000u64.to_le_bytes());
+    core.write_le(0x2000_0000, &1i32.to_le_bytes());
+    core.write_le(0x2000_0008, &0x2000_0100u64.to_le_bytes());
+    core.write_le(0x2000_0100, &2i32.to_le_bytes());
+
+    let next_of_pointee = |info: &TypeInfo| {
+        let node = &info.members.as_ref().expect("pointee missing")[0];
+        node.members.as_ref().expect("no fields").iter().find(|m| m.name == "next").cloned()
+    };
+
+    // One level by default: node_a is shown, its `next` is not followed.
+    let info = manager.resolve_variable(&mut core, "p_list", list_addr).unwrap();
+    let next = next_of_pointee(&info).expect("next missing");
+    assert_eq!(next.value_formatted_string, "0x20000100");
+    assert!(next.members.is_none());
+
+    manager.set_pointer_depth(2);
+    let info = manager.resolve_variable(&mut core, "p_list", list_addr).unwrap();
+    let next = next_of_pointee(&info).expect("next missing");
+    let node_b = &next.members.as_ref().expect("node_b missing")[0];
+    assert_eq!(node_b.members.as_ref().unwrap()[0].value_formatted_string, "2");
+}
+
+#[test]
+fn test_dwarf_string_detection() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    let mut core = MockMemory::default();
+    let ptr_addr = manager.lookup_symbol("greeting").expect("greeting not found");
+    core.write_le(ptr_addr, &0x2000_0100u64.to_le_bytes());
+    core.write_le(0x2000_0100, b"hello\0garbage");
+
+    let info = manager.resolve_variable(&mut core, "greeting", ptr_addr).unwrap();
+    assert_eq!(info.value_formatted_string, "\"hello\"");
+    let pointee = &info.members.as_ref().expect("pointee missing")[0];
+    assert_eq!(pointee.address, Some(0x2000_0100));
+
+    let buf_addr = manager.lookup_symbol("name_buf").expect("name_buf not found");
+    core.write_le(buf_addr, b"aether\0");
+    let info = manager.resolve_variable(&mut core, "name_buf", buf_addr).unwrap();
+    assert_eq!(info.kind, "Array");
+    assert_eq!(info.value_formatted_string, "\"aether\"");
+    assert_eq!(info.address, Some(buf_addr));
+}
+
+#[test]
+fn test_dwarf_bitfield_members() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    // enable:1 = 1, mode:3 = 5, rest of the byte set to make sure it is masked off.
+    let addr = manager.lookup_symbol("ctrl").expect("ctrl not found");
+    let mut core = MockMemory::default();
+    core.write_le(addr, &[0b1111_1011, 0xFF, 0xFF, 0xFF]);
+
+    let info = manager.resolve_variable(&mut core, "ctrl", addr).unwrap();
+    let members = info.members.expect("No members");
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[0].name, "enable");
+    assert_eq!(members[0].value_formatted_string, "1");
+    assert_eq!(members[1].name, "mode");
+    assert_eq!(members[1].value_formatted_string, "5");
+}
+
+#[test]
+fn test_dwarf_variable_format_override() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    let addr = manager.lookup_symbol("target_value").expect("target_value not found");
+    let mut core = MockMemory::default();
+    core.write_le(addr, &0x41u32.to_le_bytes());
+
+    let mut render = |format| {
+        manager.set_variable_format("target_value", format);
+        manager.resolve_variable(&mut core, "target_value", addr).unwrap().value_formatted_string
+    };
+    assert_eq!(render(VariableFormat::Natural), "65");
+    assert_eq!(render(VariableFormat::Dec), "65");
+    assert_eq!(render(VariableFormat::Hex), "0x41");
+    assert_eq!(render(VariableFormat::Bin), "0b1000001");
+    assert_eq!(render(VariableFormat::Char), "'A'");
+
+    // Formats start from the bytes read, not from the natural rendering.
+    core.write_le(addr, &(-2i32).to_le_bytes());
+    for (format, expected) in [(VariableFormat::Natural, "-2"), (VariableFormat::Hex, "0xFFFFFFFE")]
+    {
+        manager.set_variable_format("target_value", format);
+        let info = manager.resolve_variable(&mut core, "target_value", addr).unwrap();
+        assert_eq!(info.value_formatted_string, expected);
+    }
+}
+
+#[test]
+fn test_where_am_i_resolves_function_and_line() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    // Inside main's return statement (c_types.c:28).
+    let main = manager.lookup_symbol("main").expect("main not found");
+    match aether_core::DebugEvent::location(&manager, main + 4) {
+        aether_core::DebugEvent::Location { pc, function, file, line } => {
+            assert_eq!(pc, main + 4);
+            assert_eq!(function.as_deref(), Some("main"));
+            assert!(file.expect("file missing").ends_with("c_types.c"));
+            assert_eq!(line, Some(28));
+        }
+        other => panic!("Expected Location, got {:?}", other),
+    }
+
+    // Outside any function.
+    match aether_core::DebugEvent::location(&manager, 0x10) {
+        aether_core::DebugEvent::Location { function, line, .. } => {
+            assert_eq!(function, None);
+            assert_eq!(line, None);
+        }
+        other => panic!("Expected Location, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_disassemble_function_covers_function_range() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+
+    let (main, size) = manager.lookup_symbol_with_size("main").expect("main not found");
+    let range = aether_core::session::function_disassembly_range(&manager, main + 4);
+    assert_eq!(range, main..main + size);
+
+    // Unknown address: fixed window.
+    let range = aether_core::session::function_disassembly_range(&manager, 0x10);
+    assert_eq!(range, 0x10..0x10 + aether_core::session::DISASSEMBLE_FALLBACK_COUNT as u64 * 4);
+}
+
+#[test]
+fn test_vector_table_resolves_handlers() {
+    use aether_core::debug::vector_table::read_vector_entries;
+
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load ELF");
+    let main = manager.lookup_symbol("main").expect("main not found");
+    let start = manager.lookup_symbol("_start").expect("_start not found");
+
+    // Initial SP, handlers with the Thumb bit set, a reserved slot and an
+    // address outside any function.
+    let words = [0x2000_8000u32, start as u32 | 1, (main as u32 + 8) | 1, 0, 0x10];
+    let memory: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    let entries = read_vector_entries(
+        0x0800_0000,
+        words.len(),
+        |address, buf| {
+            assert_eq!(address, 0x0800_0000);
+            buf.copy_from_slice(&memory);
+            Ok(())
+        },
+        |address| manager.function_at(address),
+    )
+    .unwrap();
+
+    let resolved: Vec<_> =
+        entries.iter().map(|e| (e.index, e.address, e.symbol.as_deref())).collect();
+    assert_eq!(
+        resolved,
+        vec![
+            (0, 0x2000_8000, None),
+            (1, start, Some("_start")),
+            (2, main + 8, Some("main")),
+            (3, 0, None),
+            (4, 0x10, None),
+        ]
+    );
+}
+
+#[test]
+fn test_build_id_from_gnu_note() {
+    let mut manager = SymbolManager::new();
+    assert_eq!(manager.build_id(), None);
+
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+
+    // As printed by `readelf -n`.
+    assert_eq!(manager.build_id().as_deref(), Some("7ee9a6c128fc8c654719e710767a6ed52890740e"));
+}
+
+#[test]
+fn test_source_mapping_rewrites_dwarf_path() {
+    let mut manager = SymbolManager::new();
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+
+    let main = manager.function_address("main").expect("main not found");
+    let recorded = manager.lookup(main).expect("no line info for main");
+    assert!(recorded.file.ends_with("c_types.c"));
+
+    // The sources now live somewhere other than the build directory.
+    let relocated = std::env::temp_dir().join(format!("aether-srcmap-{}", std::process::id()));
+    std::fs::create_dir_all(&relocated).unwrap();
+    let source = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/c_types.c");
+    std::fs::copy(source, relocated.join("c_types.c")).unwrap();
+
+    let build_dir = recorded.file.parent().unwrap().to_path_buf();
+    manager.add_source_mapping(build_dir, relocated.clone());
+
+    let mapped = manager.lookup(main).unwrap();
+    assert_eq!(mapped.file, relocated.join("c_types.c"));
+    assert!(mapped.file.exists());
+    assert_eq!(mapped.line, recorded.line);
+
+    // Source breakpoints set from the relocated file still resolve.
+    let address = manager.get_address(&mapped.file, mapped.line);
+    assert_eq!(address, manager.get_address(&recorded.file, recorded.line));
+    assert!(address.is_some());
+
+    std::fs::remove_dir_all(&relocated).unwrap();
+}
+
+#[test]
+fn test_embedded_source_without_file_on_disk() {
+    let mut manager = SymbolManager::new();
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/embedded_source.elf");
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+
+    let main = manager.function_address("main").expect("main not found");
+    let info = manager.lookup(main).expect("no line info for main");
+    // Built in a directory that does not exist here.
+    assert!(info.file.starts_with("/build/fw") && info.file.ends_with("embedded_source.c"));
+    assert!(!info.file.exists());
+
+    let text = manager.embedded_source(&info.file).expect("no embedded source");
+    let line = text.lines().nth(info.line as usize - 1).unwrap();
+    assert_eq!(line, "int main(void) {");
+
+    // Files the ELF does not embed give nothing.
+    assert_eq!(manager.embedded_source(std::path::Path::new("/build/fw/other.c")), None);
+}
+
+#[test]
+fn test_source_files_lists_compiled_files() {
+    let mut manager = SymbolManager::new();
+    assert!(manager.source_files().is_empty());
+
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/embedded_source.elf");
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+    // DWARF 5 lists the primary file twice; it appears once.
+    assert_eq!(manager.source_files(), vec![PathBuf::from("/build/fw/embedded_source.c")]);
+
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+    let files = manager.source_files();
+    let c_types = files.iter().find(|f| f.ends_with("c_types.c")).expect("c_types.c not listed");
+    // Relative names are resolved against the compilation directory.
+    assert!(c_types.is_absolute());
+}
+
+#[test]
+fn test_get_address_prefers_matching_path_components() {
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/shared_suffix.elf");
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+    let function = |file: &str, line| {
+        let address = manager.get_address(std::path::Path::new(file), line)?;
+        manager.function_at(address)
+    };
+
+    // submain.c has code at line 4 but is not main.c.
+    assert_eq!(function("main.c", 4), None);
+    assert_eq!(function("main.c", 12).as_deref(), Some("main"));
+
+    // Both util.c files have code at line 4; the directory decides.
+    assert_eq!(function("app/util.c", 4).as_deref(), Some("app_util"));
+    assert_eq!(function("lib/util.c", 4).as_deref(), Some("lib_util"));
+    assert_eq!(function("/home/dev/fw/shared_suffix/app/util.c", 4).as_deref(), Some("app_util"));
+    assert_eq!(function("/build/fw/shared_suffix/lib/util.c", 4).as_deref(), Some("lib_util"));
+}
+
+#[test]
+fn test_get_address_dwarf5_line_program() {
+    // c_types.c built with -gdwarf-5: file names in .debug_line_str, file
+    // entries indexed from 0 and the primary file listed twice.
+    let mut elf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types_dwarf5.elf");
+    let mut manager = SymbolManager::new();
+    manager.load_elf(&elf_path).expect("Failed to load elf");
+
+    let address = manager
+        .get_address(std::path::Path::new("c_types.c"), 28)
+        .expect("line 28 not resolved in DWARF 5 line program");
+    assert_eq!(manager.function_at(address).as_deref(), Some("main"));
+    assert_eq!(manager.lookup(address).map(|info| info.line), Some(28));
+    assert_eq!(manager.get_address(std::path::Path::new("/build/fw/c_types.c"), 28), Some(address));
+    assert_eq!(manager.get_address(std::path::Path::new("c_types.c"), 1), None);
+}
@@ -1,3 +1,4 @@
+use aether_core::fake::FakeSession;
 use aether_core::{DebugCommand, DebugEvent, SessionHandle, TaskState};
 use std::sync::Arc;
 use tokio::time::{timeout, Duration};
@@ -338,10 +339,10 @@ async fn test_scenario_breakpoint_management() {
     assert!(matches!(cmd, DebugCommand::ListBreakpoints));
 
     // 3. Simulate Logic returning list
-    event_tx.send(DebugEvent::Breakpoints(vec![0x08001234])).unwrap();
+    event_tx.send(DebugEvent::Breakpoints(vec![(0x08001234, true)])).unwrap();
     let ev = timeout(Duration::from_millis(100), receiver.recv()).await.unwrap().unwrap();
     if let DebugEvent::Breakpoints(bps) = ev {
-        assert_eq!(bps, vec![0x08001234]);
+        assert_eq!(bps, vec![(0x08001234, true)]);
     } else {
         panic!("Expected Breakpoints event");
     }
@@ -379,6 +380,7 @@ async fn test_scenario_svd_interaction() {
         name: "GPIOA".to_string(),
         base_address: 0x48000000,
         description: Some("GPIO Port A".to_string()),
+        group_name: Some("GPIO".to_string()),
     }];
     event_tx.send(DebugEvent::Peripherals(mock_periphs)).unwrap();
     let ev = timeout(Duration::from_millis(100), receiver.recv()).await.unwrap().unwrap();
@@ -856,10 +858,10 @@ async fn test_scenario_breakpoint_persistence_on_reset() {
     handle.send(DebugCommand::ListBreakpoints).unwrap();
     assert!(matches!(cmd_rx.try_recv().unwrap(), DebugCommand::ListBreakpoints));
 
-    event_tx.send(DebugEvent::Breakpoints(vec![0x0800AAAA])).unwrap();
+    event_tx.send(DebugEvent::Breakpoints(vec![(0x0800AAAA, true)])).unwrap();
     let ev = timeout(Duration::from_millis(100), receiver.recv()).await.unwrap().unwrap();
     if let DebugEvent::Breakpoints(bps) = ev {
-        assert_eq!(bps, vec![0x0800AAAA]);
+        assert_eq!(bps, vec![(0x0800AAAA, true)]);
     }
 }
 
@@ -1255,3 +1257,343 @@ async fn test_perf_rtt_10khz_simulation() {
     assert_eq!(received, message_count);
     assert!(start.elapsed() < Duration::from_secs(1));
 }
+
+/// Wait for the first event matching `pred`, skipping others (e.g. status updates).
+async fn next_event(
+    receiver: &mut tokio::sync::broadcast::Receiver<DebugEvent>,
+    pred: impl Fn(&DebugEvent) -> bool,
+) -> DebugEvent {
+    timeout(Duration::from_secs(1), async {
+        loop {
+            let event = receiver.recv().await.expect("Event channel closed");
+            if pred(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for event")
+}
+
+#[tokio::test]
+async fn test_scenario_fake_target_breakpoint_round_trip() {
+    // 1. Attach to a fake target
+    let fake = FakeSession::new();
+    fake.set_register(0, 0x2A);
+    let handle = SessionHandle::with_target(fake.clone());
+    let mut receiver = handle.subscribe();
+
+    // 2. Set a breakpoint; it is armed on the target
+    handle.send(DebugCommand::SetBreakpoint(0x0800_0100)).unwrap();
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Breakpoints(_))).await;
+    assert!(matches!(event, DebugEvent::Breakpoints(bps) if bps == vec![(0x0800_0100, true)]));
+    assert_eq!(fake.breakpoints(), vec![0x0800_0100]);
+
+    // 3. Resume
+    handle.send(DebugCommand::Resume).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::Resumed)).await;
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::Status(s) if !s.is_halted())).await;
+
+    // 4. The core runs into the breakpoint; the status poll reports the halt
+    assert!(fake.hit(0x0800_0100));
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Halted { .. })).await;
+    assert!(matches!(event, DebugEvent::Halted { pc: 0x0800_0100 }));
+
+    // 5. Read registers
+    handle.send(DebugCommand::ReadRegister(0)).unwrap();
+    handle.send(DebugCommand::ReadRegister(15)).unwrap();
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::RegisterValue(0, _))).await;
+    assert!(matches!(event, DebugEvent::RegisterValue(0, 0x2A)));
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::RegisterValue(15, _))).await;
+    assert!(matches!(event, DebugEvent::RegisterValue(15, 0x0800_0100)));
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_back_to_back_tracepoint_hits_are_logged() {
+    let fake = FakeSession::new();
+    fake.set_register(0, 0x2A);
+    let handle = SessionHandle::with_target(fake.clone());
+    let mut receiver = handle.subscribe();
+
+    handle
+        .send(DebugCommand::SetTracepoint { address: 0x0800_0100, reads: vec!["r0".into()] })
+        .unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::Breakpoints(_))).await;
+    handle.send(DebugCommand::Resume).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::Status(s) if !s.is_halted())).await;
+
+    // A loop faster than the status poll: the second hit lands before the
+    // session polls again after resuming from the first.
+    for _ in 0..2 {
+        assert!(fake.hit(0x0800_0100));
+        let event = next_event(&mut receiver, |e| {
+            matches!(e, DebugEvent::TracepointHit { .. } | DebugEvent::Halted { .. })
+        })
+        .await;
+        match event {
+            DebugEvent::TracepointHit { address, values } => {
+                assert_eq!(address, 0x0800_0100);
+                assert_eq!(values, vec![("r0".to_string(), Some("0x0000002A".to_string()))]);
+            }
+            other => panic!("Expected TracepointHit, got {:?}", other),
+        }
+    }
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_step_over_call_that_never_returns_halts() {
+    let fake = FakeSession::new();
+    // bl #0x0800_0010 at the PC; the fake never reaches the return address.
+    fake.set_memory(0x0800_0000, &[0x00, 0xf0, 0x06, 0xf8]);
+    fake.set_register(15, 0x0800_0000);
+    let handle = SessionHandle::with_target(fake.clone());
+    let mut receiver = handle.subscribe();
+
+    handle.send(DebugCommand::StepOver).unwrap();
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Error(_))).await;
+    assert!(matches!(event, DebugEvent::Error(message) if message.contains("StepOver failed")));
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Halted { .. })).await;
+    assert!(matches!(event, DebugEvent::Halted { pc: 0x0800_0000 }));
+    assert!(fake.breakpoints().is_empty(), "the temporary breakpoint was left armed");
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_run_to_main_timeout_halts() {
+    let fake = FakeSession::new();
+    // Reset vector pointing at 0x0800_0100.
+    fake.set_memory(4, &0x0800_0101u32.to_le_bytes());
+    let handle = SessionHandle::with_target(fake.clone());
+    let mut receiver = handle.subscribe();
+
+    let mut elf_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    handle.send(DebugCommand::LoadSymbols(elf_path)).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::SymbolsLoaded)).await;
+
+    // The fake never reaches main on its own.
+    handle.send(DebugCommand::RunToMain).unwrap();
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Error(_))).await;
+    assert!(matches!(event, DebugEvent::Error(message) if message.contains("Run to main failed")));
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Halted { .. })).await;
+    assert!(matches!(event, DebugEvent::Halted { pc: 0x0800_0100 }));
+    assert!(fake.breakpoints().is_empty(), "the breakpoint on main was left armed");
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_oversized_snapshot_is_rejected() {
+    let fake = FakeSession::new();
+    let handle = SessionHandle::with_target(fake);
+    let mut receiver = handle.subscribe();
+
+    let len = aether_core::memory::snapshot::MAX_SNAPSHOT_LEN + 1;
+    handle.send(DebugCommand::SnapshotMemory { address: 0x2000_0000, len }).unwrap();
+    let event = next_event(&mut receiver, |e| {
+        matches!(e, DebugEvent::OperationFailed { .. } | DebugEvent::MemorySnapshot(_))
+    })
+    .await;
+    assert!(
+        matches!(&event, DebugEvent::OperationFailed { operation, .. } if operation == "Snapshot memory at 0x20000000"),
+        "{:?}",
+        event
+    );
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_checksum_refused_without_core_or_while_paused() {
+    use aether_core::memory::checksum::ChecksumAlgo;
+    let checksum =
+        DebugCommand::ChecksumRegion { address: 0x0800_0000, len: 64, algo: ChecksumAlgo::Crc32 };
+    let is_reply = |e: &DebugEvent| {
+        matches!(e, DebugEvent::OperationFailed { .. } | DebugEvent::Checksum { .. })
+    };
+
+    // 1. Nothing attached: refused rather than left waiting for a core
+    let handle = SessionHandle::new(None).unwrap();
+    let mut receiver = handle.subscribe();
+    handle.send(checksum.clone()).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(
+        matches!(&event, DebugEvent::OperationFailed { operation, .. } if operation == "Checksum at 0x08000000"),
+        "{:?}",
+        event
+    );
+    handle.send(DebugCommand::Exit).unwrap();
+
+    // 2. Background work paused: refused, and accepted again once resumed
+    let handle = SessionHandle::with_target(FakeSession::new());
+    let mut receiver = handle.subscribe();
+    handle.send(DebugCommand::PauseBackground).unwrap();
+    handle.send(checksum.clone()).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(
+        matches!(&event, DebugEvent::OperationFailed { error, .. } if error.to_string().contains("paused")),
+        "{:?}",
+        event
+    );
+
+    handle.send(DebugCommand::ResumeBackground).unwrap();
+    handle.send(checksum).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(matches!(event, DebugEvent::Checksum { len: 64, .. }), "{:?}", event);
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_peripheral_dump_refused_without_core_or_while_paused() {
+    let is_reply = |e: &DebugEvent| {
+        matches!(e, DebugEvent::OperationFailed { .. } | DebugEvent::PeripheralDump(_))
+    };
+
+    // 1. Nothing attached: refused rather than left waiting for a core
+    let handle = SessionHandle::new(None).unwrap();
+    let mut receiver = handle.subscribe();
+    handle.send(DebugCommand::DumpAllPeripherals).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(
+        matches!(&event, DebugEvent::OperationFailed { operation, .. } if operation == "Dump peripherals"),
+        "{:?}",
+        event
+    );
+    handle.send(DebugCommand::Exit).unwrap();
+
+    // 2. Background work paused: refused, and accepted again once resumed
+    let handle = SessionHandle::with_target(FakeSession::new());
+    let mut receiver = handle.subscribe();
+    handle.send(DebugCommand::PauseBackground).unwrap();
+    handle.send(DebugCommand::DumpAllPeripherals).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(
+        matches!(&event, DebugEvent::OperationFailed { error, .. } if error.to_string().contains("paused")),
+        "{:?}",
+        event
+    );
+
+    handle.send(DebugCommand::ResumeBackground).unwrap();
+    handle.send(DebugCommand::DumpAllPeripherals).unwrap();
+    let event = next_event(&mut receiver, is_reply).await;
+    assert!(matches!(event, DebugEvent::PeripheralDump(_)), "{:?}", event);
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_plot_summary_aggregates_retained_points() {
+    let mut elf_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    elf_path.push("tests/fixtures/c_types.elf");
+    let mut symbols = aether_core::symbols::SymbolManager::new();
+    symbols.load_elf(&elf_path).unwrap();
+    let address = symbols.lookup_symbol("target_value").unwrap();
+
+    let fake = FakeSession::new();
+    fake.set_memory(address, &7u32.to_le_bytes());
+    let handle = SessionHandle::with_target(fake);
+    let mut receiver = handle.subscribe();
+    handle.send(DebugCommand::LoadSymbols(elf_path)).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::SymbolsLoaded)).await;
+
+    // 1. Unknown plots are reported rather than summarized as empty
+    handle.send(DebugCommand::GetPlotSummary("target_value".to_string())).unwrap();
+    let event = next_event(&mut receiver, |e| {
+        matches!(e, DebugEvent::Error(_) | DebugEvent::PlotSummary { .. })
+    })
+    .await;
+    assert!(matches!(&event, DebugEvent::Error(m) if m.contains("Unknown plot")), "{:?}", event);
+
+    // 2. Once sampled, the summary covers the retained points
+    handle
+        .send(DebugCommand::AddPlot {
+            name: "target_value".to_string(),
+            var_type: aether_core::VarType::U32,
+        })
+        .unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::PlotData { .. })).await;
+    handle.send(DebugCommand::GetPlotSummary("target_value".to_string())).unwrap();
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::PlotSummary { .. })).await;
+    let DebugEvent::PlotSummary { summary: Some(summary), .. } = event else {
+        panic!("Expected a summary, got {:?}", event);
+    };
+    assert!(summary.count >= 1);
+    assert_eq!((summary.min, summary.max, summary.mean), (7.0, 7.0, 7.0));
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_pause_background_stops_status_polling() {
+    let fake = FakeSession::new();
+    let handle = SessionHandle::with_target(fake.clone());
+    let mut receiver = handle.subscribe();
+
+    handle.send(DebugCommand::SetBreakpoint(0x0800_0200)).unwrap();
+    handle.send(DebugCommand::Resume).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::Status(s) if !s.is_halted())).await;
+
+    // 1. Pause: the core halts, but no status poll notices
+    handle.send(DebugCommand::PauseBackground).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::BackgroundPaused(true))).await;
+    assert!(fake.hit(0x0800_0200));
+    let polled = timeout(
+        Duration::from_millis(300),
+        next_event(&mut receiver, |e| {
+            matches!(e, DebugEvent::Status(_) | DebugEvent::Halted { .. })
+        }),
+    )
+    .await;
+    assert!(polled.is_err(), "status was polled while paused");
+
+    // 2. Resume: the next poll reports the halt
+    handle.send(DebugCommand::ResumeBackground).unwrap();
+    next_event(&mut receiver, |e| matches!(e, DebugEvent::BackgroundPaused(false))).await;
+    let event = next_event(&mut receiver, |e| matches!(e, DebugEvent::Halted { .. })).await;
+    assert!(matches!(event, DebugEvent::Halted { pc: 0x0800_0200 }));
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
+
+#[tokio::test]
+async fn test_scenario_initial_session_is_supervised_for_reconnect() {
+    let fake = FakeSession::new();
+    let target = aether_core::reconnect::ReconnectTarget {
+        serial: "066DFF555".to_string(),
+        chip: "STM32L476RGTx".to_string(),
+        protocol: None,
+        under_reset: false,
+        speed_khz: None,
+    };
+    let handle = SessionHandle::with_reconnect(fake.clone(), Some(target));
+    let mut receiver = handle.subscribe();
+    handle.send(DebugCommand::SetAutoReconnect(true)).unwrap();
+
+    // 1. The probe drops off the bus; once enough status polls fail, the lost
+    // session is closed so it can be re-opened by serial
+    fake.set_connected(false);
+    let closed = timeout(Duration::from_secs(2), async {
+        loop {
+            handle.send(DebugCommand::ReadRegister(0)).unwrap();
+            let event = next_event(&mut receiver, |e| {
+                matches!(e, DebugEvent::RegisterValue(0, _) | DebugEvent::Error(_))
+            })
+            .await;
+            if let DebugEvent::Error(message) = event {
+                return message;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the lost session was never closed");
+    assert!(closed.contains("No active session"), "{}", closed);
+
+    handle.send(DebugCommand::Exit).unwrap();
+}
@@ -0,0 +1,29 @@
+/* C fixture for DWARF resolution tests.
+ * Rebuild with: gcc -g -gdwarf-4 -O0 -no-pie -o c_types.elf c_types.c
+ */
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+int target_value = 1234;
+int *p_value = &target_value;
+int *p_null = 0;
+
+struct node node_b = { 2, 0 };
+struct node node_a = { 1, &node_b };
+struct node *p_list = &node_a;
+struct ctrl_reg {
+    unsigned int enable : 1;
+    unsigned int mode : 3;
+};
+
+struct ctrl_reg ctrl = { 1, 5 };
+
+const char *greeting = "hello";
+char name_buf[16] = "aether";
+
+int main(void) {
+    return *p_value + p_list->value + (p_null == 0) + greeting[0] + name_buf[0] + ctrl.mode;
+}
@@ -0,0 +1,131 @@
+#!/usr/bin/env python3
+"""Embed a source file's text in an ELF's DWARF 5 line table.
+
+Adds a DW_LNCT_LLVM_source column to the file table, as clang's
+`-gembed-source` does, for toolchains without that option. Handles a single
+line program with 32-bit DWARF, which is what the fixture build produces.
+
+Usage: embed_source.py <elf> <source>
+"""
+
+import os
+import subprocess
+import sys
+import tempfile
+
+DW_LNCT_LLVM_SOURCE = 0x2001
+DW_FORM_LINE_STRP = 0x1F
+FIXED_FORM_SIZES = {0x0B: 1, 0x05: 2, 0x06: 4, 0x07: 8, 0x1E: 16, 0x1F: 4, 0x0E: 4}
+
+
+def uleb(data, pos):
+    value = shift = 0
+    while True:
+        byte = data[pos]
+        pos += 1
+        value |= (byte & 0x7F) << shift
+        shift += 7
+        if byte < 0x80:
+            return value, pos
+
+
+def encode_uleb(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        out.append(byte | (0x80 if value else 0))
+        if not value:
+            return bytes(out)
+
+
+def skip_form(data, pos, form):
+    if form in FIXED_FORM_SIZES:
+        return pos + FIXED_FORM_SIZES[form]
+    if form == 0x0F:  # udata
+        return uleb(data, pos)[1]
+    if form == 0x08:  # string
+        return data.index(b"\0", pos) + 1
+    raise ValueError(f"unsupported form 0x{form:x}")
+
+
+def read_formats(data, pos):
+    count = data[pos]
+    pos += 1
+    formats = []
+    for _ in range(count):
+        kind, pos = uleb(data, pos)
+        form, pos = uleb(data, pos)
+        formats.append((kind, form))
+    return formats, pos
+
+
+def embed(line, source_offset):
+    unit_length = int.from_bytes(line[0:4], "little")
+    version = int.from_bytes(line[4:6], "little")
+    if version != 5 or unit_length + 4 != len(line):
+        raise ValueError("expected one DWARF 5 line program")
+    header_length = int.from_bytes(line[8:12], "little")
+    program = line[12 + header_length:]
+
+    opcode_base = line[17]
+    pos = 18 + opcode_base - 1
+    dir_formats, pos = read_formats(line, pos)
+    dir_count, pos = uleb(line, pos)
+    for _ in range(dir_count):
+        for _, form in dir_formats:
+            pos = skip_form(line, pos, form)
+    file_format_pos = pos
+
+    file_formats, pos = read_formats(line, pos)
+    file_count, pos = uleb(line, pos)
+    entries = []
+    for _ in range(file_count):
+        start = pos
+        for _, form in file_formats:
+            pos = skip_form(line, pos, form)
+        entries.append(line[start:pos])
+
+    file_formats.append((DW_LNCT_LLVM_SOURCE, DW_FORM_LINE_STRP))
+    files = bytearray([len(file_formats)])
+    for kind, form in file_formats:
+        files += encode_uleb(kind) + encode_uleb(form)
+    files += encode_uleb(file_count)
+    for entry in entries:
+        files += entry + source_offset.to_bytes(4, "little")
+
+    header = line[12:file_format_pos] + files
+    body = line[4:8] + len(header).to_bytes(4, "little") + header + program
+    return len(body).to_bytes(4, "little") + body
+
+
+def main():
+    elf, source = sys.argv[1:3]
+    with tempfile.TemporaryDirectory() as tmp:
+        line_path = os.path.join(tmp, "line")
+        str_path = os.path.join(tmp, "line_str")
+        subprocess.run(
+            ["objcopy", "--dump-section", f".debug_line={line_path}",
+             "--dump-section", f".debug_line_str={str_path}", elf, os.path.join(tmp, "out")],
+            check=True,
+        )
+        with open(line_path, "rb") as f:
+            line = f.read()
+        with open(str_path, "rb") as f:
+            line_str = f.read()
+        with open(source, "rb") as f:
+            text = f.read()
+
+        with open(line_path, "wb") as f:
+            f.write(embed(line, len(line_str)))
+        with open(str_path, "wb") as f:
+            f.write(line_str + text + b"\0")
+        subprocess.run(
+            ["objcopy", "--update-section", f".debug_line={line_path}",
+             "--update-section", f".debug_line_str={str_path}", elf],
+            check=True,
+        )
+
+
+if __name__ == "__main__":
+    main()
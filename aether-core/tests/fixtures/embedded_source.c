@@ -0,0 +1,14 @@
+/* C fixture for embedded-source tests: the line tables carry this file's text
+ * (DW_LNCT_LLVM_source), and the recorded build directory does not exist.
+ * Rebuild with:
+ *   gcc -g -gdwarf-5 -O0 -no-pie -fdebug-prefix-map=$PWD=/build/fw \
+ *       -o embedded_source.elf embedded_source.c
+ *   python3 embed_source.py embedded_source.elf embedded_source.c
+ */
+
+int counter = 0;
+
+int main(void) {
+    counter += 1;
+    return counter;
+}
@@ -0,0 +1,5 @@
+/* Same file name as lib/util.c. */
+
+int app_util(int x) {
+    return x * 3;
+}
@@ -0,0 +1,5 @@
+/* Same file name as app/util.c. */
+
+int lib_util(int x) {
+    return x * 2;
+}
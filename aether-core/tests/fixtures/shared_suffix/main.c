@@ -0,0 +1,13 @@
+/* C fixture for source file matching: several files share a name suffix.
+ * Rebuild from tests/fixtures with:
+ *   gcc -g -gdwarf-4 -O0 -no-pie -fdebug-prefix-map=$PWD=/build/fw \
+ *       -o shared_suffix.elf shared_suffix/submain.c shared_suffix/lib/util.c \
+ *       shared_suffix/app/util.c shared_suffix/main.c
+ */
+int submain(int x);
+int lib_util(int x);
+int app_util(int x);
+
+int main(void) {
+    return submain(1) + lib_util(2) + app_util(3);
+}
@@ -0,0 +1,5 @@
+/* Shares a suffix with main.c; listed first so naive suffix matching finds it first. */
+
+int submain(int x) {
+    return x + 1;
+}
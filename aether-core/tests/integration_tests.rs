@@ -35,6 +35,9 @@ fn test_target_info_interface() {
         flash_size: 512,
         ram_size: 128,
         architecture: "Armv8-M".to_string(),
+        endian: aether_core::Endian::Little,
+        requested: "MockChip".to_string(),
+        detection_method: aether_core::DetectionMethod::Requested,
     };
 
     assert_eq!(target.name, "MockChip");
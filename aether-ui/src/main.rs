@@ -13,6 +13,7 @@ use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 use tokio_stream::StreamExt;
+use ui_logic::TimelineEvent;
 
 mod ui_tabs;
 use ui_tabs::{AetherTabViewer, DebugTab};
@@ -26,6 +27,15 @@ fn safe_pick_file(_title: &str, _extensions: &[&str]) -> Option<PathBuf> {
     None
 }
 
+#[cfg(not(target_os = "ios"))]
+fn safe_pick_folder() -> Option<PathBuf> {
+    rfd::FileDialog::new().pick_folder()
+}
+#[cfg(target_os = "ios")]
+fn safe_pick_folder() -> Option<PathBuf> {
+    None
+}
+
 #[cfg(not(target_os = "ios"))]
 fn safe_save_file(title: &str, extensions: &[&str]) -> Option<PathBuf> {
     rfd::FileDialog::new().add_filter(title, extensions).save_file()
@@ -52,6 +62,89 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+/// Tag color of an RTT channel in the combined console.
+fn rtt_channel_color(channel: usize) -> egui::Color32 {
+    const PALETTE: [egui::Color32; 6] = [
+        egui::Color32::LIGHT_BLUE,
+        egui::Color32::LIGHT_GREEN,
+        egui::Color32::GOLD,
+        egui::Color32::LIGHT_RED,
+        egui::Color32::from_rgb(200, 150, 255),
+        egui::Color32::from_rgb(120, 220, 220),
+    ];
+    PALETTE[channel % PALETTE.len()]
+}
+
+/// Lays out text with its ANSI colors applied. The monospace font has no bold face,
+/// so boldness is not rendered.
+fn ansi_layout_job(text: &str) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    for span in ui_logic::parse_ansi(text) {
+        let color =
+            span.fg.map_or(egui::Color32::LIGHT_GRAY, |[r, g, b]| egui::Color32::from_rgb(r, g, b));
+        job.append(
+            &span.text,
+            0.0,
+            egui::TextFormat {
+                color,
+                font_id: egui::FontId::monospace(14.0),
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
+
+/// Lays out one memory view row with the bytes in `diff` highlighted. In word
+/// mode a word is highlighted when any of its bytes changed.
+fn memory_diff_row(
+    ui: &egui::Ui,
+    address: u64,
+    chunk: &[u8],
+    words: Option<aether_core::Endian>,
+    diff: &aether_core::memory::snapshot::MemoryDiff,
+) -> egui::text::LayoutJob {
+    let plain = egui::TextFormat {
+        font_id: egui::TextStyle::Monospace.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let changed = egui::TextFormat {
+        color: egui::Color32::BLACK,
+        background: egui::Color32::GOLD,
+        ..plain.clone()
+    };
+    let format_for = |at: u64, len: usize| {
+        if (at..at + len as u64).any(|a| diff.changed(a)) {
+            changed.clone()
+        } else {
+            plain.clone()
+        }
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    job.append(&format!("{:08X}   ", address), 0.0, plain.clone());
+    let cell = if words.is_some() { 4 } else { 1 };
+    let mut width = 0;
+    for (i, group) in chunk.chunks(cell).enumerate() {
+        let text = match words {
+            Some(endian) => {
+                format!("{:0width$X}", endian.read_uint(group), width = group.len() * 2)
+            }
+            None => format!("{:02X}", group[0]),
+        };
+        job.append(&text, 0.0, format_for(address + (i * cell) as u64, group.len()));
+        job.append(" ", 0.0, plain.clone());
+        width += text.len() + 1;
+    }
+    job.append(&" ".repeat(48usize.saturating_sub(width) + 1), 0.0, plain.clone());
+    for (i, b) in chunk.iter().enumerate() {
+        let c = if (32..=126).contains(b) { *b as char } else { '.' };
+        job.append(&c.to_string(), 0.0, format_for(address + i as u64, 1));
+    }
+    job
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 enum RttDisplayMode {
     Text,
@@ -69,6 +162,11 @@ pub struct AetherApp {
 
     // Session & Debug state
     session_handle: Option<Arc<aether_core::SessionHandle>>,
+    // Stops the agent server and remote client when the window closes.
+    shutdown: ui_logic::ShutdownSignal,
+    // Agent server of the current session, with the signal that stops only it.
+    session_server: Option<(ui_logic::ShutdownSignal, std::thread::JoinHandle<()>)>,
+    worker_threads: Vec<std::thread::JoinHandle<()>>,
     event_receiver: Option<tokio::sync::broadcast::Receiver<aether_core::DebugEvent>>,
     registers: HashMap<u16, u64>,
     core_status: Option<aether_core::CoreStatus>,
@@ -78,12 +176,52 @@ pub struct AetherApp {
     memory_data: Vec<u8>,
     memory_address_input: String,
     memory_base_address: u64,
+    memory_window: usize,
+    /// Bookmark being resolved by the session; its `BookmarkResolved` moves the view.
+    memory_symbol_pending: Option<aether_core::config::BookmarkTarget>,
+    /// Latest memory snapshot, the baseline for the next one.
+    memory_snapshot: Option<aether_core::memory::snapshot::MemorySnapshot>,
+    /// Bytes that changed between the last two snapshots, highlighted in the view.
+    memory_diff: Option<aether_core::memory::snapshot::MemoryDiff>,
+    session_config: aether_core::config::SessionConfig,
+    last_autosave: std::time::Instant,
+    /// The background thread writing the latest auto-save, if one was started.
+    autosave_writer: Option<std::thread::JoinHandle<()>>,
+    /// Data auto-saved by an earlier run, offered for restore at startup.
+    autosave_recovery: Option<ui_logic::AutoSave>,
+    bookmark_name_input: String,
+    key_bindings: Vec<ui_logic::KeyBinding>,
+    write_log: Vec<aether_core::memory::history::WriteRecord>,
+    tracepoint_reads_input: String,
+    reset_resync: bool,
+    /// Pulse nRST and retry once when re-attaching fails.
+    attach_reset_recovery: bool,
+    background_paused: bool,
+    /// Upper bound for the probe clock while flashing, in kHz.
+    flash_speed_cap_khz: u32,
+    /// Preserve flash bytes outside the image; `None` uses the format's default.
+    flash_keep_unwritten: Option<bool>,
+    /// Last target voltage reading; the inner `None` means the probe cannot measure it.
+    target_voltage: Option<Option<f32>>,
+    /// Probe and target of the last successful attach, replayed by "Reconnect".
+    last_attach: Option<aether_core::reconnect::ReconnectTarget>,
+    vector_catch: aether_core::debug::vector_catch::VectorCatch,
+    reset_kind: aether_core::debug::reset::ResetKind,
+    tracepoint_log: std::collections::VecDeque<String>,
+    /// Text from ITM stimulus ports routed to the console.
+    itm_console: String,
+    memory_words: bool,
+    register_bases: ui_logic::RegisterBases,
 
     // Disassembly state
     disassembly: Vec<aether_core::disasm::InstructionInfo>,
+    /// Source line heading the instructions from each address, when
+    /// disassembled with source.
+    disassembly_source: HashMap<u64, String>,
 
     // Breakpoints state
-    breakpoints: Vec<u64>,
+    /// Breakpoint addresses with their enabled state.
+    breakpoints: Vec<(u64, bool)>,
     breakpoint_address_input: String,
 
     // Flashing state
@@ -96,7 +234,15 @@ pub struct AetherApp {
     peripherals: Vec<aether_core::svd::PeripheralInfo>,
     selected_peripheral: Option<String>,
     peripheral_registers: Vec<aether_core::svd::RegisterInfo>,
+    peripheral_filter: String,
+    peripheral_dump: Option<aether_core::peripheral_dump::PeripheralDump>,
+    dump_purpose: DumpPurpose,
+    peripheral_snapshot: Option<aether_core::peripheral_dump::PeripheralDump>,
+    snapshot_changes: Vec<aether_core::peripheral_dump::FieldChange>,
     expanded_registers: std::collections::HashSet<String>,
+    peripheral_live: bool,
+    peripheral_poll_ms: u64,
+    changed_fields: std::collections::HashSet<(String, String)>,
 
     // RTT State
     rtt_attached: bool,
@@ -104,12 +250,25 @@ pub struct AetherApp {
     rtt_down_channels: Vec<aether_core::rtt::RttChannelInfo>,
     rtt_selected_channel: Option<usize>,
     rtt_display_modes: HashMap<usize, RttDisplayMode>,
+    rtt_encodings: HashMap<usize, ui_logic::RttEncoding>,
     rtt_buffers: std::collections::HashMap<usize, String>,
     rtt_raw_buffers: std::collections::HashMap<usize, Vec<u8>>,
+    /// All up channels interleaved, for the combined console.
+    rtt_merged: ui_logic::RttMergedLog,
+    event_batcher: ui_logic::EventBatcher,
+    rtt_show_all: bool,
+    /// Render ANSI color codes in text output; otherwise strip them.
+    rtt_ansi_colors: bool,
     rtt_input: String,
+    /// Optional control-block address; empty scans or uses `_SEGGER_RTT`.
+    rtt_address_input: String,
 
     // Symbols & Source state
     symbols_loaded: bool,
+    /// Build identifier of the loaded ELF.
+    build_id: Option<String>,
+    /// Files in the ELF's line tables, for opening a file to set breakpoints in.
+    source_files: Vec<PathBuf>,
     source_info: Option<aether_core::SourceInfo>,
     breakpoint_locations: Vec<aether_core::SourceInfo>,
     // Cache stores raw lines and the pre-calculated layout job for syntax highlighting
@@ -125,16 +284,23 @@ pub struct AetherApp {
     remote_host: String,
     remote_port: String,
     is_remote: bool,
+    capabilities_receiver: Option<Receiver<aether_core::Capabilities>>,
+    features: std::collections::BTreeMap<ui_logic::Feature, Option<String>>,
 
     // RTOS State
     tasks: Vec<aether_core::TaskInfo>,
+    sync_objects: Vec<aether_core::SyncObjectInfo>,
+    timebase: Option<(u32, f64)>,
     timeline_events: Vec<TimelineEvent>,
+    trace_stats: Option<aether_core::itm::TraceStats>,
 
     // Stack State
     stack_frames: Vec<aether_core::StackFrame>,
 
     // Watch State
     watched_variables: Vec<aether_core::symbols::TypeInfo>,
+    variable_formats: HashMap<String, aether_core::VariableFormat>,
+    live_watch: bool,
     variable_input: String,
 
     // Syntax Highlighting
@@ -145,12 +311,12 @@ pub struct AetherApp {
     dock_state: Option<DockState<DebugTab>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TimelineEvent {
-    pub task_handle: u32,
-    pub task_name: String,
-    pub start_time: f64,
-    pub end_time: Option<f64>,
+/// What the next peripheral dump is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DumpPurpose {
+    Report,
+    Snapshot,
+    Compare,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -168,6 +334,7 @@ pub struct SessionExport {
     pub timeline_events: Vec<TimelineEvent>,
     pub stack_frames: Vec<aether_core::StackFrame>,
     pub watched_variables: Vec<aether_core::symbols::TypeInfo>,
+    #[serde(with = "ui_logic::plot_points")]
     pub plots: std::collections::HashMap<String, std::collections::VecDeque<[f64; 2]>>,
 }
 
@@ -188,6 +355,147 @@ impl AetherApp {
         }
     }
 
+    fn capture_buffers(&self) -> ui_logic::CaptureBuffers {
+        ui_logic::CaptureBuffers {
+            rtt_buffers: self.rtt_buffers.clone(),
+            plots: self.plots.clone(),
+            timeline_events: self.timeline_events.clone(),
+        }
+    }
+
+    /// Flush captured data to the auto-save file on a background thread.
+    /// Skipped while an earlier run's save is still waiting to be restored or
+    /// discarded, and while the previous write is still in progress.
+    fn autosave(&mut self) {
+        self.last_autosave = std::time::Instant::now();
+        if self.autosave_recovery.is_some()
+            || self.autosave_writer.as_ref().is_some_and(|writer| !writer.is_finished())
+        {
+            return;
+        }
+        let buffers = self.capture_buffers();
+        let Some(path) = ui_logic::autosave_path() else {
+            return;
+        };
+        if buffers.is_empty() {
+            return;
+        }
+        let saved_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.autosave_writer = Some(std::thread::spawn(move || {
+            if let Err(e) = ui_logic::write_autosave(&path, &buffers, saved_at) {
+                log::warn!("Auto-save failed: {:#}", e);
+            }
+        }));
+    }
+
+    fn restore_autosave(&mut self) {
+        let Some(save) = self.autosave_recovery.take() else {
+            return;
+        };
+        self.rtt_buffers = save.buffers.rtt_buffers;
+        self.timeline_events = save.buffers.timeline_events;
+        self.plot_names = save.buffers.plots.keys().cloned().collect();
+        self.plot_names.sort();
+        self.plots = save.buffers.plots;
+        self.status_message = "Restored auto-saved capture".to_string();
+    }
+
+    fn discard_autosave(&mut self) {
+        self.autosave_recovery = None;
+        if let Some(path) = ui_logic::autosave_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn request_peripheral_dump(&mut self, purpose: DumpPurpose) {
+        if let Some(handle) = &self.session_handle {
+            self.dump_purpose = purpose;
+            let _ = handle.send(aether_core::DebugCommand::DumpAllPeripherals);
+        }
+    }
+
+    /// Show `target` in the memory view. Symbols are resolved by the session.
+    fn open_memory_target(&mut self, target: aether_core::config::BookmarkTarget) {
+        let (cmd, pending) = match target {
+            aether_core::config::BookmarkTarget::Address(addr) => {
+                self.memory_base_address = addr;
+                self.memory_address_input = format!("0x{:08X}", addr);
+                (aether_core::DebugCommand::ReadMemory(addr, self.memory_window), None)
+            }
+            target => {
+                self.memory_address_input = target.to_string();
+                let cmd = aether_core::DebugCommand::ReadBookmark {
+                    target: target.clone(),
+                    len: self.memory_window,
+                };
+                (cmd, Some(target))
+            }
+        };
+        if let Some(handle) = &self.session_handle {
+            self.memory_symbol_pending = pending;
+            let _ = handle.send(cmd);
+        }
+    }
+
+    fn open_bookmark(&mut self, index: usize) {
+        let Some(bookmark) = self.session_config.bookmarks.get(index).cloned() else {
+            return;
+        };
+        self.memory_window = bookmark.window;
+        self.memory_words = bookmark.words;
+        self.open_memory_target(bookmark.target);
+    }
+
+    /// Bookmark the address in the memory view, replacing one with the same name.
+    fn add_bookmark(&mut self) {
+        let input = self.memory_address_input.trim();
+        let target = aether_core::config::BookmarkTarget::parse(input).or_else(|| {
+            u64::from_str_radix(input, 16).ok().map(aether_core::config::BookmarkTarget::Address)
+        });
+        let Some(target) = target else {
+            self.status_message = format!("Cannot bookmark '{}'", input);
+            return;
+        };
+        let name = match self.bookmark_name_input.trim() {
+            "" => target.to_string(),
+            name => name.to_string(),
+        };
+        self.session_config.bookmarks.retain(|b| b.name != name);
+        self.session_config.bookmarks.push(aether_core::config::MemoryBookmark {
+            name,
+            target,
+            window: self.memory_window,
+            words: self.memory_words,
+        });
+        self.bookmark_name_input.clear();
+        self.save_session_config();
+    }
+
+    fn save_session_config(&mut self) {
+        let Some(path) = aether_core::config::SessionConfig::default_path() else {
+            return;
+        };
+        if let Err(e) = self.session_config.save(&path) {
+            self.status_message = format!("Failed to save bookmarks: {:#}", e);
+        }
+    }
+
+    fn save_peripheral_dump(&self) {
+        let Some(dump) = &self.peripheral_dump else {
+            return;
+        };
+        if let Some(path) = safe_save_file("Peripheral Dump", &["json", "txt"]) {
+            let contents = if path.extension().is_some_and(|e| e == "json") {
+                serde_json::to_string_pretty(dump).unwrap_or_default()
+            } else {
+                dump.to_text()
+            };
+            let _ = std::fs::write(path, contents);
+        }
+    }
+
     fn import_session(&mut self) {
         if let Some(path) = safe_pick_file("Aether Session", &["json"]) {
             if let Ok(json) = std::fs::read_to_string(path) {
@@ -207,6 +515,44 @@ impl AetherApp {
         }
     }
 
+    /// Dispatch core control commands bound to keys, unless a text field has focus.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        let Some(handle) = &self.session_handle else {
+            return;
+        };
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let pressed: Vec<(egui::Key, bool)> = ctx.input(|i| {
+            i.events
+                .iter()
+                .filter_map(|e| match e {
+                    egui::Event::Key { key, pressed: true, repeat: false, modifiers, .. } => {
+                        Some((*key, modifiers.shift))
+                    }
+                    _ => None,
+                })
+                .collect()
+        });
+        let breakpoints_disabled = self.disabled_reason(ui_logic::Feature::Breakpoints).is_some();
+        for (key, shift) in pressed {
+            let command = ui_logic::shortcut_action(&self.key_bindings, key.name(), shift)
+                .filter(|action| {
+                    !(breakpoints_disabled && *action == ui_logic::ShortcutAction::ToggleBreakpoint)
+                })
+                .and_then(|action| {
+                    ui_logic::shortcut_command(
+                        action,
+                        self.registers.get(&15).copied(),
+                        &self.breakpoints,
+                    )
+                });
+            if let Some(command) = command {
+                let _ = handle.send(command);
+            }
+        }
+    }
+
     fn setup_fonts(ctx: &egui::Context) {
         let mut fonts = egui::FontDefinitions::default();
 
@@ -250,6 +596,9 @@ impl AetherApp {
             connection_status: ConnectionStatus::Disconnected,
             status_message: "Ready".to_string(),
             session_handle: None,
+            shutdown: ui_logic::ShutdownSignal::new(),
+            session_server: None,
+            worker_threads: Vec::new(),
             event_receiver: None,
             registers: HashMap::new(),
             core_status: None,
@@ -257,7 +606,43 @@ impl AetherApp {
             memory_data: Vec::new(),
             memory_address_input: "0x20000000".to_string(),
             memory_base_address: 0x20000000,
+            memory_window: ui_logic::DEFAULT_MEMORY_WINDOW,
+            memory_symbol_pending: None,
+            memory_snapshot: None,
+            memory_diff: None,
+            session_config: aether_core::config::SessionConfig::default_path()
+                .and_then(|path| {
+                    aether_core::config::SessionConfig::load(&path)
+                        .map_err(|e| log::warn!("{:#}", e))
+                        .ok()
+                })
+                .unwrap_or_default(),
+            last_autosave: std::time::Instant::now(),
+            autosave_writer: None,
+            autosave_recovery: ui_logic::autosave_path()
+                .and_then(|path| {
+                    ui_logic::read_autosave(&path).map_err(|e| log::warn!("{:#}", e)).ok()?
+                })
+                .filter(|save| !save.buffers.is_empty()),
+            bookmark_name_input: String::new(),
+            key_bindings: ui_logic::default_key_bindings(),
+            write_log: Vec::new(),
+            tracepoint_reads_input: String::new(),
+            reset_resync: false,
+            attach_reset_recovery: false,
+            background_paused: false,
+            flash_speed_cap_khz: aether_core::flash::DEFAULT_FLASH_SPEED_CAP_KHZ,
+            flash_keep_unwritten: None,
+            reset_kind: Default::default(),
+            target_voltage: None,
+            last_attach: None,
+            vector_catch: Default::default(),
+            tracepoint_log: std::collections::VecDeque::new(),
+            itm_console: String::new(),
+            memory_words: false,
+            register_bases: ui_logic::RegisterBases::default(),
             disassembly: Vec::new(),
+            disassembly_source: HashMap::new(),
             breakpoints: Vec::new(),
             breakpoint_address_input: "0x08000000".to_string(),
             selected_file: None,
@@ -267,16 +652,32 @@ impl AetherApp {
             peripherals: Vec::new(),
             selected_peripheral: None,
             peripheral_registers: Vec::new(),
+            peripheral_filter: String::new(),
+            peripheral_dump: None,
+            dump_purpose: DumpPurpose::Report,
+            peripheral_snapshot: None,
+            snapshot_changes: Vec::new(),
             expanded_registers: std::collections::HashSet::new(),
+            peripheral_live: false,
+            peripheral_poll_ms: 500,
+            changed_fields: std::collections::HashSet::new(),
             rtt_attached: false,
             rtt_up_channels: Vec::new(),
             rtt_down_channels: Vec::new(),
             rtt_selected_channel: None,
             rtt_display_modes: std::collections::HashMap::new(),
+            rtt_encodings: std::collections::HashMap::new(),
             rtt_buffers: std::collections::HashMap::new(),
             rtt_raw_buffers: std::collections::HashMap::new(),
+            rtt_merged: ui_logic::RttMergedLog::default(),
+            event_batcher: ui_logic::EventBatcher::default(),
+            rtt_show_all: false,
+            rtt_ansi_colors: true,
             rtt_input: String::new(),
+            rtt_address_input: String::new(),
             symbols_loaded: false,
+            build_id: None,
+            source_files: Vec::new(),
             source_info: None,
             breakpoint_locations: Vec::new(),
             source_cache: HashMap::new(),
@@ -288,10 +689,17 @@ impl AetherApp {
             remote_host: "localhost".to_string(),
             remote_port: "50051".to_string(),
             is_remote: false,
+            capabilities_receiver: None,
+            features: ui_logic::feature_enablement(&aether_core::Capabilities::all()),
             tasks: Vec::new(),
+            sync_objects: Vec::new(),
+            timebase: None,
             timeline_events: Vec::new(),
+            trace_stats: None,
             stack_frames: Vec::new(),
             watched_variables: Vec::new(),
+            variable_formats: HashMap::new(),
+            live_watch: false,
             variable_input: String::new(),
             syntax_set: SyntaxSet::load_defaults_newlines(),
             theme_set: ThemeSet::load_defaults(),
@@ -308,6 +716,7 @@ impl AetherApp {
                         DebugTab::Variables,
                         DebugTab::Stack,
                         DebugTab::Tasks,
+                        DebugTab::SyncObjects,
                         DebugTab::Timeline,
                         DebugTab::Rtt,
                         DebugTab::Agent,
@@ -353,8 +762,11 @@ impl AetherApp {
 
         let (evt_tx, evt_rx) = tokio::sync::broadcast::channel(1024);
         self.event_receiver = Some(evt_rx);
+        let (caps_tx, caps_rx) = unbounded();
+        self.capabilities_receiver = Some(caps_rx);
+        let shutdown = self.shutdown.clone();
 
-        std::thread::spawn(move || {
+        let worker = std::thread::spawn(move || {
             let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
 
             rt.block_on(async {
@@ -365,16 +777,47 @@ impl AetherApp {
                 .await
                 {
                     Ok(mut client) => {
-                        // Success!
+                        // Agents without GetInfo are assumed to support only run control.
+                        let caps = match client.get_info(aether_agent_api::proto::Empty {}).await {
+                            Ok(info) => info
+                                .into_inner()
+                                .capabilities
+                                .as_ref()
+                                .map(aether_agent_api::map_proto_capabilities_to_core)
+                                .unwrap_or_default(),
+                            Err(e) => {
+                                log::warn!("Remote agent did not report capabilities: {}", e);
+                                aether_core::Capabilities::default()
+                            }
+                        };
+                        let _ = caps_tx.send(caps);
+
                         if let Ok(response) =
                             client.subscribe_events(aether_agent_api::proto::Empty {}).await
                         {
                             let mut stream = response.into_inner();
-                            while let Some(Ok(proto_event)) = stream.next().await {
-                                if let Some(core_event) =
-                                    aether_agent_api::map_proto_event_to_core(proto_event)
+                            let mut last_seq = 0;
+                            loop {
+                                let proto_event = tokio::select! {
+                                    _ = shutdown.wait() => break,
+                                    next = stream.next() => match next {
+                                        Some(Ok(proto_event)) => proto_event,
+                                        _ => break,
+                                    },
+                                };
+                                if let Some(sequenced) =
+                                    aether_agent_api::map_proto_event_to_sequenced(proto_event)
                                 {
-                                    let _ = evt_tx.send(core_event);
+                                    // Events without a proto mapping also leave gaps, so only log them.
+                                    if last_seq != 0 && sequenced.seq > last_seq + 1 {
+                                        log::debug!(
+                                            "Remote event sequence jumped from {} to {}",
+                                            last_seq,
+                                            sequenced.seq
+                                        );
+                                    }
+                                    last_seq = sequenced.seq;
+                                    let _ = evt_tx.send(sequenced.event);
                                 }
                             }
                         }
@@ -385,6 +828,7 @@ impl AetherApp {
                 }
             });
         });
+        self.worker_threads.push(worker);
     }
 
     #[cfg(feature = "hardware")]
@@ -406,59 +850,25 @@ impl AetherApp {
                                 target.name
                             );
 
-                            // Create SessionHandle which consumes the session
-                            match aether_core::SessionHandle::new(Some(session)) {
-                                Ok(handle) => {
-                                    let handle = Arc::new(handle);
-                                    self.event_receiver = Some(handle.subscribe());
-                                    self.session_handle = Some(handle.clone());
-                                    self.connection_status = ConnectionStatus::Connected;
-
-                                    // Spawn Agent API Server
-                                    let server_handle = handle.clone();
-                                    std::thread::spawn(move || {
-                                        let rt = tokio::runtime::Builder::new_current_thread()
-                                            .enable_all()
-                                            .build()
-                                            .unwrap();
-
-                                        rt.block_on(async {
-                                            if let Err(e) = aether_agent_api::run_server(
-                                                server_handle,
-                                                "0.0.0.0",
-                                                50051,
-                                            )
-                                            .await
-                                            {
-                                                log::error!("Agent API Server Error: {}", e);
-                                            }
-                                        });
-                                    });
-
-                                    // Initial Poll
-                                    if let Some(h) = &self.session_handle {
-                                        let _ = h.send(aether_core::DebugCommand::PollStatus);
-                                        let _ = h.send(aether_core::DebugCommand::GetTasks);
-                                        // Request some registers
-                                        for i in 0..16 {
-                                            let _ =
-                                                h.send(aether_core::DebugCommand::ReadRegister(i));
-                                        }
-                                        // Request initial memory
-                                        let _ = h.send(aether_core::DebugCommand::ReadMemory(
-                                            self.memory_base_address,
-                                            256,
-                                        ));
-                                        // Request current breakpoints
-                                        let _ = h.send(aether_core::DebugCommand::ListBreakpoints);
+                            self.last_attach =
+                                self.probes[index].serial_number.clone().map(|serial| {
+                                    aether_core::reconnect::ReconnectTarget {
+                                        serial,
+                                        chip: target.name.clone(),
+                                        protocol: None,
+                                        under_reset: false,
+                                        speed_khz: None,
                                     }
-                                }
-                                Err(e) => {
-                                    self.connection_status = ConnectionStatus::Error;
-                                    self.status_message =
-                                        format!("Failed to create session: {}", e);
-                                }
-                            }
+                                });
+
+                            // Create SessionHandle which consumes the session
+                            let handle = aether_core::SessionHandle::with_reconnect(
+                                session,
+                                self.last_attach.clone(),
+                            );
+                            self.start_session(handle);
+                            self.connection_status = ConnectionStatus::Connected;
+                            self.request_initial_state();
                         }
                         Err(e) => {
                             self.connection_status = ConnectionStatus::Error;
@@ -474,6 +884,127 @@ impl AetherApp {
         }
     }
 
+    /// Take over `handle`: subscribe to its events and serve it to agents.
+    #[cfg(feature = "hardware")]
+    fn start_session(&mut self, handle: aether_core::SessionHandle) {
+        self.stop_session();
+        let handle = Arc::new(handle);
+        self.event_receiver = Some(handle.subscribe());
+        self.session_handle = Some(handle.clone());
+
+        // Spawn Agent API Server
+        let shutdown = self.shutdown.clone();
+        let stop = ui_logic::ShutdownSignal::new();
+        let server_stop = stop.clone();
+        let server = std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+            rt.block_on(async {
+                if let Err(e) = aether_agent_api::run_server_with_shutdown(
+                    handle,
+                    "0.0.0.0",
+                    50051,
+                    async move {
+                        tokio::select! {
+                            _ = shutdown.wait() => {}
+                            _ = server_stop.wait() => {}
+                        }
+                    },
+                )
+                .await
+                {
+                    log::error!("Agent API Server Error: {}", e);
+                }
+            });
+        });
+        self.session_server = Some((stop, server));
+    }
+
+    /// End the current session and stop its agent server, releasing the probe
+    /// and the server port. The server holds a handle too, so dropping ours
+    /// alone would keep the session running.
+    fn stop_session(&mut self) {
+        if let Some((stop, server)) = self.session_server.take() {
+            stop.trigger();
+            if server.join().is_err() {
+                log::error!("Agent API server thread panicked");
+            }
+        }
+        if let Some(handle) = self.session_handle.take() {
+            handle.shutdown();
+        }
+    }
+
+    /// Initial poll after attaching.
+    fn request_initial_state(&self) {
+        if let Some(h) = &self.session_handle {
+            let _ = h.send(aether_core::DebugCommand::PollStatus);
+            let _ = h.send(aether_core::DebugCommand::GetTasks);
+            // Request some registers
+            for i in 0..=ui_logic::XPSR_REGISTER_ID {
+                let _ = h.send(aether_core::DebugCommand::ReadRegister(i));
+            }
+            // Request initial memory
+            let _ = h.send(aether_core::DebugCommand::ReadMemory(
+                self.memory_base_address,
+                self.memory_window,
+            ));
+            // Request current breakpoints
+            let _ = h.send(aether_core::DebugCommand::ListBreakpoints);
+        }
+    }
+
+    /// Re-attach the running session once the probe it lent out is back.
+    #[cfg(feature = "hardware")]
+    fn reattach_session(&mut self) {
+        let Some(last) = self.last_attach.clone() else {
+            return;
+        };
+        self.refresh_probes();
+        match (last.attach_command(&self.probes), &self.session_handle) {
+            (Some(command), Some(handle)) => {
+                let _ = handle.send(aether_core::DebugCommand::SetAttachResetRecovery(
+                    self.attach_reset_recovery,
+                ));
+                let _ = handle.send(command);
+                self.status_message = format!("Re-attaching to {}...", last.chip);
+            }
+            _ => self.status_message = format!("Probe {} not found", last.serial),
+        }
+    }
+
+    /// Attach again with the parameters of the last successful attach, finding
+    /// the probe by serial in case it re-enumerated.
+    #[cfg(feature = "hardware")]
+    fn reconnect_probe(&mut self) {
+        let Some(last) = self.last_attach.clone() else {
+            return;
+        };
+        self.refresh_probes();
+        let Some(command) = last.attach_command(&self.probes) else {
+            self.status_message = format!("Probe {} not found", last.serial);
+            return;
+        };
+
+        // Release the probe if a session still holds it.
+        self.stop_session();
+        match aether_core::SessionHandle::new(None) {
+            Ok(handle) => {
+                let _ = handle.send(aether_core::DebugCommand::SetAttachResetRecovery(
+                    self.attach_reset_recovery,
+                ));
+                let _ = handle.send(command);
+                self.start_session(handle);
+                self.connection_status = ConnectionStatus::Connecting;
+                self.status_message = format!("Reconnecting to {}...", last.chip);
+            }
+            Err(e) => {
+                self.connection_status = ConnectionStatus::Error;
+                self.status_message = format!("Failed to create session: {}", e);
+            }
+        }
+    }
+
     #[cfg(feature = "hardware")]
     fn start_flashing(&mut self) {
         let file_path = if let Some(path) = &self.selected_file {
@@ -487,24 +1018,9 @@ impl AetherApp {
             return;
         }
 
-        // NOTE: Flashing currently re-opens the probe in a separate thread.
-        // This conflicts with SessionHandle which OWNS the probe.
-        // For Milestone 3, we should ideally integrate flashing into SessionHandle,
-        // OR drop SessionHandle temporarily.
-        // For now, let's warn user or implement dropping.
-
-        // Drop existing session to release probe
-        self.session_handle = None;
-        self.connection_status = ConnectionStatus::Disconnected; // Will reconnect after?
-
-        // ... Flashing Logic (Same as before but we lost connection) ...
-        // Real implementation would pass Session to FlashManager.
-        // But FlashManager takes `&mut Session`. SessionHandle runs in thread.
-        // Complex. For now, let's keep the existing "Re-open" logic but we MUST ensure the probe is free.
-        // Dropping session_handle frees the probe (thread finishes).
-
-        // Wait a bit for thread to backend drop?
-        // Let's just proceed with existing flashing logic, but user has to reconnect.
+        // Flashing re-opens the probe at a faster clock, so borrow it from the
+        // session, which pauses until the loan is dropped and then re-attaches.
+        let probe_lock = self.session_handle.as_ref().map(|h| h.probe_lock());
 
         let (tx, rx) = unbounded();
         self.progress_receiver = Some(rx);
@@ -513,47 +1029,91 @@ impl AetherApp {
 
         let probe_index = self.selected_probe.unwrap();
         let flash_manager = aether_core::FlashManager::new();
-
-        std::thread::spawn(move || {
-            // Need a slight delay to ensure previous session dropped?
-            std::thread::sleep(std::time::Duration::from_millis(100));
+        let speed_cap_khz = self.flash_speed_cap_khz;
+        let target_max_khz = self.last_attach.as_ref().and_then(|a| a.speed_khz);
+        let keep_unwritten = self.flash_keep_unwritten;
+
+        let worker = std::thread::spawn(move || {
+            let _loan = match probe_lock
+                .map(|lock| lock.acquire(aether_core::probe_lock::DEFAULT_HANDOFF_TIMEOUT))
+                .transpose()
+            {
+                Ok(loan) => loan,
+                Err(e) => {
+                    let _ = tx.send(aether_core::FlashingProgress::Failed);
+                    log::error!("Failed to take over the probe: {}", e);
+                    return;
+                }
+            };
 
             let probe_manager = aether_core::ProbeManager::new();
-            match probe_manager.open_probe(probe_index) {
-                Ok(probe) => {
-                    match probe.attach("any", probe_rs::Permissions::default()) {
-                        Ok(mut session) => {
-                            let (mpsc_tx, mpsc_rx) = mpsc::channel();
-                            let progress =
-                                aether_core::MpscFlashProgress::new(mpsc_tx).into_flash_progress();
-
-                            let tx_clone = tx.clone();
-                            std::thread::spawn(move || {
-                                while let Ok(p) = mpsc_rx.recv() {
-                                    let _ = tx_clone.send(p);
-                                }
-                            });
-
-                            if let Err(e) =
-                                flash_manager.flash_elf(&mut session, &file_path, progress)
-                            {
-                                let _ = tx.send(aether_core::FlashingProgress::Failed);
-                                log::error!("Flashing failed: {}", e);
-                            } else {
-                                // Flashing done
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(aether_core::FlashingProgress::Failed);
-                            log::error!("Failed to attach: {}", e);
+            match aether_core::flash::attach_for_flashing(
+                &probe_manager,
+                probe_index,
+                speed_cap_khz,
+                target_max_khz,
+            ) {
+                Ok((mut session, speed_khz)) => {
+                    let _ = tx.send(aether_core::FlashingProgress::Message(format!(
+                        "Probe clock: {} kHz",
+                        speed_khz
+                    )));
+                    let (mpsc_tx, mpsc_rx) = mpsc::channel();
+                    let progress =
+                        aether_core::MpscFlashProgress::new(mpsc_tx).into_flash_progress();
+
+                    let tx_clone = tx.clone();
+                    std::thread::spawn(move || {
+                        while let Ok(p) = mpsc_rx.recv() {
+                            let _ = tx_clone.send(p);
                         }
+                    });
+
+                    if let Err(e) =
+                        flash_manager.flash_file(&mut session, &file_path, keep_unwritten, progress)
+                    {
+                        let _ = tx.send(aether_core::FlashingProgress::Failed);
+                        log::error!("Flashing failed: {}", e);
+                    } else {
+                        // Flashing done
                     }
                 }
-                Err(_) => {
+                Err(e) => {
                     let _ = tx.send(aether_core::FlashingProgress::Failed);
+                    log::error!("Failed to attach: {}", e);
                 }
             }
         });
+        self.worker_threads.push(worker);
+    }
+
+    fn update_capabilities(&mut self) {
+        if let Some(rx) = &self.capabilities_receiver {
+            if let Ok(caps) = rx.try_recv() {
+                self.features = ui_logic::feature_enablement(&caps);
+                self.capabilities_receiver = None;
+            }
+        }
+    }
+
+    /// Why `feature` is unavailable in the current session, if it is.
+    fn disabled_reason(&self, feature: ui_logic::Feature) -> Option<&str> {
+        self.features.get(&feature).and_then(|reason| reason.as_deref())
+    }
+
+    /// A button that is greyed out, explaining why on hover, when the agent lacks `feature`.
+    fn feature_button(
+        &self,
+        ui: &mut egui::Ui,
+        feature: ui_logic::Feature,
+        text: impl Into<egui::WidgetText>,
+    ) -> egui::Response {
+        let reason = self.disabled_reason(feature);
+        let response = ui.add_enabled(reason.is_none(), egui::Button::new(text));
+        match reason {
+            Some(reason) => response.on_disabled_hover_text(reason),
+            None => response,
+        }
     }
 
     fn update_flashing(&mut self) {
@@ -603,21 +1163,20 @@ impl AetherApp {
         }
     }
 
-    fn process_debug_events(&mut self) {
+    fn process_debug_events(&mut self, scrolling: bool) {
         let handle = if let Some(h) = &self.session_handle {
             h.clone()
         } else {
             return;
         };
 
-        let mut events = Vec::new();
         if let Some(rx) = &mut self.event_receiver {
             while let Ok(event) = rx.try_recv() {
-                events.push(event);
+                self.event_batcher.push(event);
             }
         }
 
-        for event in events {
+        for event in self.event_batcher.next_frame(scrolling) {
             match event {
                 aether_core::DebugEvent::Status(status) => {
                     self.core_status = Some(status);
@@ -627,18 +1186,25 @@ impl AetherApp {
                     // Update status
                     let _ = handle.send(aether_core::DebugCommand::PollStatus);
                     // Update registers
-                    for i in 0..16 {
+                    for i in 0..=ui_logic::XPSR_REGISTER_ID {
                         let _ = handle.send(aether_core::DebugCommand::ReadRegister(i));
                     }
                     // Update memory
-                    let _ = handle
-                        .send(aether_core::DebugCommand::ReadMemory(self.memory_base_address, 256));
+                    let _ = handle.send(aether_core::DebugCommand::ReadMemory(
+                        self.memory_base_address,
+                        self.memory_window,
+                    ));
                     // Request disassembly
                     let _ = handle.send(aether_core::DebugCommand::Disassemble(pc, 64)); // 32 instructions roughly
                                                                                          // Request source info
                     let _ = handle.send(aether_core::DebugCommand::LookupSource(pc));
                     // Request stack
                     let _ = handle.send(aether_core::DebugCommand::GetStack);
+                    // Refresh watches in one batch
+                    if !self.watched_variables.is_empty() {
+                        let names = self.watched_variables.iter().map(|v| v.name.clone()).collect();
+                        let _ = handle.send(aether_core::DebugCommand::RefreshWatches(names));
+                    }
                 }
                 aether_core::DebugEvent::Resumed => {
                     self.status_message = "Running...".to_string();
@@ -648,13 +1214,57 @@ impl AetherApp {
                 aether_core::DebugEvent::RegisterValue(address, value) => {
                     self.registers.insert(address, value);
                 }
+                aether_core::DebugEvent::BookmarkResolved { target, address } => {
+                    if self.memory_symbol_pending.as_ref() == Some(&target) {
+                        self.memory_symbol_pending = None;
+                        self.memory_base_address = address;
+                    }
+                }
                 aether_core::DebugEvent::MemoryData(address, data) => {
                     if address == self.memory_base_address {
                         self.memory_data = data;
                     }
                 }
+                aether_core::DebugEvent::MemorySnapshot(snapshot) => {
+                    if snapshot.address == self.memory_base_address {
+                        self.memory_data = snapshot.data.clone();
+                    }
+                    self.status_message = match &self.memory_snapshot {
+                        Some(previous) => {
+                            let diff = aether_core::memory::snapshot::diff_memory_snapshots(
+                                previous, &snapshot,
+                            );
+                            let message = format!(
+                                "Snapshot {}: {} byte(s) changed since snapshot {}",
+                                snapshot.id,
+                                diff.changes.len(),
+                                previous.id
+                            );
+                            self.memory_diff = Some(diff);
+                            message
+                        }
+                        None => format!("Snapshot {} taken", snapshot.id),
+                    };
+                    self.memory_snapshot = Some(snapshot);
+                }
+                aether_core::DebugEvent::MemoryDiff { diff, .. } => {
+                    self.memory_diff = Some(diff);
+                }
                 aether_core::DebugEvent::Disassembly(insns) => {
                     self.disassembly = insns;
+                    self.disassembly_source.clear();
+                }
+                aether_core::DebugEvent::SourceDisassembly(blocks) => {
+                    self.disassembly_source = blocks
+                        .iter()
+                        .filter_map(|block| {
+                            let info = block.source_line.as_ref()?;
+                            let name = info.file.file_name()?.to_string_lossy();
+                            let address = block.instructions.first()?.address;
+                            Some((address, format!("{}:{}", name, info.line)))
+                        })
+                        .collect();
+                    self.disassembly = blocks.into_iter().flat_map(|b| b.instructions).collect();
                 }
                 aether_core::DebugEvent::Breakpoints(bps) => {
                     self.breakpoints = bps;
@@ -686,12 +1296,17 @@ impl AetherApp {
                         raw_buf.drain(0..truncate_at);
                     }
 
-                    let text = String::from_utf8_lossy(&data).to_string();
+                    let encoding = self.rtt_encodings.get(&channel).copied().unwrap_or_default();
+                    let text = ui_logic::decode_rtt(&data, encoding);
+                    self.rtt_merged.push(channel, &text);
                     self.rtt_buffers.entry(channel).or_default().push_str(&text);
                     // Limit buffer size to 64KB for performance
                     if self.rtt_buffers.get(&channel).map_or(0, |s| s.len()) > 65536 {
                         let buf = self.rtt_buffers.get_mut(&channel).unwrap();
-                        let truncate_at = buf.len() - 65536;
+                        let mut truncate_at = buf.len() - 65536;
+                        while !buf.is_char_boundary(truncate_at) {
+                            truncate_at += 1;
+                        }
                         *buf = buf[truncate_at..].to_string();
                     }
                 }
@@ -708,47 +1323,72 @@ impl AetherApp {
                 aether_core::DebugEvent::Tasks(tasks) => {
                     self.tasks = tasks;
                 }
+                aether_core::DebugEvent::SyncObjects(objects) => {
+                    self.sync_objects = objects;
+                }
+                aether_core::DebugEvent::TimebaseInfo { core_hz, tick_hz } => {
+                    self.timebase = Some((core_hz, tick_hz));
+                }
                 aether_core::DebugEvent::TaskSwitch { from, to, timestamp } => {
-                    // 1. Close previous task if it exists
-                    if let Some(from_handle) = from {
-                        if let Some(event) = self
-                            .timeline_events
-                            .iter_mut()
-                            .rev()
-                            .find(|e| e.task_handle == from_handle && e.end_time.is_none())
-                        {
-                            event.end_time = Some(timestamp);
-                        }
-                    }
-
-                    // 2. Open new task
                     let name = self
                         .tasks
                         .iter()
                         .find(|t| t.handle == to)
                         .map(|t| t.name.clone())
                         .unwrap_or_else(|| format!("0x{:08X}", to));
-                    self.timeline_events.push(TimelineEvent {
-                        task_handle: to,
-                        task_name: name,
-                        start_time: timestamp,
-                        end_time: None,
-                    });
-
-                    // Prune history (keep last 500 events for performance)
-                    if self.timeline_events.len() > 500 {
-                        self.timeline_events.remove(0);
-                    }
+                    ui_logic::record_task_switch(
+                        &mut self.timeline_events,
+                        from,
+                        to,
+                        name,
+                        timestamp,
+                    );
+                }
+                aether_core::DebugEvent::Isr { exception, action, timestamp } => {
+                    ui_logic::record_isr(&mut self.timeline_events, exception, action, timestamp);
                 }
                 aether_core::DebugEvent::Stack(frames) => {
                     self.stack_frames = frames;
                 }
                 aether_core::DebugEvent::Registers(regs) => {
+                    self.changed_fields =
+                        ui_logic::changed_fields(&self.peripheral_registers, &regs);
                     self.peripheral_registers = regs;
                 }
+                aether_core::DebugEvent::PeripheralDump(dump) => {
+                    match self.dump_purpose {
+                        DumpPurpose::Report => {
+                            self.status_message =
+                                format!("Dumped {} peripherals", dump.peripherals.len());
+                        }
+                        DumpPurpose::Snapshot => {
+                            self.snapshot_changes.clear();
+                            self.peripheral_snapshot = Some(dump.clone());
+                            self.status_message = "Peripheral snapshot captured".to_string();
+                        }
+                        DumpPurpose::Compare => {
+                            if let Some(before) = &self.peripheral_snapshot {
+                                self.snapshot_changes =
+                                    aether_core::peripheral_dump::diff_peripheral_snapshots(
+                                        before, &dump,
+                                    );
+                                self.status_message = format!(
+                                    "{} field(s) changed since snapshot",
+                                    self.snapshot_changes.len()
+                                );
+                            }
+                        }
+                    }
+                    self.dump_purpose = DumpPurpose::Report;
+                    self.peripheral_dump = Some(dump);
+                }
                 aether_core::DebugEvent::SymbolsLoaded => {
                     self.symbols_loaded = true;
                     self.status_message = "Symbols Loaded".to_string();
+                    let _ = handle.send(aether_core::DebugCommand::ListSourceFiles);
+                }
+                aether_core::DebugEvent::BuildId(id) => {
+                    self.build_id = id;
                 }
                 aether_core::DebugEvent::SourceLocation(info) => {
                     // Load source file if not in cache
@@ -758,12 +1398,26 @@ impl AetherApp {
                                 content.lines().map(|s| s.to_string()).collect();
                             let highlighted = self.highlight_file(&info.file, &content);
                             self.source_cache.insert(info.file.clone(), (lines, highlighted));
+                        } else {
+                            // Not on this machine; the ELF may carry it.
+                            let _ = handle.send(aether_core::DebugCommand::ReadEmbeddedSource(
+                                info.file.clone(),
+                            ));
                         }
                     }
                     self.source_info = Some(info);
                     // TODO: Focus Source tab in DockState (requires iterating/finding tab)
                     // if let Some(dock_state) = &mut self.dock_state { ... }
                 }
+                aether_core::DebugEvent::EmbeddedSource { file, text: Some(content) } => {
+                    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                    let highlighted = self.highlight_file(&file, &content);
+                    self.source_cache.insert(file, (lines, highlighted));
+                }
+                aether_core::DebugEvent::EmbeddedSource { text: None, .. } => {}
+                aether_core::DebugEvent::SourceFiles(files) => {
+                    self.source_files = files;
+                }
                 aether_core::DebugEvent::BreakpointLocations(locs) => {
                     self.breakpoint_locations = locs;
                 }
@@ -777,10 +1431,34 @@ impl AetherApp {
                         self.watched_variables.push(info);
                     }
                 }
+                aether_core::DebugEvent::WatchUpdate(infos) => {
+                    for info in infos {
+                        if let Some(pos) =
+                            self.watched_variables.iter().position(|v| v.name == info.name)
+                        {
+                            self.watched_variables[pos] = info;
+                        } else {
+                            self.watched_variables.push(info);
+                        }
+                    }
+                }
                 aether_core::DebugEvent::Error(e) => {
                     self.failed_requests.push(e.clone());
                     log::error!("Debug Error: {}", e);
                 }
+                aether_core::DebugEvent::OperationFailed { operation, error } => {
+                    // A bookmark whose symbol did not resolve leaves the view where it is.
+                    if operation == "Read memory" {
+                        self.memory_symbol_pending = None;
+                    }
+                    let message = match error.guidance() {
+                        Some(hint) => format!("{} failed: {}. {}", operation, error, hint),
+                        None => format!("{} failed: {}", operation, error),
+                    };
+                    log::error!("Debug Error: {}", message);
+                    self.status_message = message.clone();
+                    self.failed_requests.push(message);
+                }
                 aether_core::DebugEvent::TraceData(_data) => {
                     // Handle trace data (placeholder for visualization)
                 }
@@ -790,20 +1468,155 @@ impl AetherApp {
                 aether_core::DebugEvent::FlashStatus(s) => {
                     self.flashing_status = s;
                 }
+                aether_core::DebugEvent::AttachProgress(step) => {
+                    self.status_message = step;
+                }
+                aether_core::DebugEvent::ProbeLent(true) => {
+                    self.status_message = "Probe handed over for flashing".to_string();
+                }
+                aether_core::DebugEvent::ProbeLent(false) => {
+                    #[cfg(feature = "hardware")]
+                    self.reattach_session();
+                }
                 aether_core::DebugEvent::FlashDone => {
                     self.flashing_progress = Some(1.0);
                     self.flashing_status = "Flashing Successful".to_string();
                 }
+                aether_core::DebugEvent::Stepped { from_pc, to_pc, instruction } => {
+                    self.status_message = match instruction {
+                        Some(insn) => format!(
+                            "Stepped 0x{:08X}: {} {} -> 0x{:08X}",
+                            from_pc, insn.mnemonic, insn.op_str, to_pc
+                        ),
+                        None => format!("Stepped 0x{:08X} -> 0x{:08X}", from_pc, to_pc),
+                    };
+                }
+                aether_core::DebugEvent::TargetVoltage(volts) => {
+                    self.target_voltage = Some(volts);
+                }
+                aether_core::DebugEvent::VectorTable(entries) => {
+                    let named = entries.iter().filter(|entry| entry.symbol.is_some()).count();
+                    self.status_message =
+                        format!("Read {} vectors ({} with handler symbols)", entries.len(), named);
+                }
+                aether_core::DebugEvent::Checksum { address, len, algo, value } => {
+                    self.status_message = match address.checked_add(len) {
+                        Some(end) => format!(
+                            "{:?} of 0x{:08X}..0x{:08X}: 0x{:08X}",
+                            algo, address, end, value
+                        ),
+                        None => format!(
+                            "{:?} of {} bytes at 0x{:08X}: 0x{:08X}",
+                            algo, len, address, value
+                        ),
+                    };
+                }
+                aether_core::DebugEvent::ImageVerified(result) => {
+                    self.status_message = match (&result.flashed, result.matches()) {
+                        (_, true) => format!("Flashed image matches symbols ({})", result.loaded),
+                        (Some(flashed), false) => format!(
+                            "Symbols do not match the flashed image: loaded {}, flashed {}",
+                            result.loaded, flashed
+                        ),
+                        (None, false) => "No build ID found on the target".to_string(),
+                    };
+                }
+                aether_core::DebugEvent::Flags(flags) => {
+                    self.status_message = format!("xPSR: {}", ui_logic::format_psr_flags(&flags));
+                }
+                aether_core::DebugEvent::ActiveStackPointer { which, value } => {
+                    self.status_message = format!("Active SP: {} = 0x{:08X}", which.name(), value);
+                }
+                aether_core::DebugEvent::SpecialRegisterValue(reg, value) => {
+                    self.status_message = format!("{} = 0x{:02X}", reg.name(), value);
+                }
+                aether_core::DebugEvent::RegisterChanged { address, old, new } => {
+                    self.status_message =
+                        format!("0x{:08X} changed: 0x{:X} -> 0x{:X}", address, old, new);
+                }
+                aether_core::DebugEvent::VectorCatch(catch) => {
+                    self.vector_catch = catch;
+                }
+                aether_core::DebugEvent::DebugRegister(reg) => {
+                    self.status_message = format!(
+                        "{} (0x{:08X}) = 0x{:08X}",
+                        reg.name.as_deref().unwrap_or("Debug register"),
+                        reg.address,
+                        reg.value
+                    );
+                }
+                aether_core::DebugEvent::ResetPerformed(kind) => {
+                    self.status_message = format!("{} reset performed", kind.label());
+                }
+                aether_core::DebugEvent::TargetReset { pc } => {
+                    self.status_message = match pc {
+                        Some(pc) => format!("Target reset detected (PC 0x{:08X})", pc),
+                        None => "Target reset detected while running".to_string(),
+                    };
+                }
+                aether_core::DebugEvent::TracepointHit { address, values } => {
+                    if self.tracepoint_log.len() == ui_logic::MAX_TRACEPOINT_LOG {
+                        self.tracepoint_log.pop_front();
+                    }
+                    self.tracepoint_log
+                        .push_back(ui_logic::format_tracepoint_hit(address, &values));
+                }
+                aether_core::DebugEvent::WriteLog(log) => {
+                    self.write_log = log;
+                }
                 aether_core::DebugEvent::SemihostingOutput(msg) => {
                     self.status_message = format!("Semihosting: {}", msg);
                 }
                 aether_core::DebugEvent::ItmPacket(_) => {
                     // ITM Visualization pending
                 }
+                aether_core::DebugEvent::ItmConsole { text, .. } => {
+                    self.itm_console.push_str(&text);
+                    // Same 64KB cap as the RTT buffers
+                    if self.itm_console.len() > 65536 {
+                        let mut cut = self.itm_console.len() - 65536;
+                        while !self.itm_console.is_char_boundary(cut) {
+                            cut += 1;
+                        }
+                        self.itm_console.drain(..cut);
+                    }
+                }
+                aether_core::DebugEvent::BackgroundPaused(paused) => {
+                    self.background_paused = paused;
+                }
+                aether_core::DebugEvent::TraceStats(stats) => {
+                    self.trace_stats = Some(stats);
+                }
+                aether_core::DebugEvent::Reconnected(info) => {
+                    self.status_message = format!("Reconnected to {}", info.name);
+                    self.target_info = Some(info);
+                    self.connection_status = ConnectionStatus::Connected;
+                    let _ = handle.send(aether_core::DebugCommand::PollStatus);
+                }
+                aether_core::DebugEvent::Attached(info) => {
+                    self.status_message = match info.detection_method {
+                        aether_core::DetectionMethod::Requested => {
+                            format!("Attached to {}", info.name)
+                        }
+                        aether_core::DetectionMethod::Auto => {
+                            format!("Attached to {} (auto-detected)", info.name)
+                        }
+                        aether_core::DetectionMethod::Heuristic => format!(
+                            "Warning: chip not detected, attached as {}; select it explicitly if wrong",
+                            info.name
+                        ),
+                    };
+                    self.target_info = Some(info);
+                    self.connection_status = ConnectionStatus::Connected;
+                    self.request_initial_state();
+                }
                 aether_core::DebugEvent::Probes(_)
-                | aether_core::DebugEvent::Attached(_)
+                | aether_core::DebugEvent::Metrics(_)
+                | aether_core::DebugEvent::SourceBreakpointResolved { .. }
                 | aether_core::DebugEvent::SubSessionAttached(_, _)
-                | aether_core::DebugEvent::ParityDiverged { .. } => {}
+                | aether_core::DebugEvent::ParityDiverged { .. }
+                | aether_core::DebugEvent::PlotSummary { .. }
+                | aether_core::DebugEvent::Location { .. } => {}
             }
         }
     }
@@ -940,7 +1753,7 @@ stub.Resume(aether_pb2.Empty())
         ui.horizontal(|ui| {
             ui.heading("🛰 RTOS Analysis");
             ui.add_space(8.0);
-            if ui.button("🔄 Refresh").clicked() {
+            if self.feature_button(ui, ui_logic::Feature::Tasks, "🔄 Refresh").clicked() {
                 if let Some(h) = &self.session_handle {
                     let _ = h.send(aether_core::DebugCommand::GetTasks);
                 }
@@ -959,7 +1772,7 @@ stub.Resume(aether_pb2.Empty())
         let window = 1.0; // 1 second window
         let start_window = (now - window).max(0.0);
 
-        for event in &self.timeline_events {
+        for event in self.timeline_events.iter().filter(|e| !e.is_isr) {
             let start = event.start_time.max(start_window);
             let end = event.end_time.unwrap_or(now).max(start_window);
 
@@ -1050,19 +1863,64 @@ stub.Resume(aether_pb2.Empty())
     }
 
     pub(crate) fn draw_timeline_view(&mut self, ui: &mut egui::Ui) {
+        let now = self
+            .timeline_events
+            .iter()
+            .map(|e| e.end_time.unwrap_or(e.start_time))
+            .fold(0.0, f64::max);
+        let slices = ui_logic::task_slices(&self.timeline_events);
+        let idle_gaps = ui_logic::compute_idle_gaps(&slices, now);
+        let first_start = slices.iter().map(|s| s.0).fold(now, f64::min);
+        let idle_pct = ui_logic::idle_percentage(&idle_gaps, first_start, now);
+
         ui.horizontal(|ui| {
             ui.heading("📊 Execution Timeline");
             ui.add_space(8.0);
             if ui.button("🗑 Clear").clicked() {
                 self.timeline_events.clear();
             }
+            ui.add_space(8.0);
+            ui.label(format!("Idle/Unknown: {:.1}%", idle_pct))
+                .on_hover_text("Time between task slices where no task switch was recorded");
+            ui.add_space(8.0);
+            if ui.button("⏲ Read SysTick").clicked() {
+                if let Some(h) = &self.session_handle {
+                    let _ = h.send(aether_core::DebugCommand::ReadSysTick);
+                }
+            }
+            if let Some((core_hz, tick_hz)) = self.timebase {
+                ui.label(format!("Core {:.1} MHz, tick {:.1} Hz", core_hz as f64 / 1e6, tick_hz));
+            }
         });
 
+        if let Some(stats) = self.trace_stats {
+            egui::CollapsingHeader::new("Trace buffer").default_open(true).show(ui, |ui| {
+                egui::Grid::new("trace_stats_grid").num_columns(4).show(ui, |ui| {
+                    ui.label(format!("Bytes: {}", stats.bytes));
+                    ui.label(format!("Packets: {}", stats.packets));
+                    let normal = ui.visuals().text_color();
+                    let error_color =
+                        |n: u64| if n > 0 { egui::Color32::LIGHT_RED } else { normal };
+                    ui.colored_label(
+                        error_color(stats.decode_errors),
+                        format!("Decode errors: {}", stats.decode_errors),
+                    )
+                    .on_hover_text("Bytes that are not ITM packets: check the SWO baud rate");
+                    ui.colored_label(
+                        error_color(stats.overflows),
+                        format!("Overflows: {}", stats.overflows),
+                    )
+                    .on_hover_text("The ITM FIFO was full and dropped packets");
+                    ui.end_row();
+                });
+            });
+        }
+
         ui.separator();
 
         // Prepare task mappings for labels and coloring
         let mut sorted_handles: Vec<u32> = self.tasks.iter().map(|t| t.handle).collect();
-        for event in &self.timeline_events {
+        for event in self.timeline_events.iter().filter(|e| !e.is_isr) {
             if !sorted_handles.contains(&event.task_handle) {
                 sorted_handles.push(event.task_handle);
             }
@@ -1081,6 +1939,12 @@ stub.Resume(aether_pb2.Empty())
                 .unwrap_or_else(|| format!("0x{:08X}", handle));
             slot_to_name.insert(i as i32, name);
         }
+        // Idle/unaccounted time gets its own lane below the tasks.
+        let idle_slot = -1.0;
+        slot_to_name.insert(idle_slot as i32, "Idle/Unknown".to_string());
+        // Interrupt handlers share a lane above the tasks.
+        let isr_slot = sorted_handles.len() as f64;
+        slot_to_name.insert(isr_slot as i32, "ISRs".to_string());
 
         let plot = egui_plot::Plot::new("timeline_plot")
             .height(400.0)
@@ -1101,7 +1965,12 @@ stub.Resume(aether_pb2.Empty())
 
         plot.show(ui, |plot_ui| {
             for event in &self.timeline_events {
-                if let Some(&slot) = task_slots.get(&event.task_handle) {
+                let slot = if event.is_isr {
+                    Some(isr_slot)
+                } else {
+                    task_slots.get(&event.task_handle).copied()
+                };
+                if let Some(slot) = slot {
                     let start = event.start_time;
                     let end = event.end_time.unwrap_or_else(|| {
                         // If it's the latest event, show it ending at "now" (max start + buffer)
@@ -1120,12 +1989,16 @@ stub.Resume(aether_pb2.Empty())
                         [start, slot - 0.35],
                     ]);
 
-                    let color = egui::Color32::from_rgb(
-                        ((event.task_handle >> 16) & 0xFF) as u8,
-                        ((event.task_handle >> 8) & 0xFF) as u8,
-                        (event.task_handle & 0xFF) as u8,
-                    )
-                    .gamma_multiply(0.8);
+                    let color = if event.is_isr {
+                        egui::Color32::from_rgb(255, 120, 0)
+                    } else {
+                        egui::Color32::from_rgb(
+                            ((event.task_handle >> 16) & 0xFF) as u8,
+                            ((event.task_handle >> 8) & 0xFF) as u8,
+                            (event.task_handle & 0xFF) as u8,
+                        )
+                        .gamma_multiply(0.8)
+                    };
 
                     plot_ui.polygon(
                         egui_plot::Polygon::new(
@@ -1140,6 +2013,23 @@ stub.Resume(aether_pb2.Empty())
                     );
                 }
             }
+
+            for gap in &idle_gaps {
+                let rect = egui_plot::PlotPoints::from_iter(vec![
+                    [gap.start, idle_slot - 0.35],
+                    [gap.end, idle_slot - 0.35],
+                    [gap.end, idle_slot + 0.35],
+                    [gap.start, idle_slot + 0.35],
+                    [gap.start, idle_slot - 0.35],
+                ]);
+                plot_ui.polygon(
+                    egui_plot::Polygon::new(
+                        format!("Idle/Unknown (Duration: {:.1}ms)", (gap.end - gap.start) * 1000.0),
+                        rect,
+                    )
+                    .fill_color(egui::Color32::from_gray(90)),
+                );
+            }
         });
 
         ui.add_space(4.0);
@@ -1151,6 +2041,79 @@ stub.Resume(aether_pb2.Empty())
         ui.label("Vertical axis shows different RTOS tasks. Horizontal axis is session time (s).");
     }
 
+    pub(crate) fn draw_sync_objects_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("🔒 Queues & Semaphores");
+            ui.add_space(8.0);
+            if ui.button("🔄 Refresh").clicked() {
+                if let Some(h) = &self.session_handle {
+                    let _ = h.send(aether_core::DebugCommand::GetSyncObjects);
+                }
+            }
+        });
+
+        ui.separator();
+
+        if self.sync_objects.is_empty() {
+            ui.label("No registered objects. Objects must be added with vQueueAddToRegistry.");
+            return;
+        }
+
+        let task_name = |handle: u32| {
+            self.tasks
+                .iter()
+                .find(|t| t.handle == handle)
+                .map(|t| t.name.clone())
+                .unwrap_or_else(|| format!("0x{:08X}", handle))
+        };
+
+        egui::ScrollArea::vertical().id_salt("sync_objects_scroll").show(ui, |ui| {
+            egui::Grid::new("sync_objects_grid")
+                .striped(true)
+                .num_columns(6)
+                .spacing([25.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("Name").strong());
+                    ui.label(egui::RichText::new("Type").strong());
+                    ui.label(egui::RichText::new("Count").strong());
+                    ui.label(egui::RichText::new("Owner").strong());
+                    ui.label(egui::RichText::new("Waiting (send)").strong());
+                    ui.label(egui::RichText::new("Waiting (receive)").strong());
+                    ui.end_row();
+
+                    for obj in &self.sync_objects {
+                        ui.label(&obj.name).on_hover_text(format!("Handle: 0x{:08X}", obj.handle));
+                        ui.label(format!("{:?}", obj.kind));
+                        ui.label(format!("{}/{}", obj.count, obj.length));
+                        match obj.owner {
+                            Some(owner) if obj.priority_inversion => {
+                                ui.label(
+                                    egui::RichText::new(format!("⚠ {}", task_name(owner)))
+                                        .color(egui::Color32::from_rgb(255, 100, 100)),
+                                )
+                                .on_hover_text(
+                                    "Priority inversion: the owner has a lower priority than a blocked waiter",
+                                );
+                            }
+                            Some(owner) => {
+                                ui.label(task_name(owner));
+                            }
+                            None => {
+                                ui.label("-");
+                            }
+                        }
+                        let senders: Vec<String> =
+                            obj.waiting_to_send.iter().map(|h| task_name(*h)).collect();
+                        ui.label(senders.join(", "));
+                        let receivers: Vec<String> =
+                            obj.waiting_to_receive.iter().map(|h| task_name(*h)).collect();
+                        ui.label(receivers.join(", "));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
     pub(crate) fn draw_stack_view(&mut self, ui: &mut egui::Ui) {
         ui.heading("Call Stack");
 
@@ -1211,47 +2174,205 @@ stub.Resume(aether_pb2.Empty())
         egui::ScrollArea::both().id_salt("mem_view_scroll").show(ui, |ui| {
             ui.heading("Memory View");
 
+            let mut read_at = None;
+            let mut read_symbol = None;
             ui.horizontal(|ui| {
                 ui.label("Addr:");
-                if ui.text_edit_singleline(&mut self.memory_address_input).lost_focus() {
+                if ui
+                    .text_edit_singleline(&mut self.memory_address_input)
+                    .on_hover_text("Hex address or symbol, e.g. rx_ring+0x10")
+                    .lost_focus()
+                {
                     let addr_str = self.memory_address_input.trim_start_matches("0x");
-                    if let Ok(addr) = u64::from_str_radix(addr_str, 16) {
-                        self.memory_base_address = addr;
-                        if let Some(handle) = &self.session_handle {
-                            let _ = handle.send(aether_core::DebugCommand::ReadMemory(addr, 256));
+                    read_at = u64::from_str_radix(addr_str, 16).ok();
+                    if read_at.is_none() {
+                        read_symbol =
+                            aether_core::config::BookmarkTarget::parse(&self.memory_address_input);
+                    }
+                }
+                egui::ComboBox::from_id_salt("mem_window")
+                    .selected_text(format!("{} B", self.memory_window))
+                    .show_ui(ui, |ui| {
+                        for size in ui_logic::MEMORY_WINDOW_SIZES {
+                            if ui
+                                .selectable_value(
+                                    &mut self.memory_window,
+                                    size,
+                                    format!("{} B", size),
+                                )
+                                .clicked()
+                            {
+                                read_at = Some(self.memory_base_address);
+                            }
                         }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Read").clicked() {
+                    let addr_str = self.memory_address_input.trim_start_matches("0x");
+                    read_at = u64::from_str_radix(addr_str, 16).ok();
+                }
+                if ui.button("◀ Prev").clicked() {
+                    read_at = Some(ui_logic::prev_memory_page(
+                        self.memory_base_address,
+                        self.memory_window,
+                    ));
+                }
+                if ui.button("Next ▶").clicked() {
+                    read_at = Some(ui_logic::next_memory_page(
+                        self.memory_base_address,
+                        self.memory_window,
+                    ));
+                }
+            });
+
+            let mut open = None;
+            let mut remove = None;
+            let mut save = false;
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Bookmarks:");
+                for (i, bookmark) in self.session_config.bookmarks.iter().enumerate() {
+                    let button = ui.button(&bookmark.name).on_hover_text(format!(
+                        "{} ({} B)\nRight-click to remove",
+                        bookmark.target, bookmark.window
+                    ));
+                    if button.clicked() {
+                        open = Some(i);
+                    }
+                    if button.secondary_clicked() {
+                        remove = Some(i);
                     }
                 }
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.bookmark_name_input)
+                        .hint_text("name")
+                        .desired_width(80.0),
+                );
+                save = ui
+                    .button("★ Save")
+                    .on_hover_text("Bookmark this address, window size and format")
+                    .clicked();
             });
+            if let Some(i) = remove {
+                self.session_config.bookmarks.remove(i);
+                self.save_session_config();
+            }
+            if save {
+                self.add_bookmark();
+            }
+            if let Some(i) = open {
+                self.open_bookmark(i);
+            }
 
-            if ui.button("Read").clicked() {
-                let addr_str = self.memory_address_input.trim_start_matches("0x");
-                if let Ok(addr) = u64::from_str_radix(addr_str, 16) {
-                    self.memory_base_address = addr;
-                    if let Some(handle) = &self.session_handle {
-                        let _ = handle.send(aether_core::DebugCommand::ReadMemory(addr, 256));
+            if let Some(target) = read_symbol {
+                self.open_memory_target(target);
+            }
+            if let Some(addr) = read_at {
+                self.open_memory_target(aether_core::config::BookmarkTarget::Address(addr));
+            }
+        });
+
+        let endian = self.target_info.as_ref().map(|t| t.endian).unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.memory_words, format!("32-bit words ({:?} endian)", endian));
+            ui.menu_button("Copy as", |ui| {
+                for format in ui_logic::CopyFormat::ALL {
+                    if ui.button(format.label()).clicked() {
+                        ui.ctx().copy_text(format.format(&self.memory_data));
+                        ui.close();
                     }
                 }
+            });
+            if ui
+                .button("📷 Snapshot")
+                .on_hover_text("Snapshot this window; the next snapshot highlights what changed")
+                .clicked()
+            {
+                if let Some(handle) = &self.session_handle {
+                    let _ = handle.send(aether_core::DebugCommand::SnapshotMemory {
+                        address: self.memory_base_address,
+                        len: self.memory_window,
+                    });
+                }
+            }
+            if let Some(diff) = &self.memory_diff {
+                ui.label(format!("{} changed", diff.changes.len()));
+                if ui.button("Clear diff").clicked() {
+                    self.memory_diff = None;
+                    self.memory_snapshot = None;
+                }
             }
         });
 
-        egui::ScrollArea::vertical().id_salt("mem_hex").show(ui, |ui| {
+        if self.memory_words {
+            ui.monospace("Address    +0       +4       +8       +C                ASCII");
+        } else {
             ui.monospace("Address    00 01 02 03 04 05 06 07  08 09 0A 0B 0C 0D 0E 0F  ASCII");
-            ui.separator();
-
-            let bytes_per_line = 16;
-            for (i, chunk) in self.memory_data.chunks(bytes_per_line).enumerate() {
-                let addr = self.memory_base_address + (i * bytes_per_line) as u64;
+        }
+        ui.separator();
 
-                let (addr_str, hex_part, ascii_part) = ui_logic::format_memory_line(addr, chunk);
-                ui.monospace(format!("{}   {} {}", addr_str, hex_part, ascii_part));
-            }
-        });
+        // Only the visible rows are laid out, so large windows stay cheap.
+        let bytes_per_line = 16;
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let rows = self.memory_data.len().div_ceil(bytes_per_line);
+        egui::ScrollArea::vertical().id_salt("mem_hex").show_rows(
+            ui,
+            row_height,
+            rows,
+            |ui, range| {
+                for i in range {
+                    let start = i * bytes_per_line;
+                    let chunk = &self.memory_data
+                        [start..(start + bytes_per_line).min(self.memory_data.len())];
+                    let addr = self.memory_base_address + start as u64;
+
+                    if let Some(diff) = &self.memory_diff {
+                        let words = self.memory_words.then_some(endian);
+                        ui.label(memory_diff_row(ui, addr, chunk, words, diff));
+                        continue;
+                    }
+                    let (addr_str, mut hex_part, ascii_part) =
+                        ui_logic::format_memory_line(addr, chunk);
+                    if self.memory_words {
+                        hex_part = ui_logic::format_memory_words(chunk, endian);
+                    }
+                    ui.monospace(format!("{}   {} {}", addr_str, hex_part, ascii_part));
+                }
+            },
+        );
     }
     pub(crate) fn draw_disassembly_view(&mut self, ui: &mut egui::Ui) {
         egui::ScrollArea::both().id_salt("disasm_view_scroll").show(ui, |ui| {
-            ui.heading("Disassembly");
+            ui.horizontal(|ui| {
+                ui.heading("Disassembly");
+                if ui
+                    .button("Whole function")
+                    .on_hover_text("Disassemble the current function")
+                    .clicked()
+                {
+                    if let (Some(handle), Some(&pc)) =
+                        (&self.session_handle, self.registers.get(&15))
+                    {
+                        let _ = handle.send(aether_core::DebugCommand::DisassembleFunction(pc));
+                    }
+                }
+                if ui
+                    .button("With source")
+                    .on_hover_text("Show the source line above its instructions")
+                    .clicked()
+                {
+                    if let (Some(handle), Some(first), Some(last)) =
+                        (&self.session_handle, self.disassembly.first(), self.disassembly.last())
+                    {
+                        let range = first.address..last.next_address();
+                        let _ =
+                            handle.send(aether_core::DebugCommand::DisassembleWithSource(range));
+                    }
+                }
+            });
 
+            let bp_disabled = self.disabled_reason(ui_logic::Feature::Breakpoints);
             egui::Grid::new("disasm_grid").striped(true).num_columns(5).show(ui, |ui| {
                 ui.label("BP");
                 ui.label("Address");
@@ -1263,13 +2384,30 @@ stub.Resume(aether_pb2.Empty())
                 let pc = self.registers.get(&15).cloned().unwrap_or(0); // R15 is PC in ARM
 
                 for insn in &self.disassembly {
+                    if let Some(line) = self.disassembly_source.get(&insn.address) {
+                        ui.label("");
+                        ui.colored_label(egui::Color32::LIGHT_BLUE, line);
+                        ui.end_row();
+                    }
                     let is_pc = insn.address == pc;
-                    let is_bp = self.breakpoints.contains(&insn.address);
+                    let bp = self.breakpoints.iter().find(|(a, _)| *a == insn.address);
+                    let is_bp = bp.is_some();
 
                     let bp_marker = if is_bp { "●" } else { "○" };
-                    let marker_color = if is_bp { egui::Color32::RED } else { egui::Color32::GRAY };
+                    let marker_color = match bp {
+                        Some((_, true)) => egui::Color32::RED,
+                        Some((_, false)) => egui::Color32::DARK_RED,
+                        None => egui::Color32::GRAY,
+                    };
 
-                    if ui.colored_label(marker_color, bp_marker).clicked() {
+                    let marker = ui
+                        .add_enabled(
+                            bp_disabled.is_none(),
+                            egui::Label::new(egui::RichText::new(bp_marker).color(marker_color))
+                                .sense(egui::Sense::click()),
+                        )
+                        .on_disabled_hover_text(bp_disabled.unwrap_or_default());
+                    if marker.clicked() {
                         if let Some(handle) = &self.session_handle {
                             if is_bp {
                                 let _ = handle
@@ -1288,7 +2426,12 @@ stub.Resume(aether_pb2.Empty())
                     ui.colored_label(text_color, &insn.mnemonic);
                     ui.colored_label(text_color, &insn.op_str);
 
-                    if ui.button(">>").on_hover_text("Run to here").clicked() {
+                    if ui
+                        .add_enabled(bp_disabled.is_none(), egui::Button::new(">>"))
+                        .on_hover_text("Run to here")
+                        .on_disabled_hover_text(bp_disabled.unwrap_or_default())
+                        .clicked()
+                    {
                         if let Some(handle) = &self.session_handle {
                             let _ =
                                 handle.send(aether_core::DebugCommand::SetBreakpoint(insn.address));
@@ -1307,7 +2450,7 @@ stub.Resume(aether_pb2.Empty())
         ui.horizontal(|ui| {
             ui.label("Addr:");
             ui.text_edit_singleline(&mut self.breakpoint_address_input);
-            if ui.button("Add").clicked() {
+            if self.feature_button(ui, ui_logic::Feature::Breakpoints, "Add").clicked() {
                 let addr_str = self.breakpoint_address_input.trim_start_matches("0x");
                 if let Ok(addr) = u64::from_str_radix(addr_str, 16) {
                     if let Some(handle) = &self.session_handle {
@@ -1316,18 +2459,49 @@ stub.Resume(aether_pb2.Empty())
                 }
             }
         });
+        ui.horizontal(|ui| {
+            ui.label("Log:");
+            ui.text_edit_singleline(&mut self.tracepoint_reads_input)
+                .on_hover_text("Registers or globals to log, comma separated (e.g. r0, counter)");
+            if self.feature_button(ui, ui_logic::Feature::Breakpoints, "Add Tracepoint").clicked() {
+                let addr_str = self.breakpoint_address_input.trim_start_matches("0x");
+                if let Ok(address) = u64::from_str_radix(addr_str, 16) {
+                    let reads = self
+                        .tracepoint_reads_input
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|r| !r.is_empty())
+                        .map(String::from)
+                        .collect();
+                    if let Some(handle) = &self.session_handle {
+                        let _ = handle
+                            .send(aether_core::DebugCommand::SetTracepoint { address, reads });
+                    }
+                }
+            }
+        });
 
         ui.separator();
 
         egui::ScrollArea::vertical().id_salt("bps").max_height(200.0).show(ui, |ui| {
             egui::Grid::new("bp_grid").striped(true).show(ui, |ui| {
+                ui.label("On");
                 ui.label("Address");
                 ui.label("Action");
                 ui.end_row();
 
-                for &addr in &self.breakpoints {
+                for &(addr, enabled) in &self.breakpoints {
+                    let mut checked = enabled;
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if let Some(handle) = &self.session_handle {
+                            let _ = handle.send(aether_core::DebugCommand::SetBreakpointEnabled {
+                                address: addr,
+                                enabled: checked,
+                            });
+                        }
+                    }
                     ui.label(format!("0x{:08X}", addr));
-                    if ui.button("Remove").clicked() {
+                    if self.feature_button(ui, ui_logic::Feature::Breakpoints, "Remove").clicked() {
                         if let Some(handle) = &self.session_handle {
                             let _ = handle.send(aether_core::DebugCommand::ClearBreakpoint(addr));
                         }
@@ -1342,14 +2516,14 @@ stub.Resume(aether_pb2.Empty())
         ui.heading("Peripherals (SVD)");
 
         ui.horizontal(|ui| {
-            if ui.button("📂 Load SVD").clicked() {
+            if self.feature_button(ui, ui_logic::Feature::Peripherals, "📂 Load SVD").clicked() {
                 if let Some(path) = safe_pick_file("SVD", &["svd"]) {
                     if let Some(handle) = &self.session_handle {
                         let _ = handle.send(aether_core::DebugCommand::LoadSvd(path));
                     }
                 }
             }
-            if ui.button("🔄 Refresh").clicked() {
+            if self.feature_button(ui, ui_logic::Feature::Peripherals, "🔄 Refresh").clicked() {
                 if let Some(handle) = &self.session_handle {
                     let _ = handle.send(aether_core::DebugCommand::GetPeripherals);
                     if let Some(p_name) = &self.selected_peripheral {
@@ -1358,35 +2532,143 @@ stub.Resume(aether_pb2.Empty())
                     }
                 }
             }
+            if self
+                .feature_button(ui, ui_logic::Feature::Peripherals, "📋 Dump All")
+                .on_hover_text("Read every peripheral's registers for a report")
+                .clicked()
+            {
+                self.request_peripheral_dump(DumpPurpose::Report);
+            }
+            if self.peripheral_dump.is_some() && ui.button("💾 Save Dump").clicked() {
+                self.save_peripheral_dump();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Snapshot & Compare:");
+            if ui
+                .button("📸 Snapshot")
+                .on_hover_text("Capture all peripheral registers as the baseline")
+                .clicked()
+            {
+                self.request_peripheral_dump(DumpPurpose::Snapshot);
+            }
+            if ui
+                .add_enabled(self.peripheral_snapshot.is_some(), egui::Button::new("🔍 Compare"))
+                .on_hover_text("Capture again and list fields changed since the snapshot")
+                .clicked()
+            {
+                self.request_peripheral_dump(DumpPurpose::Compare);
+            }
         });
 
+        if !self.snapshot_changes.is_empty() {
+            egui::CollapsingHeader::new(format!(
+                "Changed fields ({})",
+                self.snapshot_changes.len()
+            ))
+            .id_salt("snapshot_changes")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical().id_salt("snapshot_scroll").max_height(150.0).show(
+                    ui,
+                    |ui| {
+                        for change in &self.snapshot_changes {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{}.{}.{}: 0x{:X} → 0x{:X}",
+                                    change.peripheral,
+                                    change.register,
+                                    change.field,
+                                    change.old,
+                                    change.new
+                                ))
+                                .monospace()
+                                .color(egui::Color32::YELLOW),
+                            );
+                        }
+                    },
+                );
+            });
+        }
+
+        ui.add(
+            egui::TextEdit::singleline(&mut self.peripheral_filter)
+                .hint_text("🔍 Filter peripherals")
+                .desired_width(f32::INFINITY),
+        );
+
         ui.separator();
 
+        let mut clicked = None;
         egui::ScrollArea::vertical().id_salt("periph_scroll").max_height(200.0).show(ui, |ui| {
-            for p in &self.peripherals {
-                let is_selected = self.selected_peripheral.as_ref() == Some(&p.name);
-                if ui.selectable_label(is_selected, &p.name).clicked() {
-                    self.selected_peripheral = Some(p.name.clone());
-                    if let Some(handle) = &self.session_handle {
-                        let _ =
-                            handle.send(aether_core::DebugCommand::GetRegisters(p.name.clone()));
-                    }
-                }
+            let filtering = !self.peripheral_filter.trim().is_empty();
+            let groups = ui_logic::group_peripherals(&self.peripherals, &self.peripheral_filter);
+            for (group, peripherals) in groups {
+                egui::CollapsingHeader::new(format!("{} ({})", group, peripherals.len()))
+                    .id_salt(&group)
+                    .open(filtering.then_some(true))
+                    .show(ui, |ui| {
+                        for p in peripherals {
+                            let is_selected = self.selected_peripheral.as_ref() == Some(&p.name);
+                            if ui.selectable_label(is_selected, &p.name).clicked() {
+                                clicked = Some(p.name.clone());
+                            }
+                        }
+                    });
             }
         });
 
+        if let Some(name) = clicked {
+            self.changed_fields.clear();
+            if let Some(handle) = &self.session_handle {
+                let _ = handle.send(aether_core::DebugCommand::GetRegisters(name.clone()));
+                if self.peripheral_live {
+                    let _ = handle.send(aether_core::DebugCommand::SetPeripheralPoll {
+                        peripheral: Some(name.clone()),
+                        interval: std::time::Duration::from_millis(self.peripheral_poll_ms),
+                    });
+                }
+            }
+            self.selected_peripheral = Some(name);
+        }
+
         ui.separator();
 
         if let Some(p_name) = &self.selected_peripheral {
             ui.horizontal(|ui| {
                 ui.label(format!("Peripheral: {}", p_name));
-                if ui.button("📥 Read Values").clicked() {
+                if self
+                    .feature_button(ui, ui_logic::Feature::Peripherals, "📥 Read Values")
+                    .clicked()
+                {
                     if let Some(handle) = &self.session_handle {
                         let _ = handle
                             .send(aether_core::DebugCommand::ReadPeripheralValues(p_name.clone()));
                     }
                 }
+                ui.add_space(8.0);
+                let live_changed = ui
+                    .checkbox(&mut self.peripheral_live, "Live")
+                    .on_hover_text("Re-read registers periodically and highlight changed fields")
+                    .changed();
+                let interval_changed = ui
+                    .add(
+                        egui::DragValue::new(&mut self.peripheral_poll_ms)
+                            .range(50..=10_000)
+                            .suffix(" ms"),
+                    )
+                    .changed();
+                if live_changed || (interval_changed && self.peripheral_live) {
+                    if let Some(handle) = &self.session_handle {
+                        let _ = handle.send(aether_core::DebugCommand::SetPeripheralPoll {
+                            peripheral: self.peripheral_live.then(|| p_name.clone()),
+                            interval: std::time::Duration::from_millis(self.peripheral_poll_ms),
+                        });
+                    }
+                }
             });
+            let fields_writable = self.disabled_reason(ui_logic::Feature::Peripherals).is_none();
             egui::ScrollArea::vertical().id_salt("reg_scroll").show(ui, |ui| {
                   for reg in &self.peripheral_registers {
                        let is_expanded = self.expanded_registers.contains(&reg.name);
@@ -1400,11 +2682,22 @@ stub.Resume(aether_pb2.Empty())
                                      self.expanded_registers.insert(reg.name.clone());
                                  }
                             }
-                            ui.label(format!("{}: +0x{:04X}", reg.name, reg.address_offset));
+                            let reg_doc = reg.description.as_deref().unwrap_or("No description");
+                            let reg_hover = match reg.reset_value {
+                                Some(reset) => format!("{}\nReset: 0x{:08X}", reg_doc, reset),
+                                None => reg_doc.to_string(),
+                            };
+                            ui.label(format!("{}: +0x{:04X}", reg.name, reg.address_offset))
+                                .on_hover_text(reg_hover);
+                            if let Some(reset) = reg.reset_value {
+                                ui.label(egui::RichText::new(format!("reset 0x{:08X}", reset)).small().weak());
+                            }
 
                             if let Some(val) = reg.value {
+                                 let reg_changed = self.changed_fields.iter().any(|(r, _)| *r == reg.name);
                                  ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                     ui.monospace(format!("0x{:08X}", val));
+                                     let text = egui::RichText::new(format!("0x{:08X}", val)).monospace();
+                                     ui.label(if reg_changed { text.color(egui::Color32::YELLOW) } else { text });
                                  });
                             }
                        });
@@ -1413,17 +2706,25 @@ stub.Resume(aether_pb2.Empty())
                             ui.indent("fields", |ui| {
                                  for field in &reg.fields {
                                       ui.horizontal(|ui| {
-                                           ui.label(format!("{}: [{}..{}]",
+                                           let label = egui::RichText::new(format!("{}: [{}..{}]",
                                                field.name,
                                                field.bit_offset,
                                                field.bit_offset + field.bit_width - 1));
+                                           let is_changed = self.changed_fields.contains(&(reg.name.clone(), field.name.clone()));
+                                           ui.label(if is_changed { label.color(egui::Color32::YELLOW) } else { label })
+                                               .on_hover_text(format!(
+                                                   "{}\n\n{}: {}",
+                                                   field.description.as_deref().unwrap_or("No description"),
+                                                   reg.name,
+                                                   reg.description.as_deref().unwrap_or("No description"),
+                                               ));
 
                                            if let Some(val) = reg.value {
                                                 let mut field_val = field.decode(val);
-                                                let field_max = (1u64 << field.bit_width) - 1;
+                                                let field_max = field.max_value();
 
                                                 ui.label("=");
-                                                if ui.add(egui::DragValue::new(&mut field_val)
+                                                if ui.add_enabled(fields_writable, egui::DragValue::new(&mut field_val)
                                                     .speed(1.0)
                                                     .range(0..=field_max)
                                                     .hexadecimal(field.bit_width as usize / 4 + 1, true, false)
@@ -1455,10 +2756,17 @@ stub.Resume(aether_pb2.Empty())
             ui.add_space(8.0);
             if ui.button("🔄 Refresh All").clicked() {
                 if let Some(handle) = &self.session_handle {
-                    for var in &self.watched_variables {
-                        let _ =
-                            handle.send(aether_core::DebugCommand::WatchVariable(var.name.clone()));
-                    }
+                    let names = self.watched_variables.iter().map(|v| v.name.clone()).collect();
+                    let _ = handle.send(aether_core::DebugCommand::RefreshWatches(names));
+                }
+            }
+            if ui
+                .checkbox(&mut self.live_watch, "Live")
+                .on_hover_text("Update watches while the target is running")
+                .changed()
+            {
+                if let Some(handle) = &self.session_handle {
+                    let _ = handle.send(aether_core::DebugCommand::SetLiveWatch(self.live_watch));
                 }
             }
         });
@@ -1490,6 +2798,7 @@ stub.Resume(aether_pb2.Empty())
 
         egui::ScrollArea::vertical().id_salt("watch_scroll").show(ui, |ui| {
             let mut to_remove = None;
+            let mut format_change = None;
             for (idx, var) in self.watched_variables.iter().enumerate() {
                 ui.horizontal(|ui| {
                     ui.push_id(idx, |ui| {
@@ -1504,13 +2813,44 @@ stub.Resume(aether_pb2.Empty())
                         {
                             to_remove = Some(idx);
                         }
+
+                        let current =
+                            self.variable_formats.get(&var.name).copied().unwrap_or_default();
+                        let mut selected = current;
+                        egui::ComboBox::from_id_salt(("watch_format", idx))
+                            .width(70.0)
+                            .selected_text(format!("{:?}", selected))
+                            .show_ui(ui, |ui| {
+                                use aether_core::VariableFormat as F;
+                                for f in [F::Natural, F::Hex, F::Dec, F::Bin, F::Char] {
+                                    ui.selectable_value(&mut selected, f, format!("{:?}", f));
+                                }
+                            });
+                        if selected != current {
+                            format_change = Some((var.name.clone(), selected));
+                        }
                     });
                 });
                 ui.add_space(2.0);
                 ui.separator();
             }
+            if let Some((name, format)) = format_change {
+                self.variable_formats.insert(name.clone(), format);
+                if let Some(handle) = &self.session_handle {
+                    let _ = handle.send(aether_core::DebugCommand::SetVariableFormat {
+                        name: name.clone(),
+                        format,
+                    });
+                    let _ = handle.send(aether_core::DebugCommand::WatchVariable(name));
+                }
+            }
             if let Some(idx) = to_remove {
                 self.watched_variables.remove(idx);
+                // Keep the core's watch list in sync so live updates stop for this row.
+                if let Some(handle) = &self.session_handle {
+                    let names = self.watched_variables.iter().map(|v| v.name.clone()).collect();
+                    let _ = handle.send(aether_core::DebugCommand::RefreshWatches(names));
+                }
             }
         });
     }
@@ -1561,9 +2901,22 @@ stub.Resume(aether_pb2.Empty())
 
     pub(crate) fn draw_rtt_view(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
+            ui.label("CB Addr:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.rtt_address_input)
+                    .hint_text("auto")
+                    .desired_width(90.0),
+            );
             if ui.button("🔌 Attach RTT").clicked() {
                 if let Some(handle) = &self.session_handle {
-                    let _ = handle.send(aether_core::DebugCommand::RttAttach);
+                    let addr_str = self.rtt_address_input.trim().trim_start_matches("0x");
+                    if addr_str.is_empty() {
+                        let _ = handle.send(aether_core::DebugCommand::RttAttach);
+                    } else if let Ok(addr) = u64::from_str_radix(addr_str, 16) {
+                        let _ = handle.send(aether_core::DebugCommand::RttAttachAt(addr));
+                    } else {
+                        self.status_message = "Invalid RTT address".to_string();
+                    }
                 }
             }
             if self.rtt_attached {
@@ -1571,12 +2924,28 @@ stub.Resume(aether_pb2.Empty())
             }
 
             ui.add_space(8.0);
-            if let Some(chan_num) = self.rtt_selected_channel {
+            if let Some(chan_num) = self.rtt_selected_channel.filter(|_| !self.rtt_show_all) {
                 let mode = self.rtt_display_modes.entry(chan_num).or_insert(RttDisplayMode::Text);
                 ui.label("View:");
                 ui.selectable_value(mode, RttDisplayMode::Text, "Text");
                 ui.selectable_value(mode, RttDisplayMode::Hex, "Hex");
+
+                let encoding = self.rtt_encodings.entry(chan_num).or_default();
+                let before = *encoding;
+                egui::ComboBox::from_id_salt(("rtt_encoding", chan_num))
+                    .selected_text(encoding.label())
+                    .show_ui(ui, |ui| {
+                        for option in ui_logic::RttEncoding::ALL {
+                            ui.selectable_value(encoding, option, option.label());
+                        }
+                    });
+                if *encoding != before {
+                    // Re-decode what is still in the raw buffer.
+                    let raw = self.rtt_raw_buffers.get(&chan_num).map_or(&[][..], Vec::as_slice);
+                    self.rtt_buffers.insert(chan_num, ui_logic::decode_rtt(raw, *encoding));
+                }
             }
+            ui.checkbox(&mut self.rtt_ansi_colors, "ANSI colors");
         });
 
         if !self.rtt_attached {
@@ -1586,32 +2955,69 @@ stub.Resume(aether_pb2.Empty())
 
         ui.horizontal(|ui| {
             ui.label("Channel:");
+            if ui.selectable_label(self.rtt_show_all, "All").clicked() {
+                self.rtt_show_all = true;
+            }
             for chan in &self.rtt_up_channels {
                 let name = chan.name.as_deref().unwrap_or("unnamed");
                 if ui
                     .selectable_label(
-                        self.rtt_selected_channel == Some(chan.number),
+                        !self.rtt_show_all && self.rtt_selected_channel == Some(chan.number),
                         format!("{}: {}", chan.number, name),
                     )
                     .clicked()
                 {
                     self.rtt_selected_channel = Some(chan.number);
+                    self.rtt_show_all = false;
                 }
             }
         });
 
         ui.separator();
 
-        if let Some(chan_num) = self.rtt_selected_channel {
+        if self.rtt_show_all {
+            egui::ScrollArea::vertical().id_salt("rtt_merged_scroll").stick_to_bottom(true).show(
+                ui,
+                |ui| {
+                    for line in self.rtt_merged.lines() {
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = 4.0;
+                            ui.label(
+                                egui::RichText::new(format!("[{}]", line.channel))
+                                    .monospace()
+                                    .color(rtt_channel_color(line.channel)),
+                            );
+                            if self.rtt_ansi_colors {
+                                ui.label(ansi_layout_job(&line.text));
+                            } else {
+                                let text: String = ui_logic::parse_ansi(&line.text)
+                                    .into_iter()
+                                    .map(|span| span.text)
+                                    .collect();
+                                ui.label(egui::RichText::new(text).monospace());
+                            }
+                        });
+                    }
+                },
+            );
+        } else if let Some(chan_num) = self.rtt_selected_channel {
             let mode = *self.rtt_display_modes.get(&chan_num).unwrap_or(&RttDisplayMode::Text);
 
             egui::ScrollArea::vertical().id_salt("rtt_scroll").stick_to_bottom(true).show(
                 ui,
                 |ui| match mode {
+                    RttDisplayMode::Text if self.rtt_ansi_colors => {
+                        let buffer = self.rtt_buffers.entry(chan_num).or_default();
+                        ui.add(egui::Label::new(ansi_layout_job(buffer)).wrap());
+                    }
                     RttDisplayMode::Text => {
                         let buffer = self.rtt_buffers.entry(chan_num).or_default();
+                        let mut text: String = ui_logic::parse_ansi(buffer)
+                            .into_iter()
+                            .map(|span| span.text)
+                            .collect();
                         ui.add(
-                            egui::TextEdit::multiline(buffer)
+                            egui::TextEdit::multiline(&mut text)
                                 .font(egui::TextStyle::Monospace)
                                 .code_editor()
                                 .lock_focus(false)
@@ -1646,7 +3052,7 @@ stub.Resume(aether_pb2.Empty())
             ui.horizontal(|ui| {
                 let response = ui.text_edit_singleline(&mut self.rtt_input);
                 if (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
-                    || ui.button("Send").clicked()
+                    || self.feature_button(ui, ui_logic::Feature::RttWrite, "Send").clicked()
                 {
                     if let Some(handle) = &self.session_handle {
                         let _ = handle.send(aether_core::DebugCommand::RttWrite {
@@ -1717,6 +3123,7 @@ stub.Resume(aether_pb2.Empty())
     pub(crate) fn draw_source_view(&mut self, ui: &mut egui::Ui) {
         ui.heading("Source Code");
 
+        let mut open_file = None;
         ui.horizontal(|ui| {
             if ui.button("📂 Load Symbols (ELF)").clicked() {
                 if let Some(path) = safe_pick_file("ELF", &["elf", "bin", "out"]) {
@@ -1727,11 +3134,54 @@ stub.Resume(aether_pb2.Empty())
             }
             if self.symbols_loaded {
                 ui.label("✅ Symbols Loaded");
+                if let Some(id) = &self.build_id {
+                    ui.label(egui::RichText::new(format!("Build {}", id)).monospace().small());
+                    if ui
+                        .button("Verify Image")
+                        .on_hover_text("Compare with the build ID flashed on the target")
+                        .clicked()
+                    {
+                        if let Some(handle) = &self.session_handle {
+                            let _ =
+                                handle.send(aether_core::DebugCommand::VerifyImageMatchesSymbols);
+                        }
+                    }
+                }
+                if !self.source_files.is_empty() {
+                    egui::ComboBox::from_id_salt("source_files")
+                        .selected_text("Open file...")
+                        .show_ui(ui, |ui| {
+                            for file in &self.source_files {
+                                if ui.selectable_label(false, file.display().to_string()).clicked()
+                                {
+                                    open_file = Some(file.clone());
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Show a file to set breakpoints by line");
+                }
             }
         });
 
+        if let Some(file) = open_file {
+            if !self.source_cache.contains_key(&file) {
+                if let Ok(content) = std::fs::read_to_string(&file) {
+                    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                    let highlighted = self.highlight_file(&file, &content);
+                    self.source_cache.insert(file.clone(), (lines, highlighted));
+                } else if let Some(handle) = &self.session_handle {
+                    let _ =
+                        handle.send(aether_core::DebugCommand::ReadEmbeddedSource(file.clone()));
+                }
+            }
+            self.source_info =
+                Some(aether_core::SourceInfo { file, line: 0, column: None, function: None });
+        }
+
         ui.separator();
 
+        let mut relocate_to = None;
         if let Some(info) = &self.source_info {
             ui.horizontal(|ui| {
                 ui.label(egui::RichText::new(format!("File: {}", info.file.display())).strong());
@@ -1748,6 +3198,7 @@ stub.Resume(aether_pb2.Empty())
             ui.separator();
 
             if let Some((_, highlighted)) = self.source_cache.get(&info.file) {
+                let bp_disabled = self.disabled_reason(ui_logic::Feature::Breakpoints);
                 egui::ScrollArea::vertical().id_salt("source_scroll").show(ui, |ui| {
                     egui::Grid::new("source_grid").num_columns(2).striped(true).show(ui, |ui| {
                         for (i, job) in highlighted.iter().enumerate() {
@@ -1770,7 +3221,11 @@ stub.Resume(aether_pb2.Empty())
                                 }
 
                                 if ui
-                                    .add(egui::Label::new(label_text).sense(egui::Sense::click()))
+                                    .add_enabled(
+                                        bp_disabled.is_none(),
+                                        egui::Label::new(label_text).sense(egui::Sense::click()),
+                                    )
+                                    .on_disabled_hover_text(bp_disabled.unwrap_or_default())
                                     .clicked()
                                 {
                                     if let Some(handle) = &self.session_handle {
@@ -1803,10 +3258,36 @@ stub.Resume(aether_pb2.Empty())
                 });
             } else {
                 ui.label("Source file not found or failed to load.");
+                if ui
+                    .button("📂 Locate Sources...")
+                    .on_hover_text("Pick the local folder holding this file's build directory")
+                    .clicked()
+                {
+                    relocate_to = safe_pick_folder();
+                }
             }
         } else {
             ui.label("No source information available. Halt the target to see source code.");
         }
+
+        if let (Some(dir), Some(mut info)) = (relocate_to, self.source_info.clone()) {
+            let (Some(build_dir), Some(name)) = (info.file.parent(), info.file.file_name()) else {
+                return;
+            };
+            if let Some(handle) = &self.session_handle {
+                let _ = handle.send(aether_core::DebugCommand::AddSourceMapping {
+                    from: build_dir.to_path_buf(),
+                    to: dir.clone(),
+                });
+            }
+            info.file = dir.join(name);
+            if let Ok(content) = std::fs::read_to_string(&info.file) {
+                let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                let highlighted = self.highlight_file(&info.file, &content);
+                self.source_cache.insert(info.file.clone(), (lines, highlighted));
+            }
+            self.source_info = Some(info);
+        }
     }
 
     fn apply_midnight_theme(&self, ctx: &egui::Context) {
@@ -1849,6 +3330,30 @@ stub.Resume(aether_pb2.Empty())
                     if ui.button("⚡ Connect").clicked() {
                         self.connect_probe();
                     }
+                    let hint = match &self.last_attach {
+                        Some(last) => format!("{} via probe {}", last.chip, last.serial),
+                        None => "Connect once to remember the probe and target".to_string(),
+                    };
+                    let reconnect = ui
+                        .add_enabled(self.last_attach.is_some(), egui::Button::new("↻ Reconnect"))
+                        .on_hover_text(&hint)
+                        .on_disabled_hover_text(&hint);
+                    if reconnect.clicked() {
+                        self.reconnect_probe();
+                    }
+                    if ui
+                        .checkbox(&mut self.attach_reset_recovery, "Reset if attach fails")
+                        .on_hover_text(
+                            "Pulse nRST and retry once when re-attaching fails; the target restarts",
+                        )
+                        .changed()
+                    {
+                        if let Some(handle) = &self.session_handle {
+                            let _ = handle.send(aether_core::DebugCommand::SetAttachResetRecovery(
+                                self.attach_reset_recovery,
+                            ));
+                        }
+                    }
                 }
                 #[cfg(not(feature = "hardware"))]
                 {
@@ -1919,6 +3424,17 @@ stub.Resume(aether_pb2.Empty())
                             .unwrap()
                             .send(aether_core::DebugCommand::StepOver);
                     }
+                    if ui
+                        .add(egui::Button::new("↷ Instr").min_size(btn_size))
+                        .on_hover_text("Step one instruction, over calls")
+                        .clicked()
+                    {
+                        let _ = self
+                            .session_handle
+                            .as_ref()
+                            .unwrap()
+                            .send(aether_core::DebugCommand::StepOverInstruction);
+                    }
                     if ui.add(egui::Button::new("↘ Into").min_size(btn_size)).clicked() {
                         let _ = self
                             .session_handle
@@ -1933,14 +3449,146 @@ stub.Resume(aether_pb2.Empty())
                             .unwrap()
                             .send(aether_core::DebugCommand::StepOut);
                     }
+                    if ui
+                        .add(egui::Button::new("⏮ main").min_size(btn_size))
+                        .on_hover_text("Reset and run to main")
+                        .clicked()
+                    {
+                        let _ = self
+                            .session_handle
+                            .as_ref()
+                            .unwrap()
+                            .send(aether_core::DebugCommand::RunToMain);
+                    }
+                    if ui
+                        .add(egui::Button::new("⟲ Reset").min_size(btn_size))
+                        .on_hover_text(format!(
+                            "{} reset, halt at the reset handler",
+                            self.reset_kind.label()
+                        ))
+                        .clicked()
+                    {
+                        let _ = self
+                            .session_handle
+                            .as_ref()
+                            .unwrap()
+                            .send(aether_core::DebugCommand::Reset(self.reset_kind));
+                    }
+                });
+            });
+            ui.add_enabled_ui(self.session_handle.is_some(), |ui| {
+                if ui
+                    .checkbox(&mut self.reset_resync, "Re-sync on target reset")
+                    .on_hover_text("Re-arm breakpoints and re-attach RTT after a watchdog reset")
+                    .changed()
+                {
+                    if let Some(handle) = &self.session_handle {
+                        let _ = handle
+                            .send(aether_core::DebugCommand::SetResetResync(self.reset_resync));
+                    }
+                }
+                if ui
+                    .checkbox(&mut self.background_paused, "Pause background polling")
+                    .on_hover_text(
+                        "Stop status, RTT, plot and watch polls, e.g. during a timing measurement",
+                    )
+                    .changed()
+                {
+                    if let Some(handle) = &self.session_handle {
+                        let _ = handle.send(if self.background_paused {
+                            aether_core::DebugCommand::PauseBackground
+                        } else {
+                            aether_core::DebugCommand::ResumeBackground
+                        });
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Reset kind:");
+                    egui::ComboBox::from_id_salt("reset_kind")
+                        .selected_text(self.reset_kind.label())
+                        .show_ui(ui, |ui| {
+                            for kind in aether_core::debug::reset::ResetKind::ALL {
+                                ui.selectable_value(&mut self.reset_kind, kind, kind.label());
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Catch:");
+                    let catch = &mut self.vector_catch;
+                    let changed = [
+                        ui.checkbox(&mut catch.hardfault, "HardFault"),
+                        ui.checkbox(&mut catch.bus_fault, "BusFault"),
+                        ui.checkbox(&mut catch.mem_manage, "MemManage"),
+                        ui.checkbox(&mut catch.usage_fault, "UsageFault"),
+                        ui.checkbox(&mut catch.reset, "Reset"),
+                    ]
+                    .iter()
+                    .any(|r| r.changed());
+                    if changed {
+                        if let Some(handle) = &self.session_handle {
+                            let _ = handle
+                                .send(aether_core::DebugCommand::SetVectorCatch(self.vector_catch));
+                        }
+                    }
                 });
             });
         });
 
         ui.add_space(8.0);
 
+        ui.collapsing("⌨ Shortcuts", |ui| {
+            egui::Grid::new("shortcut_grid").striped(true).num_columns(3).show(ui, |ui| {
+                for binding in &mut self.key_bindings {
+                    ui.label(binding.action.label());
+                    ui.add(egui::TextEdit::singleline(&mut binding.key).desired_width(50.0));
+                    ui.checkbox(&mut binding.shift, "Shift");
+                    ui.end_row();
+                }
+            });
+            if ui.button("Restore defaults").clicked() {
+                self.key_bindings = ui_logic::default_key_bindings();
+            }
+        });
+
+        ui.add_space(8.0);
+
+        ui.collapsing("🖵 Refresh", |ui| {
+            let batcher = &mut self.event_batcher;
+            ui.horizontal(|ui| {
+                ui.label("Events per frame:");
+                ui.add(egui::DragValue::new(&mut batcher.per_frame).range(10..=5000));
+            });
+            ui.checkbox(&mut batcher.suppress_while_scrolling, "Pause updates while scrolling")
+                .on_hover_text("Queued updates are applied once scrolling stops");
+        });
+
+        ui.add_space(8.0);
+
         // Registers Section
         ui.collapsing("⌗ Registers", |ui| {
+            ui.menu_button("Copy as", |ui| {
+                let endian = self.target_info.as_ref().map(|t| t.endian).unwrap_or_default();
+                let values: Vec<u64> =
+                    (0..16).map(|i| self.registers.get(&i).copied().unwrap_or(0)).collect();
+                let bytes = ui_logic::registers_as_bytes(&values, endian);
+                for format in ui_logic::CopyFormat::ALL {
+                    if ui.button(format.label()).clicked() {
+                        ui.ctx().copy_text(format.format(&bytes));
+                        ui.close();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("R0-R15 as 32-bit words in target byte order");
+            ui.horizontal(|ui| {
+                ui.label("Also show:");
+                ui.checkbox(&mut self.register_bases.decimal, "Dec");
+                ui.checkbox(&mut self.register_bases.signed, "Signed");
+                ui.checkbox(&mut self.register_bases.binary, "Bin");
+            });
+            let bases = self.register_bases;
+            // Extra bases need the width of a whole row per register.
+            let per_row = if bases.any() { 1 } else { 2 };
             egui::ScrollArea::vertical().id_salt("regs").show(ui, |ui| {
                 egui::Grid::new("reg_grid").striped(true).spacing(egui::vec2(20.0, 4.0)).show(
                     ui,
@@ -1950,15 +3598,40 @@ stub.Resume(aether_pb2.Empty())
                                 egui::RichText::new(format!("R{}", i))
                                     .color(egui::Color32::from_rgb(0, 200, 255)),
                             );
-                            if let Some(val) = self.registers.get(&i) {
-                                ui.label(egui::RichText::new(format!("0x{:08X}", val)).monospace());
+                            if let Some(&val) = self.registers.get(&i) {
+                                ui.label(
+                                    egui::RichText::new(ui_logic::format_register_value(
+                                        val, bases,
+                                    ))
+                                    .monospace(),
+                                );
                             } else {
                                 ui.label("?");
                             }
-                            if i % 2 == 1 {
+                            if (i as usize + 1) % per_row == 0 {
                                 ui.end_row();
                             }
                         }
+                        if let Some(&xpsr) = self.registers.get(&ui_logic::XPSR_REGISTER_ID) {
+                            ui.label(
+                                egui::RichText::new("xPSR")
+                                    .color(egui::Color32::from_rgb(0, 200, 255)),
+                            );
+                            ui.label(
+                                egui::RichText::new(ui_logic::format_register_value(xpsr, bases))
+                                    .monospace(),
+                            )
+                            .on_hover_text(
+                                "Negative, Zero, Carry, oVerflow, saturation (Q), Thumb",
+                            );
+                            ui.end_row();
+                            let flags = aether_core::debug::psr::PsrFlags::from_xpsr(xpsr as u32);
+                            ui.label("");
+                            ui.label(
+                                egui::RichText::new(ui_logic::format_psr_flags(&flags)).monospace(),
+                            );
+                            ui.end_row();
+                        }
                     },
                 );
             });
@@ -1966,6 +3639,43 @@ stub.Resume(aether_pb2.Empty())
 
         ui.add_space(8.0);
 
+        // Write Log Section
+        ui.collapsing("✎ Write Log", |ui| {
+            ui.add_enabled_ui(self.session_handle.is_some() && !self.write_log.is_empty(), |ui| {
+                if ui.button("↶ Undo last write").clicked() {
+                    if let Some(handle) = &self.session_handle {
+                        let _ = handle.send(aether_core::DebugCommand::UndoWrite);
+                        let _ = handle.send(aether_core::DebugCommand::ReadMemory(
+                            self.memory_base_address,
+                            self.memory_window,
+                        ));
+                        for i in 0..16 {
+                            let _ = handle.send(aether_core::DebugCommand::ReadRegister(i));
+                        }
+                    }
+                }
+            });
+            egui::ScrollArea::vertical().id_salt("write_log").max_height(150.0).show(ui, |ui| {
+                egui::Grid::new("write_log_grid").striped(true).num_columns(4).show(ui, |ui| {
+                    ui.label("Time");
+                    ui.label("Target");
+                    ui.label("Old");
+                    ui.label("New");
+                    ui.end_row();
+                    for record in self.write_log.iter().rev() {
+                        let (target, old, new) = ui_logic::format_write_record(&record.kind);
+                        ui.label(format!("{:.3}s", record.timestamp));
+                        ui.monospace(target);
+                        ui.monospace(old);
+                        ui.monospace(new);
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        ui.add_space(8.0);
+
         // Breakpoints Section
         ui.collapsing("⛔ Breakpoints", |ui| {
             self.draw_breakpoints_view(ui);
@@ -1988,14 +3698,50 @@ stub.Resume(aether_pb2.Empty())
 
             #[cfg(feature = "hardware")]
             {
-                if ui
-                    .add_enabled(
-                        self.selected_file.is_some()
-                            && self.connection_status == ConnectionStatus::Connected,
-                        egui::Button::new("🚀 Flash"),
-                    )
-                    .clicked()
-                {
+                ui.horizontal(|ui| {
+                    ui.label("Max probe clock:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.flash_speed_cap_khz)
+                            .range(100..=100_000)
+                            .suffix(" kHz"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Unwritten bytes:");
+                    let label = |keep: Option<bool>| match keep {
+                        None => "Auto",
+                        Some(true) => "Keep",
+                        Some(false) => "Erase",
+                    };
+                    egui::ComboBox::from_id_salt("flash_keep_unwritten")
+                        .selected_text(label(self.flash_keep_unwritten))
+                        .show_ui(ui, |ui| {
+                            for keep in [None, Some(true), Some(false)] {
+                                ui.selectable_value(
+                                    &mut self.flash_keep_unwritten,
+                                    keep,
+                                    label(keep),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Keep preserves data the image does not cover but is slower; \
+                             Auto keeps it for ELF/HEX and erases for raw binaries",
+                        );
+                });
+                let flash_disabled = self.disabled_reason(ui_logic::Feature::Flash);
+                let response = ui.add_enabled(
+                    self.selected_file.is_some()
+                        && self.connection_status == ConnectionStatus::Connected
+                        && flash_disabled.is_none(),
+                    egui::Button::new("🚀 Flash"),
+                );
+                let response = match flash_disabled {
+                    Some(reason) => response.on_disabled_hover_text(reason),
+                    None => response,
+                };
+                if response.clicked() {
                     self.start_flashing();
                 }
 
@@ -2015,6 +3761,42 @@ stub.Resume(aether_pb2.Empty())
             }
         });
 
+        if !self.tracepoint_log.is_empty() {
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.heading("Tracepoints");
+                if ui.button("Clear").clicked() {
+                    self.tracepoint_log.clear();
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("tracepoint_log")
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &self.tracepoint_log {
+                        ui.monospace(line);
+                    }
+                });
+        }
+
+        if !self.itm_console.is_empty() {
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.heading("ITM Console");
+                if ui.button("Clear").clicked() {
+                    self.itm_console.clear();
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("itm_console")
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    ui.monospace(&self.itm_console);
+                });
+        }
+
         ui.add_space(8.0);
         ui.heading("Quick Connect (Python)");
         let code = r#"import grpc
@@ -2038,11 +3820,43 @@ stub.Resume(aether_pb2.Empty())
     }
 }
 
+impl Drop for AetherApp {
+    /// Stop the agent server and remote client, end the session and wait for all
+    /// background threads, so the probe is released before the process exits.
+    fn drop(&mut self) {
+        // The auto-save only exists to recover from a crash. After a clean exit
+        // it goes, unless it still holds an earlier run's unrestored capture.
+        if let Some(writer) = self.autosave_writer.take() {
+            let _ = writer.join();
+        }
+        if self.autosave_recovery.is_none() {
+            if let Some(path) = ui_logic::autosave_path() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        self.shutdown.trigger();
+        self.stop_session();
+        for worker in self.worker_threads.drain(..) {
+            if worker.join().is_err() {
+                log::error!("Background thread panicked during shutdown");
+            }
+        }
+    }
+}
+
 impl eframe::App for AetherApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.apply_midnight_theme(ctx);
         self.update_flashing();
-        self.process_debug_events();
+        self.update_capabilities();
+        let scrolling = ctx.input(|i| i.smooth_scroll_delta != egui::Vec2::ZERO);
+        self.process_debug_events(scrolling);
+        self.handle_shortcuts(ctx);
+        if self.session_config.autosave
+            && self.last_autosave.elapsed() >= ui_logic::AUTOSAVE_INTERVAL
+        {
+            self.autosave();
+        }
 
         // Top Header
         egui::TopBottomPanel::top("top_header").show(ctx, |ui| {
@@ -2063,6 +3877,15 @@ impl eframe::App for AetherApp {
                 if ui.button("📂 Import").clicked() {
                     self.import_session();
                 }
+                if ui
+                    .checkbox(&mut self.session_config.autosave, "Auto-save")
+                    .on_hover_text(
+                        "Periodically save RTT, plot and timeline data to survive a crash",
+                    )
+                    .changed()
+                {
+                    self.save_session_config();
+                }
 
                 ui.add_space(12.0);
 
@@ -2075,6 +3898,7 @@ impl eframe::App for AetherApp {
                             (DebugTab::Variables, "🔎 Watch"),
                             (DebugTab::Stack, "⛃ Stack"),
                             (DebugTab::Tasks, "⚙ Tasks"),
+                            (DebugTab::SyncObjects, "🔒 Sync Objects"),
                             (DebugTab::Timeline, "⏱ Timeline"),
                             (DebugTab::Source, "✍ Source"),
                             (DebugTab::Memory, "🖴 Memory"),
@@ -2126,11 +3950,62 @@ impl eframe::App for AetherApp {
 
                     if let Some(target) = &self.target_info {
                         ui.separator();
+                        if let Some(id) = &self.build_id {
+                            let short = id.get(..12).unwrap_or(id);
+                            ui.label(
+                                egui::RichText::new(short).monospace().color(egui::Color32::GRAY),
+                            )
+                            .on_hover_text(format!("Build ID {}", id));
+                        }
                         ui.label(egui::RichText::new(&target.name).strong());
                         ui.label("Target:");
                     }
+
+                    if let Some(volts) = self.target_voltage {
+                        ui.separator();
+                        match volts {
+                            Some(v) => {
+                                let color = if v < 1.0 {
+                                    egui::Color32::RED
+                                } else {
+                                    egui::Color32::LIGHT_GRAY
+                                };
+                                ui.label(egui::RichText::new(format!("{:.2} V", v)).color(color))
+                                    .on_hover_text("Target voltage measured by the probe");
+                            }
+                            None => {
+                                ui.label(egui::RichText::new("n/a").color(egui::Color32::GRAY))
+                                    .on_hover_text("This probe cannot measure the target voltage");
+                            }
+                        }
+                        ui.label("VTarget:");
+                    }
                 });
             });
+            if let Some(save) = &self.autosave_recovery {
+                let age = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs())
+                    .saturating_sub(save.saved_at);
+                let mut restore = false;
+                let mut discard = false;
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Captured data from the last session was auto-saved {} min ago.",
+                            age / 60
+                        ))
+                        .color(egui::Color32::YELLOW),
+                    );
+                    restore = ui.button("Restore").clicked();
+                    discard = ui.button("Discard").clicked();
+                });
+                if restore {
+                    self.restore_autosave();
+                } else if discard {
+                    self.discard_autosave();
+                }
+            }
             ui.add_space(4.0);
         });
 
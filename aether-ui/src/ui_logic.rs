@@ -1,5 +1,60 @@
-use aether_core::TaskState;
-use std::path::Path;
+use aether_core::debug::psr::PsrFlags;
+use aether_core::itm::ExceptionAction;
+use aether_core::memory::history::WriteKind;
+use aether_core::{Capabilities, DebugCommand, DebugEvent, Endian, TaskState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Tells background threads (agent server, remote client) that the app is closing.
+///
+/// Clones share the same signal.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+    tx: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self { tx: Arc::new(tokio::sync::watch::channel(false).0) }
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Resolves once [`trigger`](Self::trigger) was called, immediately if it already was.
+    pub async fn wait(&self) {
+        let mut rx = self.tx.subscribe();
+        let _ = rx.wait_for(|&triggered| triggered).await;
+    }
+}
+
+/// Number of timeline events kept for display.
+pub const MAX_TIMELINE_EVENTS: usize = 500;
+
+/// A slice of execution on the timeline: a task, or an interrupt handler when `is_isr` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    /// Task handle, or the exception number for ISRs.
+    pub task_handle: u32,
+    pub task_name: String,
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    #[serde(default)]
+    pub is_isr: bool,
+}
 
 /// Formats a line of memory for the hex view.
 /// Returns (address_str, hex_str, ascii_str)
@@ -14,6 +69,258 @@ pub fn format_memory_line(address: u64, chunk: &[u8]) -> (String, String, String
     (addr_str, format!("{:48}", hex_part), ascii_part)
 }
 
+/// Formats a line of memory as 32-bit words decoded in the target's byte order.
+pub fn format_memory_words(chunk: &[u8], endian: Endian) -> String {
+    let words: String = chunk
+        .chunks(4)
+        .map(|w| format!("{:0width$X} ", endian.read_uint(w), width = w.len() * 2))
+        .collect();
+    format!("{:48}", words)
+}
+
+/// Memory view window sizes offered in the UI, in bytes.
+pub const MEMORY_WINDOW_SIZES: [usize; 4] = [256, 512, 1024, 4096];
+/// Bytes read per memory view refresh unless the user picks another window.
+pub const DEFAULT_MEMORY_WINDOW: usize = 256;
+
+/// Base address of the page after the one at `address`.
+pub fn next_memory_page(address: u64, window: usize) -> u64 {
+    address.saturating_add(window as u64)
+}
+
+/// Base address of the page before the one at `address`, stopping at zero.
+pub fn prev_memory_page(address: u64, window: usize) -> u64 {
+    address.saturating_sub(window as u64)
+}
+
+/// Bytes per line in the C / Rust copy formats.
+const COPY_BYTES_PER_LINE: usize = 16;
+
+fn hex_byte_lines(data: &[u8]) -> String {
+    data.chunks(COPY_BYTES_PER_LINE)
+        .map(|line| {
+            let bytes: Vec<String> = line.iter().map(|b| format!("0x{:02X},", b)).collect();
+            format!("    {}\n", bytes.join(" "))
+        })
+        .collect()
+}
+
+/// Formats bytes as a Rust `&[u8]` literal.
+pub fn format_as_rust_slice(data: &[u8]) -> String {
+    format!("&[\n{}]", hex_byte_lines(data))
+}
+
+/// Formats bytes as a C `uint8_t` array definition.
+pub fn format_as_c_array(data: &[u8]) -> String {
+    format!("const uint8_t data[{}] = {{\n{}}};", data.len(), hex_byte_lines(data))
+}
+
+/// Formats bytes as space-separated hex.
+pub fn format_as_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+/// Clipboard formats offered by the "Copy as" menus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    Hex,
+    CArray,
+    RustSlice,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 3] = [CopyFormat::Hex, CopyFormat::CArray, CopyFormat::RustSlice];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "Hex",
+            CopyFormat::CArray => "C array",
+            CopyFormat::RustSlice => "Rust slice",
+        }
+    }
+
+    pub fn format(self, data: &[u8]) -> String {
+        match self {
+            CopyFormat::Hex => format_as_hex(data),
+            CopyFormat::CArray => format_as_c_array(data),
+            CopyFormat::RustSlice => format_as_rust_slice(data),
+        }
+    }
+}
+
+/// Serializes 32-bit register values in the target's byte order.
+pub fn registers_as_bytes(values: &[u64], endian: Endian) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|&v| match endian {
+            Endian::Little => (v as u32).to_le_bytes(),
+            Endian::Big => (v as u32).to_be_bytes(),
+        })
+        .collect()
+}
+
+/// Register ID of xPSR, shown under R0-R15.
+pub const XPSR_REGISTER_ID: u16 = 16;
+
+/// Bases the register panel shows next to hex.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegisterBases {
+    pub decimal: bool,
+    pub signed: bool,
+    pub binary: bool,
+}
+
+impl RegisterBases {
+    pub fn any(&self) -> bool {
+        self.decimal || self.signed || self.binary
+    }
+}
+
+/// A 32-bit register value in hex followed by the selected bases; binary is
+/// grouped in nibbles.
+pub fn format_register_value(value: u64, bases: RegisterBases) -> String {
+    let value = value as u32;
+    let mut parts = vec![format!("0x{:08X}", value)];
+    if bases.decimal {
+        parts.push(value.to_string());
+    }
+    if bases.signed {
+        parts.push((value as i32).to_string());
+    }
+    if bases.binary {
+        let bits = format!("{:032b}", value);
+        let nibbles: Vec<&str> = (0..bits.len()).step_by(4).map(|i| &bits[i..i + 4]).collect();
+        parts.push(format!("0b{}", nibbles.join("_")));
+    }
+    parts.join("  ")
+}
+
+/// Decoded xPSR flags, set ones upper case and clear ones lower case, then the
+/// running exception, e.g. `N z C v q T  HardFault`.
+pub fn format_psr_flags(flags: &PsrFlags) -> String {
+    let flag = |set: bool, name: char| if set { name } else { name.to_ascii_lowercase() };
+    let flags_text: Vec<String> = [
+        flag(flags.n, 'N'),
+        flag(flags.z, 'Z'),
+        flag(flags.c, 'C'),
+        flag(flags.v, 'V'),
+        flag(flags.q, 'Q'),
+        flag(flags.thumb, 'T'),
+    ]
+    .iter()
+    .map(char::to_string)
+    .collect();
+    format!(
+        "{}  {}",
+        flags_text.join(" "),
+        aether_core::itm::exception_name(flags.exception_number)
+    )
+}
+
+/// Tracepoint hits kept in the log view.
+pub const MAX_TRACEPOINT_LOG: usize = 1000;
+
+/// One log line for a tracepoint hit, e.g. `0x08000100: r0=0x0000002A counter=?`.
+pub fn format_tracepoint_hit(address: u64, values: &[(String, Option<String>)]) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|(expr, value)| format!("{}={}", expr, value.as_deref().unwrap_or("?")))
+        .collect();
+    format!("0x{:08X}: {}", address, values.join(" "))
+}
+
+/// Columns of a write log row: (target, old value, new value).
+pub fn format_write_record(kind: &WriteKind) -> (String, String, String) {
+    match kind {
+        WriteKind::Memory { address, old, new } => (
+            format!("0x{:08X}", address),
+            old.as_deref().map_or_else(|| "?".to_string(), format_as_hex),
+            format_as_hex(new),
+        ),
+        WriteKind::Register { id, old, new } => {
+            let name = if *id == aether_core::debug::special_regs::EXTRA_REGISTER_ID {
+                "CONTROL/FAULTMASK/BASEPRI/PRIMASK".to_string()
+            } else {
+                format!("R{}", id)
+            };
+            (name, format!("0x{:08X}", old), format!("0x{:08X}", new))
+        }
+    }
+}
+
+/// Core control actions that can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    Resume,
+    ToggleBreakpoint,
+    StepOver,
+    StepInto,
+    StepOut,
+}
+
+impl ShortcutAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::Resume => "Resume",
+            ShortcutAction::ToggleBreakpoint => "Toggle breakpoint at PC",
+            ShortcutAction::StepOver => "Step over",
+            ShortcutAction::StepInto => "Step into",
+            ShortcutAction::StepOut => "Step out",
+        }
+    }
+}
+
+/// A key (by egui key name, e.g. `"F5"`) bound to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: String,
+    pub shift: bool,
+    pub action: ShortcutAction,
+}
+
+impl KeyBinding {
+    fn new(key: &str, shift: bool, action: ShortcutAction) -> Self {
+        Self { key: key.to_string(), shift, action }
+    }
+}
+
+/// The usual debugger keys: F5 resume, F9 breakpoint, F10 over, F11 into, Shift+F11 out.
+pub fn default_key_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new("F5", false, ShortcutAction::Resume),
+        KeyBinding::new("F9", false, ShortcutAction::ToggleBreakpoint),
+        KeyBinding::new("F10", false, ShortcutAction::StepOver),
+        KeyBinding::new("F11", false, ShortcutAction::StepInto),
+        KeyBinding::new("F11", true, ShortcutAction::StepOut),
+    ]
+}
+
+/// Action bound to `key` with the given Shift state, if any.
+pub fn shortcut_action(bindings: &[KeyBinding], key: &str, shift: bool) -> Option<ShortcutAction> {
+    bindings.iter().find(|b| b.key.eq_ignore_ascii_case(key) && b.shift == shift).map(|b| b.action)
+}
+
+/// Command to send for `action`. Toggling a breakpoint needs the current PC.
+pub fn shortcut_command(
+    action: ShortcutAction,
+    pc: Option<u64>,
+    breakpoints: &[(u64, bool)],
+) -> Option<DebugCommand> {
+    match action {
+        ShortcutAction::Resume => Some(DebugCommand::Resume),
+        ShortcutAction::StepOver => Some(DebugCommand::StepOver),
+        ShortcutAction::StepInto => Some(DebugCommand::StepInto),
+        ShortcutAction::StepOut => Some(DebugCommand::StepOut),
+        ShortcutAction::ToggleBreakpoint => pc.map(|pc| {
+            if breakpoints.iter().any(|(addr, _)| *addr == pc) {
+                DebugCommand::ClearBreakpoint(pc)
+            } else {
+                DebugCommand::SetBreakpoint(pc)
+            }
+        }),
+    }
+}
+
 /// Returns a user-friendly string for the task state.
 pub fn get_task_state_display(state: TaskState) -> &'static str {
     match state {
@@ -38,10 +345,729 @@ pub fn get_display_location(file: Option<&str>, line: Option<u64>) -> String {
     }
 }
 
+/// Returns `(register, field)` names whose field value differs between two reads
+/// of the same peripheral.
+pub fn changed_fields(
+    old: &[aether_core::svd::RegisterInfo],
+    new: &[aether_core::svd::RegisterInfo],
+) -> std::collections::HashSet<(String, String)> {
+    let mut changed = std::collections::HashSet::new();
+    for reg in new {
+        let Some(old_reg) = old.iter().find(|r| r.name == reg.name) else {
+            continue;
+        };
+        let (Some(old_val), Some(new_val)) = (old_reg.value, reg.value) else {
+            continue;
+        };
+        let diff = old_val ^ new_val;
+        for field in &reg.fields {
+            if diff & field.mask() != 0 {
+                changed.insert((reg.name.clone(), field.name.clone()));
+            }
+        }
+    }
+    changed
+}
+
+/// Label for peripherals without an SVD `groupName`.
+pub const UNGROUPED_PERIPHERALS: &str = "Other";
+
+/// Buckets peripherals by SVD group, keeping those whose name or group contains
+/// `filter` (case-insensitive).
+pub fn group_peripherals<'a>(
+    peripherals: &'a [aether_core::svd::PeripheralInfo],
+    filter: &str,
+) -> std::collections::BTreeMap<String, Vec<&'a aether_core::svd::PeripheralInfo>> {
+    let filter = filter.trim().to_lowercase();
+    let mut groups = std::collections::BTreeMap::<String, Vec<_>>::new();
+    for p in peripherals {
+        let group = p.group_name.as_deref().unwrap_or(UNGROUPED_PERIPHERALS);
+        if !filter.is_empty()
+            && !p.name.to_lowercase().contains(&filter)
+            && !group.to_lowercase().contains(&filter)
+        {
+            continue;
+        }
+        groups.entry(group.to_string()).or_default().push(p);
+    }
+    groups
+}
+
+/// Number of lines kept in the combined RTT console.
+pub const MAX_RTT_MERGED_LINES: usize = 5000;
+
+/// A line of RTT output tagged with the up channel it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RttLine {
+    pub channel: usize,
+    pub text: String,
+}
+
+/// Output of all up channels interleaved in arrival order.
+///
+/// Chunks are split into lines; a chunk that does not end in a newline is
+/// continued by the next chunk only if that one comes from the same channel.
+#[derive(Debug, Default)]
+pub struct RttMergedLog {
+    lines: std::collections::VecDeque<RttLine>,
+    /// The last line is still open (no trailing newline seen yet).
+    open: bool,
+}
+
+impl RttMergedLog {
+    pub fn push(&mut self, channel: usize, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let parts: Vec<&str> = text.split('\n').collect();
+        for (i, part) in parts.iter().enumerate() {
+            let continues = i == 0
+                && self.open
+                && self.lines.back().is_some_and(|line| line.channel == channel);
+            if continues {
+                if let Some(line) = self.lines.back_mut() {
+                    line.text.push_str(part);
+                }
+            } else if !(part.is_empty() && i == parts.len() - 1) {
+                // An empty last part only means the chunk ended with a newline.
+                self.lines.push_back(RttLine { channel, text: (*part).to_string() });
+            }
+        }
+        self.open = !text.ends_with('\n');
+        while self.lines.len() > MAX_RTT_MERGED_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &RttLine> {
+        self.lines.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.open = false;
+    }
+}
+
+/// How the RTT text view decodes a channel's bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RttEncoding {
+    /// UTF-8, with invalid sequences replaced by U+FFFD.
+    #[default]
+    Utf8,
+    /// ISO 8859-1: every byte is the code point of the same value.
+    Latin1,
+    /// Printable ASCII as is, every other byte except newline and tab as `\xNN`.
+    Raw,
+}
+
+impl RttEncoding {
+    pub const ALL: [RttEncoding; 3] = [RttEncoding::Utf8, RttEncoding::Latin1, RttEncoding::Raw];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RttEncoding::Utf8 => "UTF-8",
+            RttEncoding::Latin1 => "Latin-1",
+            RttEncoding::Raw => "Raw",
+        }
+    }
+}
+
+/// Decodes RTT bytes for the text view.
+pub fn decode_rtt(data: &[u8], encoding: RttEncoding) -> String {
+    match encoding {
+        RttEncoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+        RttEncoding::Latin1 => data.iter().map(|&b| char::from(b)).collect(),
+        RttEncoding::Raw => {
+            let mut text = String::with_capacity(data.len());
+            for &b in data {
+                if b.is_ascii_graphic() || matches!(b, b' ' | b'\n' | b'\t') {
+                    text.push(char::from(b));
+                } else {
+                    text.push_str(&format!("\\x{:02X}", b));
+                }
+            }
+            text
+        }
+    }
+}
+
+/// Default number of debug events applied per frame.
+pub const DEFAULT_EVENTS_PER_FRAME: usize = 200;
+/// Queue length at which events are applied even while the user scrolls.
+pub const MAX_DEFERRED_EVENTS: usize = 5000;
+
+/// Events that only matter for their latest value, keyed by what they update.
+#[derive(Debug, PartialEq, Eq)]
+enum EventSlot {
+    Status,
+    Register(u16),
+    Memory(u64),
+    Disassembly,
+    Stack,
+    Tasks,
+    WriteLog,
+    TraceStats,
+}
+
+fn event_slot(event: &DebugEvent) -> Option<EventSlot> {
+    match event {
+        DebugEvent::Status(_) => Some(EventSlot::Status),
+        DebugEvent::RegisterValue(id, _) => Some(EventSlot::Register(*id)),
+        DebugEvent::MemoryData(address, _) => Some(EventSlot::Memory(*address)),
+        DebugEvent::Disassembly(_) | DebugEvent::SourceDisassembly(_) => {
+            Some(EventSlot::Disassembly)
+        }
+        DebugEvent::Stack(_) => Some(EventSlot::Stack),
+        DebugEvent::Tasks(_) => Some(EventSlot::Tasks),
+        DebugEvent::WriteLog(_) => Some(EventSlot::WriteLog),
+        DebugEvent::TraceStats(_) => Some(EventSlot::TraceStats),
+        _ => None,
+    }
+}
+
+/// Spreads bursts of debug events over several frames.
+///
+/// A newer event replaces a queued one that updates the same state (e.g. the
+/// same register), so repeated reads are applied once. The replacement moves to
+/// the back of the queue, so it is never applied before events that were sent
+/// ahead of it. At most `per_frame`
+/// events are handed out per frame, and none while the user scrolls if
+/// `suppress_while_scrolling` is set, unless the queue grows too long.
+#[derive(Debug)]
+pub struct EventBatcher {
+    pending: VecDeque<DebugEvent>,
+    pub per_frame: usize,
+    pub suppress_while_scrolling: bool,
+}
+
+impl Default for EventBatcher {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            per_frame: DEFAULT_EVENTS_PER_FRAME,
+            suppress_while_scrolling: true,
+        }
+    }
+}
+
+impl EventBatcher {
+    pub fn push(&mut self, event: DebugEvent) {
+        if let Some(slot) = event_slot(&event) {
+            if let Some(index) =
+                self.pending.iter().position(|e| event_slot(e).as_ref() == Some(&slot))
+            {
+                self.pending.remove(index);
+            }
+        }
+        self.pending.push_back(event);
+    }
+
+    /// The events to apply this frame, oldest first.
+    pub fn next_frame(&mut self, scrolling: bool) -> Vec<DebugEvent> {
+        if scrolling && self.suppress_while_scrolling && self.pending.len() < MAX_DEFERRED_EVENTS {
+            return Vec::new();
+        }
+        let count = self.pending.len().min(self.per_frame.max(1));
+        self.pending.drain(..count).collect()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+/// A run of text sharing one ANSI SGR style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnsiSpan {
+    pub text: String,
+    /// Foreground color as RGB; `None` is the default text color.
+    pub fg: Option<[u8; 3]>,
+    pub bold: bool,
+}
+
+/// xterm colors for SGR 30-37 and, brightened, 90-97.
+const ANSI_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 49, 49],
+    [13, 188, 121],
+    [229, 229, 16],
+    [36, 114, 200],
+    [188, 63, 188],
+    [17, 168, 205],
+    [229, 229, 229],
+    [102, 102, 102],
+    [241, 76, 76],
+    [35, 209, 139],
+    [245, 245, 67],
+    [59, 142, 234],
+    [214, 112, 214],
+    [41, 184, 219],
+    [255, 255, 255],
+];
+
+/// Color `n` of the xterm 256-color palette.
+fn ansi_256_color(n: u8) -> [u8; 3] {
+    match n {
+        0..=15 => ANSI_COLORS[usize::from(n)],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            [level(n / 36), level((n / 6) % 6), level(n % 6)]
+        }
+        232..=255 => {
+            let gray = 8 + (n - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
+/// Splits text containing ANSI escape sequences into styled spans.
+///
+/// SGR sequences (`ESC [ ... m`) set the foreground color and boldness; other
+/// CSI sequences are dropped. Concatenating the span texts gives the text with
+/// all escape sequences stripped.
+pub fn parse_ansi(text: &str) -> Vec<AnsiSpan> {
+    let mut spans = Vec::new();
+    let mut current = AnsiSpan { text: String::new(), fg: None, bold: false };
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' || chars.peek() != Some(&'[') {
+            current.text.push(c);
+            continue;
+        }
+        chars.next();
+        let mut params = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if ('\x40'..='\x7e').contains(&c) {
+                terminator = Some(c);
+                break;
+            }
+            params.push(c);
+        }
+        if terminator != Some('m') {
+            continue;
+        }
+
+        let (mut fg, mut bold) = (current.fg, current.bold);
+        let mut codes = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => (fg, bold) = (None, false),
+                1 => bold = true,
+                22 => bold = false,
+                30..=37 => fg = Some(ANSI_COLORS[usize::from(code - 30)]),
+                90..=97 => fg = Some(ANSI_COLORS[usize::from(code - 90 + 8)]),
+                39 => fg = None,
+                38 => match codes.next() {
+                    Some(5) => fg = codes.next().map(ansi_256_color),
+                    Some(2) => {
+                        fg = Some([
+                            codes.next().unwrap_or(0),
+                            codes.next().unwrap_or(0),
+                            codes.next().unwrap_or(0),
+                        ]);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        if (fg, bold) != (current.fg, current.bold) {
+            let next = AnsiSpan { text: String::new(), fg, bold };
+            let done = std::mem::replace(&mut current, next);
+            if !done.text.is_empty() {
+                spans.push(done);
+            }
+        }
+    }
+    if !current.text.is_empty() {
+        spans.push(current);
+    }
+    spans
+}
+
+/// Closes the slice of the task switched away from and opens one for the new task.
+pub fn record_task_switch(
+    events: &mut Vec<TimelineEvent>,
+    from: Option<u32>,
+    to: u32,
+    name: String,
+    timestamp: f64,
+) {
+    if let Some(from_handle) = from {
+        if let Some(event) = events
+            .iter_mut()
+            .rev()
+            .find(|e| !e.is_isr && e.task_handle == from_handle && e.end_time.is_none())
+        {
+            event.end_time = Some(timestamp);
+        }
+    }
+
+    events.push(TimelineEvent {
+        task_handle: to,
+        task_name: name,
+        start_time: timestamp,
+        end_time: None,
+        is_isr: false,
+    });
+    prune_timeline(events);
+}
+
+/// Records an exception entry or exit.
+///
+/// ISR slices overlap the task slice they preempt; the task slice stays open.
+pub fn record_isr(
+    events: &mut Vec<TimelineEvent>,
+    exception: u16,
+    action: ExceptionAction,
+    timestamp: f64,
+) {
+    match action {
+        ExceptionAction::Entered => {
+            events.push(TimelineEvent {
+                task_handle: u32::from(exception),
+                task_name: aether_core::itm::exception_name(exception),
+                start_time: timestamp,
+                end_time: None,
+                is_isr: true,
+            });
+            prune_timeline(events);
+        }
+        ExceptionAction::Exited | ExceptionAction::Returned => {
+            if let Some(event) = events
+                .iter_mut()
+                .rev()
+                .find(|e| e.is_isr && e.task_handle == u32::from(exception) && e.end_time.is_none())
+            {
+                event.end_time = Some(timestamp);
+            }
+        }
+    }
+}
+
+fn prune_timeline(events: &mut Vec<TimelineEvent>) {
+    if events.len() > MAX_TIMELINE_EVENTS {
+        let excess = events.len() - MAX_TIMELINE_EVENTS;
+        events.drain(..excess);
+    }
+}
+
+/// An interval on the timeline during which no task slice was recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleGap {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// The `(start, end)` spans of the task slices in `events`. ISR slices are left
+/// out: an interrupt taken while no task runs does not make the CPU busy in the
+/// idle statistics.
+pub fn task_slices(events: &[TimelineEvent]) -> Vec<(f64, Option<f64>)> {
+    events.iter().filter(|e| !e.is_isr).map(|e| (e.start_time, e.end_time)).collect()
+}
+
+/// Finds the gaps between task slices where no task was running.
+///
+/// Slices are `(start, end)` pairs; open slices (`None`) extend to `now`.
+/// Overlapping slices are merged, so only truly uncovered time is reported.
+pub fn compute_idle_gaps(slices: &[(f64, Option<f64>)], now: f64) -> Vec<IdleGap> {
+    let mut sorted: Vec<(f64, f64)> =
+        slices.iter().map(|&(start, end)| (start, end.unwrap_or(now))).collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut gaps = Vec::new();
+    let mut covered_until: Option<f64> = None;
+    for (start, end) in sorted {
+        if let Some(until) = covered_until {
+            if start > until {
+                gaps.push(IdleGap { start: until, end: start });
+            }
+        }
+        covered_until = Some(covered_until.map_or(end, |until| until.max(end)));
+    }
+    gaps
+}
+
+/// Percentage of `[span_start, span_end]` covered by idle gaps.
+pub fn idle_percentage(gaps: &[IdleGap], span_start: f64, span_end: f64) -> f64 {
+    let span = span_end - span_start;
+    if span <= 0.0 {
+        return 0.0;
+    }
+    let idle: f64 =
+        gaps.iter().map(|g| (g.end.min(span_end) - g.start.max(span_start)).max(0.0)).sum();
+    idle / span * 100.0
+}
+
+/// UI features that a remote agent may not implement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Feature {
+    Breakpoints,
+    Peripherals,
+    RttWrite,
+    Tasks,
+    Flash,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [
+        Feature::Breakpoints,
+        Feature::Peripherals,
+        Feature::RttWrite,
+        Feature::Tasks,
+        Feature::Flash,
+    ];
+
+    fn supported(self, caps: &Capabilities) -> bool {
+        match self {
+            Feature::Breakpoints => caps.breakpoints,
+            Feature::Peripherals => caps.peripherals,
+            Feature::RttWrite => caps.rtt_write,
+            Feature::Tasks => caps.tasks,
+            Feature::Flash => caps.flash,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Feature::Breakpoints => "Breakpoints",
+            Feature::Peripherals => "Peripheral access",
+            Feature::RttWrite => "RTT input",
+            Feature::Tasks => "RTOS task listing",
+            Feature::Flash => "Flashing",
+        }
+    }
+}
+
+/// Which features are usable with the given agent capabilities.
+///
+/// Enabled features map to `None`; disabled ones carry the tooltip explaining why.
+/// Local sessions pass `Capabilities::all()`.
+pub fn feature_enablement(caps: &Capabilities) -> BTreeMap<Feature, Option<String>> {
+    Feature::ALL
+        .iter()
+        .map(|&f| {
+            let reason = (!f.supported(caps))
+                .then(|| format!("{} not supported by the remote agent", f.label()));
+            (f, reason)
+        })
+        .collect()
+}
+
+/// How often captured data is flushed to disk when auto-save is on.
+pub const AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// Version of the auto-save file; files written by another version are not restored.
+pub const AUTOSAVE_VERSION: u32 = 1;
+
+/// Data captured during a session that would be lost with the UI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureBuffers {
+    pub rtt_buffers: HashMap<usize, String>,
+    #[serde(with = "plot_points")]
+    pub plots: HashMap<String, VecDeque<[f64; 2]>>,
+    pub timeline_events: Vec<TimelineEvent>,
+}
+
+impl CaptureBuffers {
+    pub fn is_empty(&self) -> bool {
+        self.rtt_buffers.values().all(String::is_empty)
+            && self.plots.values().all(VecDeque::is_empty)
+            && self.timeline_events.is_empty()
+    }
+}
+
+/// Serde adapter for plot samples. JSON has no NaN or infinity, so non-finite
+/// values are written as the strings `"NaN"`, `"inf"` and `"-inf"`.
+pub mod plot_points {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::{HashMap, VecDeque};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        NonFinite(String),
+    }
+
+    fn encode(value: f64) -> Value {
+        if value.is_finite() {
+            Value::Number(value)
+        } else {
+            Value::NonFinite(value.to_string())
+        }
+    }
+
+    fn decode<E: serde::de::Error>(value: Value) -> Result<f64, E> {
+        match value {
+            Value::Number(value) => Ok(value),
+            Value::NonFinite(text) => {
+                text.parse().map_err(|_| E::custom(format!("invalid plot value {:?}", text)))
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(
+        plots: &HashMap<String, VecDeque<[f64; 2]>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        plots
+            .iter()
+            .map(|(name, points)| (name, points.iter().map(|p| p.map(encode)).collect::<Vec<_>>()))
+            .collect::<HashMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, VecDeque<[f64; 2]>>, D::Error> {
+        HashMap::<String, Vec<[Value; 2]>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, points)| {
+                let points = points
+                    .into_iter()
+                    .map(|[t, v]| Ok([decode(t)?, decode(v)?]))
+                    .collect::<Result<_, D::Error>>()?;
+                Ok((name, points))
+            })
+            .collect()
+    }
+}
+
+/// Contents of the auto-save file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoSave {
+    pub version: u32,
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub buffers: CaptureBuffers,
+}
+
+/// `autosave.json` next to the session config.
+pub fn autosave_path() -> Option<PathBuf> {
+    aether_core::config::SessionConfig::default_path()
+        .map(|path| path.with_file_name("autosave.json"))
+}
+
+pub fn encode_autosave(buffers: &CaptureBuffers, saved_at: u64) -> anyhow::Result<String> {
+    let save = AutoSave { version: AUTOSAVE_VERSION, saved_at, buffers: buffers.clone() };
+    Ok(serde_json::to_string(&save)?)
+}
+
+pub fn decode_autosave(json: &str) -> anyhow::Result<AutoSave> {
+    let save: AutoSave = serde_json::from_str(json)?;
+    anyhow::ensure!(
+        save.version == AUTOSAVE_VERSION,
+        "auto-save version {} is not supported",
+        save.version
+    );
+    Ok(save)
+}
+
+/// Write `buffers` to `path` through a temporary file, so a crash mid-write
+/// leaves the previous save intact.
+pub fn write_autosave(path: &Path, buffers: &CaptureBuffers, saved_at: u64) -> anyhow::Result<()> {
+    use anyhow::Context;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, encode_autosave(buffers, saved_at)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// The save at `path`, or `None` if there is none.
+pub fn read_autosave(path: &Path) -> anyhow::Result<Option<AutoSave>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(Some(decode_autosave(&json)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_autosave_round_trip() {
+        let mut buffers = CaptureBuffers::default();
+        assert!(buffers.is_empty());
+        buffers.rtt_buffers.insert(0, "boot ok\r\n> ".to_string());
+        buffers.rtt_buffers.insert(2, "\u{1b}[31merr\u{1b}[0m".to_string());
+        buffers.plots.insert("adc".to_string(), VecDeque::from([[0.0, 1.5], [0.1, -2.25]]));
+        buffers.timeline_events.push(TimelineEvent {
+            task_handle: 15,
+            task_name: "SysTick".to_string(),
+            start_time: 0.5,
+            end_time: None,
+            is_isr: true,
+        });
+        assert!(!buffers.is_empty());
+
+        let json = encode_autosave(&buffers, 1_700_000_000).unwrap();
+        let save = decode_autosave(&json).unwrap();
+        assert_eq!(save.saved_at, 1_700_000_000);
+        assert_eq!(save.buffers, buffers);
+
+        let dir = std::env::temp_dir().join(format!("aether-autosave-{}", std::process::id()));
+        let path = dir.join("autosave.json");
+        assert!(read_autosave(&path).unwrap().is_none());
+        write_autosave(&path, &buffers, 1_700_000_030).unwrap();
+        let restored = read_autosave(&path).unwrap().unwrap();
+        assert_eq!(restored.buffers, buffers);
+        assert_eq!(restored.saved_at, 1_700_000_030);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let future = json.replacen("\"version\":1", "\"version\":99", 1);
+        assert!(decode_autosave(&future).is_err());
+    }
+
+    #[test]
+    fn test_autosave_keeps_non_finite_plot_values() {
+        let mut buffers = CaptureBuffers::default();
+        buffers.plots.insert(
+            "ratio".to_string(),
+            VecDeque::from([[0.0, f64::NAN], [0.1, f64::INFINITY], [0.2, f64::NEG_INFINITY]]),
+        );
+
+        let save = decode_autosave(&encode_autosave(&buffers, 0).unwrap()).unwrap();
+        let points = &save.buffers.plots["ratio"];
+        assert!(points[0][1].is_nan());
+        assert_eq!(points[1][1], f64::INFINITY);
+        assert_eq!(points[2], [0.2, f64::NEG_INFINITY]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_signal_stops_server_loop() {
+        let signal = ShutdownSignal::new();
+        let worker = signal.clone();
+        let server = tokio::spawn(async move {
+            let mut ticks = 0u32;
+            loop {
+                tokio::select! {
+                    _ = worker.wait() => return ticks,
+                    _ = tokio::time::sleep(std::time::Duration::from_millis(1)) => ticks += 1,
+                }
+            }
+        });
+
+        assert!(!signal.is_triggered());
+        signal.trigger();
+        assert!(signal.is_triggered());
+        tokio::time::timeout(std::time::Duration::from_secs(1), server)
+            .await
+            .expect("server loop did not stop")
+            .unwrap();
+        // Waiting after the fact returns immediately.
+        signal.wait().await;
+    }
+
     #[test]
     fn test_format_memory_line() {
         let data = vec![0xDE, 0xAD, 0xBE, 0xEF];
@@ -51,6 +1077,79 @@ mod tests {
         assert_eq!(ascii, "....");
     }
 
+    #[test]
+    fn test_format_memory_words_follows_target_endian() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0xAA, 0xBB];
+        assert!(format_memory_words(&data, Endian::Little).starts_with("78563412 BBAA "));
+        assert!(format_memory_words(&data, Endian::Big).starts_with("12345678 AABB "));
+    }
+
+    #[test]
+    fn test_memory_paging() {
+        assert_eq!(next_memory_page(0x2000_0000, 1024), 0x2000_0400);
+        assert_eq!(prev_memory_page(0x2000_0400, 1024), 0x2000_0000);
+        assert_eq!(prev_memory_page(0x100, 1024), 0);
+        assert_eq!(next_memory_page(u64::MAX - 0x10, 256), u64::MAX);
+    }
+
+    #[test]
+    fn test_copy_formats() {
+        let data: Vec<u8> = (0..18).collect();
+        assert_eq!(format_as_hex(&data[..4]), "00 01 02 03");
+        assert_eq!(
+            format_as_c_array(&data[..3]),
+            "const uint8_t data[3] = {\n    0x00, 0x01, 0x02,\n};"
+        );
+        let rust = format_as_rust_slice(&data);
+        assert!(rust.starts_with("&[\n    0x00, 0x01,"));
+        assert!(rust.contains("0x0F,\n    0x10, 0x11,\n]"));
+        assert_eq!(registers_as_bytes(&[0x1234_5678], Endian::Big), [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(registers_as_bytes(&[0x1234_5678], Endian::Little), [0x78, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn test_format_tracepoint_hit() {
+        let values =
+            vec![("r0".to_string(), Some("0x0000002A".to_string())), ("x".to_string(), None)];
+        assert_eq!(format_tracepoint_hit(0x0800_0100, &values), "0x08000100: r0=0x0000002A x=?");
+    }
+
+    #[test]
+    fn test_format_write_record() {
+        let mem = WriteKind::Memory {
+            address: 0x2000_0000,
+            old: Some(vec![0, 1]),
+            new: vec![0xAB, 0xCD],
+        };
+        assert_eq!(
+            format_write_record(&mem),
+            ("0x20000000".to_string(), "00 01".to_string(), "AB CD".to_string())
+        );
+        let unread = WriteKind::Memory { address: 0x4000_0000, old: None, new: vec![1] };
+        assert_eq!(format_write_record(&unread).1, "?");
+        let reg = WriteKind::Register { id: 3, old: 1, new: 2 };
+        assert_eq!(format_write_record(&reg).0, "R3");
+    }
+
+    #[test]
+    fn test_shortcut_key_to_command() {
+        let bindings = default_key_bindings();
+        let command = |key, shift| {
+            shortcut_action(&bindings, key, shift)
+                .and_then(|a| shortcut_command(a, Some(0x0800_0100), &[(0x0800_0100, true)]))
+        };
+        assert!(matches!(command("F5", false), Some(DebugCommand::Resume)));
+        assert!(matches!(command("F10", false), Some(DebugCommand::StepOver)));
+        assert!(matches!(command("F11", false), Some(DebugCommand::StepInto)));
+        assert!(matches!(command("F11", true), Some(DebugCommand::StepOut)));
+        assert!(matches!(command("F9", false), Some(DebugCommand::ClearBreakpoint(0x0800_0100))));
+        assert!(command("F12", false).is_none());
+
+        let set = shortcut_command(ShortcutAction::ToggleBreakpoint, Some(0x0800_0200), &[]);
+        assert!(matches!(set, Some(DebugCommand::SetBreakpoint(0x0800_0200))));
+        assert!(shortcut_command(ShortcutAction::ToggleBreakpoint, None, &[]).is_none());
+    }
+
     #[test]
     fn test_task_state_display() {
         assert_eq!(get_task_state_display(TaskState::Running), "▶ Running");
@@ -62,4 +1161,222 @@ mod tests {
         assert_eq!(get_display_location(Some("/path/to/main.rs"), Some(42)), "main.rs:42");
         assert_eq!(get_display_location(None, None), "??");
     }
+
+    #[test]
+    fn test_rtt_merged_log_interleaves_channels() {
+        let mut log = RttMergedLog::default();
+        log.push(0, "boot\nsensor ");
+        log.push(1, "defmt frame\n");
+        log.push(0, "ok\n");
+        log.push(0, "tick");
+        log.push(0, " 1\n");
+
+        let lines: Vec<_> = log.lines().map(|l| (l.channel, l.text.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![(0, "boot"), (0, "sensor "), (1, "defmt frame"), (0, "ok"), (0, "tick 1")]
+        );
+    }
+
+    #[test]
+    fn test_rtt_latin1_decoding() {
+        // "Grüße 25°C" as Latin-1.
+        let data = b"Gr\xFC\xDFe 25\xB0C\n";
+        assert_eq!(decode_rtt(data, RttEncoding::Latin1), "Grüße 25°C\n");
+        assert_eq!(decode_rtt(data, RttEncoding::Utf8), "Gr\u{FFFD}\u{FFFD}e 25\u{FFFD}C\n");
+        assert_eq!(decode_rtt(data, RttEncoding::Raw), "Gr\\xFC\\xDFe 25\\xB0C\n");
+
+        let utf8 = "Grüße".as_bytes();
+        assert_eq!(decode_rtt(utf8, RttEncoding::Utf8), "Grüße");
+    }
+
+    #[test]
+    fn test_xpsr_flag_decoding() {
+        // N and C set, Thumb, in HardFault.
+        let flags = PsrFlags::from_xpsr(0xA100_0003);
+        assert_eq!(
+            flags,
+            PsrFlags {
+                n: true,
+                z: false,
+                c: true,
+                v: false,
+                q: false,
+                thumb: true,
+                exception_number: 3
+            }
+        );
+        assert_eq!(format_psr_flags(&flags), "N z C v q T  HardFault");
+
+        let thread = PsrFlags::from_xpsr(0x6100_0000);
+        assert!(thread.z && thread.c && !thread.n && !thread.v);
+        assert_eq!(format_psr_flags(&thread), "n Z C v q T  Thread");
+
+        let all = RegisterBases { decimal: true, signed: true, binary: true };
+        assert_eq!(
+            format_register_value(0xFFFF_FFFE, all),
+            "0xFFFFFFFE  4294967294  -2  0b1111_1111_1111_1111_1111_1111_1111_1110"
+        );
+        assert_eq!(format_register_value(42, RegisterBases::default()), "0x0000002A");
+    }
+
+    #[test]
+    fn test_event_burst_is_batched_per_frame() {
+        let mut batcher = EventBatcher { per_frame: 100, ..Default::default() };
+
+        // A halt storm: every register re-read many times, plus RTT output.
+        for round in 0..50u64 {
+            for id in 0..16 {
+                batcher.push(DebugEvent::RegisterValue(id, round));
+            }
+            batcher.push(DebugEvent::MemoryData(0x2000_0000, vec![round as u8; 256]));
+        }
+        for i in 0..250 {
+            batcher.push(DebugEvent::RttData(0, vec![b'0' + (i % 10) as u8]));
+        }
+
+        // Nothing is applied while scrolling.
+        assert!(batcher.next_frame(true).is_empty());
+
+        let mut frames = Vec::new();
+        while batcher.has_pending() {
+            frames.push(batcher.next_frame(false));
+        }
+        // 16 registers + 1 memory read + 250 RTT chunks, in frames of 100.
+        assert_eq!(frames.iter().map(Vec::len).collect::<Vec<_>>(), vec![100, 100, 67]);
+
+        // Coalesced events carry the newest value.
+        assert!(matches!(frames[0][0], DebugEvent::RegisterValue(0, 49)));
+        assert!(matches!(&frames[0][16], DebugEvent::MemoryData(_, data) if data[0] == 49));
+        assert!(matches!(&frames[2][66], DebugEvent::RttData(0, data) if data == b"9"));
+    }
+
+    #[test]
+    fn test_coalesced_event_moves_behind_earlier_events() {
+        let mut batcher = EventBatcher::default();
+        batcher.push(DebugEvent::RegisterValue(15, 0x100));
+        batcher.push(DebugEvent::Halted { pc: 0x200 });
+        batcher.push(DebugEvent::RegisterValue(15, 0x200));
+
+        // The newer PC read is applied after the halt it follows.
+        let frame = batcher.next_frame(false);
+        assert_eq!(frame.len(), 2);
+        assert!(matches!(frame[0], DebugEvent::Halted { pc: 0x200 }));
+        assert!(matches!(frame[1], DebugEvent::RegisterValue(15, 0x200)));
+    }
+
+    #[test]
+    fn test_parse_ansi_color_codes() {
+        let spans = parse_ansi("\x1b[32mINFO\x1b[0m boot \x1b[1;31mERROR\x1b[0m\x1b[K done");
+        let plain = |text: &str, fg, bold| AnsiSpan { text: text.to_string(), fg, bold };
+        assert_eq!(
+            spans,
+            vec![
+                plain("INFO", Some(ANSI_COLORS[2]), false),
+                plain(" boot ", None, false),
+                plain("ERROR", Some(ANSI_COLORS[1]), true),
+                plain(" done", None, false),
+            ]
+        );
+
+        let text: String = parse_ansi("no codes").into_iter().map(|s| s.text).collect();
+        assert_eq!(text, "no codes");
+    }
+
+    #[test]
+    fn test_idle_gap_between_slices() {
+        // Task A runs 0.0-1.0, nothing is recorded until task B starts at 1.5.
+        let slices = [(0.0, Some(1.0)), (1.5, Some(2.0))];
+        let gaps = compute_idle_gaps(&slices, 2.0);
+        assert_eq!(gaps, vec![IdleGap { start: 1.0, end: 1.5 }]);
+        assert!((idle_percentage(&gaps, 0.0, 2.0) - 25.0).abs() < 1e-9);
+
+        // Back-to-back and overlapping slices leave no gap.
+        let slices = [(0.0, Some(1.0)), (1.0, Some(2.0)), (0.5, None)];
+        assert!(compute_idle_gaps(&slices, 3.0).is_empty());
+    }
+
+    #[test]
+    fn test_isr_does_not_fill_idle_gap() {
+        let slice = |start, end, is_isr| TimelineEvent {
+            task_handle: 0,
+            task_name: String::new(),
+            start_time: start,
+            end_time: Some(end),
+            is_isr,
+        };
+        // A SysTick interrupt fires while no task slice is open.
+        let events = [slice(0.0, 0.5, false), slice(0.6, 0.7, true), slice(1.0, 2.0, false)];
+
+        let gaps = compute_idle_gaps(&task_slices(&events), 2.0);
+        assert_eq!(gaps, vec![IdleGap { start: 0.5, end: 1.0 }]);
+    }
+
+    #[test]
+    fn test_isr_during_task_slice_overlaps() {
+        let mut events = Vec::new();
+        record_task_switch(&mut events, None, 0x2000_0100, "Idle".to_string(), 0.0);
+        record_task_switch(&mut events, Some(0x2000_0100), 0x2000_0200, "Main".to_string(), 1.0);
+        record_isr(&mut events, 31, ExceptionAction::Entered, 1.2);
+        record_isr(&mut events, 31, ExceptionAction::Exited, 1.3);
+
+        let task = events.iter().find(|e| e.task_name == "Main").unwrap();
+        assert!(!task.is_isr);
+        assert_eq!(task.end_time, None, "task slice stays open across the ISR");
+
+        let isr = events.iter().find(|e| e.is_isr).unwrap();
+        assert_eq!(isr.task_name, "IRQ15");
+        assert_eq!((isr.start_time, isr.end_time), (1.2, Some(1.3)));
+        assert!(isr.start_time >= task.start_time);
+
+        // A task switch never closes an ISR slice with the same number.
+        record_isr(&mut events, 16, ExceptionAction::Entered, 2.0);
+        record_task_switch(&mut events, Some(16), 0x2000_0100, "Idle".to_string(), 2.1);
+        assert!(events.iter().any(|e| e.is_isr && e.task_handle == 16 && e.end_time.is_none()));
+    }
+
+    #[test]
+    fn test_group_peripherals_by_group_name() {
+        let periph = |name: &str, group: Option<&str>| aether_core::svd::PeripheralInfo {
+            name: name.to_string(),
+            base_address: 0,
+            description: None,
+            group_name: group.map(str::to_string),
+        };
+        let peripherals = vec![
+            periph("USART2", Some("USART")),
+            periph("GPIOA", Some("GPIO")),
+            periph("USART1", Some("USART")),
+            periph("CRC", None),
+        ];
+
+        let groups = group_peripherals(&peripherals, "");
+        let keys: Vec<_> = groups.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["GPIO", "Other", "USART"]);
+        let usarts: Vec<_> = groups["USART"].iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(usarts, vec!["USART2", "USART1"]);
+
+        // Filter matches peripheral names and group names.
+        let groups = group_peripherals(&peripherals, "usart1");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups["USART"][0].name, "USART1");
+        assert_eq!(group_peripherals(&peripherals, "gpio")["GPIO"].len(), 1);
+        assert!(group_peripherals(&peripherals, "spi").is_empty());
+    }
+
+    #[test]
+    fn test_feature_enablement_follows_capabilities() {
+        let caps = Capabilities { flash: true, tasks: true, ..Capabilities::default() };
+        let features = feature_enablement(&caps);
+        assert_eq!(features.len(), Feature::ALL.len());
+        assert_eq!(features[&Feature::Flash], None);
+        assert_eq!(features[&Feature::Tasks], None);
+        assert_eq!(
+            features[&Feature::Breakpoints].as_deref(),
+            Some("Breakpoints not supported by the remote agent")
+        );
+        assert!(features[&Feature::Peripherals].is_some());
+
+        assert!(feature_enablement(&Capabilities::all()).values().all(Option::is_none));
+    }
 }
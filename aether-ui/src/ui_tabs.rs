@@ -13,6 +13,7 @@ pub enum DebugTab {
     Source,
     Plot,
     Tasks,
+    SyncObjects,
     Stack,
     Timeline,
     Variables,
@@ -39,6 +40,7 @@ impl<'a> TabViewer for AetherTabViewer<'a> {
             DebugTab::Source => "✍ Source".into(),
             DebugTab::Plot => "📈 Plot".into(),
             DebugTab::Tasks => "⚙ Tasks".into(),
+            DebugTab::SyncObjects => "🔒 Sync Objects".into(),
             DebugTab::Stack => "⛃ Stack".into(),
             DebugTab::Timeline => "⏱ Timeline".into(),
             DebugTab::Variables => "🔎 Watch".into(),
@@ -57,6 +59,7 @@ impl<'a> TabViewer for AetherTabViewer<'a> {
             DebugTab::Source => self.app.draw_source_view(ui),
             DebugTab::Plot => self.app.draw_plot_view(ui),
             DebugTab::Tasks => self.app.draw_tasks_view(ui),
+            DebugTab::SyncObjects => self.app.draw_sync_objects_view(ui),
             DebugTab::Stack => self.app.draw_stack_view(ui),
             DebugTab::Timeline => self.app.draw_timeline_view(ui),
             DebugTab::Variables => self.app.draw_variables_view(ui),
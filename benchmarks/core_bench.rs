@@ -18,6 +18,9 @@ fn bench_target_info_allocation(c: &mut Criterion) {
                 flash_size: 2048 * 1024,
                 ram_size: 256 * 1024,
                 architecture: "Armv7em".to_string(),
+                endian: aether_core::Endian::Little,
+                requested: "STM32F429ZITx".to_string(),
+                detection_method: aether_core::DetectionMethod::Requested,
             });
         })
     });